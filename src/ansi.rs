@@ -0,0 +1,223 @@
+use syntect::highlighting::{Color, FontStyle, Style};
+
+const ESC: char = '\x1b';
+
+/// Standard 16-color palette (xterm defaults)
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x00, 0x00),
+    (0x00, 0xcd, 0x00),
+    (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee),
+    (0xcd, 0x00, 0xcd),
+    (0x00, 0xcd, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x7f, 0x7f, 0x7f),
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0xff, 0xff, 0x00),
+    (0x5c, 0x5c, 0xff),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+/// Check whether the text contains ANSI escape sequences
+pub fn contains_ansi(text: &str) -> bool {
+    text.contains("\x1b[") || text.contains("\x1b]")
+}
+
+/// Split a line into styled segments, interpreting SGR color codes and
+/// stripping all other escape sequences. `style` carries over between lines.
+pub fn parse_line(line: &str, style: &mut Style, default: Style) -> Vec<(Style, String)> {
+    let mut segments: Vec<(Style, String)> = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != ESC {
+            current.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            // CSI: ESC [ params final
+            Some('[') => {
+                chars.next();
+                let mut params = String::new();
+                let mut terminator = None;
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        terminator = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if terminator == Some('m') {
+                    if !current.is_empty() {
+                        segments.push((*style, std::mem::take(&mut current)));
+                    }
+                    apply_sgr(&params, style, default);
+                }
+            }
+            // OSC: ESC ] ... (BEL | ESC \)
+            Some(']') => {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == ESC {
+                        chars.next_if_eq(&'\\');
+                        break;
+                    }
+                }
+            }
+            // Other two-character escapes
+            Some(_) => {
+                chars.next();
+            }
+            None => {}
+        }
+    }
+
+    if !current.is_empty() {
+        segments.push((*style, current));
+    }
+    segments
+}
+
+/// Apply SGR parameters ("1;31", "38;5;208", ...) to the style
+fn apply_sgr(params: &str, style: &mut Style, default: Style) {
+    let codes: Vec<u32> = if params.is_empty() {
+        vec![0]
+    } else {
+        params.split(';').map(|p| p.parse().unwrap_or(0)).collect()
+    };
+
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 => *style = default,
+            1 => style.font_style |= FontStyle::BOLD,
+            3 => style.font_style |= FontStyle::ITALIC,
+            4 => style.font_style |= FontStyle::UNDERLINE,
+            22 => style.font_style.remove(FontStyle::BOLD),
+            23 => style.font_style.remove(FontStyle::ITALIC),
+            24 => style.font_style.remove(FontStyle::UNDERLINE),
+            code @ 30..=37 => style.foreground = palette_color(code - 30),
+            code @ 90..=97 => style.foreground = palette_color(code - 90 + 8),
+            39 => style.foreground = default.foreground,
+            // 拡張カラー（前景は反映、背景は読み飛ばす）
+            code @ (38 | 48) => {
+                let (color, consumed) = extended_color(&codes[i + 1..]);
+                if code == 38
+                    && let Some(color) = color
+                {
+                    style.foreground = color;
+                }
+                i += consumed;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// Parse "5;n" or "2;r;g;b". Returns the color and the number of codes consumed.
+fn extended_color(codes: &[u32]) -> (Option<Color>, usize) {
+    match codes {
+        [5, n, ..] => (Some(color_256(*n)), 2),
+        [2, r, g, b, ..] => (Some(rgb(*r as u8, *g as u8, *b as u8)), 4),
+        _ => (None, codes.len()),
+    }
+}
+
+fn palette_color(index: u32) -> Color {
+    let (r, g, b) = PALETTE[index as usize % PALETTE.len()];
+    rgb(r, g, b)
+}
+
+/// xterm 256-color table
+fn color_256(n: u32) -> Color {
+    match n {
+        0..=15 => palette_color(n),
+        16..=231 => {
+            let n = n - 16;
+            let level = |v: u32| if v == 0 { 0 } else { (55 + v * 40) as u8 };
+            rgb(level(n / 36), level((n / 6) % 6), level(n % 6))
+        }
+        _ => {
+            let gray = (8 + (n.min(255) - 232) * 10) as u8;
+            rgb(gray, gray, gray)
+        }
+    }
+}
+
+fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color { r, g, b, a: 0xff }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_of(segments: &[(Style, String)]) -> String {
+        segments.iter().map(|(_, t)| t.as_str()).collect()
+    }
+
+    #[test]
+    fn test_contains_ansi() {
+        assert!(contains_ansi("\x1b[31merror\x1b[0m"));
+        assert!(!contains_ansi("plain text"));
+    }
+
+    #[test]
+    fn test_parse_line_applies_colors() {
+        let default = Style::default();
+        let mut style = default;
+        let segments = parse_line("ok \x1b[31merror\x1b[0m done", &mut style, default);
+
+        assert_eq!(text_of(&segments), "ok error done");
+        assert_eq!(segments[1].1, "error");
+        assert_eq!(segments[1].0.foreground, palette_color(1));
+        assert_eq!(segments[2].0, default);
+    }
+
+    #[test]
+    fn test_parse_line_style_carries_over() {
+        let default = Style::default();
+        let mut style = default;
+        parse_line("\x1b[1;32mstart", &mut style, default);
+
+        let segments = parse_line("continued", &mut style, default);
+        assert_eq!(segments[0].0.foreground, palette_color(2));
+        assert!(segments[0].0.font_style.contains(FontStyle::BOLD));
+    }
+
+    #[test]
+    fn test_parse_line_extended_colors() {
+        let default = Style::default();
+        let mut style = default;
+        let segments = parse_line(
+            "\x1b[38;2;1;2;3mx\x1b[48;5;10;38;5;196my",
+            &mut style,
+            default,
+        );
+
+        assert_eq!(segments[0].0.foreground, rgb(1, 2, 3));
+        assert_eq!(segments[1].0.foreground, rgb(255, 0, 0));
+    }
+
+    #[test]
+    fn test_parse_line_strips_other_sequences() {
+        let default = Style::default();
+        let mut style = default;
+        let segments = parse_line(
+            "\x1b[2K\x1b]0;title\x07text\x1b]8;;url\x1b\\link",
+            &mut style,
+            default,
+        );
+        assert_eq!(text_of(&segments), "textlink");
+    }
+}
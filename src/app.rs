@@ -1,14 +1,40 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
+use ratatui::layout::{Position, Rect};
 use ratatui::widgets::ListState;
+use unicode_width::UnicodeWidthStr;
 
-use crate::config::Config;
-use crate::editor::Editor;
+use crate::bookmarks::Bookmarks;
+use crate::commands;
+use crate::config::{Config, SPLIT_RATIOS};
+use crate::confirm::{Choice, Confirm, Response, Subject};
+use crate::disk_usage::{self, UsageEntry, UsageEvent};
+use crate::editor::{self, Editor};
 use crate::file_browser::FileBrowser;
-use crate::preview::{PreviewContent, Previewer};
-use crate::search::{FileSearcher, SearchResult};
+use crate::file_ops::{self, PasteItem, TransferEvent, TransferKind};
+use crate::git::{self, DiffMarker};
+use crate::keymap::Keymap;
+use crate::layout::SavedLayout;
+use crate::notify::{Level, Toasts};
+use crate::opener::{self, Opener};
+use crate::owners::Owners;
+use crate::palette::{self, Command, SetOption};
+use crate::places::{self, Place};
+use crate::preview::{self, PreviewContent, Previewer};
+use crate::prompt::TextInput;
+use crate::recent::RecentFiles;
+use crate::search::{FileSearcher, SearchOptions, SearchResult};
+use crate::trash_bin::{self, TrashEntry};
+use crate::ui_theme::UiTheme;
+use crate::watcher::{DirWatcher, FileWatcher};
+use crate::zoxide;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InputMode {
@@ -17,21 +43,287 @@ pub enum InputMode {
     Searching,    // 検索実行中（スピナー表示）
     SearchResult, // 検索結果選択中
     Preview,
-    JumpInput, // fキー後の1文字待ち
-    Help,      // ヘルプ画面
+    Visual,       // プレビュー内の行選択
+    GotoLine,     // プレビューで :行番号 入力中
+    JumpInput,    // fキー後の1文字待ち
+    FoldInput,    // ツリー表示で zキー後の1文字待ち
+    Rename,       // 名前変更の入力中
+    NewFile,      // 新規ファイル名の入力中
+    NewDir,       // 新規ディレクトリ名の入力中
+    Archive,      // 作成するアーカイブ名の入力中
+    ZoxideInput,  // zoxide で移動するディレクトリの入力中
+    MarkRange,    // V による範囲マーク中
+    Chmod,        // パーミッションの編集中
+    Trash,        // ゴミ箱の一覧
+    Recent,       // 最近開いたファイルの一覧
+    DiskUsage,    // ディスク使用量の一覧
+    OpenWith,     // 「このアプリで開く」メニュー
+    Places,       // マウント先・ドライブへの移動メニュー
+    SetBookmark,  // mキー後の1文字待ち
+    JumpBookmark, // 'キー後の1文字待ち
+    Breadcrumb,   // bキー後の数字待ち（何階層上に移動するか）
+    Command,      // : でコマンド入力中
+    Confirm,      // 確認ダイアログ（削除・上書きなど）の表示中
+    Help,         // ヘルプ画面
+    PathList,     // --stdin で渡されたパスの一覧
+}
+
+/// One directory of the current path as shown in the header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crumb {
+    pub name: String,
+    pub path: PathBuf,
+    /// Levels above the current directory, shown while picking one by number
+    pub number: Option<usize>,
+    /// Columns the crumb (number included) covers in the header
+    pub columns: std::ops::Range<u16>,
+}
+
+/// Lay out the crumbs left to right; returns the column after the last one
+fn place_crumbs(crumbs: &mut [Crumb]) -> u16 {
+    let mut column = 0;
+    for index in 0..crumbs.len() {
+        // ルート（/ や C:\）以外は区切り文字を挟む
+        if index > 0 && !crumbs[index - 1].name.ends_with(std::path::is_separator) {
+            column += 1;
+        }
+        let crumb = &mut crumbs[index];
+        let width = crumb.name.width() + crumb.number.map_or(0, |_| 2);
+        crumb.columns = column..column + width as u16;
+        column = crumb.columns.end;
+    }
+    column
+}
+
+/// Facts about the current directory for the status bar, found in the background
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirInfo {
+    pub branch: Option<String>,
+    /// Bytes free on the filesystem
+    pub free_space: Option<u64>,
+}
+
+/// An external previewer running on a worker thread for the selected file
+struct ExternalPreview {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    receiver: Receiver<io::Result<Vec<u8>>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Drop for ExternalPreview {
+    // 別のファイルに移ったら、まだ動いているコマンドは止める
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// External previewer outputs kept for moving back and forth over files
+const EXTERNAL_CACHE_SIZE: usize = 16;
+
+/// `git diff` running on a worker thread for the previewed file
+struct DiffRun {
+    path: PathBuf,
+    receiver: Receiver<HashMap<usize, DiffMarker>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Drop for DiffRun {
+    // 別のファイルに移ったら、まだ動いている git は止める
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// How long the selection must rest on a file before `git diff` runs, so
+/// holding a movement key does not start one per file
+const DIFF_DELAY: Duration = Duration::from_millis(150);
+
+fn apply_diff_markers(content: &mut PreviewContent, markers: &HashMap<usize, DiffMarker>) {
+    for line in &mut content.lines {
+        line.diff = markers.get(&line.line_number).copied();
+    }
+}
+
+/// Entries yanked for pasting
+#[derive(Debug, Clone)]
+pub struct Register {
+    pub kind: TransferKind,
+    pub paths: Vec<PathBuf>,
+}
+
+/// How to resolve a name collision when pasting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictChoice {
+    Overwrite,
+    /// Overwrite this and every remaining conflict
+    OverwriteAll,
+    Skip,
+    KeepBoth,
+    Abort,
+}
+
+/// Progress of a running copy, move, delete or archive
+#[derive(Debug, Clone)]
+pub struct TransferProgress {
+    /// What is happening, e.g. "Copying"
+    pub label: &'static str,
+    pub done: u64,
+    pub total: u64,
+    pub current: String,
+    pub started: Instant,
+}
+
+impl TransferProgress {
+    fn new(label: &'static str, started: Instant) -> Self {
+        Self {
+            label,
+            done: 0,
+            total: 0,
+            current: String::new(),
+            started,
+        }
+    }
+
+    /// Time left at the average speed so far (None for the first second)
+    pub fn eta(&self, now: Instant) -> Option<Duration> {
+        let elapsed = now.saturating_duration_since(self.started);
+        if self.done == 0 || elapsed < Duration::from_secs(1) {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.done);
+        Some(elapsed.mul_f64(remaining as f64 / self.done as f64))
+    }
+}
+
+/// What the background worker behind `transfer_receiver` does
+#[derive(Debug, Clone)]
+enum Operation {
+    Paste { kind: TransferKind, count: usize },
+    Delete { paths: Vec<PathBuf>, to_trash: bool },
+    Archive { dest: PathBuf, count: usize },
+}
+
+impl Operation {
+    fn label(&self) -> &'static str {
+        match self {
+            Operation::Paste {
+                kind: TransferKind::Copy,
+                ..
+            } => "Copying",
+            Operation::Paste {
+                kind: TransferKind::Move,
+                ..
+            } => "Moving",
+            Operation::Delete { to_trash: true, .. } => "Trashing",
+            Operation::Delete { .. } => "Deleting",
+            Operation::Archive { .. } => "Archiving",
+        }
+    }
+}
+
+/// Candidates Tab cycles through on the `:` prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// Byte offset of the word being completed
+    pub start: usize,
+    pub words: Vec<String>,
+    pub index: usize,
+}
+
+/// Permission bits being edited in the chmod dialog
+#[derive(Debug, Clone)]
+pub struct PermissionEdit {
+    pub path: PathBuf,
+    pub mode: u32,
+    /// Selected bit, 0 (owner read) through 8 (other execute)
+    pub cursor: usize,
+    /// Octal digits typed so far
+    pub input: String,
+}
+
+impl PermissionEdit {
+    /// Bit for the cell at `cursor` (owner rwx, group rwx, other rwx)
+    pub fn bit(cursor: usize) -> u32 {
+        0o400 >> cursor
+    }
+
+    pub fn toggle(&mut self) {
+        self.mode ^= Self::bit(self.cursor);
+        self.input.clear();
+    }
+
+    /// Move the cursor by rows (owner/group/other) and columns (r/w/x)
+    pub fn move_cursor(&mut self, rows: isize, cols: isize) {
+        let row = (self.cursor / 3) as isize + rows;
+        let col = (self.cursor % 3) as isize + cols;
+        self.cursor = (row.clamp(0, 2) * 3 + col.clamp(0, 2)) as usize;
+    }
+
+    /// Type an octal digit; three or four digits replace the mode
+    pub fn push_digit(&mut self, c: char) {
+        if !('0'..='7').contains(&c) || self.input.len() >= 4 {
+            return;
+        }
+        self.input.push(c);
+        if self.input.len() >= 3
+            && let Ok(mode) = u32::from_str_radix(&self.input, 8)
+        {
+            // 3桁なら setuid などの上位ビットは保つ
+            let keep = if self.input.len() == 3 {
+                self.mode & 0o7000
+            } else {
+                0
+            };
+            self.mode = keep | mode;
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+    }
 }
 
 pub struct App {
+    /// フォーカス中のペインのブラウザ
     pub browser: FileBrowser,
+    /// 2ペイン表示時のもう一方のブラウザ
+    pub other_browser: Option<FileBrowser>,
+    pub other_list_state: ListState,
+    /// フォーカス中のペインの位置（0: 左, 1: 右）
+    pub active_pane: usize,
     pub previewer: Previewer,
     pub editor: Editor,
     pub config: Config,
     pub preview_content: Option<PreviewContent>,
+    external_preview: Option<ExternalPreview>,
+    /// Outputs of external previewers by path and modification time
+    external_cache: VecDeque<(PathBuf, Option<SystemTime>, Vec<u8>)>,
+    /// Git change markers last found for a previewed file
+    diff_markers: Option<(PathBuf, HashMap<usize, DiffMarker>)>,
+    /// File whose markers are still to be computed once a preview shows it
+    diff_wanted: Option<PathBuf>,
+    diff_run: Option<DiffRun>,
     pub preview_scroll: usize,
     pub preview_height: usize,
+    /// ヘルプの先頭に表示している行
+    pub help_scroll: usize,
+    /// ヘルプのポップアップに収まる行数（描画時に更新）
+    pub help_height: usize,
+    help_max_scroll: usize,
+    /// 折り返し幅（ガターを除いたプレビュー本文の幅）
+    pub preview_width: usize,
+    /// 端末の幅が narrow_width 未満（1ペイン・短いパスとヒントで表示）
+    pub narrow: bool,
+    /// プレビューを端末いっぱいに表示（ヘッダー・フッター・枠なし）
+    pub fullscreen_preview: bool,
+    /// 端末の幅（ヘッダーのパンくずを収める）
+    screen_width: u16,
     pub input_mode: InputMode,
-    pub search_input: String,
-    pub status_message: Option<String>,
+    pub search_input: TextInput,
+    pub toasts: Toasts,
+    /// Where each toast was drawn, for dismissing it with a click
+    pub toast_areas: Vec<Rect>,
     pub should_quit: bool,
     pub list_state: ListState,
     pub needs_redraw: bool,
@@ -40,18 +332,145 @@ pub struct App {
     pub search_selected: usize,
     pub search_list_state: ListState,
     pub base_dir: PathBuf,
-    pub search_dirs_only: bool,
+    /// セッション中に記憶される検索フラグ
+    pub search_options: SearchOptions,
     pub search_receiver: Option<Receiver<Vec<SearchResult>>>,
     pub spinner_frame: usize,
+    // ファイル選択モード（--choose-files）と選ばれたパス
+    pub picker: bool,
+    pub chosen: Vec<PathBuf>,
     // ジャンプ関連
     pub last_jump_char: Option<char>,
+    // ディレクトリのブックマーク（m / ' で登録・移動）
+    pub bookmarks: Bookmarks,
+    // 設定の [keys] を反映したキー割り当て
+    pub keymap: Keymap,
+    // 行ジャンプ関連（:123 / 123G）
+    pub goto_input: String,
+    pub preview_count: Option<usize>,
+    // ビジュアル選択関連
+    pub visual_anchor: usize,
+    pub visual_cursor: usize,
+    // 削除確認中のパス
+    pub pending_delete: Vec<PathBuf>,
+    // 表示中の確認ダイアログ
+    pub confirm: Option<Confirm>,
+    // ファイル一覧の詳細表示（サイズ・更新日時・パーミッション）
+    pub show_details: bool,
+    // ファイル一覧の右に選択中のエントリのプレビューを表示
+    pub show_preview_pane: bool,
+    // プレビューペインの幅と 2ペイン表示の左ペインの幅（%）
+    pub preview_ratio: u16,
+    pub dual_ratio: u16,
+    // < / > で変えた幅（次回の起動でも使う）
+    saved_layout: SavedLayout,
+    // 詳細表示中のディレクトリサイズ（バックグラウンドで計測して埋める）
+    pub dir_sizes: HashMap<PathBuf, u64>,
+    // 詳細表示の所有者・グループ名
+    pub owners: Owners,
+    dir_size_receiver: Option<Receiver<(PathBuf, u64)>>,
+    dir_size_cancel: Arc<AtomicBool>,
+    // dir_sizes を計測したディレクトリ
+    dir_sizes_for: PathBuf,
+    // 一覧が変わったので、測り終えたディレクトリも測り直す
+    dir_sizes_stale: bool,
+    // ステータスバーに出す現在のディレクトリの情報
+    pub dir_info: DirInfo,
+    dir_info_for: PathBuf,
+    dir_info_receiver: Option<Receiver<DirInfo>>,
+    // V による範囲マークの起点
+    pub mark_anchor: usize,
+    // 名前変更・新規作成の入力
+    pub name_input: TextInput,
+    // C で移動するディレクトリの入力
+    pub path_input: TextInput,
+    // : で入力中のコマンドと、Tab で巡回中の補完候補（入力を変えると捨てる）
+    pub command_input: TextInput,
+    pub command_completion: Option<Completion>,
+    // パーミッション編集中の状態
+    pub permission_edit: Option<PermissionEdit>,
+    // ディスク使用量の一覧（計測中は usage_receiver が Some）
+    pub usage_dir: PathBuf,
+    pub usage_entries: Vec<UsageEntry>,
+    pub usage_list_state: ListState,
+    pub usage_receiver: Option<Receiver<UsageEvent>>,
+    // 一覧を並べ替えても選択し続けるエントリ
+    usage_reselect: Option<PathBuf>,
+    // zoxide に最後に記録したディレクトリ
+    last_visited: PathBuf,
+    // ゴミ箱の一覧
+    pub trash_entries: Vec<TrashEntry>,
+    pub trash_list_state: ListState,
+    // 最近開いたファイルの履歴と一覧での選択位置
+    pub recent: RecentFiles,
+    pub recent_list_state: ListState,
+    // --stdin で渡されたパス、絞り込み文字列、一致したパスの位置
+    pub path_list: Vec<String>,
+    pub path_filter: String,
+    pub path_matches: Vec<usize>,
+    pub path_list_state: ListState,
+    // プレビューを閉じたらパス一覧に戻る
+    return_to_path_list: bool,
+    // 「このアプリで開く」メニューの候補と選択位置
+    pub open_with: Vec<Opener>,
+    pub open_with_selected: usize,
+    // 移動先（ホーム・マウント先・ドライブ）のメニューと選択位置
+    pub places: Vec<Place>,
+    pub places_selected: usize,
+    // コピー・移動関連
+    pub register: Option<Register>,
+    paste_kind: TransferKind,
+    paste_dir: PathBuf,
+    paste_ready: Vec<PasteItem>,
+    pub paste_conflicts: VecDeque<PasteItem>,
+    pub transfer_receiver: Option<Receiver<TransferEvent>>,
+    pub transfer_progress: Option<TransferProgress>,
+    transfer_cancel: Arc<AtomicBool>,
+    operation: Option<Operation>,
+    // current_dir の変更監視（作成できない環境では None）
+    watcher: Option<DirWatcher>,
+    // config.toml と .vfv.toml の変更監視（監視できないものは含めない）
+    config_watchers: Vec<FileWatcher>,
 }
 
 impl App {
     pub fn new(start_path: &Path, config: Config) -> Self {
-        let previewer = Previewer::new(&config.theme, config.preview_max_lines);
+        Self::with_saved_state(start_path, config, RecentFiles::load(), SavedLayout::load())
+    }
+
+    /// Like `new`, with the file history and the saved pane widths given
+    /// instead of read from the data dir
+    pub fn with_saved_state(
+        start_path: &Path,
+        config: Config,
+        recent: RecentFiles,
+        saved_layout: SavedLayout,
+    ) -> Self {
+        let mut previewer = Previewer::new(
+            config.syntax_theme(),
+            config.preview_max_lines,
+            config.tab_width,
+        );
+        previewer.external_previewers = config.previewers.clone();
+        previewer.max_bytes = config.preview_max_bytes;
         let editor = Editor::new(&config);
-        let browser = FileBrowser::new(start_path, config.show_hidden);
+        // ファイルを指定した場合は親ディレクトリを開き、そのファイルをプレビューする
+        let start_file = start_path.is_file().then_some(start_path);
+        let start_path = match start_file.and_then(Path::parent) {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            Some(_) => Path::new("."),
+            None => start_path,
+        };
+        let mut browser = FileBrowser::new(start_path, config.show_hidden);
+        browser.natural_sort = config.natural_sort;
+        let ignore_error = browser
+            .set_ignore(&config.browser_ignore)
+            .err()
+            .map(|e| format!("Invalid browser_ignore pattern: {}", e));
+        let (keymap, key_errors) = Keymap::new(&config.keys, &config.commands);
+        let key_error = (!key_errors.is_empty())
+            .then(|| format!("Config [keys]/[commands]: {}", key_errors.join(", ")));
+        browser.set_sort(config.sort, config.sort_reverse);
         let base_dir = start_path
             .canonicalize()
             .unwrap_or_else(|_| start_path.to_path_buf());
@@ -61,18 +480,53 @@ impl App {
 
         let mut search_list_state = ListState::default();
         search_list_state.select(Some(0));
+        // vfv find と同じ結果になるよう [find] の既定値から始める
+        let search_options = SearchOptions {
+            dirs_only: false,
+            exact: config.find.exact,
+            hidden: config.find.hidden,
+            no_ignore: config.find.no_ignore,
+        };
 
+        let show_preview_pane = config.preview_pane;
+        let preview_ratio = clamp_split(
+            saved_layout
+                .preview_ratio(config.preview_pane_ratio)
+                .unwrap_or(config.preview_pane_ratio),
+        );
+        let dual_ratio = clamp_split(
+            saved_layout
+                .dual_ratio(config.dual_pane_ratio)
+                .unwrap_or(config.dual_pane_ratio),
+        );
         let mut app = Self {
             browser,
+            other_browser: None,
+            other_list_state: ListState::default(),
+            active_pane: 0,
             previewer,
             editor,
             config,
             preview_content: None,
+            external_preview: None,
+            external_cache: VecDeque::new(),
+            diff_markers: None,
+            diff_wanted: None,
+            diff_run: None,
             preview_scroll: 0,
             preview_height: 20,
+            help_scroll: 0,
+            help_height: 20,
+            help_max_scroll: 0,
+            preview_width: 80,
+            narrow: false,
+            fullscreen_preview: false,
+            screen_width: u16::MAX,
             input_mode: InputMode::Normal,
-            search_input: String::new(),
-            status_message: None,
+            // CLI と同じく 1000 文字まで
+            search_input: TextInput::with_limit(1000),
+            toasts: Toasts::default(),
+            toast_areas: Vec::new(),
             should_quit: false,
             list_state,
             needs_redraw: false,
@@ -80,27 +534,262 @@ impl App {
             search_selected: 0,
             search_list_state,
             base_dir,
-            search_dirs_only: false,
+            search_options,
             search_receiver: None,
             spinner_frame: 0,
+            picker: false,
+            chosen: Vec::new(),
             last_jump_char: None,
+            bookmarks: Bookmarks::load(),
+            keymap,
+            goto_input: String::new(),
+            preview_count: None,
+            visual_anchor: 0,
+            visual_cursor: 0,
+            pending_delete: Vec::new(),
+            confirm: None,
+            show_details: false,
+            show_preview_pane,
+            preview_ratio,
+            dual_ratio,
+            saved_layout,
+            dir_sizes: HashMap::new(),
+            owners: Owners::default(),
+            dir_size_receiver: None,
+            dir_size_cancel: Arc::new(AtomicBool::new(false)),
+            dir_sizes_for: PathBuf::new(),
+            dir_sizes_stale: false,
+            dir_info: DirInfo::default(),
+            dir_info_for: PathBuf::new(),
+            dir_info_receiver: None,
+            mark_anchor: 0,
+            name_input: TextInput::default(),
+            path_input: TextInput::default(),
+            command_input: TextInput::default(),
+            command_completion: None,
+            permission_edit: None,
+            usage_dir: PathBuf::new(),
+            usage_entries: Vec::new(),
+            usage_list_state: ListState::default(),
+            usage_receiver: None,
+            usage_reselect: None,
+            last_visited: PathBuf::new(),
+            trash_entries: Vec::new(),
+            trash_list_state: ListState::default(),
+            recent,
+            recent_list_state: ListState::default(),
+            path_list: Vec::new(),
+            path_filter: String::new(),
+            path_matches: Vec::new(),
+            path_list_state: ListState::default(),
+            return_to_path_list: false,
+            open_with: Vec::new(),
+            open_with_selected: 0,
+            places: Vec::new(),
+            places_selected: 0,
+            register: None,
+            paste_kind: TransferKind::Copy,
+            paste_dir: PathBuf::new(),
+            paste_ready: Vec::new(),
+            paste_conflicts: VecDeque::new(),
+            transfer_receiver: None,
+            transfer_progress: None,
+            transfer_cancel: Arc::new(AtomicBool::new(false)),
+            operation: None,
+            watcher: DirWatcher::new().ok(),
+            config_watchers: std::iter::once(Config::config_path())
+                .chain(Config::project_config_path())
+                .filter_map(|path| FileWatcher::new(&path).ok())
+                .collect(),
         };
 
+        for message in [ignore_error, key_error].into_iter().flatten() {
+            app.toasts.warn(message);
+        }
+        app.last_visited = app.browser.current_dir.clone();
         app.update_preview();
+        if let Some(file) = start_file {
+            app.preview_file(file);
+        }
         app
     }
 
     pub fn update_preview(&mut self) {
         self.preview_scroll = 0;
-        if let Some(entry) = self.browser.selected_entry() {
-            if !entry.is_dir {
-                self.preview_content = Some(self.previewer.preview(&entry.path));
-            } else {
-                self.preview_content = None;
+        let file = self
+            .browser
+            .selected_entry()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| entry.path.clone());
+        self.preview_content = file.as_ref().map(|path| self.file_preview(path));
+
+        // git diff は大きなリポジトリだと時間がかかるので、プレビューが
+        // 見えているときに poll_diff_markers から別スレッドで動かす
+        self.diff_run = None;
+        self.diff_wanted = file.filter(|_| {
+            self.preview_content
+                .as_ref()
+                .is_some_and(|content| content.source_lines)
+        });
+        // 同じファイルなら届くまで前回のマーカーを出しておく
+        if let Some((ref marked, ref markers)) = self.diff_markers
+            && self.diff_wanted.as_ref() == Some(marked)
+            && let Some(ref mut content) = self.preview_content
+        {
+            apply_diff_markers(content, markers);
+        }
+    }
+
+    /// プレビューに出ているファイルの git diff を別スレッドで始める
+    fn start_diff_markers(&mut self) {
+        if !self.preview_visible() {
+            return;
+        }
+        let Some(path) = self.diff_wanted.take() else {
+            return;
+        };
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        let target = path.clone();
+        thread::spawn(move || {
+            // 選択がすぐ移るなら git は動かさない
+            thread::sleep(DIFF_DELAY);
+            if !flag.load(Ordering::Relaxed) {
+                let _ = tx.send(git::diff_markers(&target, &flag));
             }
-        } else {
-            self.preview_content = None;
+        });
+        self.diff_run = Some(DiffRun {
+            path,
+            receiver: rx,
+            cancel,
+        });
+    }
+
+    /// プレビューが画面に出ているか（一覧の横か、プレビューモード）
+    fn preview_visible(&self) -> bool {
+        self.show_preview_pane
+            || matches!(
+                self.input_mode,
+                InputMode::Preview | InputMode::Visual | InputMode::GotoLine
+            )
+    }
+
+    /// git の差分マーカーを求め、届いたらプレビューに反映（main loopから呼ばれる）
+    pub fn poll_diff_markers(&mut self) -> bool {
+        let Some(ref running) = self.diff_run else {
+            self.start_diff_markers();
+            return false;
+        };
+        let markers = match running.receiver.try_recv() {
+            Ok(markers) => markers,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => HashMap::new(),
+        };
+        let Some(running) = self.diff_run.take() else {
+            return false;
+        };
+        // 外部プレビューに置き換わっていたら行番号が合わない
+        let changed = match self.preview_content {
+            Some(ref mut content) if content.source_lines => {
+                apply_diff_markers(content, &markers);
+                true
+            }
+            _ => false,
+        };
+        self.diff_markers = Some((running.path.clone(), markers));
+        changed
+    }
+
+    /// 外部プレビューコマンドはワーカースレッドで動かし、結果が届くまでは
+    /// 内蔵のプレビューを出す（同じ内容のファイルなら前回の結果を使う）
+    fn file_preview(&mut self, path: &Path) -> PreviewContent {
+        let Some(command) = self.previewer.external_command(path) else {
+            self.external_preview = None;
+            return self.previewer.preview(path);
+        };
+        let modified = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if let Some((_, _, output)) = self
+            .external_cache
+            .iter()
+            .find(|(cached, time, _)| cached == path && *time == modified)
+        {
+            self.external_preview = None;
+            return self.previewer.external_content(output);
+        }
+
+        let running = self
+            .external_preview
+            .as_ref()
+            .is_some_and(|running| running.path == path && running.modified == modified);
+        if !running {
+            let (tx, rx) = mpsc::channel();
+            let cancel = Arc::new(AtomicBool::new(false));
+            let flag = Arc::clone(&cancel);
+            let target = path.to_path_buf();
+            thread::spawn(move || {
+                let _ = tx.send(preview::run_external(&command, &target, &flag));
+            });
+            self.external_preview = Some(ExternalPreview {
+                path: path.to_path_buf(),
+                modified,
+                receiver: rx,
+                cancel,
+            });
+        }
+        self.previewer.preview(path)
+    }
+
+    /// 外部プレビューコマンドの結果を反映（main loopから呼ばれる）
+    pub fn poll_external_preview(&mut self) -> bool {
+        let Some(ref running) = self.external_preview else {
+            return false;
+        };
+        let result = match running.receiver.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => Err(io::Error::other("previewer stopped")),
+        };
+        let Some(running) = self.external_preview.take() else {
+            return false;
+        };
+        // 失敗したら内蔵のプレビューのまま
+        let Ok(output) = result else {
+            return false;
+        };
+
+        let content = self.previewer.external_content(&output);
+        if self.external_cache.len() >= EXTERNAL_CACHE_SIZE {
+            self.external_cache.pop_front();
+        }
+        self.external_cache
+            .push_back((running.path.clone(), running.modified, output));
+        let selected = self.browser.selected_entry().map(|entry| &entry.path);
+        if selected != Some(&running.path) {
+            return false;
         }
+        self.preview_content = Some(content);
+        self.preview_scroll = self.preview_scroll.min(self.preview_max_scroll());
+        true
+    }
+
+    /// プレビューを再生成（スクロール位置は維持）
+    fn refresh_preview(&mut self) {
+        let scroll = self.preview_scroll;
+        self.update_preview();
+        self.preview_scroll = scroll.min(self.preview_max_scroll());
+    }
+
+    pub fn toggle_whitespace(&mut self) {
+        self.previewer.show_whitespace = !self.previewer.show_whitespace;
+        self.refresh_preview();
+        self.toasts.info(if self.previewer.show_whitespace {
+            "Whitespace: visible"
+        } else {
+            "Whitespace: hidden"
+        });
     }
 
     pub fn move_up(&mut self) {
@@ -121,6 +810,135 @@ impl App {
         self.last_jump_char = None;
     }
 
+    /// 選択中のエントリのマークを切り替えて次へ
+    pub fn toggle_mark(&mut self) {
+        self.clear_jump();
+        self.browser.toggle_mark();
+        self.list_state.select(Some(self.browser.selected_index));
+        self.update_preview();
+    }
+
+    /// ソート順を name → size → mtime → ext の順に切り替え
+    pub fn cycle_sort(&mut self) {
+        self.clear_jump();
+        let sort = self.browser.sort.next();
+        self.browser.set_sort(sort, self.browser.sort_reverse);
+        self.list_state.select(Some(self.browser.selected_index));
+    }
+
+    pub fn toggle_sort_reverse(&mut self) {
+        self.clear_jump();
+        self.browser
+            .set_sort(self.browser.sort, !self.browser.sort_reverse);
+        self.list_state.select(Some(self.browser.selected_index));
+    }
+
+    /// 2ペイン表示の切り替え（新しいペインは現在のディレクトリで開く）
+    pub fn toggle_dual_pane(&mut self) {
+        self.clear_jump();
+        if self.other_browser.take().is_some() {
+            self.active_pane = 0;
+            return;
+        }
+        let mut other = FileBrowser::new(&self.browser.current_dir, self.browser.show_hidden);
+        other.natural_sort = self.browser.natural_sort;
+        other.ignore = self.browser.ignore.clone();
+        other.set_sort(self.browser.sort, self.browser.sort_reverse);
+        self.other_list_state.select(Some(other.selected_index));
+        self.other_browser = Some(other);
+        self.active_pane = 0;
+    }
+
+    /// もう一方のペインにフォーカスを移す
+    pub fn switch_pane(&mut self) {
+        self.clear_jump();
+        let Some(ref mut other) = self.other_browser else {
+            return;
+        };
+        std::mem::swap(&mut self.browser, other);
+        std::mem::swap(&mut self.list_state, &mut self.other_list_state);
+        self.active_pane = 1 - self.active_pane;
+        self.update_preview();
+    }
+
+    /// ファイル操作の後にもう一方のペインも読み直す
+    fn refresh_other_pane(&mut self) {
+        if let Some(ref mut other) = self.other_browser {
+            other.refresh();
+            self.other_list_state.select(Some(other.selected_index));
+        }
+    }
+
+    pub fn toggle_details(&mut self) {
+        self.clear_jump();
+        self.show_details = !self.show_details;
+        // 表示し直すたびに測り直す
+        self.dir_sizes.clear();
+        self.stop_dir_sizes();
+    }
+
+    pub fn toggle_preview_pane(&mut self) {
+        self.clear_jump();
+        self.show_preview_pane = !self.show_preview_pane;
+    }
+
+    /// < / >: move the divider of the dual panes (or of the list and the
+    /// preview pane) by one step and remember the new widths
+    pub fn move_split(&mut self, direction: i16) {
+        self.clear_jump();
+        let step = direction * SPLIT_STEP as i16;
+        let (label, ratio) = if self.other_browser.is_some() {
+            self.dual_ratio = clamp_split(self.dual_ratio.saturating_add_signed(step));
+            self.saved_layout
+                .set_dual_ratio(self.dual_ratio, self.config.dual_pane_ratio);
+            ("Left pane", self.dual_ratio)
+        } else if self.show_preview_pane {
+            // 境界を右に動かすとプレビューは狭くなる
+            self.preview_ratio = clamp_split(self.preview_ratio.saturating_add_signed(-step));
+            self.saved_layout
+                .set_preview_ratio(self.preview_ratio, self.config.preview_pane_ratio);
+            ("Preview pane", self.preview_ratio)
+        } else {
+            self.toasts
+                .warn("No split to resize (v: preview pane, w: dual pane)");
+            return;
+        };
+        match self.saved_layout.save() {
+            Ok(()) => self.toasts.info(format!("{}: {}%", label, ratio)),
+            Err(e) => self.toasts.error(format!("Failed to save layout: {}", e)),
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.browser.clear_marks();
+    }
+
+    /// V: 現在位置から範囲マークを開始
+    pub fn start_mark_range(&mut self) {
+        self.clear_jump();
+        if self.browser.entries.is_empty() {
+            return;
+        }
+        self.mark_anchor = self.browser.selected_index;
+        self.input_mode = InputMode::MarkRange;
+    }
+
+    pub fn confirm_mark_range(&mut self) {
+        self.browser
+            .mark_range(self.mark_anchor, self.browser.selected_index);
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn cancel_mark_range(&mut self) {
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// 範囲マーク中の範囲（開始, 終了）
+    pub fn mark_range(&self) -> (usize, usize) {
+        let cursor = self.browser.selected_index;
+        (self.mark_anchor.min(cursor), self.mark_anchor.max(cursor))
+    }
+
     pub fn go_to_top(&mut self) {
         self.clear_jump();
         self.browser.go_to_top();
@@ -144,13 +962,102 @@ impl App {
                     self.update_preview();
                 }
             } else {
-                // ファイルの場合はプレビューモードに入る
+                // ファイルの場合はプレビューモードに入る（拡張子に割り当てたプログラムが優先）
+                let path = entry.path.clone();
+                if self.open_by_extension(&path) {
+                    return;
+                }
+                self.record_recent(&path);
                 self.input_mode = InputMode::Preview;
             }
         }
     }
 
+    /// ツリー表示では展開、それ以外はディレクトリに入る（→キー）
+    pub fn expand_or_enter(&mut self) {
+        if self.browser.tree_mode {
+            self.clear_jump();
+            if self.browser.expand_selected() {
+                self.list_state.select(Some(self.browser.selected_index));
+            }
+            return;
+        }
+        self.enter();
+    }
+
+    /// ツリー表示では折りたたみ（入れ子なら親へ）、それ以外は親ディレクトリへ（←キー）
+    pub fn collapse_or_parent(&mut self) {
+        if self.browser.tree_mode {
+            self.clear_jump();
+            if self.browser.collapse_selected() {
+                self.list_state.select(Some(self.browser.selected_index));
+                self.update_preview();
+            }
+            return;
+        }
+        self.go_parent();
+    }
+
+    pub fn toggle_tree(&mut self) {
+        self.clear_jump();
+        self.browser.toggle_tree();
+        self.list_state.select(Some(self.browser.selected_index));
+        self.update_preview();
+        self.toasts.info(if self.browser.tree_mode {
+            "Tree view: on"
+        } else {
+            "Tree view: off"
+        });
+    }
+
+    pub fn start_fold(&mut self) {
+        self.input_mode = InputMode::FoldInput;
+    }
+
+    /// z に続くキー（a: 切替, o: 展開, c: 折りたたみ）
+    pub fn execute_fold(&mut self, c: char) {
+        self.input_mode = InputMode::Normal;
+        // ツリー表示でなければ有効にしてから展開する
+        if !self.browser.tree_mode {
+            self.toggle_tree();
+        }
+        let changed = match c {
+            'a' => self.browser.toggle_expanded(),
+            'o' => self.browser.expand_selected(),
+            'c' => self.browser.collapse_selected(),
+            _ => return,
+        };
+        if changed {
+            self.list_state.select(Some(self.browser.selected_index));
+            self.update_preview();
+        }
+    }
+
+    /// ファイル選択モードで Enter: マークしたもの（なければ選択中のファイル）を選んで終了
+    pub fn pick(&mut self) {
+        self.clear_jump();
+        if !self.browser.marked.is_empty() {
+            self.chosen = self.browser.marked.iter().cloned().collect();
+            self.quit();
+            return;
+        }
+        let Some(entry) = self.browser.selected_entry() else {
+            return;
+        };
+        if entry.is_dir {
+            self.enter();
+            return;
+        }
+        self.chosen = vec![entry.path.clone()];
+        self.quit();
+    }
+
     pub fn exit_preview(&mut self) {
+        if self.return_to_path_list {
+            self.return_to_path_list = false;
+            self.input_mode = InputMode::PathList;
+            return;
+        }
         self.input_mode = InputMode::Normal;
     }
 
@@ -162,6 +1069,44 @@ impl App {
         }
     }
 
+    /// シンボリックリンクの実体があるディレクトリに移動して実体を選択
+    pub fn follow_link(&mut self) {
+        self.clear_jump();
+        let Some(entry) = self.browser.selected_entry() else {
+            return;
+        };
+        if entry.link_target.is_none() {
+            self.toasts
+                .warn(format!("'{}' is not a symlink", entry.name));
+            return;
+        }
+        let target = match entry.path.canonicalize() {
+            Ok(target) => target,
+            Err(e) => {
+                self.toasts.error(format!("Broken link: {}", e));
+                return;
+            }
+        };
+        let Some(parent) = target.parent() else {
+            // ルートへのリンクはそのまま移動
+            self.browser.change_dir(&target);
+            self.list_state.select(Some(self.browser.selected_index));
+            self.update_preview();
+            return;
+        };
+
+        if target
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+        {
+            self.browser.show_hidden = true;
+        }
+        self.browser.change_dir(parent);
+        self.browser.select_path(&target);
+        self.list_state.select(Some(self.browser.selected_index));
+        self.update_preview();
+    }
+
     pub fn toggle_hidden(&mut self) {
         self.clear_jump();
         self.browser.toggle_hidden();
@@ -174,484 +1119,3986 @@ impl App {
         self.browser.refresh();
         self.list_state.select(Some(self.browser.selected_index));
         self.update_preview();
-        self.status_message = Some("Reloaded".to_string());
+        self.toasts.info("Reloaded");
     }
 
-    pub fn open_in_editor(&mut self) {
-        if let Some(entry) = self.browser.selected_entry()
-            && !entry.is_dir
-        {
-            match self.editor.open(&entry.path) {
-                Ok(_) => {
-                    self.needs_redraw = true;
-                }
-                Err(e) => {
-                    self.status_message = Some(e);
-                    self.needs_redraw = true;
-                }
-            }
+    /// 操作対象のパス（マークがあればマークしたもの、なければ選択中のエントリ）
+    pub fn target_paths(&self) -> Vec<PathBuf> {
+        if !self.browser.marked.is_empty() {
+            return self.browser.marked.iter().cloned().collect();
         }
+        self.browser
+            .selected_entry()
+            .map(|entry| vec![entry.path.clone()])
+            .unwrap_or_default()
     }
 
-    pub fn start_search(&mut self) {
+    /// 対象エントリの削除確認を開始
+    pub fn start_delete(&mut self) {
         self.clear_jump();
-        self.input_mode = InputMode::SearchInput;
-        self.search_input.clear();
-        self.search_results.clear();
-        self.search_selected = 0;
-        self.search_list_state.select(Some(0));
-        self.search_dirs_only = false;
+        if self.operation_running() {
+            return;
+        }
+        let paths = self.target_paths();
+        if paths.is_empty() {
+            return;
+        }
+        let target = match paths.as_slice() {
+            [path] => format!("'{}'", display_name(path)),
+            paths => format!("{} entries", paths.len()),
+        };
+        self.pending_delete = paths;
+        self.ask_delete(Subject::Delete, &target);
     }
 
-    pub fn cancel_search(&mut self) {
-        self.input_mode = InputMode::Normal;
-        self.search_input.clear();
-        self.search_results.clear();
-        self.search_dirs_only = false;
-    }
+    /// 削除の確認（ゴミ箱に移すか完全に削除するかで文言を変える）
+    fn ask_delete(&mut self, subject: Subject, target: &str) {
+        let message = if self.config.delete_to_trash {
+            format!("Move {} to trash?", target)
+        } else {
+            format!("Permanently delete {}?", target)
+        };
+        self.ask(Confirm::new(
+            subject,
+            "Delete",
+            message,
+            &[Choice::Yes, Choice::No],
+        ));
+    }
 
-    /// 検索入力をパースしてクエリとオプションを分離
-    /// 戻り値: (query, dirs_only, exact, base_path)
-    fn parse_search_input(&self) -> (String, bool, bool, Option<PathBuf>) {
-        let mut query_parts: Vec<&str> = Vec::new();
-        let mut exact = false;
-        let mut dirs_only = false;
-        let mut base_path: Option<PathBuf> = None;
+    /// 確認ダイアログを開く
+    pub fn ask(&mut self, confirm: Confirm) {
+        self.confirm = Some(confirm);
+        self.input_mode = InputMode::Confirm;
+    }
 
-        let parts: Vec<&str> = self.search_input.split_whitespace().collect();
-        let mut i = 0;
-        while i < parts.len() {
-            match parts[i] {
-                "-e" | "--exact" => exact = true,
-                "-d" | "--dir" => dirs_only = true,
-                "-b" | "--base" => {
-                    if i + 1 < parts.len() {
-                        i += 1;
-                        let path_str = parts[i];
-                        let expanded = if let Some(stripped) = path_str.strip_prefix("~/") {
-                            if let Ok(home) = std::env::var("HOME") {
-                                PathBuf::from(home).join(stripped)
-                            } else {
-                                PathBuf::from(path_str)
-                            }
-                        } else if path_str == "~" {
-                            if let Ok(home) = std::env::var("HOME") {
-                                PathBuf::from(home)
-                            } else {
-                                PathBuf::from(path_str)
-                            }
-                        } else {
-                            PathBuf::from(path_str)
-                        };
-                        base_path = Some(expanded);
-                    }
-                }
-                _ => query_parts.push(parts[i]),
+    /// 確認ダイアログでのキー操作。答えが決まれば問い合わせ元の処理に渡す
+    pub fn confirm_key(&mut self, key: crossterm::event::KeyEvent) {
+        let Some(ref mut dialog) = self.confirm else {
+            return;
+        };
+        let response = dialog.handle_key(key);
+        let subject = dialog.subject;
+        match response {
+            Response::Pending => {}
+            Response::Answer(choice) => self.answer(subject, Some(choice)),
+            Response::Cancel => self.answer(subject, None),
+        }
+    }
+
+    /// `choice` が None ならキャンセル
+    fn answer(&mut self, subject: Subject, choice: Option<Choice>) {
+        self.confirm = None;
+        match (subject, choice) {
+            (Subject::Delete, Some(Choice::Yes)) => self.confirm_delete(),
+            (Subject::Delete, _) => self.cancel_delete(),
+            (Subject::UsageDelete, Some(Choice::Yes)) => self.confirm_usage_delete(),
+            (Subject::UsageDelete, _) => self.cancel_usage_delete(),
+            (Subject::Purge, Some(Choice::Yes)) => self.confirm_purge(),
+            (Subject::Purge, _) => self.cancel_purge(),
+            (Subject::PasteConflict, choice) => self.resolve_paste_conflict(match choice {
+                Some(Choice::Yes) => ConflictChoice::Overwrite,
+                Some(Choice::All) => ConflictChoice::OverwriteAll,
+                Some(Choice::KeepBoth) => ConflictChoice::KeepBoth,
+                Some(Choice::No) => ConflictChoice::Skip,
+                None => ConflictChoice::Abort,
+            }),
+        }
+    }
+
+    /// 確認済みの削除をバックグラウンドで開始
+    pub fn confirm_delete(&mut self) {
+        self.confirm = None;
+        self.input_mode = InputMode::Normal;
+        let paths = std::mem::take(&mut self.pending_delete);
+        let to_trash = self.config.delete_to_trash;
+        let job = paths.clone();
+        self.spawn_operation(Operation::Delete { paths, to_trash }, move |tx, cancel| {
+            file_ops::delete_all(job, to_trash, tx, cancel)
+        });
+    }
+
+    pub fn cancel_delete(&mut self) {
+        self.pending_delete.clear();
+        self.confirm = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// 現在の名前を入力済みの状態で名前変更を開始
+    pub fn start_rename(&mut self) {
+        self.clear_jump();
+        if let Some(entry) = self.browser.selected_entry() {
+            self.name_input.set(entry.name.clone());
+            self.input_mode = InputMode::Rename;
+        }
+    }
+
+    /// 入力中のプロンプト（文字入力を受け付けるモードのみ）
+    pub fn active_input(&mut self) -> Option<&mut TextInput> {
+        match self.input_mode {
+            InputMode::SearchInput => Some(&mut self.search_input),
+            InputMode::Rename | InputMode::NewFile | InputMode::NewDir | InputMode::Archive => {
+                Some(&mut self.name_input)
             }
-            i += 1;
+            InputMode::ZoxideInput => Some(&mut self.path_input),
+            InputMode::Command => Some(&mut self.command_input),
+            _ => None,
         }
+    }
 
-        (query_parts.join(" "), dirs_only, exact, base_path)
+    /// 新規ファイル（dir = false）またはディレクトリの作成を開始
+    pub fn start_create(&mut self, dir: bool) {
+        self.clear_jump();
+        self.name_input.clear();
+        self.input_mode = if dir {
+            InputMode::NewDir
+        } else {
+            InputMode::NewFile
+        };
     }
 
-    /// 検索を実行（Enter で確定時）- バックグラウンドで実行開始
-    pub fn execute_search(&mut self) {
-        if self.search_input.is_empty() {
-            self.cancel_search();
+    /// 名前入力を確定（名前変更・新規作成）
+    pub fn confirm_name_input(&mut self) {
+        match self.input_mode {
+            InputMode::Rename => self.confirm_rename(),
+            InputMode::NewFile => self.confirm_create(false),
+            InputMode::NewDir => self.confirm_create(true),
+            InputMode::Archive => self.confirm_archive(),
+            InputMode::ZoxideInput => self.confirm_zoxide(),
+            _ => {}
+        }
+    }
+
+    fn confirm_create(&mut self, dir: bool) {
+        self.input_mode = InputMode::Normal;
+        let name = self.name_input.submit();
+        self.create_entry(name.trim(), dir);
+    }
+
+    fn create_entry(&mut self, name: &str, dir: bool) {
+        match file_ops::create(&self.browser.current_dir, name, dir) {
+            Ok(path) => {
+                self.refresh_other_pane();
+                self.browser.refresh();
+                self.browser.select_path(&path);
+                self.list_state.select(Some(self.browser.selected_index));
+                self.update_preview();
+                self.toasts.info(format!("Created {}", name));
+            }
+            Err(e) => {
+                self.toasts
+                    .error(format!("Failed to create {}: {}", name, e));
+            }
+        }
+    }
+
+    /// 対象エントリをまとめるアーカイブ名の入力を開始
+    pub fn start_archive(&mut self) {
+        self.clear_jump();
+        let paths = self.target_paths();
+        // 1件ならその名前、複数なら現在のディレクトリ名を初期値にする
+        let stem = match paths.as_slice() {
+            [] => return,
+            [path] => display_name(path),
+            _ => display_name(&self.browser.current_dir),
+        };
+        self.name_input.set(format!("{}.zip", stem));
+        self.input_mode = InputMode::Archive;
+    }
+
+    fn confirm_archive(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let name = self.name_input.submit();
+        let name = name.trim();
+        let paths = self.target_paths();
+        if self.operation_running() {
             return;
         }
 
-        // 検索入力をパース
-        let (query, dirs_only, exact, base_path) = self.parse_search_input();
+        let dest = match file_ops::archive_path(&self.browser.current_dir, name) {
+            Ok(dest) => dest,
+            Err(e) => {
+                self.toasts
+                    .error(format!("Failed to create {}: {}", name, e));
+                return;
+            }
+        };
+        // 大きなディレクトリは時間がかかるので、コピーと同じくバックグラウンドで
+        self.browser.clear_marks();
+        let operation = Operation::Archive {
+            dest: dest.clone(),
+            count: paths.len(),
+        };
+        self.spawn_operation(operation, move |tx, cancel| {
+            file_ops::archive(dest, paths, tx, cancel)
+        });
+    }
 
-        if query.is_empty() {
-            self.cancel_search();
+    /// zoxide のキーワード（またはパス）で移動するプロンプトを開始
+    pub fn start_zoxide(&mut self) {
+        self.clear_jump();
+        self.path_input.clear();
+        self.input_mode = InputMode::ZoxideInput;
+    }
+
+    fn confirm_zoxide(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let input = self.path_input.submit();
+        let input = input.trim();
+        if input.is_empty() {
             return;
         }
 
-        // UI表示用に状態を更新
-        self.search_dirs_only = dirs_only;
-        self.base_dir = base_path.unwrap_or_else(|| self.browser.current_dir.clone());
+        // 実在するパスならそのまま移動し、それ以外は zoxide に問い合わせる
+        let cwd = self.browser.current_dir.clone();
+        let dir = match zoxide::as_path(input, &cwd) {
+            Some(dir) => dir,
+            None => match zoxide::query(input, &cwd) {
+                Ok(dir) => dir,
+                Err(e) => {
+                    self.toasts.error(e);
+                    return;
+                }
+            },
+        };
+        self.browser.change_dir(&dir);
+        self.list_state.select(Some(self.browser.selected_index));
+        self.update_preview();
+    }
 
-        // 検索をバックグラウンドスレッドで実行
-        let (tx, rx): (Sender<Vec<SearchResult>>, Receiver<Vec<SearchResult>>) = mpsc::channel();
-        let search_base = self.base_dir.clone();
+    fn confirm_rename(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let new_name = self.name_input.submit();
+        let Some(path) = self.browser.selected_entry().map(|e| e.path.clone()) else {
+            return;
+        };
 
-        thread::spawn(move || {
-            let mut searcher = FileSearcher::new();
-            let results = searcher.search(&search_base, &query, 100, dirs_only, exact);
-            let _ = tx.send(results);
-        });
+        match file_ops::rename(&path, new_name.trim()) {
+            Ok(new_path) => {
+                self.refresh_other_pane();
+                self.browser.refresh();
+                self.browser.select_path(&new_path);
+                self.list_state.select(Some(self.browser.selected_index));
+                self.update_preview();
+                self.toasts.info(format!("Renamed to {}", new_name.trim()));
+            }
+            Err(e) => {
+                self.toasts.error(format!("Failed to rename: {}", e));
+            }
+        }
+    }
 
-        self.search_receiver = Some(rx);
-        self.spinner_frame = 0;
-        self.input_mode = InputMode::Searching;
+    pub fn cancel_name_input(&mut self) {
+        self.name_input.clear();
+        self.path_input.clear();
+        self.input_mode = InputMode::Normal;
     }
 
-    /// 検索結果をポーリング（main loopから呼ばれる）
-    pub fn poll_search(&mut self) -> bool {
-        if let Some(ref rx) = self.search_receiver {
-            match rx.try_recv() {
-                Ok(results) => {
-                    self.search_results = results;
-                    self.search_selected = 0;
-                    self.search_list_state.select(Some(0));
-                    self.search_receiver = None;
+    /// : のコマンド入力を開始
+    pub fn start_command(&mut self) {
+        self.clear_jump();
+        self.command_input.clear();
+        self.command_completion = None;
+        self.input_mode = InputMode::Command;
+    }
 
-                    if self.search_results.is_empty() {
-                        self.status_message = Some("No results found".to_string());
-                        self.input_mode = InputMode::Normal;
-                    } else {
-                        self.input_mode = InputMode::SearchResult;
-                    }
-                    return true;
+    /// コマンドラインでの編集キー。空の状態で Backspace を押すとコマンド
+    /// 入力をやめる（vim と同じ）
+    pub fn command_key(&mut self, key: crossterm::event::KeyEvent) {
+        if key.code == crossterm::event::KeyCode::Backspace && self.command_input.is_empty() {
+            self.cancel_command();
+        } else if self.command_input.handle_key(key) {
+            self.command_completion = None;
+        }
+    }
+
+    pub fn cancel_command(&mut self) {
+        self.command_input.clear();
+        self.command_completion = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Tab / Shift+Tab: replace the word being typed with the next (or
+    /// previous) candidate
+    pub fn complete_command(&mut self, forward: bool) {
+        let completion = match self.command_completion.take() {
+            Some(mut completion) => {
+                let len = completion.words.len();
+                completion.index = if forward {
+                    (completion.index + 1) % len
+                } else {
+                    (completion.index + len - 1) % len
+                };
+                completion
+            }
+            None => {
+                let (start, words) =
+                    palette::complete(self.command_input.text(), &self.browser.current_dir);
+                if words.is_empty() {
+                    return;
                 }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // まだ検索中
-                    self.spinner_frame = (self.spinner_frame + 1) % 10;
+                let index = if forward { 0 } else { words.len() - 1 };
+                Completion {
+                    start,
+                    words,
+                    index,
                 }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    // スレッドが終了（エラー）
-                    self.search_receiver = None;
-                    self.status_message = Some("Search failed".to_string());
-                    self.input_mode = InputMode::Normal;
-                    return true;
+            }
+        };
+        let mut line = self.command_input.text()[..completion.start].to_string();
+        line.push_str(&completion.words[completion.index]);
+        if completion.words.len() > 1 {
+            self.command_completion = Some(completion);
+        } else if completion.start == 0 {
+            // コマンド名が確定したら引数を続けて入力できるようにする
+            line.push(' ');
+        }
+        self.command_input.set(line);
+    }
+
+    pub fn confirm_command(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.command_completion = None;
+        let line = self.command_input.submit();
+        if line.trim().is_empty() {
+            return;
+        }
+        match palette::parse(&line) {
+            Ok(command) => self.run_command(command),
+            Err(e) => self.toasts.error(e),
+        }
+    }
+
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::Cd(path) => match zoxide::as_path(&path, &self.browser.current_dir) {
+                Some(dir) => {
+                    self.browser.change_dir(&dir);
+                    self.list_state.select(Some(self.browser.selected_index));
+                    self.update_preview();
                 }
+                None => self.toasts.warn(format!("Not a directory: {}", path)),
+            },
+            Command::Mkdir(name) => self.create_entry(&name, true),
+            Command::Touch(name) => self.create_entry(&name, false),
+            Command::Sort(sort) => {
+                self.browser.set_sort(sort, self.browser.sort_reverse);
+                self.list_state.select(Some(self.browser.selected_index));
+                self.toasts.info(format!("Sort: {}", sort.label()));
             }
+            Command::Theme(name) => self.set_theme(&name),
+            Command::Set { option, value } => self.set_option(option, value),
+            Command::Reload => self.reload(),
+            Command::Help => self.show_help(),
+            Command::Quit => self.quit(),
         }
-        false
     }
 
-    /// スピナー文字を取得
-    pub fn spinner_char(&self) -> char {
-        const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
-        SPINNER[self.spinner_frame % SPINNER.len()]
+    /// :theme でこのセッションの配色を変える（設定ファイルは書き換えない）
+    fn set_theme(&mut self, name: &str) {
+        let mut config = self.config.clone();
+        if let Some(theme) = UiTheme::from_name(name) {
+            // プリセットはプレビューの配色ごと切り替える
+            config.ui_theme = theme;
+            config.theme = None;
+        } else if Previewer::theme_names().iter().any(|theme| theme == name) {
+            config.theme = Some(name.to_string());
+        } else {
+            self.toasts.warn(format!("Unknown theme: {}", name));
+            return;
+        }
+        self.apply_config(config);
+        self.toasts.info(format!("Theme: {}", name));
     }
 
-    /// 検索結果から選択確定
-    pub fn confirm_search_result(&mut self) {
-        if let Some(result) = self.search_results.get(self.search_selected) {
-            let path = result.path.clone();
-            let is_dir = result.is_dir;
+    /// :set の切り替え（`value` が None なら反転）
+    fn set_option(&mut self, option: SetOption, value: Option<bool>) {
+        let current = match option {
+            SetOption::ShowHidden => self.browser.show_hidden,
+            SetOption::Details => self.show_details,
+            SetOption::PreviewPane => self.show_preview_pane,
+            SetOption::DualPane => self.other_browser.is_some(),
+            SetOption::Tree => self.browser.tree_mode,
+            SetOption::SortReverse => self.browser.sort_reverse,
+        };
+        let wanted = value.unwrap_or(!current);
+        if wanted != current {
+            match option {
+                SetOption::ShowHidden => self.toggle_hidden(),
+                SetOption::Details => self.toggle_details(),
+                SetOption::PreviewPane => self.toggle_preview_pane(),
+                SetOption::DualPane => self.toggle_dual_pane(),
+                SetOption::Tree => self.toggle_tree(),
+                SetOption::SortReverse => self.toggle_sort_reverse(),
+            }
+        }
+        let state = if wanted { "on" } else { "off" };
+        self.toasts.info(format!("{}: {}", option.name(), state));
+    }
 
-            self.input_mode = InputMode::Normal;
-            self.search_input.clear();
-            self.search_results.clear();
+    /// 選択中のエントリのパーミッション編集を開始
+    pub fn start_chmod(&mut self) {
+        self.clear_jump();
+        let Some(entry) = self.browser.selected_entry() else {
+            return;
+        };
+        let Some(mode) = entry.mode else {
+            self.toasts.warn("Permissions are not supported here");
+            return;
+        };
+        self.permission_edit = Some(PermissionEdit {
+            path: entry.path.clone(),
+            mode: mode & 0o7777,
+            cursor: 0,
+            input: String::new(),
+        });
+        self.input_mode = InputMode::Chmod;
+    }
 
-            // 隠しファイル/ディレクトリの場合は表示を有効にする
-            let is_hidden = path
-                .file_name()
-                .map(|n| n.to_string_lossy().starts_with('.'))
-                .unwrap_or(false);
-            let show_hidden = self.config.show_hidden || is_hidden;
+    pub fn confirm_chmod(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let Some(edit) = self.permission_edit.take() else {
+            return;
+        };
+        if !edit.input.is_empty() && edit.input.len() < 3 {
+            self.toasts.error(format!("Invalid mode: {}", edit.input));
+            return;
+        }
 
-            if is_dir {
-                self.browser = FileBrowser::new(&path, show_hidden);
-                self.list_state.select(Some(0));
-                self.update_preview();
-            } else {
-                if let Some(parent) = path.parent() {
-                    self.browser = FileBrowser::new(parent, show_hidden);
-                    if let Some(file_name) = path.file_name() {
-                        let name = file_name.to_string_lossy().to_string();
-                        if let Some(idx) = self.browser.entries.iter().position(|e| e.name == name)
-                        {
-                            self.browser.selected_index = idx;
-                            self.list_state.select(Some(idx));
-                        }
-                    }
-                }
-                self.update_preview();
-                self.input_mode = InputMode::Preview;
+        let name = display_name(&edit.path);
+        match file_ops::set_mode(&edit.path, edit.mode) {
+            Ok(()) => {
+                self.browser.refresh();
+                self.refresh_other_pane();
+                self.toasts
+                    .info(format!("Changed mode of {} to {:o}", name, edit.mode));
             }
-        } else {
-            self.cancel_search();
+            Err(e) => {
+                self.toasts
+                    .error(format!("Failed to change mode of {}: {}", name, e));
+            }
+        }
+    }
+
+    pub fn chmod_toggle(&mut self) {
+        if let Some(ref mut edit) = self.permission_edit {
+            edit.toggle();
+        }
+    }
+
+    pub fn chmod_move(&mut self, rows: isize, cols: isize) {
+        if let Some(ref mut edit) = self.permission_edit {
+            edit.move_cursor(rows, cols);
         }
     }
 
-    pub fn search_input_char(&mut self, c: char) {
-        // Limit query length to prevent pathological input (same as CLI: 1000 chars)
-        if self.search_input.len() < 1000 {
-            self.search_input.push(c);
+    pub fn chmod_digit(&mut self, c: char) {
+        if let Some(ref mut edit) = self.permission_edit {
+            edit.push_digit(c);
+        }
+    }
+
+    pub fn chmod_backspace(&mut self) {
+        if let Some(ref mut edit) = self.permission_edit {
+            edit.backspace();
+        }
+    }
+
+    pub fn cancel_chmod(&mut self) {
+        self.permission_edit = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// 対象エントリをコピー（TransferKind::Copy）または切り取り用に記憶
+    pub fn yank_to_register(&mut self, kind: TransferKind) {
+        self.clear_jump();
+        let paths = self.target_paths();
+        let verb = match kind {
+            TransferKind::Copy => "Copy",
+            TransferKind::Move => "Cut",
+        };
+        self.toasts.info(match paths.as_slice() {
+            [] => return,
+            [path] => format!("{}: {} (p to paste)", verb, display_name(path)),
+            _ => format!("{}: {} entries (p to paste)", verb, paths.len()),
+        });
+        self.browser.clear_marks();
+        self.register = Some(Register { kind, paths });
+    }
+
+    /// レジスタの内容を現在のディレクトリに貼り付け
+    pub fn paste(&mut self) {
+        self.clear_jump();
+        if self.operation_running() {
+            return;
+        }
+        let Some(register) = self.register.clone() else {
+            self.toasts.warn("Nothing to paste");
+            return;
+        };
+        let dir = self.browser.current_dir.clone();
+        self.start_paste(register.kind, register.paths, dir);
+    }
+
+    /// 対象エントリをもう一方のペインのディレクトリへコピー・移動
+    pub fn transfer_to_other_pane(&mut self, kind: TransferKind) {
+        self.clear_jump();
+        let Some(dir) = self.other_browser.as_ref().map(|b| b.current_dir.clone()) else {
+            self.toasts.warn("Dual-pane mode is off");
+            return;
+        };
+        if self.operation_running() {
+            return;
+        }
+        let paths = self.target_paths();
+        if paths.is_empty() {
+            return;
+        }
+        self.browser.clear_marks();
+        self.start_paste(kind, paths, dir);
+    }
+
+    /// 貼り付け計画を作り、衝突がなければ転送を開始
+    fn start_paste(&mut self, kind: TransferKind, paths: Vec<PathBuf>, dir: PathBuf) {
+        self.paste_kind = kind;
+        self.paste_ready.clear();
+        self.paste_conflicts.clear();
+        for source in paths {
+            let Some(name) = source.file_name() else {
+                continue;
+            };
+            let dest = dir.join(name);
+            // 同じ場所への移動は何もしない
+            if kind == TransferKind::Move && dest == source {
+                continue;
+            }
+            if dest.starts_with(&source) && dest != source {
+                self.toasts
+                    .warn(format!("Cannot paste {} into itself", source.display()));
+                return;
+            }
+
+            let item = PasteItem {
+                source,
+                dest,
+                overwrite: false,
+            };
+            if std::fs::symlink_metadata(&item.dest).is_ok() {
+                self.paste_conflicts.push_back(item);
+            } else {
+                self.paste_ready.push(item);
+            }
+        }
+        self.paste_dir = dir;
+        self.next_paste_conflict();
+    }
+
+    /// 先頭の衝突を解決して次へ
+    pub fn resolve_paste_conflict(&mut self, choice: ConflictChoice) {
+        let Some(mut item) = self.paste_conflicts.pop_front() else {
+            return;
+        };
+
+        match choice {
+            // 自分自身は上書きできないのでスキップ扱い
+            ConflictChoice::Overwrite if item.dest != item.source => {
+                item.overwrite = true;
+                self.paste_ready.push(item);
+            }
+            ConflictChoice::OverwriteAll => {
+                // 残りの衝突もまとめて上書き（自分自身への貼り付けは除く）
+                for mut item in std::iter::once(item).chain(self.paste_conflicts.drain(..)) {
+                    if item.dest != item.source {
+                        item.overwrite = true;
+                        self.paste_ready.push(item);
+                    }
+                }
+            }
+            ConflictChoice::Overwrite | ConflictChoice::Skip => {}
+            ConflictChoice::KeepBoth => {
+                let name = item.dest.file_name().unwrap_or_default().to_string_lossy();
+                item.dest = file_ops::unique_path(&self.paste_dir, &name);
+                self.paste_ready.push(item);
+            }
+            ConflictChoice::Abort => {
+                self.paste_ready.clear();
+                self.paste_conflicts.clear();
+                self.confirm = None;
+                self.input_mode = InputMode::Normal;
+                self.toasts.info("Paste cancelled");
+                return;
+            }
+        }
+        self.next_paste_conflict();
+    }
+
+    fn next_paste_conflict(&mut self) {
+        if let Some(item) = self.paste_conflicts.front() {
+            let mut message = format!("'{}' already exists. Overwrite?", display_name(&item.dest));
+            let remaining = self.paste_conflicts.len() - 1;
+            if remaining > 0 {
+                message.push_str(&format!(" ({} more conflicts)", remaining));
+            }
+            self.ask(Confirm::new(
+                Subject::PasteConflict,
+                "Paste",
+                message,
+                &[Choice::Yes, Choice::No, Choice::All, Choice::KeepBoth],
+            ));
+            return;
+        }
+        self.confirm = None;
+        self.input_mode = InputMode::Normal;
+
+        let items = std::mem::take(&mut self.paste_ready);
+        if items.is_empty() {
+            self.toasts.warn("Nothing to paste");
+            return;
+        }
+
+        // コピー・移動をバックグラウンドスレッドで実行
+        let kind = self.paste_kind;
+        let count = items.len();
+        self.spawn_operation(Operation::Paste { kind, count }, move |tx, cancel| {
+            file_ops::transfer(kind, items, tx, cancel)
+        });
+    }
+
+    /// 実行中のコピー・移動・削除・圧縮があれば知らせて true
+    fn operation_running(&mut self) -> bool {
+        if self.transfer_receiver.is_none() {
+            return false;
+        }
+        self.toasts
+            .warn("Another copy, move, delete or archive is still running (X to cancel)");
+        true
+    }
+
+    /// コピー・移動・削除・圧縮をバックグラウンドスレッドで開始
+    fn spawn_operation(
+        &mut self,
+        operation: Operation,
+        run: impl FnOnce(Sender<TransferEvent>, &AtomicBool) + Send + 'static,
+    ) {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        thread::spawn(move || run(tx, &flag));
+        self.transfer_receiver = Some(rx);
+        self.transfer_progress = Some(TransferProgress::new(operation.label(), Instant::now()));
+        self.transfer_cancel = cancel;
+        self.operation = Some(operation);
+    }
+
+    /// 実行中の操作を中断（済んだ分はそのまま残る）
+    pub fn cancel_operation(&mut self) {
+        if self.transfer_receiver.is_none() {
+            self.toasts.warn("Nothing to cancel");
+            return;
+        }
+        self.transfer_cancel.store(true, Ordering::Relaxed);
+        self.toasts.info("Cancelling...");
+    }
+
+    /// コピー・移動・削除・圧縮の進捗をポーリング（main loopから呼ばれる）
+    pub fn poll_transfer(&mut self) -> bool {
+        let Some(ref rx) = self.transfer_receiver else {
+            return false;
+        };
+
+        let (done, errors) = loop {
+            match rx.try_recv() {
+                Ok(TransferEvent::Progress {
+                    done,
+                    total,
+                    current,
+                }) => {
+                    if let Some(ref mut progress) = self.transfer_progress {
+                        progress.done = done;
+                        progress.total = total;
+                        progress.current = current;
+                    }
+                }
+                Ok(TransferEvent::Finished { done, errors }) => break (done, errors),
+                Err(mpsc::TryRecvError::Empty) => return false,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    break (0, vec!["Transfer failed".to_string()]);
+                }
+            }
+        };
+
+        self.transfer_receiver = None;
+        self.transfer_progress = None;
+        let cancelled = self.transfer_cancel.load(Ordering::Relaxed);
+        // 1件だけ削除したときはパスを表示する
+        let mut single = None;
+        let mut created = None;
+        let (verb, total) = match self.operation.take() {
+            Some(Operation::Delete { paths, to_trash }) => {
+                let verb = if to_trash { "Trashed" } else { "Deleted" };
+                if let [path] = paths.as_slice() {
+                    single = Some(path.clone());
+                }
+                (verb, paths.len())
+            }
+            Some(Operation::Paste { kind, count }) => {
+                // 移動した後はレジスタから消えたものを外す（失敗したものは残す）
+                if kind == TransferKind::Move
+                    && let Some(ref mut register) = self.register
+                {
+                    register
+                        .paths
+                        .retain(|path| std::fs::symlink_metadata(path).is_ok());
+                    if register.paths.is_empty() {
+                        self.register = None;
+                    }
+                }
+                let verb = match kind {
+                    TransferKind::Copy => "Copied",
+                    TransferKind::Move => "Moved",
+                };
+                (verb, count)
+            }
+            Some(Operation::Archive { dest, count }) => {
+                if done == 1 {
+                    created = Some(dest);
+                }
+                ("Archived", count)
+            }
+            None => ("Finished", done + errors.len()),
+        };
+        let mut message = match (single, &created) {
+            (Some(path), _) if done == 1 => format!("{}: {}", verb, path.display()),
+            (_, Some(dest)) => format!("{} {} entries into {}", verb, total, display_name(dest)),
+            _ => summarize(verb, done, total, &errors),
+        };
+        if cancelled {
+            message.push_str(" (cancelled)");
+        }
+        let level = match result_level(&errors) {
+            Level::Info if cancelled => Level::Warn,
+            level => level,
+        };
+        self.toasts.push(level, message);
+
+        self.refresh_other_pane();
+        self.browser.refresh();
+        if let Some(ref dest) = created {
+            self.browser.select_path(dest);
+        }
+        self.list_state.select(Some(self.browser.selected_index));
+        self.update_preview();
+        true
+    }
+
+    /// 移動したディレクトリを zoxide に記録（main loopから呼ばれる）
+    pub fn record_visit(&mut self) {
+        if self.browser.current_dir == self.last_visited {
+            return;
+        }
+        self.last_visited = self.browser.current_dir.clone();
+        if self.config.zoxide {
+            zoxide::add(&self.last_visited);
+        }
+    }
+
+    /// config.toml か .vfv.toml が保存されたら読み込み直す（main loopから呼ばれる）
+    pub fn poll_config(&mut self) -> bool {
+        // すべての監視のイベントを読み捨てるため any は使わない
+        let changed = self
+            .config_watchers
+            .iter()
+            .fold(false, |changed, watcher| watcher.has_changed() | changed);
+        if !changed {
+            return false;
+        }
+        match Config::load_with_notices() {
+            Ok((config, notices)) => {
+                self.toasts.info("Config reloaded");
+                for notice in notices {
+                    self.toasts.warn(notice);
+                }
+                self.apply_config(config);
+            }
+            Err(e) => {
+                self.toasts.error(format!("Config not reloaded: {}", e));
+            }
+        }
+        // 読み込み時の警告の出力を消す
+        self.needs_redraw = true;
+        true
+    }
+
+    /// 読み込み直した設定を反映（ソート順や隠しファイルなどの切り替え状態は保つ）
+    pub fn apply_config(&mut self, config: Config) {
+        if config.syntax_theme() != self.config.syntax_theme() {
+            let show_whitespace = self.previewer.show_whitespace;
+            self.previewer = Previewer::new(
+                config.syntax_theme(),
+                config.preview_max_lines,
+                config.tab_width,
+            );
+            self.previewer.show_whitespace = show_whitespace;
+        }
+        self.previewer.max_lines = config.preview_max_lines;
+        self.previewer.tab_width = config.tab_width;
+        self.previewer.max_bytes = config.preview_max_bytes;
+        self.previewer.external_previewers = config.previewers.clone();
+        self.external_cache.clear();
+        self.editor = Editor::new(&config);
+
+        let (keymap, key_errors) = Keymap::new(&config.keys, &config.commands);
+        self.keymap = keymap;
+        if !key_errors.is_empty() {
+            self.toasts.warn(format!(
+                "Config [keys]/[commands]: {}",
+                key_errors.join(", ")
+            ));
+        }
+
+        let selected = self.browser.selected_entry().map(|e| e.path.clone());
+        self.browser.natural_sort = config.natural_sort;
+        if let Err(e) = self.browser.set_ignore(&config.browser_ignore) {
+            self.toasts
+                .error(format!("Invalid browser_ignore pattern: {}", e));
+        }
+        self.browser
+            .set_sort(self.browser.sort, self.browser.sort_reverse);
+        if let Some(ref path) = selected {
+            self.browser.select_path(path);
+        }
+        self.list_state.select(Some(self.browser.selected_index));
+        if let Some(ref mut other) = self.other_browser {
+            other.natural_sort = self.browser.natural_sort;
+            other.ignore = self.browser.ignore.clone();
+            other.set_sort(other.sort, other.sort_reverse);
+            self.other_list_state.select(Some(other.selected_index));
+        }
+
+        // 設定を変えたときだけ表示を切り替える（v で切り替えた状態は残す）
+        if config.preview_pane != self.config.preview_pane {
+            self.show_preview_pane = config.preview_pane;
+        }
+        if config.preview_pane_ratio != self.config.preview_pane_ratio {
+            self.preview_ratio = clamp_split(config.preview_pane_ratio);
+        }
+        if config.dual_pane_ratio != self.config.dual_pane_ratio {
+            self.dual_ratio = clamp_split(config.dual_pane_ratio);
+        }
+        self.config = config;
+        self.refresh_preview();
+    }
+
+    /// 詳細表示中のディレクトリサイズを受け取り、未計測のものがあれば
+    /// 計測を開始（main loopから呼ばれる）
+    pub fn poll_dir_sizes(&mut self) -> bool {
+        if !self.show_details {
+            return false;
+        }
+        // ディレクトリを移動したら計測中のものは捨てる
+        if self.dir_sizes_for != self.browser.current_dir {
+            self.dir_sizes_for = self.browser.current_dir.clone();
+            self.dir_sizes.clear();
+            self.stop_dir_sizes();
+        }
+
+        let mut received = false;
+        if let Some(ref rx) = self.dir_size_receiver {
+            loop {
+                match rx.try_recv() {
+                    Ok((path, size)) => {
+                        self.dir_sizes.insert(path, size);
+                        received = true;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        self.dir_size_receiver = None;
+                        break;
+                    }
+                }
+            }
+        }
+
+        if self.dir_size_receiver.is_none() {
+            let stale = std::mem::take(&mut self.dir_sizes_stale);
+            let missing: Vec<PathBuf> = self
+                .browser
+                .entries
+                .iter()
+                .filter(|e| e.is_dir && !e.is_broken_link)
+                .filter(|e| stale || !self.dir_sizes.contains_key(&e.path))
+                .map(|e| e.path.clone())
+                .collect();
+            if !missing.is_empty() {
+                let (tx, rx) = mpsc::channel();
+                let cancel = Arc::new(AtomicBool::new(false));
+                let flag = Arc::clone(&cancel);
+                thread::spawn(move || disk_usage::measure(missing, tx, &flag));
+                self.dir_size_receiver = Some(rx);
+                self.dir_size_cancel = cancel;
+            }
+        }
+        received
+    }
+
+    /// 計測中のディレクトリサイズは捨てて、走査も止める
+    fn stop_dir_sizes(&mut self) {
+        self.dir_size_cancel.store(true, Ordering::Relaxed);
+        self.dir_size_receiver = None;
+    }
+
+    /// ステータスバーを設定しているとき、移動先のディレクトリの git ブランチと
+    /// 空き容量を調べ直す（main loopから呼ばれる）
+    pub fn poll_dir_info(&mut self) -> bool {
+        if self.config.status_bar.is_empty() {
+            return false;
+        }
+        if self.dir_info_for != self.browser.current_dir {
+            self.dir_info_for = self.browser.current_dir.clone();
+            self.dir_info = DirInfo::default();
+            let dir = self.dir_info_for.clone();
+            let (tx, rx) = mpsc::channel();
+            // df はネットワークドライブで待たされることがあるので別スレッドで
+            thread::spawn(move || {
+                let _ = tx.send(DirInfo {
+                    branch: git::branch(&dir),
+                    free_space: disk_usage::available_space(&dir),
+                });
+            });
+            self.dir_info_receiver = Some(rx);
+        }
+        let Some(info) = self
+            .dir_info_receiver
+            .as_ref()
+            .and_then(|rx| rx.try_recv().ok())
+        else {
+            return false;
+        };
+        self.dir_info = info;
+        self.dir_info_receiver = None;
+        true
+    }
+
+    /// 監視中のディレクトリに変化があれば一覧を更新（main loopから呼ばれる）
+    pub fn poll_watcher(&mut self) -> bool {
+        let Some(ref mut watcher) = self.watcher else {
+            return false;
+        };
+        // ディレクトリを移動していたら監視先を切り替える
+        watcher.watch(&self.browser.current_dir);
+        if !watcher.has_changes() {
+            return false;
+        }
+
+        // 選択中のエントリを維持して再読み込み
+        let selected = self.browser.selected_entry().map(|e| e.path.clone());
+        self.browser.refresh();
+        // 新しい値が届くまでは前のサイズを出しておく
+        let entries = &self.browser.entries;
+        self.dir_sizes
+            .retain(|path, _| entries.iter().any(|e| &e.path == path));
+        self.dir_sizes_stale = true;
+        self.stop_dir_sizes();
+        if let Some(ref path) = selected {
+            self.browser.select_path(path);
+        }
+        self.list_state.select(Some(self.browser.selected_index));
+        if self.browser.selected_entry().map(|e| &e.path) != selected.as_ref() {
+            self.update_preview();
+        }
+        true
+    }
+
+    pub fn open_in_editor(&mut self) {
+        // マークがあればマークしたファイルをまとめて開く
+        if !self.browser.marked.is_empty() {
+            let files: Vec<PathBuf> = self
+                .browser
+                .marked
+                .iter()
+                .filter(|path| path.is_file())
+                .cloned()
+                .collect();
+            if files.is_empty() {
+                self.toasts.warn("No marked files to edit");
+                return;
+            }
+            let file_refs: Vec<&Path> = files.iter().map(PathBuf::as_path).collect();
+            match self.editor.open_all(&file_refs) {
+                Ok(_) => {
+                    for file in &files {
+                        self.record_recent(file);
+                    }
+                }
+                Err(e) => {
+                    self.toasts.error(e);
+                }
+            }
+            self.needs_redraw = true;
+            return;
+        }
+
+        if let Some(entry) = self.browser.selected_entry()
+            && !entry.is_dir
+        {
+            let path = entry.path.clone();
+            if self.open_by_extension(&path) {
+                return;
+            }
+            match self.editor.open(&path, self.preview_position()) {
+                Ok(_) => {
+                    self.record_recent(&path);
+                    self.needs_redraw = true;
+                }
+                Err(e) => {
+                    self.toasts.error(e);
+                    self.needs_redraw = true;
+                }
+            }
+        }
+    }
+
+    /// プレビューから開くときの位置: 範囲選択の先頭か、画面の一番上の行
+    fn preview_position(&self) -> Option<editor::Position> {
+        let index = match self.input_mode {
+            InputMode::Visual => self.visual_range().0,
+            InputMode::Preview | InputMode::GotoLine => self.preview_scroll,
+            _ => return None,
+        };
+        let content = self.preview_content.as_ref()?;
+        if !content.source_lines {
+            return None;
+        }
+        // 省略マーカーなど行番号のない行は開く位置にしない
+        match content.lines.get(index)?.line_number {
+            0 => None,
+            line => Some(editor::Position { line, column: 1 }),
+        }
+    }
+
+    /// 対象エントリの名前をエディタでまとめて編集し、保存した名前に変更する
+    pub fn bulk_rename(&mut self) {
+        self.clear_jump();
+        let paths = self.target_paths();
+        if paths.is_empty() {
+            return;
+        }
+
+        // 1行に1つずつ名前を書いた一時ファイルをエディタで開く
+        let names: Vec<String> = paths.iter().map(|p| display_name(p)).collect();
+        // 推測されにくい名前で作り、閉じたときに消える
+        let buffer = tempfile::Builder::new()
+            .prefix("vfv-rename-")
+            .suffix(".txt")
+            .tempfile()
+            .and_then(|mut buffer| {
+                buffer.write_all((names.join("\n") + "\n").as_bytes())?;
+                Ok(buffer)
+            });
+        let buffer = match buffer {
+            Ok(buffer) => buffer,
+            Err(e) => {
+                self.toasts
+                    .error(format!("Failed to write rename buffer: {}", e));
+                return;
+            }
+        };
+        let edited = self
+            .editor
+            .edit(buffer.path())
+            .and_then(|_| std::fs::read_to_string(buffer.path()).map_err(|e| e.to_string()));
+        drop(buffer);
+        self.needs_redraw = true;
+        let edited = match edited {
+            Ok(content) => content,
+            Err(e) => {
+                self.toasts.error(e);
+                return;
+            }
+        };
+
+        let new_names: Vec<String> = edited.lines().map(str::to_string).collect();
+        let result = file_ops::plan_renames(&paths, &new_names)
+            .and_then(|plan| file_ops::apply_renames(&plan).map(|_| plan.len()));
+        match result {
+            Ok(0) => {
+                self.toasts.warn("No names changed");
+            }
+            Ok(count) => {
+                self.browser.clear_marks();
+                self.refresh_other_pane();
+                self.browser.refresh();
+                self.list_state.select(Some(self.browser.selected_index));
+                self.update_preview();
+                self.toasts.info(format!("Renamed {} entries", count));
+            }
+            Err(e) => {
+                self.toasts.error(format!("Failed to rename: {}", e));
+            }
+        }
+    }
+
+    /// 開いたファイルを履歴に記録（保存の失敗は操作を妨げない）
+    fn record_recent(&mut self, path: &Path) {
+        let _ = self.recent.record(path);
+    }
+
+    /// 最近開いたファイルの一覧を開く
+    pub fn open_recent(&mut self) {
+        self.clear_jump();
+        if self.recent.files.is_empty() {
+            self.toasts.warn("No recently opened files");
+            return;
+        }
+        self.recent_list_state.select(Some(0));
+        self.input_mode = InputMode::Recent;
+    }
+
+    pub fn close_recent(&mut self) {
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// 渡されたパスの一覧を開く（--stdin）
+    pub fn open_path_list(&mut self, paths: Vec<String>) {
+        self.path_list = paths;
+        self.path_filter.clear();
+        self.filter_path_list();
+        self.input_mode = InputMode::PathList;
+    }
+
+    /// 絞り込み文字列でパスを選び直す（スコア順）
+    fn filter_path_list(&mut self) {
+        self.path_matches = FileSearcher::new().rank(&self.path_list, &self.path_filter);
+        self.path_list_state.select(Some(0));
+    }
+
+    pub fn path_filter_char(&mut self, c: char) {
+        // 検索入力と同じく長さを制限する
+        if self.path_filter.len() < 1000 {
+            self.path_filter.push(c);
+            self.filter_path_list();
+        }
+    }
+
+    pub fn path_filter_backspace(&mut self) {
+        if self.path_filter.pop().is_some() {
+            self.filter_path_list();
+        }
+    }
+
+    pub fn path_list_move(&mut self, delta: isize) {
+        let len = self.path_matches.len() as isize;
+        if len == 0 {
+            return;
+        }
+        let current = self.path_list_state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, len - 1);
+        self.path_list_state.select(Some(next as usize));
+    }
+
+    /// 選択中のパス（絞り込み後の一覧での位置から引く）
+    pub fn selected_path_list_entry(&self) -> Option<&str> {
+        let index = self.path_list_state.selected()?;
+        let entry = self.path_matches.get(index)?;
+        self.path_list.get(*entry).map(String::as_str)
+    }
+
+    /// 選択したパスを開く: ファイルはプレビュー（ピッカーでは選択して終了）、
+    /// ディレクトリはブラウザで開く
+    pub fn open_path_list_entry(&mut self) {
+        let Some(path) = self.selected_path_list_entry().map(PathBuf::from) else {
+            return;
+        };
+        if path.is_dir() {
+            self.browser.change_dir(&path);
+            self.list_state.select(Some(0));
+            self.update_preview();
+            self.input_mode = InputMode::Normal;
+            return;
+        }
+        if !path.is_file() {
+            self.toasts
+                .warn(format!("No longer exists: {}", path.display()));
+            return;
+        }
+        if self.picker {
+            self.chosen = vec![path.canonicalize().unwrap_or(path)];
+            self.quit();
+            return;
+        }
+        self.preview_file(&path);
+        self.return_to_path_list = self.input_mode == InputMode::Preview;
+    }
+
+    /// Esc: 絞り込み中なら解除し、そうでなければブラウザに戻る
+    pub fn close_path_list(&mut self) {
+        if !self.path_filter.is_empty() {
+            self.path_filter.clear();
+            self.filter_path_list();
+            return;
+        }
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn recent_move(&mut self, delta: isize) {
+        let len = self.recent.files.len() as isize;
+        if len == 0 {
+            return;
+        }
+        let current = self.recent_list_state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, len - 1);
+        self.recent_list_state.select(Some(next as usize));
+    }
+
+    /// 選択したファイルのディレクトリに移動してプレビューを開く
+    pub fn open_recent_file(&mut self) {
+        let Some(path) = self
+            .recent_list_state
+            .selected()
+            .and_then(|i| self.recent.files.get(i))
+            .cloned()
+        else {
+            return;
+        };
+        if !path.is_file() {
+            self.toasts
+                .warn(format!("No longer exists: {}", path.display()));
+            return;
+        }
+        self.preview_file(&path);
+    }
+
+    /// ファイルのディレクトリに移動し、ファイルを選択してプレビューを開く
+    pub fn preview_file(&mut self, path: &Path) {
+        if let Some(path) = self.reveal(path) {
+            self.record_recent(&path);
+            self.input_mode = InputMode::Preview;
+        }
+    }
+
+    /// path の親ディレクトリに移動して path を選択する（選択できたらそのパスを返す）。
+    /// path がなければ何もしない
+    pub fn reveal(&mut self, path: &Path) -> Option<PathBuf> {
+        let name = path.file_name()?;
+        // リンク切れのシンボリックリンクも一覧にあるので symlink_metadata で確かめる
+        path.symlink_metadata().ok()?;
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        self.clear_jump();
+        self.input_mode = InputMode::Normal;
+        if name.to_string_lossy().starts_with('.') {
+            self.browser.show_hidden = true;
+        }
+        self.browser.change_dir(parent);
+        // 一覧のパスは正規化済みのディレクトリを基準にしている
+        let path = self.browser.current_dir.join(name);
+        let found = self.browser.select_path(&path);
+        self.list_state.select(Some(self.browser.selected_index));
+        self.update_preview();
+        found.then_some(path)
+    }
+
+    /// 現在のディレクトリのディスク使用量の一覧を開く
+    pub fn open_disk_usage(&mut self) {
+        self.clear_jump();
+        let dir = self.browser.current_dir.clone();
+        self.scan_disk_usage(dir);
+        self.input_mode = InputMode::DiskUsage;
+    }
+
+    /// バックグラウンドで dir 直下の各エントリのサイズを計測
+    fn scan_disk_usage(&mut self, dir: PathBuf) {
+        let (tx, rx) = mpsc::channel();
+        let scan_dir = dir.clone();
+        thread::spawn(move || disk_usage::scan(&scan_dir, tx));
+        self.usage_dir = dir;
+        self.usage_reselect = None;
+        self.usage_entries.clear();
+        self.usage_list_state.select(Some(0));
+        self.usage_receiver = Some(rx);
+    }
+
+    /// 計測結果を取り込む（main loopから呼ばれる）。計測が終わったら true
+    pub fn poll_disk_usage(&mut self) -> bool {
+        let Some(ref rx) = self.usage_receiver else {
+            return false;
+        };
+        let mut finished = false;
+        let mut received = false;
+        loop {
+            match rx.try_recv() {
+                Ok(UsageEvent::Entry(entry)) => {
+                    self.usage_entries.push(entry);
+                    received = true;
+                }
+                Ok(UsageEvent::Finished) | Err(mpsc::TryRecvError::Disconnected) => {
+                    finished = true;
+                    break;
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+            }
+        }
+
+        if received {
+            // 選んだエントリがなければ最大のものを選ぶ
+            disk_usage::sort_by_size(&mut self.usage_entries);
+            let index = self
+                .usage_reselect
+                .as_ref()
+                .and_then(|path| self.usage_entries.iter().position(|e| &e.path == path));
+            self.usage_list_state.select(Some(index.unwrap_or(0)));
+        }
+        if finished {
+            self.usage_receiver = None;
+        }
+        finished
+    }
+
+    pub fn selected_usage_entry(&self) -> Option<&UsageEntry> {
+        self.usage_entries.get(self.usage_list_state.selected()?)
+    }
+
+    pub fn usage_move(&mut self, delta: isize) {
+        let len = self.usage_entries.len() as isize;
+        if len == 0 {
+            return;
+        }
+        let current = self.usage_list_state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, len - 1);
+        self.usage_list_state.select(Some(next as usize));
+        // 計測中に並べ替わっても選択を保つ
+        self.usage_reselect = self.selected_usage_entry().map(|e| e.path.clone());
+    }
+
+    /// 選択中のディレクトリに降りる
+    pub fn usage_enter(&mut self) {
+        if let Some(entry) = self.selected_usage_entry()
+            && entry.is_dir
+        {
+            let dir = entry.path.clone();
+            self.scan_disk_usage(dir);
+        }
+    }
+
+    /// 親ディレクトリに戻り、元のディレクトリを選択
+    pub fn usage_parent(&mut self) {
+        let Some(parent) = self.usage_dir.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        let previous = self.usage_dir.clone();
+        self.scan_disk_usage(parent);
+        // 計測結果が届いたら元のディレクトリを選択する
+        self.usage_reselect = Some(previous);
+    }
+
+    pub fn close_disk_usage(&mut self) {
+        self.usage_receiver = None;
+        self.usage_entries.clear();
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn start_usage_delete(&mut self) {
+        if let Some(entry) = self.selected_usage_entry() {
+            let target = format!("'{}'", entry.name);
+            self.pending_delete = vec![entry.path.clone()];
+            self.ask_delete(Subject::UsageDelete, &target);
+        }
+    }
+
+    /// 一覧で選んだエントリを削除し、合計から差し引く
+    pub fn confirm_usage_delete(&mut self) {
+        self.confirm = None;
+        self.input_mode = InputMode::DiskUsage;
+        let Some(path) = self.pending_delete.pop() else {
+            return;
+        };
+        self.pending_delete.clear();
+        let to_trash = self.config.delete_to_trash;
+        match file_ops::delete(&path, to_trash) {
+            Ok(()) => {
+                self.usage_entries.retain(|e| e.path != path);
+                self.usage_move(0);
+                let action = if to_trash { "Trashed" } else { "Deleted" };
+                self.toasts.info(format!("{}: {}", action, path.display()));
+                self.refresh_other_pane();
+                self.browser.refresh();
+                self.list_state.select(Some(self.browser.selected_index));
+                self.update_preview();
+            }
+            Err(e) => {
+                self.toasts
+                    .error(format!("Failed to delete {}: {}", display_name(&path), e));
+            }
+        }
+    }
+
+    pub fn cancel_usage_delete(&mut self) {
+        self.pending_delete.clear();
+        self.confirm = None;
+        self.input_mode = InputMode::DiskUsage;
+    }
+
+    /// ゴミ箱の一覧を開く
+    pub fn open_trash(&mut self) {
+        self.clear_jump();
+        match trash_bin::list() {
+            Ok(entries) => {
+                self.trash_entries = entries;
+                self.trash_list_state.select(Some(0));
+                self.input_mode = InputMode::Trash;
+            }
+            Err(e) => {
+                self.toasts.error(format!("Failed to read trash: {}", e));
+            }
+        }
+    }
+
+    pub fn close_trash(&mut self) {
+        self.trash_entries.clear();
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn trash_move(&mut self, delta: isize) {
+        let len = self.trash_entries.len() as isize;
+        if len == 0 {
+            return;
+        }
+        let current = self.trash_list_state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, len - 1);
+        self.trash_list_state.select(Some(next as usize));
+    }
+
+    fn selected_trash_entry(&self) -> Option<TrashEntry> {
+        let index = self.trash_list_state.selected()?;
+        self.trash_entries.get(index).cloned()
+    }
+
+    /// 復元・削除したエントリを一覧から外す
+    fn remove_selected_trash_entry(&mut self) {
+        let Some(index) = self.trash_list_state.selected() else {
+            return;
+        };
+        if index < self.trash_entries.len() {
+            self.trash_entries.remove(index);
+        }
+        let last = self.trash_entries.len().saturating_sub(1);
+        self.trash_list_state.select(Some(index.min(last)));
+    }
+
+    /// 選択中のエントリを元の場所に戻す
+    pub fn restore_trash_entry(&mut self) {
+        if !trash_bin::can_restore() {
+            self.toasts
+                .warn("Restoring is not supported on this platform");
+            return;
+        }
+        let Some(entry) = self.selected_trash_entry() else {
+            return;
+        };
+        let name = entry.name.clone();
+        match trash_bin::restore(entry) {
+            Ok(()) => {
+                self.remove_selected_trash_entry();
+                self.toasts.info(format!("Restored {}", name));
+                self.browser.refresh();
+                self.refresh_other_pane();
+                self.list_state.select(Some(self.browser.selected_index));
+            }
+            Err(e) => {
+                self.toasts
+                    .error(format!("Failed to restore {}: {}", name, e));
+            }
+        }
+    }
+
+    pub fn start_purge(&mut self) {
+        if let Some(entry) = self.selected_trash_entry() {
+            let message = format!("Permanently delete '{}'?", entry.name);
+            self.ask(Confirm::new(
+                Subject::Purge,
+                "Delete from trash",
+                message,
+                &[Choice::Yes, Choice::No],
+            ));
+        }
+    }
+
+    /// 選択中のエントリをゴミ箱から完全に削除
+    pub fn confirm_purge(&mut self) {
+        self.confirm = None;
+        self.input_mode = InputMode::Trash;
+        let Some(entry) = self.selected_trash_entry() else {
+            return;
+        };
+        let name = entry.name.clone();
+        match trash_bin::purge(entry) {
+            Ok(()) => {
+                self.remove_selected_trash_entry();
+                self.toasts.info(format!("Permanently deleted {}", name));
+            }
+            Err(e) => {
+                self.toasts
+                    .error(format!("Failed to delete {}: {}", name, e));
+            }
+        }
+    }
+
+    pub fn cancel_purge(&mut self) {
+        self.confirm = None;
+        self.input_mode = InputMode::Trash;
+    }
+
+    /// 選択中のファイルを開くプログラムのメニューを表示
+    pub fn start_open_with(&mut self) {
+        self.clear_jump();
+        if self.browser.selected_entry().is_none() {
+            return;
+        }
+        self.open_with = opener::openers(&self.config.openers);
+        if self.open_with.is_empty() {
+            self.toasts.warn("No openers configured");
+            return;
+        }
+        self.open_with_selected = 0;
+        self.input_mode = InputMode::OpenWith;
+    }
+
+    pub fn open_with_move(&mut self, delta: isize) {
+        let len = self.open_with.len() as isize;
+        if len > 0 {
+            self.open_with_selected =
+                (self.open_with_selected as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    /// 番号キーで直接選んで開く
+    pub fn open_with_index(&mut self, index: usize) {
+        if index < self.open_with.len() {
+            self.open_with_selected = index;
+            self.confirm_open_with();
+        }
+    }
+
+    pub fn confirm_open_with(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let openers = std::mem::take(&mut self.open_with);
+        let Some(opener) = openers.get(self.open_with_selected) else {
+            return;
+        };
+        let Some(path) = self.browser.selected_entry().map(|e| e.path.clone()) else {
+            return;
+        };
+        self.run_opener(opener, &path);
+    }
+
+    fn run_opener(&mut self, opener: &Opener, path: &Path) {
+        let Some(mut cmd) = opener::build_command(&opener.command, path) else {
+            self.toasts
+                .error(format!("Empty command for '{}'", opener.name));
+            return;
+        };
+
+        if opener.detach {
+            if let Err(e) = editor::spawn_detached(&mut cmd, &opener.name) {
+                self.toasts.error(e);
+            }
+            return;
+        }
+        if let Err(e) = editor::run_in_terminal(&mut cmd, &opener.name) {
+            self.toasts.error(e);
+        }
+        self.needs_redraw = true;
+        self.refresh_preview();
+    }
+
+    /// 拡張子に割り当てたプログラムがあれば、それで開く
+    fn open_by_extension(&mut self, path: &Path) -> bool {
+        let Some(opener) = opener::for_extension(&self.config.extension_openers, path) else {
+            return false;
+        };
+        self.run_opener(&opener, path);
+        self.record_recent(path);
+        true
+    }
+
+    /// [commands] のコマンドを実行（実行中は TUI を中断する）
+    pub fn run_custom_command(&mut self, template: &str) {
+        self.clear_jump();
+        let path = self
+            .browser
+            .selected_entry()
+            .map(|entry| entry.path.clone());
+        let line = commands::expand(
+            template,
+            path.as_deref(),
+            &self.browser.current_dir,
+            &self.target_paths(),
+        );
+        let mut cmd = commands::shell_command(&line);
+        cmd.current_dir(&self.browser.current_dir);
+
+        match editor::run_in_terminal(&mut cmd, "command") {
+            Ok(status) if !status.success() => {
+                self.toasts
+                    .error(format!("Command failed ({}): {}", status, line));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                self.toasts.error(e);
+            }
+        }
+        self.needs_redraw = true;
+        // コマンドでファイルが変わっているかもしれない
+        let selected = self.browser.selected_entry().map(|e| e.path.clone());
+        self.browser.refresh();
+        if let Some(ref path) = selected {
+            self.browser.select_path(path);
+        }
+        self.list_state.select(Some(self.browser.selected_index));
+        self.update_preview();
+    }
+
+    pub fn cancel_open_with(&mut self) {
+        self.open_with.clear();
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// ホームやマウント済みのファイルシステムへの移動メニューを表示
+    pub fn open_places(&mut self) {
+        self.clear_jump();
+        self.places = places::list();
+        if self.places.is_empty() {
+            self.toasts.warn("No mounted filesystems found");
+            return;
+        }
+        // 現在のディレクトリを含む場所を初期選択にする
+        self.places_selected = self
+            .places
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| self.browser.current_dir.starts_with(&p.path))
+            .max_by_key(|(_, p)| p.path.components().count())
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        self.input_mode = InputMode::Places;
+    }
+
+    pub fn places_move(&mut self, delta: isize) {
+        let len = self.places.len() as isize;
+        if len > 0 {
+            self.places_selected = (self.places_selected as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    /// 番号キーで直接選んで移動
+    pub fn places_index(&mut self, index: usize) {
+        if index < self.places.len() {
+            self.places_selected = index;
+            self.confirm_places();
+        }
+    }
+
+    pub fn confirm_places(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let places = std::mem::take(&mut self.places);
+        let Some(place) = places.get(self.places_selected) else {
+            return;
+        };
+        if !place.path.is_dir() {
+            self.toasts
+                .warn(format!("Not available: {}", place.path.display()));
+            return;
+        }
+        self.browser.change_dir(&place.path);
+        self.list_state.select(Some(self.browser.selected_index));
+        self.update_preview();
+    }
+
+    pub fn cancel_places(&mut self) {
+        self.places.clear();
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn start_search(&mut self) {
+        self.clear_jump();
+        self.input_mode = InputMode::SearchInput;
+        self.search_input.clear();
+        self.search_results.clear();
+        self.search_selected = 0;
+        self.search_list_state.select(Some(0));
+    }
+
+    pub fn cancel_search(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.search_input.clear();
+        self.search_results.clear();
+    }
+
+    /// 検索入力をパースしてクエリとオプションを分離
+    /// オプションは記憶済みのフラグに入力されたフラグを加えたもの
+    /// 戻り値: (query, options, base_path)
+    fn parse_search_input(&self) -> (String, SearchOptions, Option<PathBuf>) {
+        let mut query_parts: Vec<&str> = Vec::new();
+        let mut options = self.search_options;
+        let mut base_path: Option<PathBuf> = None;
+
+        let parts: Vec<&str> = self.search_input.text().split_whitespace().collect();
+        let mut i = 0;
+        while i < parts.len() {
+            match parts[i] {
+                "-e" | "--exact" => options.exact = true,
+                "-d" | "--dir" => options.dirs_only = true,
+                "-H" | "--hidden" => options.hidden = true,
+                "-I" | "--no-ignore" => options.no_ignore = true,
+                "-b" | "--base" => {
+                    if i + 1 < parts.len() {
+                        i += 1;
+                        let path_str = parts[i];
+                        let expanded = if let Some(stripped) = path_str.strip_prefix("~/") {
+                            if let Ok(home) = std::env::var("HOME") {
+                                PathBuf::from(home).join(stripped)
+                            } else {
+                                PathBuf::from(path_str)
+                            }
+                        } else if path_str == "~" {
+                            if let Ok(home) = std::env::var("HOME") {
+                                PathBuf::from(home)
+                            } else {
+                                PathBuf::from(path_str)
+                            }
+                        } else {
+                            PathBuf::from(path_str)
+                        };
+                        base_path = Some(expanded);
+                    }
+                }
+                _ => query_parts.push(parts[i]),
+            }
+            i += 1;
+        }
+
+        (query_parts.join(" "), options, base_path)
+    }
+
+    /// 検索を実行（Enter で確定時）- バックグラウンドで実行開始
+    pub fn execute_search(&mut self) {
+        if self.search_input.is_empty() {
+            self.cancel_search();
+            return;
+        }
+        self.search_input.remember();
+
+        // 検索入力をパース
+        let (query, options, base_path) = self.parse_search_input();
+
+        if query.is_empty() {
+            self.cancel_search();
+            return;
+        }
+
+        // 使用したフラグを次回の検索のために記憶
+        self.search_options = options;
+        self.base_dir = base_path.unwrap_or_else(|| self.browser.current_dir.clone());
+
+        // 検索をバックグラウンドスレッドで実行
+        let (tx, rx): (Sender<Vec<SearchResult>>, Receiver<Vec<SearchResult>>) = mpsc::channel();
+        let search_base = self.base_dir.clone();
+
+        thread::spawn(move || {
+            let mut searcher = FileSearcher::new();
+            let results = searcher.search(&search_base, &query, 100, options);
+            let _ = tx.send(results);
+        });
+
+        self.search_receiver = Some(rx);
+        self.spinner_frame = 0;
+        self.input_mode = InputMode::Searching;
+    }
+
+    /// 検索結果をポーリング（main loopから呼ばれる）
+    pub fn poll_search(&mut self) -> bool {
+        if let Some(ref rx) = self.search_receiver {
+            match rx.try_recv() {
+                Ok(results) => {
+                    self.search_results = results;
+                    self.search_selected = 0;
+                    self.search_list_state.select(Some(0));
+                    self.search_receiver = None;
+
+                    if self.search_results.is_empty() {
+                        self.toasts.warn("No results found");
+                        self.input_mode = InputMode::Normal;
+                    } else {
+                        self.input_mode = InputMode::SearchResult;
+                    }
+                    return true;
+                }
+                Err(mpsc::TryRecvError::Empty) => {} // まだ検索中
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    // スレッドが終了（エラー）
+                    self.search_receiver = None;
+                    self.toasts.error("Search failed");
+                    self.input_mode = InputMode::Normal;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Whether something on screen moves on its own (a spinner or a progress
+    /// bar), so the main loop keeps redrawing without input
+    pub fn is_busy(&self) -> bool {
+        self.search_receiver.is_some()
+            || self.transfer_receiver.is_some()
+            || self.usage_receiver.is_some()
+    }
+
+    /// スピナーを1コマ進める（main loop の一定間隔で呼ばれる）
+    pub fn tick_spinner(&mut self) {
+        self.spinner_frame = (self.spinner_frame + 1) % 10;
+    }
+
+    /// スピナー文字を取得
+    pub fn spinner_char(&self) -> char {
+        const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+        SPINNER[self.spinner_frame % SPINNER.len()]
+    }
+
+    /// 検索結果から選択確定
+    pub fn confirm_search_result(&mut self) {
+        if let Some(result) = self.search_results.get(self.search_selected) {
+            let path = result.path.clone();
+            let is_dir = result.is_dir;
+
+            self.input_mode = InputMode::Normal;
+            self.search_input.clear();
+            self.search_results.clear();
+
+            // 隠しファイル/ディレクトリの場合は表示を有効にする
+            let is_hidden = path
+                .file_name()
+                .map(|n| n.to_string_lossy().starts_with('.'))
+                .unwrap_or(false);
+            let show_hidden = self.config.show_hidden || is_hidden;
+
+            // マークやソート順を保つためブラウザは作り直さない
+            self.browser.show_hidden = show_hidden;
+            if is_dir {
+                self.browser.change_dir(&path);
+                self.list_state.select(Some(0));
+                self.update_preview();
+            } else {
+                if let Some(parent) = path.parent() {
+                    self.browser.change_dir(parent);
+                    if let Some(file_name) = path.file_name() {
+                        let name = file_name.to_string_lossy().to_string();
+                        if let Some(idx) = self.browser.entries.iter().position(|e| e.name == name)
+                        {
+                            self.browser.selected_index = idx;
+                            self.list_state.select(Some(idx));
+                        }
+                    }
+                }
+                self.record_recent(&path);
+                self.update_preview();
+                self.input_mode = InputMode::Preview;
+            }
+        } else {
+            self.cancel_search();
+        }
+    }
+
+    pub fn toggle_search_dirs_only(&mut self) {
+        self.search_options.dirs_only = !self.search_options.dirs_only;
+    }
+
+    pub fn toggle_search_exact(&mut self) {
+        self.search_options.exact = !self.search_options.exact;
+    }
+
+    pub fn toggle_search_hidden(&mut self) {
+        self.search_options.hidden = !self.search_options.hidden;
+    }
+
+    pub fn toggle_search_no_ignore(&mut self) {
+        self.search_options.no_ignore = !self.search_options.no_ignore;
+    }
+
+    pub fn search_move_up(&mut self) {
+        if self.search_results.is_empty() {
+            return;
+        }
+        if self.search_selected > 0 {
+            self.search_selected -= 1;
+        } else {
+            self.search_selected = self.search_results.len() - 1;
+        }
+        self.search_list_state.select(Some(self.search_selected));
+    }
+
+    pub fn search_move_down(&mut self) {
+        if self.search_results.is_empty() {
+            return;
+        }
+        if self.search_selected < self.search_results.len() - 1 {
+            self.search_selected += 1;
+        } else {
+            self.search_selected = 0;
+        }
+        self.search_list_state.select(Some(self.search_selected));
+    }
+
+    /// 論理行 index が折り返しで占める表示行数
+    pub fn preview_rows(&self, index: usize) -> usize {
+        let width = self.preview_width.max(1);
+        self.preview_content
+            .as_ref()
+            .and_then(|content| content.lines.get(index))
+            .map(|line| line.rows(width))
+            .unwrap_or(1)
+    }
+
+    /// 最終行が画面下端に来るスクロール位置（折り返しを考慮）
+    pub fn preview_max_scroll(&self) -> usize {
+        let Some(ref content) = self.preview_content else {
+            return 0;
+        };
+        let mut rows = 0;
+        let mut index = content.lines.len();
+        while index > 0 {
+            rows += self.preview_rows(index - 1);
+            if rows > self.preview_height {
+                break;
+            }
+            index -= 1;
+        }
+        index
+    }
+
+    /// 画面に（一部でも）表示される最後の論理行の index + 1
+    pub fn preview_visible_end(&self) -> usize {
+        let total = self
+            .preview_content
+            .as_ref()
+            .map(|content| content.lines.len())
+            .unwrap_or(0);
+        let mut rows = 0;
+        let mut index = self.preview_scroll;
+        while index < total && rows < self.preview_height {
+            rows += self.preview_rows(index);
+            index += 1;
+        }
+        index
+    }
+
+    /// 表示行単位でスクロール
+    pub fn scroll_preview_up(&mut self, amount: usize) {
+        let mut moved = 0;
+        while moved < amount && self.preview_scroll > 0 {
+            self.preview_scroll -= 1;
+            moved += self.preview_rows(self.preview_scroll);
+        }
+    }
+
+    /// 表示行単位でスクロール
+    pub fn scroll_preview_down(&mut self, amount: usize) {
+        let max_scroll = self.preview_max_scroll();
+        let mut moved = 0;
+        while moved < amount && self.preview_scroll < max_scroll {
+            moved += self.preview_rows(self.preview_scroll);
+            self.preview_scroll += 1;
+        }
+    }
+
+    /// 指定行（1始まり）が先頭に来るようにスクロール
+    pub fn go_to_line(&mut self, line: usize) {
+        // 途中が省略されている場合もあるので行番号で位置を探す
+        if let Some(ref content) = self.preview_content {
+            let index = content
+                .lines
+                .iter()
+                .position(|l| l.line_number >= line)
+                .unwrap_or(content.lines.len());
+            self.preview_scroll = index.min(self.preview_max_scroll());
+        }
+    }
+
+    /// プレビューでの数字プレフィックス（123G 用）
+    pub fn push_preview_count(&mut self, digit: u32) {
+        let count = self.preview_count.unwrap_or(0);
+        self.preview_count = Some(count.saturating_mul(10).saturating_add(digit as usize));
+    }
+
+    /// G: 数字プレフィックスがあればその行へ、なければ末尾へ
+    pub fn preview_go_to_bottom_or_count(&mut self) {
+        match self.preview_count.take() {
+            Some(line) => self.go_to_line(line),
+            None => self.go_to_line(usize::MAX),
+        }
+    }
+
+    pub fn start_goto_line(&mut self) {
+        self.preview_count = None;
+        self.goto_input.clear();
+        self.input_mode = InputMode::GotoLine;
+    }
+
+    pub fn goto_input_char(&mut self, c: char) {
+        if c.is_ascii_digit() && self.goto_input.len() < 10 {
+            self.goto_input.push(c);
+        }
+    }
+
+    pub fn goto_input_backspace(&mut self) {
+        self.goto_input.pop();
+    }
+
+    pub fn confirm_goto_line(&mut self) {
+        if let Ok(line) = self.goto_input.parse::<usize>() {
+            self.go_to_line(line);
+        }
+        self.cancel_goto_line();
+    }
+
+    pub fn cancel_goto_line(&mut self) {
+        self.goto_input.clear();
+        self.input_mode = InputMode::Preview;
+    }
+
+    /// Under `narrow_width` columns the layout is compacted
+    pub fn set_screen_width(&mut self, width: u16) {
+        self.narrow = width < self.config.narrow_width;
+        self.screen_width = width;
+    }
+
+    pub fn set_preview_size(&mut self, height: usize, width: usize) {
+        self.preview_height = height;
+        self.preview_width = width;
+    }
+
+    pub fn quit(&mut self) {
+        self.should_quit = true;
+    }
+
+    pub fn copy_path(&mut self) {
+        if let Some(entry) = self.browser.selected_entry() {
+            let path_str = entry.path.to_string_lossy().to_string();
+
+            match copy_to_clipboard(&path_str) {
+                Ok(_) => {
+                    self.toasts.info(format!("Copied: {}", path_str));
+                }
+                Err(e) => {
+                    self.toasts.error(format!("Failed to copy: {}", e));
+                }
+            }
+        }
+    }
+
+    /// ビジュアル選択を開始（表示中の先頭行から）
+    pub fn start_visual(&mut self) {
+        if self.preview_content.is_none() {
+            return;
+        }
+        self.visual_anchor = self.preview_scroll;
+        self.visual_cursor = self.preview_scroll;
+        self.input_mode = InputMode::Visual;
+    }
+
+    pub fn cancel_visual(&mut self) {
+        self.input_mode = InputMode::Preview;
+    }
+
+    pub fn visual_move(&mut self, delta: isize) {
+        let Some(ref content) = self.preview_content else {
+            return;
+        };
+        let last = content.lines.len().saturating_sub(1);
+        self.visual_cursor = self.visual_cursor.saturating_add_signed(delta).min(last);
+
+        // カーソルが画面外に出たらスクロール
+        if self.visual_cursor < self.preview_scroll {
+            self.preview_scroll = self.visual_cursor;
+        } else {
+            while self.preview_scroll < self.visual_cursor
+                && self.visual_cursor >= self.preview_visible_end()
+            {
+                self.preview_scroll += 1;
+            }
+        }
+    }
+
+    /// 選択範囲（開始行, 終了行）のインデックス
+    pub fn visual_range(&self) -> (usize, usize) {
+        (
+            self.visual_anchor.min(self.visual_cursor),
+            self.visual_anchor.max(self.visual_cursor),
+        )
+    }
+
+    /// 選択範囲をクリップボードにコピーしてプレビューに戻る
+    pub fn yank_visual(&mut self) {
+        let (start, end) = self.visual_range();
+        self.input_mode = InputMode::Preview;
+
+        let Some(ref content) = self.preview_content else {
+            return;
+        };
+        let text = content.lines[start..=end.min(content.lines.len().saturating_sub(1))]
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let count = end - start + 1;
+        match copy_to_clipboard(&text) {
+            Ok(_) => {
+                self.toasts.info(format!("Yanked {} line(s)", count));
+            }
+            Err(e) => {
+                self.toasts.error(format!("Failed to copy: {}", e));
+            }
+        }
+    }
+
+    pub fn start_jump(&mut self) {
+        self.input_mode = InputMode::JumpInput;
+    }
+
+    pub fn execute_jump(&mut self, c: char) {
+        self.last_jump_char = Some(c);
+        self.jump_to_char(c, true);
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn jump_next(&mut self) {
+        if let Some(c) = self.last_jump_char {
+            self.jump_to_char(c, true);
+        }
+    }
+
+    pub fn jump_prev(&mut self) {
+        if let Some(c) = self.last_jump_char {
+            self.jump_to_char(c, false);
+        }
+    }
+
+    fn jump_to_char(&mut self, c: char, forward: bool) {
+        let entries = &self.browser.entries;
+        if entries.is_empty() {
+            return;
+        }
+
+        let c_lower = c.to_lowercase().next().unwrap_or(c);
+        let current = self.browser.selected_index;
+        let len = entries.len();
+
+        if forward {
+            // 現在位置の次から検索、末尾まで行ったら先頭から
+            for i in 1..=len {
+                let idx = (current + i) % len;
+                if entries[idx].name.to_lowercase().starts_with(c_lower) {
+                    self.browser.selected_index = idx;
+                    self.list_state.select(Some(idx));
+                    self.update_preview();
+                    return;
+                }
+            }
+        } else {
+            // 現在位置の前から検索、先頭まで行ったら末尾から
+            for i in 1..=len {
+                let idx = (current + len - i) % len;
+                if entries[idx].name.to_lowercase().starts_with(c_lower) {
+                    self.browser.selected_index = idx;
+                    self.list_state.select(Some(idx));
+                    self.update_preview();
+                    return;
+                }
+            }
+        }
+
+        self.toasts.warn(format!("No match for '{}'", c));
+    }
+
+    pub fn cancel_jump(&mut self) {
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Directories of the current path from the root, with the columns each
+    /// takes in the header (numbered while waiting for a digit after b).
+    /// When the path is wider than the screen, directories after the root
+    /// are folded into one "…" crumb that goes to the last of them.
+    pub fn breadcrumbs(&self) -> Vec<Crumb> {
+        let numbered = self.input_mode == InputMode::Breadcrumb;
+        let mut ancestors: Vec<&Path> = self.browser.current_dir.ancestors().collect();
+        ancestors.reverse();
+        let depth = ancestors.len() - 1;
+
+        let mut crumbs: Vec<Crumb> = Vec::new();
+        for (index, path) in ancestors.into_iter().enumerate() {
+            let levels = depth - index;
+            let name = match path.file_name() {
+                // 狭い端末では上位のディレクトリを頭文字だけにする（.config は .c）
+                Some(name) if self.narrow && levels > 0 => {
+                    let name = name.to_string_lossy();
+                    let keep = if name.starts_with('.') { 2 } else { 1 };
+                    name.chars().take(keep).collect()
+                }
+                Some(name) => name.to_string_lossy().to_string(),
+                None => path.to_string_lossy().to_string(),
+            };
+            let number = (numbered && (1..=9).contains(&levels)).then_some(levels);
+            crumbs.push(Crumb {
+                name,
+                path: path.to_path_buf(),
+                number,
+                columns: 0..0,
+            });
+        }
+
+        // 収まらなければルートの次から順に … にまとめる（今のディレクトリは残す）
+        let mut folded = 0;
+        loop {
+            let end = place_crumbs(&mut crumbs);
+            if end <= self.screen_width || crumbs.len() <= 3 {
+                return crumbs;
+            }
+            folded += 1;
+            let removed = if folded == 1 {
+                std::mem::replace(
+                    &mut crumbs[1],
+                    Crumb {
+                        name: "…".to_string(),
+                        path: PathBuf::new(),
+                        number: None,
+                        columns: 0..0,
+                    },
+                )
+            } else {
+                crumbs.remove(2)
+            };
+            crumbs[1].path = removed.path;
+        }
+    }
+
+    pub fn start_breadcrumb(&mut self) {
+        self.clear_jump();
+        if self.browser.current_dir.parent().is_none() {
+            self.toasts.warn("Already at the root");
+            return;
+        }
+        self.input_mode = InputMode::Breadcrumb;
+    }
+
+    /// b + 数字: その階層だけ上のディレクトリに移動
+    pub fn jump_up(&mut self, levels: usize) {
+        self.input_mode = InputMode::Normal;
+        let target = self.browser.current_dir.ancestors().nth(levels);
+        match target.map(Path::to_path_buf) {
+            Some(dir) if levels > 0 => self.go_to_ancestor(&dir),
+            _ => self
+                .toasts
+                .warn(format!("No directory {} levels up", levels)),
+        }
+    }
+
+    /// ヘッダーのパスのクリック: クリックしたディレクトリに移動
+    pub fn click_header(&mut self, column: u16) {
+        if !matches!(self.input_mode, InputMode::Normal | InputMode::Breadcrumb) {
+            return;
+        }
+        let crumb = self
+            .breadcrumbs()
+            .into_iter()
+            .find(|crumb| crumb.columns.contains(&column));
+        self.input_mode = InputMode::Normal;
+        if let Some(crumb) = crumb
+            && crumb.path != self.browser.current_dir
+        {
+            self.clear_jump();
+            self.go_to_ancestor(&crumb.path);
+        }
+    }
+
+    /// クリックされた通知を閉じる（通知の上でなければ false）
+    pub fn click_toast(&mut self, column: u16, row: u16) -> bool {
+        let position = Position::new(column, row);
+        let Some(index) = self
+            .toast_areas
+            .iter()
+            .position(|area| area.contains(position))
+        else {
+            return false;
+        };
+        self.toasts.dismiss(index);
+        self.toast_areas.clear();
+        true
+    }
+
+    /// 上位のディレクトリに移動し、元いたディレクトリ側のエントリを選択
+    fn go_to_ancestor(&mut self, dir: &Path) {
+        let from = self.browser.current_dir.clone();
+        self.browser.change_dir(dir);
+        if let Some(child) = from.ancestors().find(|p| p.parent() == Some(dir)) {
+            self.browser.select_path(child);
+        }
+        self.list_state.select(Some(self.browser.selected_index));
+        self.update_preview();
+    }
+
+    pub fn start_set_bookmark(&mut self) {
+        self.input_mode = InputMode::SetBookmark;
+    }
+
+    pub fn start_jump_bookmark(&mut self) {
+        if self.bookmarks.keys().is_empty() {
+            self.toasts.warn("No bookmarks (set one with m + char)");
+            return;
+        }
+        self.input_mode = InputMode::JumpBookmark;
+    }
+
+    /// 現在のディレクトリを c に登録
+    pub fn set_bookmark(&mut self, c: char) {
+        self.input_mode = InputMode::Normal;
+        let dir = self.browser.current_dir.clone();
+        match self.bookmarks.set(&c.to_string(), &dir) {
+            Ok(()) => self
+                .toasts
+                .info(format!("Bookmarked '{}' -> {}", c, dir.display())),
+            Err(e) => self.toasts.error(format!("Failed to save bookmark: {}", e)),
+        }
+    }
+
+    pub fn jump_to_bookmark(&mut self, c: char) {
+        self.input_mode = InputMode::Normal;
+        self.clear_jump();
+        let Some(dir) = self.bookmarks.get(&c.to_string()).map(Path::to_path_buf) else {
+            self.toasts.warn(format!("No bookmark '{}'", c));
+            return;
+        };
+        if !dir.is_dir() {
+            self.toasts
+                .error(format!("Bookmark '{}' not found: {}", c, dir.display()));
+            return;
+        }
+        self.browser.change_dir(&dir);
+        self.list_state.select(Some(self.browser.selected_index));
+        self.update_preview();
+    }
+
+    pub fn show_help(&mut self) {
+        self.help_scroll = 0;
+        self.input_mode = InputMode::Help;
+    }
+
+    /// Record how many help lines there are and how many fit in the popup
+    pub fn set_help_size(&mut self, total: usize, height: usize) {
+        self.help_height = height;
+        self.help_max_scroll = total.saturating_sub(height);
+        self.help_scroll = self.help_scroll.min(self.help_max_scroll);
+    }
+
+    pub fn help_max_scroll(&self) -> usize {
+        self.help_max_scroll
+    }
+
+    pub fn scroll_help_down(&mut self, amount: usize) {
+        self.help_scroll = self
+            .help_scroll
+            .saturating_add(amount)
+            .min(self.help_max_scroll);
+    }
+
+    pub fn scroll_help_up(&mut self, amount: usize) {
+        self.help_scroll = self.help_scroll.saturating_sub(amount);
+    }
+
+    pub fn close_help(&mut self) {
+        self.input_mode = InputMode::Normal;
+    }
+}
+
+/// ファイル名部分（ステータス表示用）
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// 一括操作の結果を1行にまとめる（失敗したものは理由付きで列挙）
+/// Error level when some entries failed, info otherwise
+fn result_level(errors: &[String]) -> Level {
+    if errors.is_empty() {
+        Level::Info
+    } else {
+        Level::Error
+    }
+}
+
+fn summarize(verb: &str, succeeded: usize, total: usize, errors: &[String]) -> String {
+    if errors.is_empty() {
+        if succeeded == total {
+            return format!("{} {} entries", verb, succeeded);
+        }
+        return format!("{} {} of {} entries", verb, succeeded, total);
+    }
+    format!(
+        "{} {} of {} entries; failed: {}",
+        verb,
+        succeeded,
+        total,
+        errors.join(", ")
+    )
+}
+
+/// Percent one press of < / > moves a pane divider
+const SPLIT_STEP: u16 = 5;
+
+fn clamp_split(ratio: u16) -> u16 {
+    ratio.clamp(*SPLIT_RATIOS.start(), *SPLIT_RATIOS.end())
+}
+
+/// Program and arguments that copy their stdin to the system clipboard
+pub const CLIPBOARD_COMMAND: (&str, &[&str]) = if cfg!(target_os = "macos") {
+    ("pbcopy", &[])
+} else if cfg!(windows) {
+    ("clip", &[])
+} else {
+    ("xclip", &["-selection", "clipboard"])
+};
+
+/// Copy text to the system clipboard
+fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    let (program, args) = CLIPBOARD_COMMAND;
+    std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            if let Some(stdin) = child.stdin.as_mut() {
+                stdin.write_all(text.as_bytes())?;
+            }
+            child.wait()
+        })
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// App that keeps its history and pane widths in memory only
+    fn test_app(start_path: &Path, config: Config) -> App {
+        App::with_saved_state(
+            start_path,
+            config,
+            RecentFiles::default(),
+            SavedLayout::default(),
+        )
+    }
+
+    fn create_test_app() -> (App, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::default();
+        let app = test_app(temp_dir.path(), config);
+        (app, temp_dir)
+    }
+
+    #[test]
+    fn test_parse_search_input_simple() {
+        let (mut app, _temp) = create_test_app();
+        app.search_input.set("main.rs");
+
+        let (query, options, base_path) = app.parse_search_input();
+        assert_eq!(query, "main.rs");
+        assert!(!options.dirs_only);
+        assert!(!options.exact);
+        assert!(base_path.is_none());
+    }
+
+    #[test]
+    fn test_parse_search_input_with_options() {
+        let (mut app, _temp) = create_test_app();
+        app.search_input.set("config -e -d");
+
+        let (query, options, _) = app.parse_search_input();
+        assert_eq!(query, "config");
+        assert!(options.dirs_only);
+        assert!(options.exact);
+    }
+
+    #[test]
+    fn test_parse_search_input_with_base_path() {
+        let (mut app, _temp) = create_test_app();
+        app.search_input.set("main -b /tmp");
+
+        let (query, _, base_path) = app.parse_search_input();
+        assert_eq!(query, "main");
+        assert_eq!(base_path, Some(PathBuf::from("/tmp")));
+    }
+
+    #[test]
+    fn test_parse_search_input_with_home_expansion() {
+        let (mut app, _temp) = create_test_app();
+        app.search_input.set("main -b ~/dev");
+
+        let (query, _, base_path) = app.parse_search_input();
+        assert_eq!(query, "main");
+        assert!(base_path.is_some());
+        let path = base_path.unwrap();
+        assert!(path.to_string_lossy().contains("dev"));
+        assert!(!path.to_string_lossy().starts_with("~"));
+    }
+
+    #[test]
+    fn test_input_mode_transitions() {
+        let (mut app, _temp) = create_test_app();
+
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        app.start_search();
+        assert_eq!(app.input_mode, InputMode::SearchInput);
+
+        app.cancel_search();
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        app.show_help();
+        assert_eq!(app.input_mode, InputMode::Help);
+
+        app.close_help();
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn test_command_palette() {
+        let (mut app, temp) = create_test_app();
+
+        app.start_command();
+        for c in "mk".chars() {
+            app.command_input.insert(c);
+        }
+        // コマンド名が1つに決まれば空白まで補う
+        app.complete_command(true);
+        assert_eq!(app.command_input.text(), "mkdir ");
+        for c in "newdir".chars() {
+            app.command_input.insert(c);
+        }
+        app.confirm_command();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(temp.path().join("newdir").is_dir());
+
+        app.start_command();
+        for c in "cd new".chars() {
+            app.command_input.insert(c);
+        }
+        app.complete_command(true);
+        assert_eq!(app.command_input.text(), "cd newdir/");
+        app.confirm_command();
+        assert_eq!(app.browser.current_dir, temp.path().join("newdir"));
+
+        app.start_command();
+        for c in "set details!".chars() {
+            app.command_input.insert(c);
+        }
+        app.confirm_command();
+        assert!(app.show_details);
+        assert_eq!(app.toasts.latest(), Some("details: on"));
+
+        app.start_command();
+        for c in "theme light".chars() {
+            app.command_input.insert(c);
+        }
+        app.confirm_command();
+        assert_eq!(app.config.ui_theme, UiTheme::Light);
+
+        app.start_command();
+        for c in "frobnicate".chars() {
+            app.command_input.insert(c);
+        }
+        app.confirm_command();
+        assert_eq!(app.toasts.latest(), Some("Unknown command: frobnicate"));
+    }
+
+    #[test]
+    fn test_help_scroll_clamps() {
+        let (mut app, _temp) = create_test_app();
+        app.show_help();
+        app.set_help_size(50, 20);
+
+        app.scroll_help_down(10);
+        assert_eq!(app.help_scroll, 10);
+        app.scroll_help_down(usize::MAX);
+        assert_eq!(app.help_scroll, 30);
+        app.scroll_help_up(5);
+        assert_eq!(app.help_scroll, 25);
+
+        // 端末が広がって全部収まるなら先頭に戻る
+        app.set_help_size(50, 60);
+        assert_eq!(app.help_scroll, 0);
+
+        app.scroll_help_down(3);
+        app.show_help();
+        assert_eq!(app.help_scroll, 0);
+    }
+
+    #[test]
+    fn test_search_input_manipulation() {
+        let (mut app, _temp) = create_test_app();
+
+        app.search_input.insert('h');
+        app.search_input.insert('e');
+        app.search_input.insert('l');
+        app.search_input.insert('l');
+        app.search_input.insert('o');
+
+        assert_eq!(app.search_input.text(), "hello");
+
+        app.search_input.backspace();
+        assert_eq!(app.search_input.text(), "hell");
+    }
+
+    #[test]
+    fn test_preview_scroll_up() {
+        let (mut app, _temp) = create_test_app();
+
+        // Set initial scroll position
+        app.preview_scroll = 10;
+
+        app.scroll_preview_up(3);
+        assert_eq!(app.preview_scroll, 7);
+
+        app.scroll_preview_up(10);
+        assert_eq!(app.preview_scroll, 0); // saturating_sub prevents negative
+    }
+
+    #[test]
+    fn test_visual_selection_range() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        let content: String = (1..=50).map(|i| format!("line {}\n", i)).collect();
+        fs::write(temp_dir.path().join("file.txt"), content).unwrap();
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.preview_height = 10;
+
+        app.enter();
+        app.start_visual();
+        assert_eq!(app.input_mode, InputMode::Visual);
+
+        app.visual_move(3);
+        assert_eq!(app.visual_range(), (0, 3));
+
+        // 画面外に出るとスクロールする
+        app.visual_move(10);
+        assert_eq!(app.visual_cursor, 13);
+        assert_eq!(app.preview_scroll, 4);
+
+        app.visual_move(-20);
+        assert_eq!(app.visual_range(), (0, 0));
+
+        app.cancel_visual();
+        assert_eq!(app.input_mode, InputMode::Preview);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_external_previewer_runs_in_background() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.custom"), "raw\n").unwrap();
+        let mut config = Config::default();
+        config
+            .previewers
+            .insert("custom".to_string(), "echo converted".to_string());
+
+        // 結果が届くまでは内蔵のプレビュー
+        let mut app = test_app(temp_dir.path(), config);
+        assert_eq!(app.preview_content.as_ref().unwrap().lines[0].text, "raw");
+        let start = Instant::now();
+        while !app.poll_external_preview() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+        let text = &app.preview_content.as_ref().unwrap().lines[0].text;
+        assert!(text.starts_with("converted "));
+
+        // 同じファイルに戻ったらキャッシュを使う
+        app.update_preview();
+        assert!(app.external_preview.is_none());
+        assert!(
+            app.preview_content.as_ref().unwrap().lines[0]
+                .text
+                .starts_with("converted ")
+        );
+    }
+
+    #[test]
+    fn test_diff_markers_arrive_in_background() {
+        use std::process::Command;
+
+        let temp_dir = TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(temp_dir.path())
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .is_ok_and(|output| output.status.success())
+        };
+        std::fs::write(temp_dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+        // git が無い環境では確かめようがない
+        if !git(&["init", "-q"]) || !git(&["add", "."]) || !git(&["commit", "-qm", "init"]) {
+            return;
+        }
+        std::fs::write(temp_dir.path().join("a.txt"), "one\nTWO\nthree\n").unwrap();
+
+        let mut app = test_app(temp_dir.path(), Config::default());
+        assert!(
+            app.preview_content.as_ref().unwrap().lines[1]
+                .diff
+                .is_none()
+        );
+        // プレビューが見えていなければ git は動かさない
+        assert!(!app.poll_diff_markers());
+        assert!(app.diff_run.is_none());
+
+        app.show_preview_pane = true;
+        let start = Instant::now();
+        while !app.poll_diff_markers() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+        let lines = &app.preview_content.as_ref().unwrap().lines;
+        assert_eq!(lines[0].diff, None);
+        assert_eq!(lines[1].diff, Some(DiffMarker::Modified));
+        assert_eq!(lines[2].diff, Some(DiffMarker::Added));
+
+        // 再表示では前回のマーカーをすぐに出す
+        app.update_preview();
+        let lines = &app.preview_content.as_ref().unwrap().lines;
+        assert_eq!(lines[1].diff, Some(DiffMarker::Modified));
+
+        // 選択が移ったら前の git は止める
+        app.poll_diff_markers();
+        let cancel = Arc::clone(&app.diff_run.as_ref().unwrap().cancel);
+        app.update_preview();
+        assert!(cancel.load(Ordering::Relaxed));
+    }
+
+    /// ゴミ箱に入れたファイルを一覧で選ぶ（ゴミ箱が使えない環境では None）
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn trash_and_select(app: &mut App, path: &Path) -> Option<()> {
+        file_ops::delete(path, true).ok()?;
+        app.open_trash();
+        let index = app
+            .trash_entries
+            .iter()
+            .position(|entry| entry.original_path.as_deref() == Some(path))?;
+        app.trash_list_state.select(Some(index));
+        Some(())
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_trash_restores_to_original_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path().canonicalize().unwrap();
+        let path = base.join("restore-me.txt");
+        std::fs::write(&path, "kept").unwrap();
+        let mut app = test_app(&base, Config::default());
+        if trash_and_select(&mut app, &path).is_none() {
+            return;
+        }
+        assert!(!path.exists());
+        let count = app.trash_entries.len();
+
+        app.restore_trash_entry();
+        assert_eq!(app.toasts.latest(), Some("Restored restore-me.txt"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "kept");
+        assert_eq!(app.trash_entries.len(), count - 1);
+        assert!(app.browser.entries.iter().any(|e| e.path == path));
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_trash_restore_keeps_a_file_in_the_way() {
+        use crossterm::event::{KeyCode, KeyEvent};
+
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path().canonicalize().unwrap();
+        let path = base.join("conflict.txt");
+        std::fs::write(&path, "old").unwrap();
+        let mut app = test_app(&base, Config::default());
+        if trash_and_select(&mut app, &path).is_none() {
+            return;
+        }
+        std::fs::write(&path, "new").unwrap();
+        let count = app.trash_entries.len();
+
+        // 同じ名前のファイルがあれば上書きせずに失敗し、一覧にも残す
+        app.restore_trash_entry();
+        let toast = app.toasts.latest().unwrap();
+        assert!(
+            toast.starts_with("Failed to restore conflict.txt"),
+            "{}",
+            toast
+        );
+        assert!(toast.contains("already exists"), "{}", toast);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(app.trash_entries.len(), count);
+
+        app.start_purge();
+        app.confirm_key(KeyEvent::from(KeyCode::Char('y')));
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_trash_purge_asks_first() {
+        use crossterm::event::{KeyCode, KeyEvent};
+
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path().canonicalize().unwrap();
+        let path = base.join("purge-me.txt");
+        std::fs::write(&path, "gone").unwrap();
+        let mut app = test_app(&base, Config::default());
+        if trash_and_select(&mut app, &path).is_none() {
+            return;
+        }
+        let in_trash = || {
+            trash_bin::list()
+                .unwrap()
+                .iter()
+                .any(|entry| entry.original_path.as_deref() == Some(path.as_path()))
+        };
+
+        // いいえならゴミ箱に残る
+        app.start_purge();
+        assert_eq!(app.input_mode, InputMode::Confirm);
+        app.confirm_key(KeyEvent::from(KeyCode::Char('n')));
+        assert_eq!(app.input_mode, InputMode::Trash);
+        assert!(in_trash());
+
+        app.start_purge();
+        app.confirm_key(KeyEvent::from(KeyCode::Char('y')));
+        assert_eq!(app.input_mode, InputMode::Trash);
+        assert_eq!(
+            app.toasts.latest(),
+            Some("Permanently deleted purge-me.txt")
+        );
+        assert!(!in_trash());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_editor_opens_at_preview_position() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        let content: String = (1..=50).map(|i| format!("line {}\n", i)).collect();
+        fs::write(temp_dir.path().join("file.txt"), content).unwrap();
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.preview_height = 10;
+        assert_eq!(app.preview_position(), None);
+
+        app.enter();
+        app.preview_scroll = 19;
+        assert_eq!(
+            app.preview_position(),
+            Some(editor::Position {
+                line: 20,
+                column: 1
+            })
+        );
+        app.start_visual();
+        app.visual_move(2);
+        assert_eq!(app.preview_position().map(|p| p.line), Some(20));
+    }
+
+    #[test]
+    fn test_go_to_line() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        let content: String = (1..=50).map(|i| format!("line {}\n", i)).collect();
+        fs::write(temp_dir.path().join("file.txt"), content).unwrap();
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.preview_height = 10;
+        app.enter();
+
+        // :25
+        app.start_goto_line();
+        assert_eq!(app.input_mode, InputMode::GotoLine);
+        app.goto_input_char('2');
+        app.goto_input_char('x');
+        app.goto_input_char('5');
+        app.confirm_goto_line();
+        assert_eq!(app.input_mode, InputMode::Preview);
+        assert_eq!(app.preview_scroll, 24);
+
+        // 12G
+        app.push_preview_count(1);
+        app.push_preview_count(2);
+        app.preview_go_to_bottom_or_count();
+        assert_eq!(app.preview_scroll, 11);
+
+        // 末尾を超える行はクランプ
+        app.go_to_line(1000);
+        assert_eq!(app.preview_scroll, 40);
+
+        // 数字なしの G は末尾
+        app.go_to_line(1);
+        app.preview_go_to_bottom_or_count();
+        assert_eq!(app.preview_scroll, 40);
+    }
+
+    #[test]
+    fn test_scroll_with_wrapped_lines() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        // 各行が幅10で3行に折り返される
+        let content: String = (0..10).map(|i| format!("{:0>25}\n", i)).collect();
+        fs::write(temp_dir.path().join("wide.txt"), content).unwrap();
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.enter();
+        app.set_preview_size(6, 10);
+
+        assert_eq!(app.preview_rows(0), 3);
+        // 最後の2論理行（6表示行）が画面に収まる位置が最大
+        assert_eq!(app.preview_max_scroll(), 8);
+
+        // 半ページ（3表示行）で1論理行進む
+        app.scroll_preview_down(3);
+        assert_eq!(app.preview_scroll, 1);
+        assert_eq!(app.preview_visible_end(), 3);
+
+        app.scroll_preview_down(100);
+        assert_eq!(app.preview_scroll, 8);
+        assert_eq!(app.preview_visible_end(), 10);
+
+        app.scroll_preview_up(6);
+        assert_eq!(app.preview_scroll, 6);
+    }
+
+    #[test]
+    fn test_sort_from_config() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("small.txt"), "a").unwrap();
+        fs::write(temp_dir.path().join("large.txt"), "aaaa").unwrap();
+        let config = Config {
+            sort: crate::file_browser::SortMode::Size,
+            sort_reverse: true,
+            ..Config::default()
+        };
+        let app = test_app(temp_dir.path(), config);
+
+        assert_eq!(app.browser.sort, crate::file_browser::SortMode::Size);
+        assert!(app.browser.sort_reverse);
+        // 大きい順の逆なので小さいファイルが先
+        assert_eq!(app.browser.entries[0].name, "small.txt");
+    }
+
+    #[test]
+    fn test_delete_with_confirmation() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "a").unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "b").unwrap();
+        let config = Config {
+            delete_to_trash: false,
+            ..Config::default()
+        };
+        let mut app = test_app(temp_dir.path(), config);
+
+        app.start_delete();
+        assert_eq!(app.input_mode, InputMode::Confirm);
+        assert_eq!(
+            app.confirm.as_ref().unwrap().message,
+            "Permanently delete 'a.txt'?"
+        );
+        app.cancel_delete();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(temp_dir.path().join("a.txt").exists());
+
+        app.start_delete();
+        app.confirm_delete();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        wait_for_transfer(&mut app);
+        assert!(!temp_dir.path().join("a.txt").exists());
+        assert_eq!(app.browser.entries.len(), 1);
+        assert_eq!(app.browser.selected_entry().unwrap().name, "b.txt");
+    }
+
+    #[test]
+    fn test_rename_keeps_selection() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "a").unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "b").unwrap();
+        let mut app = test_app(temp_dir.path(), Config::default());
+
+        app.start_rename();
+        assert_eq!(app.input_mode, InputMode::Rename);
+        assert_eq!(app.name_input.text(), "a.txt");
+
+        app.name_input.clear();
+        for c in "z.txt".chars() {
+            app.name_input.insert(c);
+        }
+        app.confirm_name_input();
+
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(temp_dir.path().join("z.txt").exists());
+        assert_eq!(app.browser.selected_entry().unwrap().name, "z.txt");
+    }
+
+    #[test]
+    fn test_paste_conflicts_overwrite_all() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        for name in ["a.txt", "b.txt"] {
+            fs::write(temp_dir.path().join(name), "new").unwrap();
+            fs::write(temp_dir.path().join("sub").join(name), "old").unwrap();
+        }
+        let mut app = test_app(temp_dir.path(), Config::default());
+        let press = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+
+        for name in ["a.txt", "b.txt"] {
+            app.browser.marked.insert(temp_dir.path().join(name));
+        }
+        app.yank_to_register(TransferKind::Copy);
+        app.browser.change_dir(&temp_dir.path().join("sub"));
+        app.paste();
+        assert_eq!(app.input_mode, InputMode::Confirm);
+        assert!(app.confirm.as_ref().unwrap().message.contains("1 more"));
+
+        // ←/→ は選択を動かすだけ
+        app.confirm_key(KeyEvent::new(KeyCode::Right, KeyModifiers::NONE));
+        assert_eq!(app.input_mode, InputMode::Confirm);
+        app.confirm_key(press('a'));
+        assert!(app.confirm.is_none());
+        wait_for_transfer(&mut app);
+        for name in ["a.txt", "b.txt"] {
+            let content = fs::read_to_string(temp_dir.path().join("sub").join(name)).unwrap();
+            assert_eq!(content, "new");
+        }
+    }
+
+    fn wait_for_transfer(app: &mut App) {
+        while !app.poll_transfer() {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_poll_watcher_keeps_selection() {
+        use std::fs;
+        use std::time::{Duration, Instant};
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "").unwrap();
+        fs::write(temp_dir.path().join("c.txt"), "").unwrap();
+
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.poll_watcher();
+        app.move_down();
+        assert_eq!(app.browser.selected_entry().unwrap().name, "c.txt");
+
+        // 外部で作成されたファイルが一覧に反映され、選択は c.txt のまま
+        fs::write(temp_dir.path().join("a.txt"), "").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !app.poll_watcher() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(app.browser.entries.len(), 3);
+        assert_eq!(app.browser.selected_entry().unwrap().name, "c.txt");
+    }
+
+    #[test]
+    fn test_copy_and_paste() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        fs::write(temp_dir.path().join("file.txt"), "content").unwrap();
+        let mut app = test_app(temp_dir.path(), Config::default());
+
+        // sub/ の次に file.txt
+        app.move_down();
+        app.yank_to_register(TransferKind::Copy);
+        app.move_up();
+        app.enter();
+        app.paste();
+        wait_for_transfer(&mut app);
+
+        assert!(temp_dir.path().join("sub/file.txt").exists());
+        assert!(temp_dir.path().join("file.txt").exists());
+        assert!(app.register.is_some());
+
+        // 同じ場所に貼り付けると衝突の確認になる
+        app.paste();
+        assert_eq!(app.input_mode, InputMode::Confirm);
+        app.resolve_paste_conflict(ConflictChoice::KeepBoth);
+        wait_for_transfer(&mut app);
+        assert!(temp_dir.path().join("sub/file (1).txt").exists());
+    }
+
+    #[test]
+    fn test_cut_and_paste() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        fs::write(temp_dir.path().join("file.txt"), "content").unwrap();
+        let mut app = test_app(temp_dir.path(), Config::default());
+
+        app.move_down();
+        app.yank_to_register(TransferKind::Move);
+        app.move_up();
+        app.enter();
+        app.paste();
+        wait_for_transfer(&mut app);
+
+        assert!(temp_dir.path().join("sub/file.txt").exists());
+        assert!(!temp_dir.path().join("file.txt").exists());
+        assert!(app.register.is_none());
+    }
+
+    #[test]
+    fn test_create_file_and_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "a").unwrap();
+        let mut app = test_app(temp_dir.path(), Config::default());
+
+        app.start_create(false);
+        assert_eq!(app.input_mode, InputMode::NewFile);
+        for c in "new.txt".chars() {
+            app.name_input.insert(c);
+        }
+        app.confirm_name_input();
+        assert!(temp_dir.path().join("new.txt").is_file());
+        assert_eq!(app.browser.selected_entry().unwrap().name, "new.txt");
+
+        app.start_create(true);
+        for c in "docs".chars() {
+            app.name_input.insert(c);
+        }
+        app.confirm_name_input();
+        assert!(temp_dir.path().join("docs").is_dir());
+        assert_eq!(app.browser.selected_entry().unwrap().name, "docs");
+
+        // 既存の名前は作成しない
+        app.start_create(false);
+        for c in "a.txt".chars() {
+            app.name_input.insert(c);
+        }
+        app.confirm_name_input();
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("a.txt")).unwrap(),
+            "a"
+        );
+        assert!(app.toasts.latest().unwrap().starts_with("Failed"));
+    }
+
+    #[test]
+    fn test_mark_range() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+            fs::write(temp_dir.path().join(name), "").unwrap();
+        }
+        let mut app = test_app(temp_dir.path(), Config::default());
+
+        app.move_down();
+        app.start_mark_range();
+        assert_eq!(app.input_mode, InputMode::MarkRange);
+        app.move_down();
+        app.move_down();
+        assert_eq!(app.mark_range(), (1, 3));
+        app.confirm_mark_range();
+
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.browser.marked.len(), 3);
+
+        app.start_mark_range();
+        app.cancel_mark_range();
+        assert_eq!(app.browser.marked.len(), 3);
+
+        app.clear_marks();
+        assert!(app.browser.marked.is_empty());
+    }
+
+    #[test]
+    fn test_bulk_delete_marked() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(temp_dir.path().join(name), "").unwrap();
+        }
+        let config = Config {
+            delete_to_trash: false,
+            ..Config::default()
+        };
+        let mut app = test_app(temp_dir.path(), config);
+
+        app.toggle_mark();
+        app.toggle_mark();
+        app.start_delete();
+        assert_eq!(app.pending_delete.len(), 2);
+        app.confirm_delete();
+        wait_for_transfer(&mut app);
+
+        assert!(!temp_dir.path().join("a.txt").exists());
+        assert!(!temp_dir.path().join("b.txt").exists());
+        assert!(temp_dir.path().join("c.txt").exists());
+        assert!(app.browser.marked.is_empty());
+        assert_eq!(app.toasts.latest(), Some("Deleted 2 entries"));
+    }
+
+    #[test]
+    fn test_bulk_copy_marked() {
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        for name in ["a.txt", "b.txt"] {
+            fs::write(temp_dir.path().join(name), "").unwrap();
+        }
+        let mut app = test_app(temp_dir.path(), Config::default());
+
+        // sub/, a.txt, b.txt
+        app.move_down();
+        app.start_mark_range();
+        app.go_to_bottom();
+        app.confirm_mark_range();
+        app.yank_to_register(TransferKind::Copy);
+        assert_eq!(app.register.as_ref().unwrap().paths.len(), 2);
+        assert!(app.browser.marked.is_empty());
+
+        app.go_to_top();
+        app.enter();
+        app.paste();
+        wait_for_transfer(&mut app);
+        assert!(temp_dir.path().join("sub/a.txt").exists());
+        assert!(temp_dir.path().join("sub/b.txt").exists());
+        assert_eq!(app.toasts.latest(), Some("Copied 2 entries"));
+    }
+
+    #[test]
+    fn test_summarize_reports_failures() {
+        assert_eq!(summarize("Deleted", 3, 3, &[]), "Deleted 3 entries");
+        assert_eq!(
+            summarize("Moved", 1, 2, &["b.txt: Permission denied".to_string()]),
+            "Moved 1 of 2 entries; failed: b.txt: Permission denied"
+        );
+        // 中断したときは終わった数だけ
+        assert_eq!(summarize("Copied", 3, 10, &[]), "Copied 3 of 10 entries");
+    }
+
+    #[test]
+    fn test_transfer_progress_eta() {
+        let started = Instant::now();
+        let mut progress = TransferProgress::new("Copying", started);
+        progress.total = 300;
+        progress.done = 100;
+        assert_eq!(progress.eta(started + Duration::from_millis(500)), None);
+        assert_eq!(
+            progress.eta(started + Duration::from_secs(2)),
+            Some(Duration::from_secs(4))
+        );
+    }
+
+    #[test]
+    fn test_permission_edit() {
+        let mut edit = PermissionEdit {
+            path: PathBuf::from("file"),
+            mode: 0o4644,
+            cursor: 0,
+            input: String::new(),
+        };
+
+        // 所有者の実行ビットを切り替え
+        edit.move_cursor(0, 2);
+        edit.toggle();
+        assert_eq!(edit.mode, 0o4744);
+        edit.move_cursor(5, 5);
+        assert_eq!(edit.cursor, 8);
+
+        // 3桁の8進数は上位ビットを保ち、4桁なら置き換える
+        for c in "750".chars() {
+            edit.push_digit(c);
+        }
+        assert_eq!(edit.mode, 0o4750);
+        edit.input.clear();
+        for c in "0640".chars() {
+            edit.push_digit(c);
+        }
+        assert_eq!(edit.mode, 0o640);
+        edit.push_digit('9');
+        assert_eq!(edit.input, "0640");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_chmod_applies_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("run.sh");
+        std::fs::write(&file, "").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.start_chmod();
+        assert_eq!(app.input_mode, InputMode::Chmod);
+        for c in "755".chars() {
+            app.chmod_digit(c);
         }
-    }
+        app.confirm_chmod();
 
-    pub fn search_input_backspace(&mut self) {
-        self.search_input.pop();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let mode = std::fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert_eq!(
+            app.browser.selected_entry().unwrap().mode.unwrap() & 0o777,
+            0o755
+        );
     }
 
-    pub fn search_move_up(&mut self) {
-        if self.search_results.is_empty() {
-            return;
-        }
-        if self.search_selected > 0 {
-            self.search_selected -= 1;
-        } else {
-            self.search_selected = self.search_results.len() - 1;
-        }
-        self.search_list_state.select(Some(self.search_selected));
-    }
+    #[test]
+    fn test_dir_sizes_fill_in_with_details() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        std::fs::write(temp_dir.path().join("sub/a.bin"), vec![0u8; 64]).unwrap();
 
-    pub fn search_move_down(&mut self) {
-        if self.search_results.is_empty() {
-            return;
+        let mut app = test_app(temp_dir.path(), Config::default());
+        assert!(!app.poll_dir_sizes());
+        app.toggle_details();
+        let sub = app.browser.current_dir.join("sub");
+        while !app.dir_sizes.contains_key(&sub) {
+            app.poll_dir_sizes();
+            std::thread::sleep(std::time::Duration::from_millis(10));
         }
-        if self.search_selected < self.search_results.len() - 1 {
-            self.search_selected += 1;
-        } else {
-            self.search_selected = 0;
+        assert_eq!(app.dir_sizes[&sub], 64);
+
+        // 一覧を読み直しても新しい値が届くまでは前のサイズのまま
+        std::fs::write(temp_dir.path().join("sub/b.bin"), vec![0u8; 36]).unwrap();
+        app.dir_sizes_stale = true;
+        app.stop_dir_sizes();
+        assert!(app.dir_size_cancel.load(Ordering::Relaxed));
+        assert_eq!(app.dir_sizes[&sub], 64);
+        while app.dir_sizes[&sub] == 64 {
+            app.poll_dir_sizes();
+            std::thread::sleep(std::time::Duration::from_millis(10));
         }
-        self.search_list_state.select(Some(self.search_selected));
+        assert_eq!(app.dir_sizes[&sub], 100);
     }
 
-    pub fn scroll_preview_up(&mut self, amount: usize) {
-        self.preview_scroll = self.preview_scroll.saturating_sub(amount);
-    }
+    #[test]
+    fn test_recent_files() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        std::fs::write(temp_dir.path().join("sub/notes.txt"), "hi").unwrap();
 
-    pub fn scroll_preview_down(&mut self, amount: usize) {
-        if let Some(ref content) = self.preview_content {
-            let max_scroll = content.lines.len().saturating_sub(self.preview_height);
-            self.preview_scroll = (self.preview_scroll + amount).min(max_scroll);
-        }
-    }
+        let mut app = test_app(&temp_dir.path().join("sub"), Config::default());
+        app.enter();
+        assert_eq!(app.input_mode, InputMode::Preview);
+        let notes = app.browser.current_dir.join("notes.txt");
+        assert_eq!(app.recent.files, vec![notes.clone()]);
 
-    pub fn set_preview_height(&mut self, height: usize) {
-        self.preview_height = height;
+        app.input_mode = InputMode::Normal;
+        app.go_parent();
+        app.open_recent();
+        assert_eq!(app.input_mode, InputMode::Recent);
+        app.open_recent_file();
+        assert_eq!(app.input_mode, InputMode::Preview);
+        assert_eq!(app.browser.selected_entry().unwrap().path, notes);
     }
 
-    pub fn quit(&mut self) {
-        self.should_quit = true;
-    }
+    #[test]
+    fn test_start_with_file_opens_preview() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("src")).unwrap();
+        std::fs::write(temp_dir.path().join("src/a.rs"), "fn a() {}").unwrap();
+        std::fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(temp_dir.path().join("src/.env"), "KEY=1").unwrap();
 
-    pub fn copy_path(&mut self) {
-        if let Some(entry) = self.browser.selected_entry() {
-            let path_str = entry.path.to_string_lossy().to_string();
+        let app = test_app(&temp_dir.path().join("src/main.rs"), Config::default());
+        assert_eq!(app.input_mode, InputMode::Preview);
+        let src = temp_dir.path().join("src").canonicalize().unwrap();
+        assert_eq!(app.browser.current_dir, src);
+        assert_eq!(app.browser.selected_entry().unwrap().name, "main.rs");
+        assert!(app.preview_content.is_some());
 
-            #[cfg(target_os = "macos")]
-            let result = std::process::Command::new("pbcopy")
-                .stdin(std::process::Stdio::piped())
-                .spawn()
-                .and_then(|mut child| {
-                    use std::io::Write;
-                    if let Some(stdin) = child.stdin.as_mut() {
-                        stdin.write_all(path_str.as_bytes())?;
-                    }
-                    child.wait()
-                });
+        // 隠しファイルを指定した場合は隠しファイルを表示する
+        let app = test_app(&temp_dir.path().join("src/.env"), Config::default());
+        assert_eq!(app.input_mode, InputMode::Preview);
+        assert_eq!(app.browser.selected_entry().unwrap().name, ".env");
+    }
 
-            #[cfg(target_os = "linux")]
-            let result = std::process::Command::new("xclip")
-                .args(["-selection", "clipboard"])
-                .stdin(std::process::Stdio::piped())
-                .spawn()
-                .and_then(|mut child| {
-                    use std::io::Write;
-                    if let Some(stdin) = child.stdin.as_mut() {
-                        stdin.write_all(path_str.as_bytes())?;
-                    }
-                    child.wait()
-                });
+    #[test]
+    fn test_path_list_filter_and_open() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path().canonicalize().unwrap();
+        std::fs::create_dir(base.join("src")).unwrap();
+        std::fs::write(base.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(base.join("README.md"), "# readme").unwrap();
 
-            #[cfg(target_os = "windows")]
-            let result = std::process::Command::new("clip")
-                .stdin(std::process::Stdio::piped())
-                .spawn()
-                .and_then(|mut child| {
-                    use std::io::Write;
-                    if let Some(stdin) = child.stdin.as_mut() {
-                        stdin.write_all(path_str.as_bytes())?;
-                    }
-                    child.wait()
-                });
+        let mut app = test_app(&base, Config::default());
+        let paths = ["README.md", "src/main.rs", "src"]
+            .iter()
+            .map(|p| base.join(p).to_string_lossy().to_string())
+            .collect();
+        app.open_path_list(paths);
+        assert_eq!(app.input_mode, InputMode::PathList);
+        assert_eq!(app.path_matches, vec![0, 1, 2]);
 
-            match result {
-                Ok(_) => {
-                    self.status_message = Some(format!("Copied: {}", path_str));
-                }
-                Err(e) => {
-                    self.status_message = Some(format!("Failed to copy: {}", e));
-                }
-            }
+        for c in "main".chars() {
+            app.path_filter_char(c);
         }
-    }
+        assert_eq!(app.path_matches, vec![1]);
+        app.open_path_list_entry();
+        assert_eq!(app.input_mode, InputMode::Preview);
+        assert_eq!(app.browser.selected_entry().unwrap().name, "main.rs");
 
-    pub fn start_jump(&mut self) {
-        self.input_mode = InputMode::JumpInput;
+        // プレビューを閉じると一覧に戻り、Esc で絞り込みを解除してからブラウザへ
+        app.exit_preview();
+        assert_eq!(app.input_mode, InputMode::PathList);
+        app.close_path_list();
+        assert_eq!(app.path_matches.len(), 3);
+        app.path_list_move(2);
+        app.open_path_list_entry();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.browser.current_dir, base.join("src"));
     }
 
-    pub fn execute_jump(&mut self, c: char) {
-        self.last_jump_char = Some(c);
-        self.jump_to_char(c, true);
-        self.input_mode = InputMode::Normal;
-    }
+    #[test]
+    fn test_reveal_selects_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("src/deep")).unwrap();
+        std::fs::write(temp_dir.path().join("src/b.rs"), "").unwrap();
+        std::fs::write(temp_dir.path().join("src/deep/c.rs"), "").unwrap();
+        std::fs::write(temp_dir.path().join("z.txt"), "").unwrap();
 
-    pub fn jump_next(&mut self) {
-        if let Some(c) = self.last_jump_char {
-            self.jump_to_char(c, true);
-        }
+        let mut app = test_app(temp_dir.path(), Config::default());
+        let base = app.browser.current_dir.clone();
+        assert_eq!(app.reveal(&base.join("z.txt")), Some(base.join("z.txt")));
+        assert_eq!(app.browser.selected_entry().unwrap().name, "z.txt");
+        assert_eq!(app.list_state.selected(), Some(app.browser.selected_index));
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        // 別のディレクトリのエントリはその親に移動して選択する
+        let deep = base.join("src/deep/c.rs");
+        assert_eq!(app.reveal(&deep), Some(deep));
+        assert_eq!(app.browser.current_dir, base.join("src/deep"));
+
+        // ないパスでは今のディレクトリに留まる
+        assert_eq!(app.reveal(&base.join("missing.rs")), None);
+        assert_eq!(app.browser.current_dir, base.join("src/deep"));
+        assert_eq!(app.browser.selected_entry().unwrap().name, "c.rs");
+        assert_eq!(app.reveal(&base.join("no/such/dir.rs")), None);
+        assert_eq!(app.browser.current_dir, base.join("src/deep"));
     }
 
-    pub fn jump_prev(&mut self) {
-        if let Some(c) = self.last_jump_char {
-            self.jump_to_char(c, false);
-        }
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_link() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(base.join("real/deep")).unwrap();
+        std::fs::create_dir(base.join("links")).unwrap();
+        std::os::unix::fs::symlink("../real/deep", base.join("links/shortcut")).unwrap();
+
+        let mut app = test_app(&base.join("links"), Config::default());
+        app.follow_link();
+        assert_eq!(app.browser.current_dir, base.join("real"));
+        assert_eq!(
+            app.browser.selected_entry().unwrap().path,
+            base.join("real/deep")
+        );
+
+        // リンクでなければ移動しない
+        app.follow_link();
+        assert_eq!(app.browser.current_dir, base.join("real"));
+        assert!(!app.toasts.is_empty());
     }
 
-    fn jump_to_char(&mut self, c: char, forward: bool) {
-        let entries = &self.browser.entries;
-        if entries.is_empty() {
-            return;
+    #[test]
+    fn test_apply_config() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["file10.txt", "file2.txt", "junk.pyc"] {
+            std::fs::write(temp_dir.path().join(name), "x").unwrap();
         }
 
-        let c_lower = c.to_lowercase().next().unwrap_or(c);
-        let current = self.browser.selected_index;
-        let len = entries.len();
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.go_to_bottom();
+        assert_eq!(app.browser.selected_entry().unwrap().name, "junk.pyc");
 
-        if forward {
-            // 現在位置の次から検索、末尾まで行ったら先頭から
-            for i in 1..=len {
-                let idx = (current + i) % len;
-                if entries[idx].name.to_lowercase().starts_with(c_lower) {
-                    self.browser.selected_index = idx;
-                    self.list_state.select(Some(idx));
-                    self.update_preview();
-                    return;
-                }
-            }
-        } else {
-            // 現在位置の前から検索、先頭まで行ったら末尾から
-            for i in 1..=len {
-                let idx = (current + len - i) % len;
-                if entries[idx].name.to_lowercase().starts_with(c_lower) {
-                    self.browser.selected_index = idx;
-                    self.list_state.select(Some(idx));
-                    self.update_preview();
-                    return;
-                }
-            }
-        }
+        app.apply_config(Config {
+            natural_sort: true,
+            browser_ignore: vec!["*.pyc".to_string()],
+            keys: [(
+                "move_down".to_string(),
+                crate::keymap::KeySpec::One("n".to_string()),
+            )]
+            .into(),
+            preview_max_lines: 7,
+            ..Config::default()
+        });
 
-        self.status_message = Some(format!("No match for '{}'", c));
+        let names: Vec<&str> = app
+            .browser
+            .entries
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["file2.txt", "file10.txt"]);
+        assert_eq!(app.previewer.max_lines, 7);
+        assert!(app.config.natural_sort);
+        let n = crossterm::event::KeyEvent::new(
+            crossterm::event::KeyCode::Char('n'),
+            crossterm::event::KeyModifiers::NONE,
+        );
+        assert_eq!(
+            app.keymap.action(crate::keymap::KeyContext::Browser, n),
+            Some(crate::keymap::Action::MoveDown)
+        );
+        assert!(app.toasts.is_empty());
     }
 
-    pub fn cancel_jump(&mut self) {
-        self.input_mode = InputMode::Normal;
+    #[test]
+    fn test_preview_pane_toggle_and_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = test_app(temp_dir.path(), Config::default());
+        assert!(!app.show_preview_pane);
+        app.toggle_preview_pane();
+        assert!(app.show_preview_pane);
+
+        // 設定が変わらなければ v で切り替えた状態を保つ
+        app.apply_config(Config::default());
+        assert!(app.show_preview_pane);
+        app.toggle_preview_pane();
+        app.apply_config(Config {
+            preview_pane: true,
+            ..Config::default()
+        });
+        assert!(app.show_preview_pane);
+        app.apply_config(Config::default());
+        assert!(!app.show_preview_pane);
+
+        let app = test_app(
+            temp_dir.path(),
+            Config {
+                preview_pane: true,
+                ..Config::default()
+            },
+        );
+        assert!(app.show_preview_pane);
     }
 
-    pub fn show_help(&mut self) {
-        self.input_mode = InputMode::Help;
+    #[test]
+    fn test_move_split() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = test_app(
+            temp_dir.path(),
+            Config {
+                preview_pane_ratio: 95,
+                ..Config::default()
+            },
+        );
+        // 設定値も範囲内に収める
+        assert_eq!(app.preview_ratio, 90);
+
+        app.move_split(-1);
+        assert!(app.toasts.latest().unwrap().starts_with("No split"));
+
+        app.toggle_preview_pane();
+        app.move_split(1);
+        assert_eq!(app.preview_ratio, 85);
+        app.move_split(-1);
+        app.move_split(-1);
+        assert_eq!(app.preview_ratio, 90);
+        assert_eq!(app.saved_layout.preview_pane_ratio, Some(90));
+
+        // 2ペイン表示中はペインの境界を動かす
+        app.toggle_dual_pane();
+        app.move_split(-1);
+        assert_eq!(app.dual_ratio, 45);
+        assert_eq!(app.preview_ratio, 90);
+        assert_eq!(app.toasts.latest(), Some("Left pane: 45%"));
+
+        // 次の起動では保存した幅を使うが、config を変えたらそちらに従う
+        let saved = app.saved_layout.clone();
+        let config = |ratio| Config {
+            preview_pane_ratio: ratio,
+            ..Config::default()
+        };
+        let app = App::with_saved_state(
+            temp_dir.path(),
+            config(95),
+            RecentFiles::default(),
+            saved.clone(),
+        );
+        assert_eq!(app.preview_ratio, 90);
+        assert_eq!(app.dual_ratio, 45);
+        let app = App::with_saved_state(temp_dir.path(), config(30), RecentFiles::default(), saved);
+        assert_eq!(app.preview_ratio, 30);
+        assert_eq!(app.dual_ratio, 45);
     }
 
-    pub fn close_help(&mut self) {
-        self.input_mode = InputMode::Normal;
+    #[cfg(unix)]
+    #[test]
+    fn test_breadcrumbs_jump_up() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path().canonicalize().unwrap();
+        let deep = base.join("a/bb/ccc");
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::write(base.join("a/file.txt"), "x").unwrap();
+
+        let mut app = test_app(&deep, Config::default());
+        let crumbs = app.breadcrumbs();
+        assert_eq!(crumbs[0].name, "/");
+        assert_eq!(crumbs[0].columns, 0..1);
+        let last = crumbs.last().unwrap();
+        assert_eq!(last.path, deep);
+        assert_eq!(last.name, "ccc");
+        // 表示は元のパス文字列と同じ幅になる
+        assert_eq!(last.columns.end as usize, deep.to_string_lossy().len());
+
+        app.start_breadcrumb();
+        let numbered = app.breadcrumbs();
+        assert_eq!(numbered.last().unwrap().number, None);
+        assert_eq!(numbered[numbered.len() - 2].number, Some(1));
+        app.jump_up(2);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.browser.current_dir, base.join("a"));
+        assert_eq!(app.browser.selected_entry().unwrap().name, "bb");
+
+        // クリックした位置のディレクトリに移動する（現在のディレクトリなら何もしない）
+        let column = app.breadcrumbs().last().unwrap().columns.start;
+        app.click_header(column);
+        assert_eq!(app.browser.current_dir, base.join("a"));
+        let parent = &app.breadcrumbs()[app.breadcrumbs().len() - 2];
+        let (path, column) = (parent.path.clone(), parent.columns.end - 1);
+        app.click_header(column);
+        assert_eq!(app.browser.current_dir, path);
+        assert_eq!(app.browser.selected_entry().unwrap().name, "a");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
+    #[cfg(unix)]
+    #[test]
+    fn test_narrow_screen_abbreviates_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path().canonicalize().unwrap();
+        let deep = base.join(".config/vfv");
+        std::fs::create_dir_all(&deep).unwrap();
 
-    fn create_test_app() -> (App, TempDir) {
+        let mut app = test_app(&deep, Config::default());
+        app.set_screen_width(100);
+        assert_eq!(app.breadcrumbs().last().unwrap().name, "vfv");
+        app.set_screen_width(40);
+        let crumbs = app.breadcrumbs();
+        assert_eq!(crumbs[0].name, "/");
+        assert_eq!(crumbs[crumbs.len() - 2].name, ".c");
+        assert_eq!(crumbs.last().unwrap().name, "vfv");
+
+        // 縮めた名前をクリックしても元のディレクトリに移動する
+        let column = crumbs[crumbs.len() - 2].columns.start;
+        app.click_header(column);
+        assert_eq!(app.browser.current_dir, base.join(".config"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_long_path_folds_into_ellipsis() {
         let temp_dir = TempDir::new().unwrap();
-        let config = Config::default();
-        let app = App::new(temp_dir.path(), config);
-        (app, temp_dir)
+        let base = temp_dir.path().canonicalize().unwrap();
+        let deep = base.join("aaaaaaaaaa/bbbbbbbbbb/cccccccccc/current");
+        std::fs::create_dir_all(&deep).unwrap();
+
+        let config = Config {
+            narrow_width: 0,
+            ..Config::default()
+        };
+        let mut app = test_app(&deep, config);
+        app.set_screen_width(200);
+        assert!(app.breadcrumbs().iter().all(|crumb| crumb.name != "…"));
+
+        // "/…/bbbbbbbbbb/cccccccccc/current" がちょうど収まる幅
+        app.set_screen_width(32);
+        let crumbs = app.breadcrumbs();
+        let names: Vec<&str> = crumbs.iter().map(|crumb| crumb.name.as_str()).collect();
+        assert_eq!(names, ["/", "…", "bbbbbbbbbb", "cccccccccc", "current"]);
+        assert_eq!(crumbs.last().unwrap().columns.end, 32);
+
+        // … は省いたうちいちばん深いディレクトリに移動する
+        app.click_header(crumbs[1].columns.start);
+        assert_eq!(app.browser.current_dir, base.join("aaaaaaaaaa"));
     }
 
     #[test]
-    fn test_parse_search_input_simple() {
-        let (mut app, _temp) = create_test_app();
-        app.search_input = "main.rs".to_string();
+    fn test_click_toast_dismisses_it() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.toasts.info("Copied");
+        app.toasts.error("Failed to delete a");
+        app.toast_areas = vec![Rect::new(40, 1, 20, 3), Rect::new(40, 4, 30, 3)];
 
-        let (query, dirs_only, exact, base_path) = app.parse_search_input();
-        assert_eq!(query, "main.rs");
-        assert!(!dirs_only);
-        assert!(!exact);
-        assert!(base_path.is_none());
+        assert!(!app.click_toast(10, 2));
+        assert!(app.click_toast(45, 2));
+        assert_eq!(app.toasts.latest(), Some("Failed to delete a"));
+        assert_eq!(app.toasts.iter().count(), 1);
+        // キーを押しても通知は消えない
+        app.move_down();
+        assert!(!app.toasts.is_empty());
     }
 
     #[test]
-    fn test_parse_search_input_with_options() {
-        let (mut app, _temp) = create_test_app();
-        app.search_input = "config -e -d".to_string();
+    fn test_places_menu() {
+        let temp_dir = TempDir::new().unwrap();
+        let mount = temp_dir.path().join("mnt");
+        std::fs::create_dir(&mount).unwrap();
 
-        let (query, dirs_only, exact, _) = app.parse_search_input();
-        assert_eq!(query, "config");
-        assert!(dirs_only);
-        assert!(exact);
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.open_places();
+        assert_eq!(app.input_mode, InputMode::Places);
+        app.places = vec![
+            Place {
+                name: "/".to_string(),
+                path: PathBuf::from("/"),
+            },
+            Place {
+                name: "mnt".to_string(),
+                path: mount.clone(),
+            },
+        ];
+        app.places_selected = 0;
+        app.places_move(-1);
+        assert_eq!(app.places_selected, 1);
+        app.confirm_places();
+
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.browser.current_dir, mount);
+        assert!(app.places.is_empty());
     }
 
     #[test]
-    fn test_parse_search_input_with_base_path() {
-        let (mut app, _temp) = create_test_app();
-        app.search_input = "main -b /tmp".to_string();
+    fn test_open_with_menu() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("file.txt"), "").unwrap();
+        let mut config = Config::default();
+        config.openers.insert(
+            "less".to_string(),
+            opener::OpenerSpec::Command("less -R".to_string()),
+        );
 
-        let (query, _, _, base_path) = app.parse_search_input();
-        assert_eq!(query, "main");
-        assert_eq!(base_path, Some(PathBuf::from("/tmp")));
+        let mut app = test_app(temp_dir.path(), config);
+        app.start_open_with();
+        assert_eq!(app.input_mode, InputMode::OpenWith);
+        assert_eq!(app.open_with[0].name, "less");
+
+        // 末尾から先頭へ折り返す
+        app.open_with_move(-1);
+        assert_eq!(app.open_with_selected, app.open_with.len() - 1);
+        app.open_with_move(1);
+        assert_eq!(app.open_with_selected, 0);
+
+        app.cancel_open_with();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.open_with.is_empty());
     }
 
     #[test]
-    fn test_parse_search_input_with_home_expansion() {
-        let (mut app, _temp) = create_test_app();
-        app.search_input = "main -b ~/dev".to_string();
+    fn test_archive_marked_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "a").unwrap();
+        std::fs::write(temp_dir.path().join("b.txt"), "b").unwrap();
 
-        let (query, _, _, base_path) = app.parse_search_input();
-        assert_eq!(query, "main");
-        assert!(base_path.is_some());
-        let path = base_path.unwrap();
-        assert!(path.to_string_lossy().contains("dev"));
-        assert!(!path.to_string_lossy().starts_with("~"));
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.start_archive();
+        assert_eq!(app.name_input.text(), "a.txt.zip");
+        app.cancel_name_input();
+
+        app.toggle_mark();
+        app.toggle_mark();
+        app.start_archive();
+        assert_eq!(app.input_mode, InputMode::Archive);
+        app.name_input.set("both.tar.gz");
+        app.confirm_name_input();
+        wait_for_transfer(&mut app);
+
+        assert!(temp_dir.path().join("both.tar.gz").is_file());
+        assert!(app.browser.marked.is_empty());
+        assert_eq!(app.browser.selected_entry().unwrap().name, "both.tar.gz");
     }
 
     #[test]
-    fn test_input_mode_transitions() {
-        let (mut app, _temp) = create_test_app();
+    fn test_zoxide_prompt_accepts_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.start_zoxide();
+        assert_eq!(app.input_mode, InputMode::ZoxideInput);
+        app.path_input.set("sub");
+        app.confirm_name_input();
 
         assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.browser.current_dir, temp_dir.path().join("sub"));
+    }
 
-        app.start_search();
-        assert_eq!(app.input_mode, InputMode::SearchInput);
+    fn wait_for_disk_usage(app: &mut App) {
+        while !app.poll_disk_usage() {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
 
-        app.cancel_search();
-        assert_eq!(app.input_mode, InputMode::Normal);
+    #[test]
+    fn test_disk_usage_drill_down_and_delete() {
+        use std::fs;
 
-        app.show_help();
-        assert_eq!(app.input_mode, InputMode::Help);
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("big/inner")).unwrap();
+        fs::write(temp_dir.path().join("big/inner/data.bin"), vec![0u8; 1000]).unwrap();
+        fs::write(temp_dir.path().join("small.txt"), "x").unwrap();
 
-        app.close_help();
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.config.delete_to_trash = false;
+        app.open_disk_usage();
+        assert_eq!(app.input_mode, InputMode::DiskUsage);
+        wait_for_disk_usage(&mut app);
+        assert_eq!(app.selected_usage_entry().unwrap().name, "big");
+        assert_eq!(app.selected_usage_entry().unwrap().size, 1000);
+
+        // 降りて戻ると元のディレクトリが選択される
+        app.usage_enter();
+        wait_for_disk_usage(&mut app);
+        assert_eq!(app.selected_usage_entry().unwrap().name, "inner");
+        app.usage_parent();
+        wait_for_disk_usage(&mut app);
+        assert_eq!(app.selected_usage_entry().unwrap().name, "big");
+
+        app.start_usage_delete();
+        assert_eq!(app.input_mode, InputMode::Confirm);
+        app.confirm_usage_delete();
+        assert_eq!(app.input_mode, InputMode::DiskUsage);
+        assert!(!temp_dir.path().join("big").exists());
+        assert_eq!(app.usage_entries.len(), 1);
+
+        app.close_disk_usage();
         assert_eq!(app.input_mode, InputMode::Normal);
     }
 
     #[test]
-    fn test_search_input_manipulation() {
-        let (mut app, _temp) = create_test_app();
+    fn test_pick() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("dir")).unwrap();
+        std::fs::write(temp_dir.path().join("dir/inner.txt"), "").unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "").unwrap();
+        std::fs::write(temp_dir.path().join("b.txt"), "").unwrap();
+
+        // ディレクトリでは中に入り、ファイルで選んで終了
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.pick();
+        assert!(!app.should_quit);
+        app.pick();
+        assert!(app.should_quit);
+        assert_eq!(app.chosen.len(), 1);
+        assert!(app.chosen[0].ends_with("dir/inner.txt"));
+
+        // マークがあればマークしたものを全部選ぶ
+        let mut app = test_app(temp_dir.path(), Config::default());
+        app.move_down();
+        app.toggle_mark();
+        app.toggle_mark();
+        app.pick();
+        assert!(app.should_quit);
+        let names: Vec<String> = app.chosen.iter().map(|p| display_name(p)).collect();
+        assert_eq!(names, ["a.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn test_bookmarks() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir(&project).unwrap();
+
+        let mut app = test_app(&project, Config::default());
+        app.bookmarks = Bookmarks::load_from(temp_dir.path().join("bookmarks.toml"));
 
-        app.search_input_char('h');
-        app.search_input_char('e');
-        app.search_input_char('l');
-        app.search_input_char('l');
-        app.search_input_char('o');
+        app.start_set_bookmark();
+        assert_eq!(app.input_mode, InputMode::SetBookmark);
+        app.set_bookmark('p');
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        app.browser.change_dir(temp_dir.path());
+        app.start_jump_bookmark();
+        assert_eq!(app.input_mode, InputMode::JumpBookmark);
+        app.jump_to_bookmark('p');
+        assert_eq!(app.browser.current_dir, project.canonicalize().unwrap());
 
-        assert_eq!(app.search_input, "hello");
+        app.jump_to_bookmark('z');
+        assert!(app.toasts.latest().unwrap().contains("No bookmark"));
+        assert_eq!(app.browser.current_dir, project.canonicalize().unwrap());
 
-        app.search_input_backspace();
-        assert_eq!(app.search_input, "hell");
+        // 保存したブックマークは再読み込みしても残る
+        let reloaded = Bookmarks::load_from(temp_dir.path().join("bookmarks.toml"));
+        assert_eq!(
+            reloaded.get("p"),
+            Some(project.canonicalize().unwrap().as_path())
+        );
     }
 
     #[test]
-    fn test_preview_scroll_up() {
-        let (mut app, _temp) = create_test_app();
+    fn test_dual_pane_transfer() {
+        use std::fs;
 
-        // Set initial scroll position
-        app.preview_scroll = 10;
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("dest")).unwrap();
+        fs::write(temp_dir.path().join("file.txt"), "x").unwrap();
+        let mut app = test_app(temp_dir.path(), Config::default());
 
-        app.scroll_preview_up(3);
-        assert_eq!(app.preview_scroll, 7);
+        app.toggle_dual_pane();
+        assert!(app.other_browser.is_some());
 
-        app.scroll_preview_up(10);
-        assert_eq!(app.preview_scroll, 0); // saturating_sub prevents negative
+        // 右ペインで dest/ に入り、左ペインに戻る
+        app.switch_pane();
+        assert_eq!(app.active_pane, 1);
+        app.enter();
+        app.switch_pane();
+        assert_eq!(app.active_pane, 0);
+        assert_eq!(
+            app.browser.current_dir,
+            temp_dir.path().canonicalize().unwrap()
+        );
+
+        app.move_down();
+        app.transfer_to_other_pane(TransferKind::Move);
+        wait_for_transfer(&mut app);
+
+        assert!(temp_dir.path().join("dest/file.txt").exists());
+        assert!(!temp_dir.path().join("file.txt").exists());
+        assert_eq!(app.other_browser.as_ref().unwrap().entries.len(), 1);
+
+        app.toggle_dual_pane();
+        assert!(app.other_browser.is_none());
     }
 
     #[test]
@@ -669,14 +5116,14 @@ mod tests {
 
         // 1000文字まで入力できることを確認
         for _ in 0..1000 {
-            app.search_input_char('a');
+            app.search_input.insert('a');
         }
-        assert_eq!(app.search_input.len(), 1000);
+        assert_eq!(app.search_input.text().len(), 1000);
 
         // 1001文字目は追加されないことを確認
-        app.search_input_char('b');
-        assert_eq!(app.search_input.len(), 1000);
-        assert!(!app.search_input.contains('b'));
+        app.search_input.insert('b');
+        assert_eq!(app.search_input.text().len(), 1000);
+        assert!(!app.search_input.text().contains('b'));
     }
 
     #[test]
@@ -685,16 +5132,16 @@ mod tests {
 
         // 検索入力を設定（-d と -b オプション付き）
         let search_dir = temp.path().to_string_lossy().to_string();
-        app.search_input = format!("test -d -b {}", search_dir);
+        app.search_input.set(format!("test -d -b {}", search_dir));
 
         // 初期状態を確認
-        assert!(!app.search_dirs_only);
+        assert!(!app.search_options.dirs_only);
 
         // 検索を実行
         app.execute_search();
 
         // 状態が更新されていることを確認
-        assert!(app.search_dirs_only);
+        assert!(app.search_options.dirs_only);
         assert_eq!(app.base_dir, temp.path().to_path_buf());
         assert_eq!(app.input_mode, InputMode::Searching);
     }
@@ -703,7 +5150,7 @@ mod tests {
     fn test_execute_search_uses_current_dir_as_default_base() {
         let (mut app, _temp) = create_test_app();
 
-        app.search_input = "test".to_string();
+        app.search_input.set("test");
 
         // -b オプションなしで検索実行
         app.execute_search();
@@ -712,6 +5159,42 @@ mod tests {
         assert_eq!(app.base_dir, app.browser.current_dir);
     }
 
+    #[test]
+    fn test_search_flags_persist_across_searches() {
+        let (mut app, _temp) = create_test_app();
+
+        app.start_search();
+        app.search_input.set("test -d -I");
+        app.execute_search();
+        app.cancel_search();
+
+        // 次の検索でもフラグが記憶されている
+        app.start_search();
+        assert!(app.search_options.dirs_only);
+        assert!(app.search_options.no_ignore);
+
+        app.search_input.set("test");
+        let (_, options, _) = app.parse_search_input();
+        assert!(options.dirs_only);
+        assert!(options.no_ignore);
+        assert!(!options.exact);
+    }
+
+    #[test]
+    fn test_toggle_search_flags() {
+        let (mut app, _temp) = create_test_app();
+        // 隠しファイルは vfv find と同じく既定で検索する
+        assert!(app.search_options.hidden);
+
+        app.toggle_search_exact();
+        app.toggle_search_hidden();
+        assert!(app.search_options.exact);
+        assert!(!app.search_options.hidden);
+
+        app.toggle_search_exact();
+        assert!(!app.search_options.exact);
+    }
+
     #[test]
     fn test_confirm_search_result_with_hidden_file() {
         use std::fs::File;
@@ -721,7 +5204,7 @@ mod tests {
         File::create(&hidden_file).unwrap();
 
         let config = Config::default(); // show_hidden = false
-        let mut app = App::new(temp_dir.path(), config);
+        let mut app = test_app(temp_dir.path(), config);
 
         // 隠しファイルを検索結果としてセット
         app.search_results = vec![SearchResult {
@@ -752,7 +5235,7 @@ mod tests {
         fs::create_dir(&hidden_dir).unwrap();
 
         let config = Config::default(); // show_hidden = false
-        let mut app = App::new(temp_dir.path(), config);
+        let mut app = test_app(temp_dir.path(), config);
 
         // 隠しディレクトリを検索結果としてセット
         app.search_results = vec![SearchResult {
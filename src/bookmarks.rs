@@ -0,0 +1,134 @@
+use directories::ProjectDirs;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Named directory bookmarks, persisted as TOML
+///
+/// The TUI sets and jumps to single-character names; `vfv bookmarks` can use
+/// longer ones.
+#[derive(Debug, Clone, Default)]
+pub struct Bookmarks {
+    pub marks: BTreeMap<String, PathBuf>,
+    /// Where to save; `None` keeps bookmarks in memory only
+    path: Option<PathBuf>,
+}
+
+impl Bookmarks {
+    /// Load bookmarks from the config dir (missing or broken files yield none)
+    pub fn load() -> Self {
+        Self::load_from(Self::bookmarks_path())
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let marks = fs::read_to_string(&path)
+            .ok()
+            .map(|content| parse(&content))
+            .unwrap_or_default();
+        Self {
+            marks,
+            path: Some(path),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Path> {
+        self.marks.get(name).map(PathBuf::as_path)
+    }
+
+    /// Bookmark a directory and save immediately
+    pub fn set(&mut self, name: &str, dir: &Path) -> io::Result<()> {
+        self.marks.insert(name.to_string(), dir.to_path_buf());
+        self.save()
+    }
+
+    /// Delete a bookmark and save; `Ok(false)` when it did not exist
+    pub fn remove(&mut self, name: &str) -> io::Result<bool> {
+        if self.marks.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Names reachable from the TUI (single characters)
+    pub fn keys(&self) -> String {
+        self.marks
+            .keys()
+            .filter(|name| name.chars().count() == 1)
+            .map(String::as_str)
+            .collect()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let table: BTreeMap<String, String> = self
+            .marks
+            .iter()
+            .map(|(name, dir)| (name.clone(), dir.to_string_lossy().to_string()))
+            .collect();
+        let content = toml::to_string(&table).map_err(io::Error::other)?;
+        fs::write(path, content)
+    }
+
+    pub fn bookmarks_path() -> PathBuf {
+        if let Some(proj_dirs) = ProjectDirs::from("", "", "vive-file-viewer") {
+            proj_dirs.config_dir().join("bookmarks.toml")
+        } else {
+            PathBuf::from("~/.config/vive-file-viewer/bookmarks.toml")
+        }
+    }
+}
+
+/// Parse `name = "path"` lines, ignoring empty names
+fn parse(content: &str) -> BTreeMap<String, PathBuf> {
+    let table: BTreeMap<String, String> = toml::from_str(content).unwrap_or_default();
+    table
+        .into_iter()
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, dir)| (name, PathBuf::from(dir)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested/bookmarks.toml");
+
+        let mut bookmarks = Bookmarks::load_from(path.clone());
+        assert!(bookmarks.marks.is_empty());
+        bookmarks.set("a", Path::new("/tmp/project")).unwrap();
+        bookmarks.set("'", Path::new("/home")).unwrap();
+        bookmarks.set("work", Path::new("/srv/work")).unwrap();
+
+        let mut loaded = Bookmarks::load_from(path.clone());
+        assert_eq!(loaded.get("a"), Some(Path::new("/tmp/project")));
+        assert_eq!(loaded.get("'"), Some(Path::new("/home")));
+        assert_eq!(loaded.get("work"), Some(Path::new("/srv/work")));
+        assert_eq!(loaded.get("b"), None);
+        assert_eq!(loaded.keys(), "'a");
+
+        assert!(loaded.remove("work").unwrap());
+        assert!(!loaded.remove("work").unwrap());
+        assert_eq!(Bookmarks::load_from(path).get("work"), None);
+    }
+
+    #[test]
+    fn test_parse_ignores_invalid_keys() {
+        let marks = parse("a = \"/one\"\nlong = \"/two\"\n\"\" = \"/three\"\n");
+        assert_eq!(marks.len(), 2);
+        assert_eq!(marks.get("a"), Some(&PathBuf::from("/one")));
+        assert_eq!(marks.get("long"), Some(&PathBuf::from("/two")));
+        assert!(parse("not toml").is_empty());
+    }
+}
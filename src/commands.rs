@@ -0,0 +1,144 @@
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Fill a `[commands]` template: `{path}` is the selected entry, `{dir}` the
+/// current directory and `{marked}` the marked entries (the selected one when
+/// nothing is marked). Every path is quoted for the shell.
+pub fn expand(template: &str, path: Option<&Path>, dir: &Path, marked: &[PathBuf]) -> String {
+    let path = path.map(|p| shell_quote(&p.to_string_lossy()));
+    let dir = shell_quote(&dir.to_string_lossy());
+    let marked = marked
+        .iter()
+        .map(|p| shell_quote(&p.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let values = [
+        ("{path}", OsStr::new(path.as_deref().unwrap_or(""))),
+        ("{dir}", OsStr::new(&dir)),
+        ("{marked}", OsStr::new(&marked)),
+    ];
+    // 値はすべて UTF-8 の文字列なので失われるものはない
+    fill(template, &values).to_string_lossy().into_owned()
+}
+
+/// `template` with each placeholder of `values` replaced in a single pass
+/// from left to right; inserted values are never scanned again, so a file
+/// named `{dir}` stays a file name
+pub fn fill(template: &str, values: &[(&str, &OsStr)]) -> OsString {
+    let mut filled = OsString::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push(&rest[..start]);
+        rest = &rest[start..];
+        match values
+            .iter()
+            .find(|(placeholder, _)| rest.starts_with(placeholder))
+        {
+            Some((placeholder, value)) => {
+                filled.push(value);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                filled.push("{");
+                rest = &rest[1..];
+            }
+        }
+    }
+    filled.push(rest);
+    filled
+}
+
+/// Process running `line` through the platform shell
+pub fn shell_command(line: &str) -> Command {
+    #[cfg(windows)]
+    {
+        // cmd は MSVC 流のエスケープを解さないので、行をそのまま渡す
+        use std::os::windows::process::CommandExt;
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").raw_arg(line);
+        cmd
+    }
+    #[cfg(not(windows))]
+    {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", line]);
+        cmd
+    }
+}
+
+/// Quote `s` as one shell word: single quotes for `sh`, double quotes for
+/// `cmd` with `%` kept outside them (escaped by `^`) so variables are not
+/// expanded
+pub fn shell_quote(s: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", s.replace('"', "\"\"").replace('%', "\"^%\""))
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_expand_quotes_paths() {
+        let marked = vec![PathBuf::from("/p/a b"), PathBuf::from("/p/it's")];
+        let line = expand(
+            "tar czf out.tgz {marked} && git add {path} # {dir}",
+            Some(Path::new("/p/a b")),
+            Path::new("/p"),
+            &marked,
+        );
+        assert_eq!(
+            line,
+            "tar czf out.tgz '/p/a b' '/p/it'\\''s' && git add '/p/a b' # '/p'"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shell_command_runs_expanded_line() {
+        let line = expand(
+            "printf %s {path}",
+            Some(Path::new("a'b c")),
+            Path::new("."),
+            &[],
+        );
+        let output = shell_command(&line).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "a'b c");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_placeholder_in_file_name_is_not_expanded() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let name = "{dir}'; touch pwned; '";
+        let line = expand(
+            "printf %s {path}",
+            Some(Path::new(name)),
+            temp_dir.path(),
+            &[],
+        );
+        let output = shell_command(&line)
+            .current_dir(temp_dir.path())
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), name);
+        assert!(!temp_dir.path().join("pwned").exists());
+    }
+
+    #[test]
+    fn test_fill_single_pass() {
+        let values = [("{a}", OsStr::new("{b}")), ("{b}", OsStr::new("x"))];
+        assert_eq!(fill("{a} {b} {c} {", &values), "{b} x {c} {");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_shell_quote_for_cmd() {
+        assert_eq!(shell_quote("C:\\a b\\100%"), "\"C:\\a b\\100\"^%\"\"");
+    }
+}
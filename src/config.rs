@@ -1,7 +1,20 @@
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::editor::find_executable;
+use crate::file_browser::SortMode;
+use crate::icons::IconStyle;
+use crate::keymap::{KeySpec, Keymap};
+use crate::opener::OpenerSpec;
+use crate::preview::Previewer;
+use crate::status_bar;
+use crate::ui_theme::UiTheme;
+use crate::widget_style::WidgetStyle;
 
 /// Result type for config operations
 pub type ConfigResult<T> = Result<T, ConfigError>;
@@ -14,6 +27,12 @@ pub enum ConfigError {
     ReadError(std::io::Error),
     /// Failed to parse config file
     ParseError(toml::de::Error),
+    /// Config file given with `--config` or `VFV_CONFIG` does not exist
+    NotFound(PathBuf),
+    /// Failed to read or parse a project `.vfv.toml`
+    ProjectError(PathBuf, String),
+    /// A `VFV_*` environment variable has a value the setting cannot take
+    EnvError(String, String),
     /// Config file has insecure permissions (Unix only)
     #[cfg(unix)]
     InsecurePermissions(PathBuf),
@@ -24,6 +43,11 @@ impl std::fmt::Display for ConfigError {
         match self {
             ConfigError::ReadError(e) => write!(f, "Failed to read config: {}", e),
             ConfigError::ParseError(e) => write!(f, "Failed to parse config: {}", e),
+            ConfigError::NotFound(path) => write!(f, "Config file not found: {}", path.display()),
+            ConfigError::ProjectError(path, e) => {
+                write!(f, "Failed to load {}: {}", path.display(), e)
+            }
+            ConfigError::EnvError(name, e) => write!(f, "Invalid {}: {}", name, e),
             #[cfg(unix)]
             ConfigError::InsecurePermissions(path) => {
                 write!(f, "Config file has insecure permissions: {:?}", path)
@@ -37,6 +61,8 @@ impl std::error::Error for ConfigError {
         match self {
             ConfigError::ReadError(e) => Some(e),
             ConfigError::ParseError(e) => Some(e),
+            ConfigError::NotFound(_) => None,
+            ConfigError::ProjectError(..) | ConfigError::EnvError(..) => None,
             #[cfg(unix)]
             ConfigError::InsecurePermissions(_) => None,
         }
@@ -45,24 +71,168 @@ impl std::error::Error for ConfigError {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Config format version, see [`CONFIG_VERSION`]
+    #[serde(default = "default_version")]
+    pub version: u32,
+
+    /// Editor for `e`; empty picks `$VISUAL`, `$EDITOR` or a common one
     #[serde(default = "default_editor")]
     pub editor: String,
 
     #[serde(default = "default_editor_args")]
     pub editor_args: Vec<String>,
 
+    /// Start the editor in the background instead of suspending vfv until
+    /// it exits (GUI editors such as code, subl or zed)
+    #[serde(default)]
+    pub editor_detach: bool,
+
     #[serde(default = "default_show_hidden")]
     pub show_hidden: bool,
 
+    /// Glob patterns for names hidden from the file list (even with hidden files shown)
+    #[serde(default)]
+    pub browser_ignore: Vec<String>,
+
     #[serde(default = "default_preview_max_lines")]
     pub preview_max_lines: usize,
 
-    #[serde(default = "default_theme")]
-    pub theme: String,
+    #[serde(default = "default_preview_max_bytes")]
+    pub preview_max_bytes: usize,
+
+    /// Show the preview pane beside the file list at startup
+    #[serde(default)]
+    pub preview_pane: bool,
+
+    /// Width of the preview pane in percent of the screen
+    #[serde(default = "default_split_ratio")]
+    pub preview_pane_ratio: u16,
+
+    /// Width of the left pane in dual-pane mode in percent of the screen
+    #[serde(default = "default_split_ratio")]
+    pub dual_pane_ratio: u16,
+
+    /// Terminals narrower than this many columns get the compact layout:
+    /// one pane, a shortened header path and short footer hints
+    #[serde(default = "default_narrow_width")]
+    pub narrow_width: u16,
+
+    /// Syntax highlighting theme of the preview (defaults to `ui_theme`'s)
+    #[serde(default)]
+    pub theme: Option<String>,
+
+    /// Color preset for the whole UI
+    #[serde(default)]
+    pub ui_theme: UiTheme,
+
+    /// Borders, title alignment and the selection highlight
+    #[serde(default)]
+    pub style: WidgetStyle,
+
+    #[serde(default = "default_tab_width")]
+    pub tab_width: usize,
+
+    /// File list icons: "plain" or "nerd" (requires a Nerd Font)
+    #[serde(default)]
+    pub icons: IconStyle,
+
+    /// Initial sort order of the file list
+    #[serde(default)]
+    pub sort: SortMode,
+
+    /// Start with the sort order reversed
+    #[serde(default)]
+    pub sort_reverse: bool,
+
+    /// Order numbered names numerically ("file2" before "file10")
+    #[serde(default)]
+    pub natural_sort: bool,
+
+    /// Record visited directories in zoxide's database
+    #[serde(default)]
+    pub zoxide: bool,
+
+    /// Footer template of `{segment}`s; empty shows the built-in key hints
+    #[serde(default)]
+    pub status_bar: String,
+
+    /// Capture the mouse (clicking a directory in the header path goes there).
+    /// Off by default so the terminal's own text selection keeps working.
+    #[serde(default)]
+    pub mouse: bool,
+
+    /// Turn on the kitty keyboard protocol when the terminal supports it,
+    /// so keys such as shift-enter and ctrl-i (apart from tab) can be bound
+    #[serde(default = "default_keyboard_protocol")]
+    pub keyboard_protocol: bool,
+
+    /// Move deleted entries to the system trash instead of removing them
+    #[serde(default = "default_delete_to_trash")]
+    pub delete_to_trash: bool,
+
+    /// External preview commands keyed by file extension
+    #[serde(default)]
+    pub previewers: BTreeMap<String, String>,
+
+    /// Programs offered by the "open with" menu, keyed by display name
+    #[serde(default)]
+    pub openers: BTreeMap<String, OpenerSpec>,
+
+    /// Programs that open files with these extensions on Enter/`e` instead
+    /// of the preview and the editor
+    #[serde(default)]
+    pub extension_openers: BTreeMap<String, OpenerSpec>,
+
+    /// Key bindings replacing the defaults, keyed by action name
+    #[serde(default)]
+    pub keys: BTreeMap<String, KeySpec>,
+
+    /// Shell commands run from the browser, keyed by the key that runs them
+    #[serde(default)]
+    pub commands: BTreeMap<String, String>,
+
+    /// Defaults for `vfv find` flags
+    #[serde(default)]
+    pub find: FindDefaults,
+}
+
+/// The `[find]` table: values `vfv find` uses for flags not given
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FindDefaults {
+    /// Maximum number of results
+    pub limit: usize,
+    /// Timeout in seconds (0 = no timeout)
+    pub timeout: u64,
+    /// Exact match instead of fuzzy
+    pub exact: bool,
+    /// Include hidden files
+    pub hidden: bool,
+    /// Do not respect .gitignore and other ignore files
+    pub no_ignore: bool,
+    /// Output as JSON
+    pub json: bool,
+}
+
+impl Default for FindDefaults {
+    fn default() -> Self {
+        Self {
+            limit: 20,
+            timeout: 0,
+            exact: false,
+            hidden: true,
+            no_ignore: false,
+            json: false,
+        }
+    }
+}
+
+fn default_version() -> u32 {
+    CONFIG_VERSION
 }
 
 fn default_editor() -> String {
-    "vim".to_string()
+    String::new()
 }
 
 fn default_editor_args() -> Vec<String> {
@@ -77,23 +247,149 @@ fn default_preview_max_lines() -> usize {
     1000
 }
 
-fn default_theme() -> String {
-    "base16-ocean.dark".to_string()
+fn default_preview_max_bytes() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_split_ratio() -> u16 {
+    50
+}
+
+fn default_narrow_width() -> u16 {
+    60
+}
+
+fn default_tab_width() -> usize {
+    4
+}
+
+fn default_keyboard_protocol() -> bool {
+    true
+}
+
+fn default_delete_to_trash() -> bool {
+    true
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: default_version(),
             editor: default_editor(),
             editor_args: default_editor_args(),
+            editor_detach: false,
             show_hidden: default_show_hidden(),
+            browser_ignore: Vec::new(),
             preview_max_lines: default_preview_max_lines(),
-            theme: default_theme(),
+            preview_max_bytes: default_preview_max_bytes(),
+            preview_pane: false,
+            preview_pane_ratio: default_split_ratio(),
+            dual_pane_ratio: default_split_ratio(),
+            narrow_width: default_narrow_width(),
+            theme: None,
+            ui_theme: UiTheme::default(),
+            style: WidgetStyle::default(),
+            tab_width: default_tab_width(),
+            icons: IconStyle::default(),
+            sort: SortMode::default(),
+            sort_reverse: false,
+            natural_sort: false,
+            zoxide: false,
+            status_bar: String::new(),
+            mouse: false,
+            keyboard_protocol: default_keyboard_protocol(),
+            delete_to_trash: default_delete_to_trash(),
+            previewers: BTreeMap::new(),
+            openers: BTreeMap::new(),
+            extension_openers: BTreeMap::new(),
+            keys: BTreeMap::new(),
+            commands: BTreeMap::new(),
+            find: FindDefaults::default(),
         }
     }
 }
 
+/// Widths (percent) a split pane may take; others are clamped into range
+pub const SPLIT_RATIOS: std::ops::RangeInclusive<u16> = 10..=90;
+
+/// Version of the config format written by this vfv. Files without a
+/// `version` key are version 1.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// A change to the config format: the version that introduced it and the
+/// step that rewrites older settings, returning a note for each change
+struct Migration {
+    version: u32,
+    apply: fn(&mut toml::Table) -> Vec<String>,
+}
+
+/// Format changes since version 1, oldest first
+const MIGRATIONS: &[Migration] = &[];
+
+fn file_version(table: &toml::Table) -> u32 {
+    table
+        .get("version")
+        .and_then(toml::Value::as_integer)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(1)
+}
+
+/// Upgrade settings written for an older config format in place, returning
+/// what was changed
+fn migrate(table: &mut toml::Table, migrations: &[Migration]) -> Vec<String> {
+    let version = file_version(table);
+    let mut changes = Vec::new();
+    for migration in migrations.iter().filter(|m| m.version > version) {
+        changes.extend((migration.apply)(table));
+    }
+    if version < CONFIG_VERSION {
+        table.insert(
+            "version".to_string(),
+            toml::Value::Integer(CONFIG_VERSION.into()),
+        );
+    }
+    changes
+}
+
+/// Config file given with `--config` (takes precedence over `VFV_CONFIG`)
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Directory `.vfv.toml` is searched from (the current directory if unset)
+static PROJECT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Per-project config file, searched for upward from the start directory
+pub const PROJECT_CONFIG_NAME: &str = ".vfv.toml";
+
+/// Settings a project file may not change, since they run commands and a
+/// cloned repository should not be able to pick them
+const PROJECT_DENIED_KEYS: &[&str] = &[
+    "editor",
+    "editor_args",
+    "previewers",
+    "openers",
+    "extension_openers",
+    "commands",
+];
+
 impl Config {
+    /// Syntax theme for the preview: `theme` when set, otherwise the one
+    /// that goes with `ui_theme`
+    pub fn syntax_theme(&self) -> &str {
+        self.theme
+            .as_deref()
+            .unwrap_or_else(|| self.ui_theme.syntax_theme())
+    }
+
+    /// Use `path` instead of `VFV_CONFIG` or the default location
+    pub fn set_config_path(path: PathBuf) {
+        let _ = CONFIG_PATH_OVERRIDE.set(path);
+    }
+
+    /// Search for `.vfv.toml` from `dir` instead of the current directory
+    pub fn set_project_dir(dir: PathBuf) {
+        let _ = PROJECT_DIR.set(dir);
+    }
+
     pub fn load() -> Self {
         match Self::load_with_result() {
             Ok(config) => config,
@@ -106,23 +402,75 @@ impl Config {
 
     /// Load config with detailed error handling
     pub fn load_with_result() -> ConfigResult<Self> {
+        let (config, notices) = Self::load_with_notices()?;
+        for notice in notices {
+            eprintln!("Warning: {}", notice);
+        }
+        Ok(config)
+    }
+
+    /// Load config along with notices about it (upgraded settings, ignored
+    /// project entries) for the caller to show instead of printing them
+    pub fn load_with_notices() -> ConfigResult<(Self, Vec<String>)> {
         let config_path = Self::config_path();
 
-        if !config_path.exists() {
-            return Ok(Self::default());
-        }
+        let content = if config_path.exists() {
+            // Check permissions on Unix
+            #[cfg(unix)]
+            Self::check_permissions(&config_path)?;
 
-        // Check permissions on Unix
-        #[cfg(unix)]
-        Self::check_permissions(&config_path)?;
+            fs::read_to_string(&config_path).map_err(ConfigError::ReadError)?
+        } else if Self::explicit_config_path().is_some() {
+            // 明示的に指定されたファイルがなければ、既定値で黙って続けない
+            return Err(ConfigError::NotFound(config_path));
+        } else {
+            String::new()
+        };
+
+        let mut table: toml::Table = toml::from_str(&content).map_err(ConfigError::ParseError)?;
+        let mut notices = Vec::new();
+        let version = file_version(&table);
+        if version > CONFIG_VERSION {
+            notices.push(format!(
+                "Config version {} is newer than this vfv supports ({})",
+                version, CONFIG_VERSION
+            ));
+        }
+        let migrated = migrate(&mut table, MIGRATIONS);
+        notices.extend(
+            migrated
+                .iter()
+                .map(|change| format!("Outdated setting in {}: {}", config_path.display(), change)),
+        );
 
-        let content = fs::read_to_string(&config_path).map_err(ConfigError::ReadError)?;
-        let config: Config = toml::from_str(&content).map_err(ConfigError::ParseError)?;
+        // 移行がなければ、エラーを行番号付きで報告できるよう元の文字列から読む
+        let mut config: Config = if migrated.is_empty() {
+            toml::from_str(&content).map_err(ConfigError::ParseError)?
+        } else {
+            toml::Value::Table(table.clone())
+                .try_into()
+                .map_err(ConfigError::ParseError)?
+        };
+        let project_path = Self::project_config_path();
+        let overrides = env_overrides(|name| std::env::var(name).ok());
+        if project_path.is_some() || !overrides.is_empty() {
+            if let Some(project_path) = project_path {
+                let project = fs::read_to_string(&project_path)
+                    .map_err(|e| ConfigError::ProjectError(project_path.clone(), e.to_string()))?;
+                merge_project(&mut table, &project, &mut notices)
+                    .and_then(|()| to_config(&table).map(|_| ()))
+                    .map_err(|e| ConfigError::ProjectError(project_path, e))?;
+            }
+            apply_env_overrides(&mut table, &overrides)?;
+            // 各変数は個別に検証済み
+            config = to_config(&table)
+                .map_err(|e| ConfigError::EnvError("VFV_* variables".to_string(), e))?;
+        }
 
         // Validate editor command
         config.validate_editor()?;
 
-        Ok(config)
+        Ok((config, notices))
     }
 
     /// Check that config file has secure permissions (Unix only)
@@ -167,7 +515,33 @@ impl Config {
         Ok(())
     }
 
+    /// Config file location: `--config`, then `VFV_CONFIG`, then the default
     pub fn config_path() -> PathBuf {
+        Self::explicit_config_path().unwrap_or_else(Self::default_config_path)
+    }
+
+    /// The nearest `.vfv.toml` in the start directory or one of its parents
+    pub fn project_config_path() -> Option<PathBuf> {
+        let start = match PROJECT_DIR.get() {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir().ok()?,
+        };
+        find_project_config(&start)
+    }
+
+    /// Whether the config file was chosen with `--config` or `VFV_CONFIG`
+    pub fn has_explicit_path() -> bool {
+        Self::explicit_config_path().is_some()
+    }
+
+    fn explicit_config_path() -> Option<PathBuf> {
+        resolve_explicit_path(
+            CONFIG_PATH_OVERRIDE.get().map(PathBuf::as_path),
+            std::env::var_os("VFV_CONFIG"),
+        )
+    }
+
+    fn default_config_path() -> PathBuf {
         if let Some(proj_dirs) = ProjectDirs::from("", "", "vive-file-viewer") {
             let config_dir = proj_dirs.config_dir();
             config_dir.join("config.toml")
@@ -177,18 +551,400 @@ impl Config {
     }
 }
 
+fn find_project_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_NAME))
+        .find(|path| path.is_file())
+}
+
+/// Lay the settings of a project file over the global ones; tables such as
+/// `[keys]` are merged entry by entry
+fn merge_project(
+    table: &mut toml::Table,
+    project: &str,
+    notices: &mut Vec<String>,
+) -> Result<(), String> {
+    let mut project: toml::Table = toml::from_str(project).map_err(|e| e.message().to_string())?;
+    for change in migrate(&mut project, MIGRATIONS) {
+        notices.push(format!(
+            "Outdated setting in {}: {}",
+            PROJECT_CONFIG_NAME, change
+        ));
+    }
+    // 形式の版はグローバルな設定のものを使う
+    project.remove("version");
+    for (key, value) in project {
+        if PROJECT_DENIED_KEYS.contains(&key.as_str()) {
+            notices.push(format!(
+                "'{}' cannot be set in {}",
+                key, PROJECT_CONFIG_NAME
+            ));
+            continue;
+        }
+        match (table.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => base.extend(overlay),
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn to_config(table: &toml::Table) -> Result<Config, String> {
+    toml::Value::Table(table.clone())
+        .try_into()
+        .map_err(|e: toml::de::Error| e.message().to_string())
+}
+
+/// A `VFV_*` variable that is set: its name, the setting's path and the value
+type EnvOverride = (String, Vec<String>, String);
+
+/// `VFV_<KEY>` variables that are set (and not empty), for every top-level
+/// setting (`VFV_EDITOR`) and every entry of fixed tables (`VFV_FIND_LIMIT`)
+fn env_overrides(var: impl Fn(&str) -> Option<String>) -> Vec<EnvOverride> {
+    let Ok(toml::Value::Table(defaults)) = lookup(&Config::default(), None) else {
+        return Vec::new();
+    };
+    let mut paths = Vec::new();
+    for (key, value) in defaults {
+        match value {
+            // previewers などの自由なキーのテーブルは対象外
+            toml::Value::Table(table) => {
+                paths.extend(table.into_iter().map(|(sub, _)| vec![key.clone(), sub]))
+            }
+            _ => paths.push(vec![key]),
+        }
+    }
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let name = format!("VFV_{}", path.join("_").to_uppercase());
+            let value = var(&name).filter(|value| !value.is_empty())?;
+            Some((name, path, value))
+        })
+        .collect()
+}
+
+/// Set the overridden settings in `table`. A value is read as TOML when that
+/// gives the setting a valid type (`true`, `4`, `["--wait"]`), otherwise as a
+/// string.
+fn apply_env_overrides(table: &mut toml::Table, overrides: &[EnvOverride]) -> ConfigResult<()> {
+    for (name, path, raw) in overrides {
+        let parsed = toml::from_str::<toml::Table>(&format!("value = {}", raw))
+            .ok()
+            .and_then(|mut t| t.remove("value"));
+        let candidates = parsed
+            .into_iter()
+            .chain(std::iter::once(toml::Value::String(raw.clone())));
+
+        let mut first_error = None;
+        for value in candidates {
+            let mut attempt = table.clone();
+            match path.as_slice() {
+                [key] => {
+                    attempt.insert(key.clone(), value);
+                }
+                [key, sub] => {
+                    if let toml::Value::Table(nested) = attempt
+                        .entry(key.clone())
+                        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                    {
+                        nested.insert(sub.clone(), value);
+                    }
+                }
+                _ => continue,
+            }
+            match to_config(&attempt) {
+                Ok(_) => {
+                    *table = attempt;
+                    first_error = None;
+                    break;
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        if let Some(e) = first_error {
+            return Err(ConfigError::EnvError(name.clone(), e));
+        }
+    }
+    Ok(())
+}
+
+/// Value of a setting by dotted key (e.g. `editor`, `previewers.md`);
+/// no key returns every setting
+pub fn lookup(config: &Config, key: Option<&str>) -> Result<toml::Value, String> {
+    let mut value = toml::Value::try_from(config).map_err(|e| e.to_string())?;
+    // theme は未設定なら ui_theme が選んだものを見せる
+    if let toml::Value::Table(ref mut table) = value {
+        table
+            .entry("theme")
+            .or_insert_with(|| config.syntax_theme().into());
+    }
+    let Some(key) = key else {
+        return Ok(value);
+    };
+    for part in key.split('.') {
+        value = match value {
+            toml::Value::Table(mut table) => table
+                .remove(part)
+                .ok_or_else(|| format!("Unknown key '{}'", key))?,
+            _ => return Err(format!("Unknown key '{}'", key)),
+        };
+    }
+    Ok(value)
+}
+
+/// Return `content` (a config file) with `key` set to `raw`, keeping
+/// comments and layout. `raw` is read as a TOML value when it is one
+/// (`true`, `4`, `["--wait"]`), otherwise as a string. The result must still
+/// be a valid config.
+pub fn set_value(content: &str, key: &str, raw: &str) -> Result<String, String> {
+    let mut doc: toml_edit::DocumentMut = content.parse().map_err(|e| format!("{}", e))?;
+    let parts: Vec<&str> = key.split('.').collect();
+
+    // 既知の設定項目か、テーブル項目（previewers など）の中のキーだけ受け付ける
+    let defaults = lookup(&Config::default(), None)?;
+    let known = defaults.get(parts[0]);
+    let valid_shape = match (known, parts.len()) {
+        (Some(toml::Value::Table(_)), 2) => true,
+        (Some(toml::Value::Table(_)), _) => false,
+        (Some(_), 1) => true,
+        _ => false,
+    };
+    if !valid_shape || parts.iter().any(|p| p.is_empty()) {
+        return Err(format!("Unknown key '{}'", key));
+    }
+
+    let string_value = toml_edit::Value::from(raw);
+    let candidates = match raw.parse::<toml_edit::Value>() {
+        Ok(parsed) if !parsed.is_str() => vec![parsed, string_value],
+        _ => vec![string_value],
+    };
+
+    let mut first_error = None;
+    for value in candidates {
+        let mut attempt = doc.clone();
+        set_item(&mut attempt, &parts, value);
+        match toml::from_str::<Config>(&attempt.to_string()) {
+            Ok(config) => {
+                // ui_theme は列挙型なので読み込みで、theme は config check と同じく名前の一覧で確かめる
+                if parts[0] == "theme"
+                    && let Some(problem) = unknown_theme(&config)
+                {
+                    first_error.get_or_insert(problem);
+                    continue;
+                }
+                doc = attempt;
+                return Ok(doc.to_string());
+            }
+            Err(e) => {
+                first_error.get_or_insert_with(|| e.message().to_string());
+            }
+        }
+    }
+    Err(format!(
+        "Invalid value for '{}': {}",
+        key,
+        first_error.unwrap_or_default()
+    ))
+}
+
+/// The problem with `theme` when it names a syntax theme that does not exist
+fn unknown_theme(config: &Config) -> Option<String> {
+    let theme = config.theme.as_ref()?;
+    let themes = Previewer::theme_names();
+    (!themes.contains(theme)).then(|| {
+        format!(
+            "Unknown theme '{}' (available: {})",
+            theme,
+            themes.join(", ")
+        )
+    })
+}
+
+fn set_item(doc: &mut toml_edit::DocumentMut, parts: &[&str], value: toml_edit::Value) {
+    match parts {
+        [name] => {
+            doc[name] = toml_edit::value(value);
+        }
+        [table, name] => {
+            let item = doc.entry(table).or_insert_with(toml_edit::table);
+            if let Some(table) = item.as_table_like_mut() {
+                table.insert(name, toml_edit::value(value));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Problems in a config file: syntax and type errors, unknown keys, a
+/// theme that does not exist, an editor not found on `PATH`, an unknown
+/// highlight color, and invalid `[keys]` or `browser_ignore` entries. Empty when the config is fine.
+pub fn check(content: &str) -> Vec<String> {
+    let mut table: toml::Table = match toml::from_str(content) {
+        Ok(table) => table,
+        Err(e) => return vec![e.message().to_string()],
+    };
+    // 古い形式の設定は移行が必要なものとして報告する
+    let mut problems: Vec<String> = migrate(&mut table, MIGRATIONS)
+        .into_iter()
+        .map(|change| format!("Outdated setting: {}", change))
+        .collect();
+    let config = match to_config(&table) {
+        Ok(config) => config,
+        Err(e) => {
+            problems.push(e);
+            return problems;
+        }
+    };
+    if file_version(&table) > CONFIG_VERSION {
+        problems.push(format!(
+            "Config version {} is newer than this vfv supports ({})",
+            file_version(&table),
+            CONFIG_VERSION
+        ));
+    }
+
+    // serde は未知のキーを無視するので、既定値のキー一覧と突き合わせる
+    if let Ok(toml::Value::Table(known)) = lookup(&Config::default(), None) {
+        for key in table.keys().filter(|key| !known.contains_key(*key)) {
+            problems.push(format!("Unknown key '{}'", key));
+        }
+    }
+
+    for (key, ratio) in [
+        ("preview_pane_ratio", config.preview_pane_ratio),
+        ("dual_pane_ratio", config.dual_pane_ratio),
+    ] {
+        if !SPLIT_RATIOS.contains(&ratio) {
+            problems.push(format!(
+                "{} must be between {} and {} (got {})",
+                key,
+                SPLIT_RATIOS.start(),
+                SPLIT_RATIOS.end(),
+                ratio
+            ));
+        }
+    }
+
+    problems.extend(unknown_theme(&config));
+    problems.extend(config.style.problems());
+
+    if !config.editor.is_empty() && find_executable(&config.editor).is_none() {
+        problems.push(format!("Editor '{}' not found in PATH", config.editor));
+    }
+
+    let (_, key_errors) = Keymap::new(&config.keys, &config.commands);
+    problems.extend(
+        key_errors
+            .into_iter()
+            .map(|e| format!("[keys]/[commands]: {}", e)),
+    );
+
+    for name in status_bar::unknown_segments(&config.status_bar) {
+        problems.push(format!(
+            "Unknown status_bar segment '{{{}}}' (available: {})",
+            name,
+            status_bar::SEGMENTS.join(", ")
+        ));
+    }
+
+    for pattern in &config.browser_ignore {
+        if let Err(e) = globset::Glob::new(pattern) {
+            problems.push(format!("Invalid browser_ignore pattern: {}", e));
+        }
+    }
+    problems
+}
+
+/// The `--config` path if given, otherwise a non-empty `VFV_CONFIG`
+fn resolve_explicit_path(flag: Option<&Path>, env: Option<OsString>) -> Option<PathBuf> {
+    flag.map(Path::to_path_buf)
+        .or_else(|| env.filter(|value| !value.is_empty()).map(PathBuf::from))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_ui_theme_picks_syntax_theme() {
+        let config: Config = toml::from_str("ui_theme = \"light\"").unwrap();
+        assert_eq!(config.ui_theme, UiTheme::Light);
+        assert_eq!(config.syntax_theme(), "InspiredGitHub");
+
+        // theme を指定したらそちらを使う
+        let config: Config =
+            toml::from_str("ui_theme = \"high-contrast\"\ntheme = \"Solarized (dark)\"").unwrap();
+        assert_eq!(config.syntax_theme(), "Solarized (dark)");
+        assert_eq!(Config::default().syntax_theme(), "base16-ocean.dark");
+        // 既定と同じ名前でも書いてあれば固定する
+        let config: Config =
+            toml::from_str("ui_theme = \"light\"\ntheme = \"base16-ocean.dark\"").unwrap();
+        assert_eq!(config.syntax_theme(), "base16-ocean.dark");
+
+        let themes = Previewer::theme_names();
+        for ui_theme in UiTheme::ALL {
+            assert!(themes.iter().any(|t| t == ui_theme.syntax_theme()));
+        }
+    }
 
     #[test]
     fn test_default_config() {
         let config = Config::default();
-        assert_eq!(config.editor, "vim");
+        assert_eq!(config.editor, "");
         assert!(config.editor_args.is_empty());
         assert!(!config.show_hidden);
         assert_eq!(config.preview_max_lines, 1000);
-        assert_eq!(config.theme, "base16-ocean.dark");
+        assert_eq!(config.preview_max_bytes, 10 * 1024 * 1024);
+        assert_eq!(config.theme, None);
+        assert_eq!(config.tab_width, 4);
+        assert!(config.delete_to_trash);
+        assert_eq!(config.sort, SortMode::Name);
+        assert!(!config.sort_reverse);
+        assert!(!config.natural_sort);
+        assert!(!config.zoxide);
+        assert!(config.browser_ignore.is_empty());
+        assert_eq!(config.icons, IconStyle::Plain);
+    }
+
+    #[test]
+    fn test_parse_icon_style() {
+        let config: Config = toml::from_str("icons = \"nerd\"").unwrap();
+        assert_eq!(config.icons, IconStyle::Nerd);
+    }
+
+    #[test]
+    fn test_parse_find_defaults() {
+        let config: Config = toml::from_str("[find]\nlimit = 50\njson = true\n").unwrap();
+        assert_eq!(config.find.limit, 50);
+        assert!(config.find.json);
+        assert!(config.find.hidden);
+        assert_eq!(Config::default().find, FindDefaults::default());
+
+        assert!(toml::from_str::<Config>("[find]\nlimt = 50\n").is_err());
+        assert!(
+            set_value("", "find.timeout", "5")
+                .unwrap()
+                .contains("timeout = 5")
+        );
+    }
+
+    #[test]
+    fn test_parse_sort_mode() {
+        let parse = |s: &str| toml::from_str::<Config>(&format!("sort = \"{}\"", s));
+        assert_eq!(parse("size").unwrap().sort, SortMode::Size);
+        assert_eq!(parse("mtime").unwrap().sort, SortMode::Modified);
+        assert_eq!(parse("ext").unwrap().sort, SortMode::Extension);
+        assert!(parse("bogus").is_err());
+
+        let config: Config = toml::from_str("sort = \"size\"\nsort_reverse = true").unwrap();
+        assert!(config.sort_reverse);
     }
 
     #[test]
@@ -205,7 +961,7 @@ mod tests {
         assert_eq!(config.editor_args, vec!["-c", "startinsert"]);
         assert!(config.show_hidden);
         assert_eq!(config.preview_max_lines, 500);
-        assert_eq!(config.theme, "Solarized (dark)");
+        assert_eq!(config.theme.as_deref(), Some("Solarized (dark)"));
     }
 
     #[test]
@@ -220,6 +976,281 @@ mod tests {
         assert_eq!(config.preview_max_lines, 1000);
     }
 
+    #[test]
+    fn test_parse_previewers_table() {
+        let toml_str = r#"
+            [previewers]
+            pdf = "pdftotext {} -"
+            md = "glow -s dark"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.previewers.len(), 2);
+        assert_eq!(config.previewers["pdf"], "pdftotext {} -");
+        assert!(Config::default().previewers.is_empty());
+    }
+
+    #[test]
+    fn test_parse_openers_table() {
+        let toml_str = r#"
+            [openers]
+            hexyl = "hexyl {}"
+            less = "less -R"
+            code = { command = "code", detach = true }
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.openers.len(), 3);
+        assert_eq!(config.openers["less"].command(), "less -R");
+        assert!(!config.openers["less"].detach());
+        assert_eq!(config.openers["code"].command(), "code");
+        assert!(config.openers["code"].detach());
+        assert!(Config::default().openers.is_empty());
+    }
+
+    #[test]
+    fn test_parse_extension_openers_table() {
+        let toml_str = r#"
+            [extension_openers]
+            png = "feh"
+            pdf = "zathura"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.extension_openers.len(), 2);
+        assert_eq!(config.extension_openers["png"].command(), "feh");
+        assert!(Config::default().extension_openers.is_empty());
+    }
+
+    #[test]
+    fn test_parse_keys_table() {
+        let toml_str = r#"
+            [keys]
+            move_down = "n"
+            open_editor = ["ctrl-e", "E"]
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.keys["move_down"], KeySpec::One("n".to_string()));
+        assert_eq!(
+            config.keys["open_editor"],
+            KeySpec::Many(vec!["ctrl-e".to_string(), "E".to_string()])
+        );
+        assert!(Config::default().keys.is_empty());
+    }
+
+    #[test]
+    fn test_lookup() {
+        let config = Config {
+            editor: "vim".to_string(),
+            previewers: BTreeMap::from([("md".to_string(), "glow {}".to_string())]),
+            ..Config::default()
+        };
+        assert_eq!(
+            lookup(&config, Some("editor")).unwrap(),
+            toml::Value::String("vim".to_string())
+        );
+        assert_eq!(
+            lookup(&config, Some("previewers.md")).unwrap(),
+            toml::Value::String("glow {}".to_string())
+        );
+        assert_eq!(
+            lookup(&config, Some("tab_width")).unwrap(),
+            toml::Value::Integer(4)
+        );
+        assert!(lookup(&config, Some("nope")).is_err());
+        assert!(lookup(&config, Some("editor.x")).is_err());
+        assert!(lookup(&config, None).unwrap().get("theme").is_some());
+    }
+
+    #[test]
+    fn test_set_value_keeps_comments() {
+        let content = "# My editor\neditor = \"vim\"\n";
+        let updated = set_value(content, "theme", "Solarized (dark)").unwrap();
+        assert!(updated.starts_with("# My editor\neditor = \"vim\"\n"));
+        assert!(updated.contains("theme = \"Solarized (dark)\""));
+
+        let updated = set_value(&updated, "tab_width", "8").unwrap();
+        let updated = set_value(&updated, "editor_args", "[\"--wait\"]").unwrap();
+        let updated = set_value(&updated, "previewers.md", "glow -s dark {}").unwrap();
+        let config: Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.theme.as_deref(), Some("Solarized (dark)"));
+        assert_eq!(config.tab_width, 8);
+        assert_eq!(config.editor_args, vec!["--wait"]);
+        assert_eq!(config.previewers["md"], "glow -s dark {}");
+    }
+
+    #[test]
+    fn test_set_value_validates() {
+        // 文字列の項目は数字でも文字列として書く
+        let updated = set_value("", "editor", "42").unwrap();
+        assert!(updated.contains("editor = \"42\""));
+
+        assert!(set_value("", "tab_width", "wide").is_err());
+        assert!(set_value("", "sort", "random").is_err());
+        // テーマは config check と同じく実在するものだけ
+        let error = set_value("", "theme", "No Such Theme").unwrap_err();
+        assert!(error.contains("Unknown theme 'No Such Theme'"), "{}", error);
+        assert!(set_value("", "ui_theme", "neon").is_err());
+        assert!(set_value("", "ui_theme", "light").is_ok());
+        assert!(set_value("", "no_such_key", "1").is_err());
+        assert!(set_value("", "previewers", "x").is_err());
+        assert!(set_value("", "previewers.md.x", "x").is_err());
+    }
+
+    #[test]
+    fn test_find_project_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let nested = temp_dir.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        assert!(find_project_config(&nested).is_none_or(|p| !p.starts_with(temp_dir.path())));
+
+        fs::write(temp_dir.path().join(PROJECT_CONFIG_NAME), "").unwrap();
+        assert_eq!(
+            find_project_config(&nested),
+            Some(temp_dir.path().join(PROJECT_CONFIG_NAME))
+        );
+
+        fs::write(nested.join(PROJECT_CONFIG_NAME), "").unwrap();
+        assert_eq!(
+            find_project_config(&nested),
+            Some(nested.join(PROJECT_CONFIG_NAME))
+        );
+    }
+
+    #[test]
+    fn test_merge_project() {
+        let mut table: toml::Table = toml::from_str(
+            "editor = \"vim\"\nsort = \"name\"\n[keys]\nquit = \"x\"\nhelp = \"h\"\n",
+        )
+        .unwrap();
+        let mut notices = Vec::new();
+        merge_project(
+            &mut table,
+            "editor = \"evil\"\nsort = \"size\"\nbrowser_ignore = [\"target\"]\n[keys]\nquit = \"z\"\n",
+            &mut notices,
+        )
+        .unwrap();
+        let config: Config = toml::Value::Table(table).try_into().unwrap();
+
+        assert_eq!(config.editor, "vim");
+        assert_eq!(config.sort, SortMode::Size);
+        assert_eq!(config.browser_ignore, vec!["target".to_string()]);
+        assert_eq!(
+            config.keys.get("quit"),
+            Some(&KeySpec::One("z".to_string()))
+        );
+        assert!(config.keys.contains_key("help"));
+        assert_eq!(
+            notices,
+            vec!["'editor' cannot be set in .vfv.toml".to_string()]
+        );
+
+        assert!(merge_project(&mut toml::Table::new(), "sort = ", &mut notices).is_err());
+    }
+
+    #[test]
+    fn test_migrate_runs_only_newer_steps() {
+        fn rename_hidden(table: &mut toml::Table) -> Vec<String> {
+            match table.remove("hidden") {
+                Some(value) => {
+                    table.insert("show_hidden".to_string(), value);
+                    vec!["hidden is now show_hidden".to_string()]
+                }
+                None => Vec::new(),
+            }
+        }
+        let migrations = [Migration {
+            version: 2,
+            apply: rename_hidden,
+        }];
+
+        let mut table: toml::Table = toml::from_str("hidden = true\n").unwrap();
+        assert_eq!(migrate(&mut table, &migrations).len(), 1);
+        assert!(to_config(&table).unwrap().show_hidden);
+
+        let mut table: toml::Table = toml::from_str("version = 2\nhidden = true\n").unwrap();
+        assert!(migrate(&mut table, &migrations).is_empty());
+        assert!(table.contains_key("hidden"));
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let vars = BTreeMap::from([
+            ("VFV_EDITOR", "nvim"),
+            ("VFV_SHOW_HIDDEN", "true"),
+            ("VFV_TAB_WIDTH", "8"),
+            ("VFV_EDITOR_ARGS", "[\"--wait\"]"),
+            ("VFV_THEME", "Solarized (dark)"),
+            ("VFV_FIND_LIMIT", "5"),
+            ("VFV_SORT", ""),
+            ("VFV_PREVIEWERS", "x"),
+        ]);
+        let overrides = env_overrides(|name| vars.get(name).map(|v| v.to_string()));
+        assert_eq!(overrides.len(), 6);
+
+        let mut table: toml::Table = toml::from_str("editor = \"vim\"\ntab_width = 2\n").unwrap();
+        apply_env_overrides(&mut table, &overrides).unwrap();
+        let config = to_config(&table).unwrap();
+        assert_eq!(config.editor, "nvim");
+        assert!(config.show_hidden);
+        assert_eq!(config.tab_width, 8);
+        assert_eq!(config.editor_args, vec!["--wait".to_string()]);
+        assert_eq!(config.theme.as_deref(), Some("Solarized (dark)"));
+        assert_eq!(config.find.limit, 5);
+
+        let bad = vec![(
+            "VFV_TAB_WIDTH".to_string(),
+            vec!["tab_width".to_string()],
+            "wide".to_string(),
+        )];
+        let err = apply_env_overrides(&mut table, &bad).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid VFV_TAB_WIDTH: "));
+    }
+
+    #[test]
+    fn test_check_reports_problems() {
+        assert!(check("editor = \"sh\"\n").is_empty());
+
+        let problems = check(
+            "editor = \"vfv-no-such-editor\"\ntheme = \"Nope\"\ncolour = 1\nbrowser_ignore = [\"[\"]\n[keys]\nfly = \"x\"\n",
+        );
+        assert_eq!(problems.len(), 5, "{:?}", problems);
+        assert!(problems[0].contains("'colour'"));
+        assert!(problems[1].contains("theme 'Nope'"));
+        assert!(problems[2].contains("vfv-no-such-editor"));
+        assert!(problems[3].contains("fly"));
+        assert!(problems[4].contains("browser_ignore"));
+
+        let problems = check("editor = \"sh\"\nstatus_bar = \"{mode} {brnch}\"\n");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("Unknown status_bar segment '{brnch}'"));
+
+        let problems = check("tab_width = \"wide\"\n");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("invalid type"));
+
+        let problems = check("editor = \"sh\"\npreview_pane_ratio = 95\ndual_pane_ratio = 5\n");
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("preview_pane_ratio must be between 10 and 90"));
+        assert!(problems[1].starts_with("dual_pane_ratio"));
+
+        let problems =
+            check("editor = \"sh\"\n[style]\nborder = \"thick\"\nhighlight_bg = \"bluish\"\n");
+        assert_eq!(problems, vec!["style.highlight_bg: unknown color 'bluish'"]);
+    }
+
+    #[test]
+    fn test_explicit_config_path_precedence() {
+        let flag = Path::new("/flag/config.toml");
+        assert_eq!(
+            resolve_explicit_path(Some(flag), Some(OsString::from("/env/config.toml"))),
+            Some(flag.to_path_buf())
+        );
+        assert_eq!(
+            resolve_explicit_path(None, Some(OsString::from("/env/config.toml"))),
+            Some(PathBuf::from("/env/config.toml"))
+        );
+        assert_eq!(resolve_explicit_path(None, Some(OsString::new())), None);
+        assert_eq!(resolve_explicit_path(None, None), None);
+    }
+
     #[test]
     fn test_config_path_is_not_empty() {
         let path = Config::config_path();
@@ -291,7 +1322,7 @@ mod tests {
         // When config file doesn't exist, load_with_result should return default
         // We can't easily test this without mocking, but we test the logic path
         let config = Config::default();
-        assert_eq!(config.editor, "vim");
+        assert_eq!(config.editor, "");
     }
 
     #[test]
@@ -308,7 +1339,7 @@ mod tests {
         // This tests the fallback behavior
         let config = Config::load();
         // Should have default values
-        assert_eq!(config.editor, "vim");
+        assert_eq!(config.editor, "");
         assert!(!config.show_hidden);
     }
 
@@ -335,13 +1366,23 @@ mod tests {
             editor_args = ["--no-splash"]
             show_hidden = true
             preview_max_lines = 2000
+            preview_max_bytes = 52428800
             theme = "base16-mocha.dark"
+            tab_width = 8
+            delete_to_trash = false
+            natural_sort = true
+            browser_ignore = ["*.pyc", "__pycache__"]
         "#;
         let config: Config = toml::from_str(toml_str).unwrap();
         assert_eq!(config.editor, "emacs");
         assert_eq!(config.editor_args, vec!["--no-splash"]);
         assert!(config.show_hidden);
         assert_eq!(config.preview_max_lines, 2000);
-        assert_eq!(config.theme, "base16-mocha.dark");
+        assert_eq!(config.preview_max_bytes, 52428800);
+        assert_eq!(config.theme.as_deref(), Some("base16-mocha.dark"));
+        assert_eq!(config.tab_width, 8);
+        assert!(!config.delete_to_trash);
+        assert!(config.natural_sort);
+        assert_eq!(config.browser_ignore, vec!["*.pyc", "__pycache__"]);
     }
 }
@@ -0,0 +1,188 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Answers a confirmation dialog can offer, each picked with its own key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    Yes,
+    No,
+    /// Yes for this and every remaining question of the same kind
+    All,
+    /// Paste under a new name instead of overwriting
+    KeepBoth,
+}
+
+impl Choice {
+    pub fn label(self) -> &'static str {
+        match self {
+            Choice::Yes => "Yes",
+            Choice::No => "No",
+            Choice::All => "All",
+            Choice::KeepBoth => "Keep both",
+        }
+    }
+
+    /// Key that picks the choice directly
+    pub fn key(self) -> char {
+        match self {
+            Choice::Yes => 'y',
+            Choice::No => 'n',
+            Choice::All => 'a',
+            Choice::KeepBoth => 'b',
+        }
+    }
+
+    /// The label split around the first letter matching `key` (either
+    /// case), so the dialog can underline it: "Keep both" -> ("Keep ", "b", "oth")
+    pub fn label_parts(self) -> (&'static str, &'static str, &'static str) {
+        let label = self.label();
+        let Some(start) = label
+            .char_indices()
+            .find(|(_, c)| c.to_ascii_lowercase() == self.key())
+            .map(|(i, _)| i)
+        else {
+            return (label, "", "");
+        };
+        let end = start + self.key().len_utf8();
+        (&label[..start], &label[start..end], &label[end..])
+    }
+}
+
+/// What a dialog asks about; decides what its answer does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subject {
+    Delete,
+    UsageDelete,
+    Purge,
+    PasteConflict,
+}
+
+/// A Yes/No(/All) question shown as a popup over the current view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Confirm {
+    pub subject: Subject,
+    pub title: String,
+    pub message: String,
+    pub choices: Vec<Choice>,
+    /// Index of the highlighted choice (Enter picks it)
+    pub selected: usize,
+}
+
+/// What a key press does to an open dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Response {
+    Answer(Choice),
+    Cancel,
+    /// Only the highlight moved (or the key is not used)
+    Pending,
+}
+
+impl Confirm {
+    /// Dialog that starts on "No", so an accidental Enter changes nothing
+    pub fn new(
+        subject: Subject,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        choices: &[Choice],
+    ) -> Self {
+        let selected = choices.iter().position(|c| *c == Choice::No).unwrap_or(0);
+        Self {
+            subject,
+            title: title.into(),
+            message: message.into(),
+            choices: choices.to_vec(),
+            selected,
+        }
+    }
+
+    /// Same keys in every dialog: the choice's letter, ←/→ (h/l, Tab) and
+    /// Enter, and Esc/q/Ctrl+C to back out
+    pub fn handle_key(&mut self, key: KeyEvent) -> Response {
+        let len = self.choices.len();
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Response::Cancel,
+            KeyCode::Esc | KeyCode::Char('q') => Response::Cancel,
+            KeyCode::Enter => Response::Answer(self.choices[self.selected]),
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab => {
+                self.selected = (self.selected + len - 1) % len;
+                Response::Pending
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => {
+                self.selected = (self.selected + 1) % len;
+                Response::Pending
+            }
+            KeyCode::Char(c) => self
+                .choices
+                .iter()
+                .find(|choice| choice.key() == c.to_ascii_lowercase())
+                .map_or(Response::Pending, |choice| Response::Answer(*choice)),
+            _ => Response::Pending,
+        }
+    }
+
+    /// Key hints for the footer, e.g. "y:Yes  n:No  ←/→:select  Enter:confirm  Esc:cancel"
+    pub fn hints(&self) -> String {
+        let mut hints: Vec<String> = self
+            .choices
+            .iter()
+            .map(|choice| format!("{}:{}", choice.key(), choice.label()))
+            .collect();
+        hints.push("←/→:select  Enter:confirm  Esc:cancel".to_string());
+        hints.join("  ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_keys_pick_choices() {
+        let mut confirm = Confirm::new(
+            Subject::PasteConflict,
+            "Paste",
+            "'a' exists",
+            &[Choice::Yes, Choice::No, Choice::All, Choice::KeepBoth],
+        );
+        // 既定は No
+        assert_eq!(
+            confirm.handle_key(press(KeyCode::Enter)),
+            Response::Answer(Choice::No)
+        );
+        assert_eq!(
+            confirm.handle_key(press(KeyCode::Char('B'))),
+            Response::Answer(Choice::KeepBoth)
+        );
+        assert_eq!(
+            confirm.handle_key(press(KeyCode::Char('x'))),
+            Response::Pending
+        );
+        assert_eq!(confirm.handle_key(press(KeyCode::Esc)), Response::Cancel);
+    }
+
+    #[test]
+    fn test_label_parts_mark_the_key() {
+        assert_eq!(Choice::Yes.label_parts(), ("", "Y", "es"));
+        assert_eq!(Choice::KeepBoth.label_parts(), ("Keep ", "b", "oth"));
+    }
+
+    #[test]
+    fn test_arrows_wrap_around() {
+        let mut confirm = Confirm::new(Subject::Delete, "Delete", "?", &[Choice::Yes, Choice::No]);
+        confirm.handle_key(press(KeyCode::Right));
+        assert_eq!(confirm.selected, 0);
+        confirm.handle_key(press(KeyCode::Left));
+        assert_eq!(
+            confirm.handle_key(press(KeyCode::Enter)),
+            Response::Answer(Choice::No)
+        );
+        // 候補にないキーの文字は何もしない
+        assert_eq!(
+            confirm.handle_key(press(KeyCode::Char('a'))),
+            Response::Pending
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+
+use crate::file_ops;
+
+/// Size of one entry of the scanned directory
+#[derive(Debug, Clone)]
+pub struct UsageEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+    /// Total size in bytes, including everything below a directory
+    pub size: u64,
+}
+
+/// Messages sent from a background scan
+#[derive(Debug)]
+pub enum UsageEvent {
+    Entry(UsageEntry),
+    Finished,
+}
+
+/// Measure each entry of `dir`, sending results as they complete.
+/// Stops early once the receiver is dropped.
+pub fn scan(dir: &Path, tx: Sender<UsageEvent>) {
+    if let Ok(read_dir) = fs::read_dir(dir) {
+        for entry in read_dir.flatten() {
+            let path = entry.path();
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            let usage = UsageEntry {
+                name: entry.file_name().to_string_lossy().to_string(),
+                size: file_ops::total_size(&path),
+                path,
+                is_dir,
+            };
+            if tx.send(UsageEvent::Entry(usage)).is_err() {
+                return;
+            }
+        }
+    }
+    let _ = tx.send(UsageEvent::Finished);
+}
+
+/// Measure each directory in `dirs`, sending `(path, total size)` as each
+/// completes. Stops early once the receiver is dropped or `cancel` is set.
+pub fn measure(dirs: Vec<PathBuf>, tx: Sender<(PathBuf, u64)>, cancel: &AtomicBool) {
+    for dir in dirs {
+        let Some(size) = size_unless_cancelled(&dir, cancel) else {
+            return;
+        };
+        if tx.send((dir, size)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Like `file_ops::total_size`, but gives up (None) as soon as `cancel` is set
+fn size_unless_cancelled(path: &Path, cancel: &AtomicBool) -> Option<u64> {
+    if cancel.load(Ordering::Relaxed) {
+        return None;
+    }
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Some(0);
+    };
+    if !metadata.is_dir() {
+        return Some(metadata.len());
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return Some(0);
+    };
+    let mut total = 0;
+    for entry in entries.flatten() {
+        total += size_unless_cancelled(&entry.path(), cancel)?;
+    }
+    Some(total)
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `dir`,
+/// from `df` (None where it is missing, e.g. on Windows)
+pub fn available_space(dir: &Path) -> Option<u64> {
+    let output = std::process::Command::new("df")
+        .arg("-Pk")
+        .arg(dir)
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_df(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Available bytes from `df -Pk` output (the fourth column of the last line)
+fn parse_df(output: &str) -> Option<u64> {
+    let kilobytes: u64 = output
+        .lines()
+        .last()?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// Largest first, then by name
+pub fn sort_by_size(entries: &mut [UsageEntry]) {
+    entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_sums_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        fs::create_dir_all(base.join("big/nested")).unwrap();
+        fs::write(base.join("big/nested/a.bin"), vec![0u8; 300]).unwrap();
+        fs::write(base.join("big/b.bin"), vec![0u8; 200]).unwrap();
+        fs::write(base.join("small.txt"), vec![0u8; 10]).unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        scan(base, tx);
+        let mut entries: Vec<UsageEntry> = rx
+            .iter()
+            .filter_map(|event| match event {
+                UsageEvent::Entry(entry) => Some(entry),
+                UsageEvent::Finished => None,
+            })
+            .collect();
+        sort_by_size(&mut entries);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "big");
+        assert!(entries[0].is_dir);
+        assert_eq!(entries[0].size, 500);
+        assert_eq!(entries[1].size, 10);
+    }
+
+    #[test]
+    fn test_parse_df() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/sda1         98298500  41254012  52000000      45% /\n";
+        assert_eq!(parse_df(output), Some(52000000 * 1024));
+        assert_eq!(parse_df(""), None);
+    }
+
+    #[test]
+    fn test_measure_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        fs::create_dir_all(base.join("a/nested")).unwrap();
+        fs::create_dir(base.join("empty")).unwrap();
+        fs::write(base.join("a/nested/x.bin"), vec![0u8; 40]).unwrap();
+        fs::write(base.join("a/y.bin"), vec![0u8; 2]).unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        measure(
+            vec![base.join("a"), base.join("empty")],
+            tx,
+            &AtomicBool::new(false),
+        );
+        let sizes: Vec<(PathBuf, u64)> = rx.iter().collect();
+
+        assert_eq!(sizes, vec![(base.join("a"), 42), (base.join("empty"), 0)]);
+
+        // 止められたら何も送らない
+        let (tx, rx) = std::sync::mpsc::channel();
+        measure(vec![base.join("a")], tx, &AtomicBool::new(true));
+        assert_eq!(rx.iter().count(), 0);
+    }
+}
@@ -0,0 +1,359 @@
+use std::path::PathBuf;
+
+use crate::app::CLIPBOARD_COMMAND;
+use crate::config::{self, Config};
+use crate::editor::{self, Candidate, find_executable};
+
+/// How a doctor check turned out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Ok,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn label(self) -> &'static str {
+        match self {
+            Level::Ok => "ok",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+}
+
+/// Result of one check, with a suggested fix when something is wrong
+#[derive(Debug)]
+pub struct Finding {
+    pub level: Level,
+    pub name: &'static str,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Finding {
+    fn new(level: Level, name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            level,
+            name,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+/// Run every check against the real environment and config
+pub fn run() -> Vec<Finding> {
+    let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let mut findings = terminal(&var);
+    findings.push(locale(&var));
+    findings.extend(config_files());
+
+    let config = Config::load_with_result().unwrap_or_default();
+    findings.push(editor(&editor::candidates(&config, var), find_executable));
+    findings.push(clipboard(&var, find_executable));
+    findings
+}
+
+/// TERM, truecolor, kitty graphics and OSC 52 support
+fn terminal(var: &dyn Fn(&str) -> Option<String>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let term = var("TERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+
+    // Windows のコンソールは TERM を設定しない
+    if !cfg!(windows) {
+        findings.push(if term.is_empty() || term == "dumb" {
+            Finding::new(Level::Error, "TERM", format!("'{}' cannot run a TUI", term))
+                .fix("Set TERM to your terminal's type, e.g. export TERM=xterm-256color")
+        } else {
+            Finding::new(Level::Ok, "TERM", term.clone())
+        });
+    }
+
+    let colorterm = var("COLORTERM").unwrap_or_default();
+    findings.push(if colorterm == "truecolor" || colorterm == "24bit" {
+        Finding::new(Level::Ok, "Truecolor", format!("COLORTERM={}", colorterm))
+    } else {
+        Finding::new(
+            Level::Warn,
+            "Truecolor",
+            "COLORTERM does not advertise 24-bit color; preview colors may look wrong",
+        )
+        .fix("If your terminal supports 24-bit color, export COLORTERM=truecolor")
+    });
+
+    let kitty = term.contains("kitty")
+        || var("KITTY_WINDOW_ID").is_some()
+        || matches!(program.as_str(), "WezTerm" | "ghostty");
+    findings.push(if kitty {
+        Finding::new(Level::Ok, "Kitty graphics", "supported by this terminal")
+    } else {
+        Finding::new(Level::Info, "Kitty graphics", "not detected")
+    });
+
+    // OSC 52 は環境変数からは判定できないので、既知の端末だけ報告する
+    let osc52 = kitty
+        || matches!(program.as_str(), "iTerm.app" | "WezTerm" | "ghostty")
+        || var("WT_SESSION").is_some();
+    let osc52 = if osc52 {
+        Finding::new(
+            Level::Ok,
+            "OSC 52",
+            "clipboard escape supported by this terminal",
+        )
+    } else {
+        Finding::new(
+            Level::Info,
+            "OSC 52",
+            "unknown terminal; support cannot be detected",
+        )
+    };
+    findings.push(if var("TMUX").is_some() {
+        osc52.fix("Inside tmux, forward OSC 52 with: set -g set-clipboard on")
+    } else {
+        osc52
+    });
+
+    findings
+}
+
+/// UTF-8 locale (needed for icons and box drawing)
+fn locale(var: &dyn Fn(&str) -> Option<String>) -> Finding {
+    if cfg!(windows) {
+        return Finding::new(Level::Info, "Locale", "not checked on Windows");
+    }
+    let (name, value) = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(|name| var(name).map(|value| (name, value)))
+        .unwrap_or(("LANG", String::new()));
+    let lower = value.to_lowercase();
+    if lower.contains("utf-8") || lower.contains("utf8") {
+        Finding::new(Level::Ok, "Locale", format!("{}={}", name, value))
+    } else {
+        Finding::new(
+            Level::Warn,
+            "Locale",
+            format!(
+                "{}='{}' is not UTF-8; icons and borders may be garbled",
+                name, value
+            ),
+        )
+        .fix("Use a UTF-8 locale, e.g. export LANG=en_US.UTF-8")
+    }
+}
+
+/// The config file's own problems, then anything that stops it from loading
+fn config_files() -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let path = Config::config_path();
+    match std::fs::read_to_string(&path) {
+        Ok(content) => {
+            let problems = config::check(&content);
+            findings.push(if problems.is_empty() {
+                Finding::new(Level::Ok, "Config", path.display().to_string())
+            } else {
+                let mut detail = path.display().to_string();
+                for problem in problems {
+                    detail.push_str("\n- ");
+                    detail.push_str(&problem);
+                }
+                Finding::new(Level::Error, "Config", detail)
+                    .fix("Correct the listed settings, then re-run `vfv config check`")
+            });
+        }
+        Err(_) => findings.push(Finding::new(
+            Level::Info,
+            "Config",
+            format!("no file at {} (defaults in use)", path.display()),
+        )),
+    }
+    // 環境変数・.vfv.toml・明示したパスの誤りはここで分かる
+    if let Err(e) = Config::load_with_result()
+        && findings.iter().all(|f| f.level != Level::Error)
+    {
+        findings.push(
+            Finding::new(Level::Error, "Config", e.to_string())
+                .fix("Fix the setting named above; vfv falls back to defaults until then"),
+        );
+    }
+    if let Some(project) = Config::project_config_path() {
+        findings.push(Finding::new(
+            Level::Info,
+            "Project config",
+            project.display().to_string(),
+        ));
+    }
+    findings
+}
+
+/// The editor `e` runs, with the order the candidates were tried in
+fn editor(candidates: &[Candidate], find: impl Fn(&str) -> Option<PathBuf>) -> Finding {
+    let order = candidates
+        .iter()
+        .map(|candidate| format!("{} {}", candidate.source, candidate.command))
+        .collect::<Vec<_>>()
+        .join(", ");
+    match editor::resolve(candidates, find) {
+        // 設定したエディタが見つからず、別のものにしたとき
+        Some((chosen, path)) if candidates[0].source == "config" && chosen != &candidates[0] => {
+            Finding::new(
+                Level::Warn,
+                "Editor",
+                format!(
+                    "'{}' is not on PATH, using {} from {} (tried {})",
+                    candidates[0].command,
+                    path.display(),
+                    chosen.source,
+                    order
+                ),
+            )
+            .fix("Install it, or remove editor from the config to pick one automatically")
+        }
+        Some((chosen, path)) => Finding::new(
+            Level::Ok,
+            "Editor",
+            format!(
+                "{} from {} (tried {})",
+                path.display(),
+                chosen.source,
+                order
+            ),
+        ),
+        None => Finding::new(
+            Level::Error,
+            "Editor",
+            format!("None found (tried {})", order),
+        )
+        .fix("Install one, set $EDITOR, or choose one: vfv config set editor nano"),
+    }
+}
+
+fn clipboard(
+    var: &dyn Fn(&str) -> Option<String>,
+    find: impl Fn(&str) -> Option<PathBuf>,
+) -> Finding {
+    let (program, _) = CLIPBOARD_COMMAND;
+    let Some(path) = find(program) else {
+        return Finding::new(
+            Level::Warn,
+            "Clipboard",
+            format!("'{}' is not on PATH; copying paths will fail", program),
+        )
+        .fix(format!("Install {} with your package manager", program));
+    };
+    // xclip は X のディスプレイがないと動かない
+    if program == "xclip" && var("DISPLAY").is_none() {
+        let fix = if var("SSH_CONNECTION").is_some() {
+            "Connect with X forwarding (ssh -X) to copy over SSH"
+        } else {
+            "Run vfv inside a graphical session (Wayland sessions need XWayland)"
+        };
+        return Finding::new(
+            Level::Warn,
+            "Clipboard",
+            format!("{} found, but DISPLAY is not set", path.display()),
+        )
+        .fix(fix);
+    }
+    Finding::new(Level::Ok, "Clipboard", path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name: &str| map.get(name).cloned()
+    }
+
+    fn level(findings: &[Finding], name: &str) -> Level {
+        findings.iter().find(|f| f.name == name).unwrap().level
+    }
+
+    #[test]
+    fn test_terminal_capabilities() {
+        let findings = terminal(&env(&[("TERM", "xterm-kitty"), ("COLORTERM", "truecolor")]));
+        assert_eq!(level(&findings, "Truecolor"), Level::Ok);
+        assert_eq!(level(&findings, "Kitty graphics"), Level::Ok);
+        assert_eq!(level(&findings, "OSC 52"), Level::Ok);
+
+        let findings = terminal(&env(&[("TERM", "xterm-256color"), ("TMUX", "/tmp/t,1,0")]));
+        let truecolor = findings.iter().find(|f| f.name == "Truecolor").unwrap();
+        assert_eq!(truecolor.level, Level::Warn);
+        assert!(
+            truecolor
+                .fix
+                .as_ref()
+                .unwrap()
+                .contains("COLORTERM=truecolor")
+        );
+        assert_eq!(level(&findings, "Kitty graphics"), Level::Info);
+        let osc52 = findings.iter().find(|f| f.name == "OSC 52").unwrap();
+        assert!(osc52.fix.as_ref().unwrap().contains("set-clipboard"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dumb_term_and_locale() {
+        let findings = terminal(&env(&[("TERM", "dumb")]));
+        assert_eq!(level(&findings, "TERM"), Level::Error);
+
+        assert_eq!(locale(&env(&[("LANG", "ja_JP.UTF-8")])).level, Level::Ok);
+        // LC_ALL は LANG より優先される
+        let finding = locale(&env(&[("LC_ALL", "C"), ("LANG", "en_US.utf8")]));
+        assert_eq!(finding.level, Level::Warn);
+        assert!(finding.detail.starts_with("LC_ALL='C'"));
+    }
+
+    #[test]
+    fn test_editor_and_clipboard() {
+        let found = |_: &str| Some(PathBuf::from("/usr/bin/tool"));
+        let missing = |_: &str| None;
+
+        let config = Config {
+            editor: "nvim".to_string(),
+            ..Config::default()
+        };
+        let candidates = editor::candidates(&config, env(&[("EDITOR", "nano")]));
+        let finding = editor(&candidates, found);
+        assert_eq!(finding.level, Level::Ok);
+        assert!(
+            finding
+                .detail
+                .contains("from config (tried config nvim, $EDITOR nano")
+        );
+        let finding = editor(&candidates, |program: &str| {
+            (program == "nano").then(|| PathBuf::from("/bin/nano"))
+        });
+        assert_eq!(finding.level, Level::Warn);
+        assert!(
+            finding
+                .detail
+                .starts_with("'nvim' is not on PATH, using /bin/nano from $EDITOR")
+        );
+        let finding = editor(&candidates, missing);
+        assert_eq!(finding.level, Level::Error);
+        assert!(finding.fix.unwrap().contains("vfv config set editor"));
+
+        let finding = clipboard(&env(&[("DISPLAY", ":0")]), missing);
+        assert_eq!(finding.level, Level::Warn);
+        assert!(finding.fix.unwrap().starts_with("Install"));
+        assert_eq!(
+            clipboard(&env(&[("DISPLAY", ":0")]), found).level,
+            Level::Ok
+        );
+    }
+}
@@ -1,24 +1,124 @@
+use std::ffi::{OsStr, OsString};
 use std::io::{self, Write};
-use std::path::Path;
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crossterm::{
+    event::{
+        DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 
+use crate::commands;
 use crate::config::Config;
 
+/// Whether the TUI captures the mouse, so it can be turned back on after a
+/// program run in the foreground exits
+pub static MOUSE_CAPTURE: AtomicBool = AtomicBool::new(false);
+
+/// Whether the kitty keyboard protocol is on, for the same reason
+pub static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
+
+/// Protocol features asked for: unambiguous keys (Ctrl+I apart from Tab,
+/// Shift+Enter) and press/repeat/release kinds
+pub const KEYBOARD_FLAGS: KeyboardEnhancementFlags =
+    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+        .union(KeyboardEnhancementFlags::REPORT_EVENT_TYPES);
+
+/// Where to put the cursor when opening a file (1-based)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Editors tried when neither the config nor the environment names one
+const FALLBACK_EDITORS: &[&str] = if cfg!(windows) {
+    &["notepad"]
+} else {
+    &["vim", "nvim", "nano", "vi"]
+};
+
+/// An editor to try, with where it came from ("config", "$VISUAL", ...)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub source: &'static str,
+    pub command: String,
+    pub args: Vec<String>,
+    pub detach: bool,
+}
+
+/// Editors in the order they are tried: `editor` from the config (with
+/// `editor_args`), `$VISUAL`, `$EDITOR`, then common editors for the OS.
+/// The variables may carry arguments ("code --wait").
+pub fn candidates(config: &Config, var: impl Fn(&str) -> Option<String>) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    if !config.editor.trim().is_empty() {
+        candidates.push(Candidate {
+            source: "config",
+            command: config.editor.clone(),
+            args: config.editor_args.clone(),
+            detach: config.editor_detach,
+        });
+    }
+    for name in ["VISUAL", "EDITOR"] {
+        let value = var(name).unwrap_or_default();
+        let mut words = value.split_whitespace().map(str::to_string);
+        if let Some(command) = words.next() {
+            candidates.push(Candidate {
+                source: if name == "VISUAL" {
+                    "$VISUAL"
+                } else {
+                    "$EDITOR"
+                },
+                command,
+                args: words.collect(),
+                detach: false,
+            });
+        }
+    }
+    candidates.extend(FALLBACK_EDITORS.iter().map(|command| Candidate {
+        source: "default",
+        command: command.to_string(),
+        args: Vec::new(),
+        detach: false,
+    }));
+    candidates
+}
+
+/// The first candidate that is installed, with its location
+pub fn resolve(
+    candidates: &[Candidate],
+    find: impl Fn(&str) -> Option<PathBuf>,
+) -> Option<(&Candidate, PathBuf)> {
+    candidates
+        .iter()
+        .find_map(|candidate| find(&candidate.command).map(|path| (candidate, path)))
+}
+
 pub struct Editor {
     command: String,
     args: Vec<String>,
+    detach: bool,
 }
 
 impl Editor {
+    /// The first installed editor of `candidates`; when none is, the first
+    /// one anyway, so the error names the editor that was asked for
     pub fn new(config: &Config) -> Self {
+        let candidates = candidates(config, |name| std::env::var(name).ok());
+        let chosen = match resolve(&candidates, find_executable) {
+            Some((candidate, _)) => candidate,
+            None => &candidates[0],
+        };
         Self {
-            command: config.editor.clone(),
-            args: config.editor_args.clone(),
+            command: chosen.command.clone(),
+            args: chosen.args.clone(),
+            detach: chosen.detach,
         }
     }
 
@@ -47,44 +147,215 @@ impl Editor {
         Ok(())
     }
 
-    pub fn open(&self, path: &Path) -> Result<(), String> {
-        // Validate path before opening
+    /// Open one file, at `position` when the editor is known to take one
+    pub fn open(&self, path: &Path, position: Option<Position>) -> Result<(), String> {
         Self::validate_path(path)?;
 
-        let path_str = path.to_string_lossy().to_string();
+        let mut cmd = Command::new(&self.command);
+        match expand_args(&self.args, path, position) {
+            Some(args) => cmd.args(args),
+            None => cmd
+                .args(&self.args)
+                .args(position_args(&self.command, path, position)),
+        };
+        self.launch(&mut cmd)
+    }
 
-        // Restore terminal to normal state
-        disable_raw_mode().map_err(|e| format!("Failed to disable raw mode: {}", e))?;
-        execute!(io::stdout(), LeaveAlternateScreen)
-            .map_err(|e| format!("Failed to leave alternate screen: {}", e))?;
+    /// Open one file and wait for the editor to exit, even a detached one,
+    /// for files vfv reads back afterwards
+    pub fn edit(&self, path: &Path) -> Result<(), String> {
+        Self::validate_path(path)?;
 
-        // Run editor with inherited stdio
         let mut cmd = Command::new(&self.command);
-        for arg in &self.args {
-            cmd.arg(arg);
+        match expand_args(&self.args, path, None) {
+            Some(args) => cmd.args(args),
+            None => cmd.args(&self.args).arg(path),
+        };
+        run_in_terminal(&mut cmd, "editor").map(|_| ())
+    }
+
+    /// Open several files in a single editor invocation
+    pub fn open_all(&self, paths: &[&Path]) -> Result<(), String> {
+        // Validate paths before opening
+        for path in paths {
+            Self::validate_path(path)?;
         }
-        cmd.arg(&path_str);
-        cmd.stdin(Stdio::inherit());
-        cmd.stdout(Stdio::inherit());
-        cmd.stderr(Stdio::inherit());
-
-        let result = match cmd.spawn() {
-            Ok(mut child) => match child.wait() {
-                Ok(_) => Ok(()),
-                Err(e) => Err(format!("Editor process error: {}", e)),
-            },
-            Err(e) => Err(format!("Failed to open editor '{}': {}", self.command, e)),
+
+        let mut cmd = Command::new(&self.command);
+        // プレースホルダがあれば引数一式をファイルごとに繰り返す
+        let expanded: Option<Vec<OsString>> = paths
+            .iter()
+            .map(|path| expand_args(&self.args, path, None))
+            .collect::<Option<Vec<_>>>()
+            .map(|args| args.concat());
+        match expanded {
+            Some(args) => cmd.args(args),
+            None => cmd.args(&self.args).args(paths),
         };
+        self.launch(&mut cmd)
+    }
+
+    fn launch(&self, cmd: &mut Command) -> Result<(), String> {
+        if self.detach {
+            spawn_detached(cmd, "editor")
+        } else {
+            run_in_terminal(cmd, "editor").map(|_| ())
+        }
+    }
+}
+
+/// Placeholders `editor_args` may contain
+const PLACEHOLDERS: &[&str] = &["{file}", "{line}", "{dir}"];
+
+/// `args` with `{file}`, `{line}` (1 without a position) and `{dir}` (the
+/// file's directory) filled in; None when no argument has a placeholder,
+/// in which case the path goes last as usual
+fn expand_args(args: &[String], path: &Path, position: Option<Position>) -> Option<Vec<OsString>> {
+    if !args.iter().any(|arg| {
+        PLACEHOLDERS
+            .iter()
+            .any(|placeholder| arg.contains(placeholder))
+    }) {
+        return None;
+    }
+    let line = position.map_or(1, |position| position.line).to_string();
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.as_os_str(),
+        _ => OsStr::new("."),
+    };
+    let values = [
+        ("{file}", path.as_os_str()),
+        ("{line}", OsStr::new(&line)),
+        ("{dir}", dir),
+    ];
+    Some(
+        args.iter()
+            .map(|arg| commands::fill(arg, &values))
+            .collect(),
+    )
+}
+
+/// Arguments that open `path` at `position`, in the syntax of the editor
+/// `command` names; unknown editors just get the path
+fn position_args(command: &str, path: &Path, position: Option<Position>) -> Vec<OsString> {
+    let Some(Position { line, column }) = position else {
+        return vec![path.into()];
+    };
+    let program = Path::new(command)
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    // path:line:col 形式
+    let with_position = || {
+        let mut arg = path.as_os_str().to_owned();
+        arg.push(format!(":{}:{}", line, column));
+        arg
+    };
+    match program.as_str() {
+        "vi" | "vim" | "nvim" | "gvim" | "mvim" | "view" if column > 1 => vec![
+            format!("+call cursor({}, {})", line, column).into(),
+            path.into(),
+        ],
+        "vi" | "vim" | "nvim" | "gvim" | "mvim" | "view" => {
+            vec![format!("+{}", line).into(), path.into()]
+        }
+        "nano" => vec![format!("+{},{}", line, column).into(), path.into()],
+        "emacs" | "emacsclient" | "kak" => {
+            vec![format!("+{}:{}", line, column).into(), path.into()]
+        }
+        "code" | "code-insiders" | "codium" | "cursor" => vec!["--goto".into(), with_position()],
+        "subl" | "zed" | "hx" | "helix" | "micro" => vec![with_position()],
+        _ => vec![path.into()],
+    }
+}
 
-        // Restore TUI state
-        enable_raw_mode().map_err(|e| format!("Failed to enable raw mode: {}", e))?;
-        execute!(io::stdout(), EnterAlternateScreen)
-            .map_err(|e| format!("Failed to enter alternate screen: {}", e))?;
+/// Run a command in the foreground, suspending the TUI until it exits
+pub fn run_in_terminal(cmd: &mut Command, label: &str) -> Result<ExitStatus, String> {
+    // Restore terminal to normal state
+    if KEYBOARD_ENHANCED.load(Ordering::Relaxed) {
+        execute!(io::stdout(), PopKeyboardEnhancementFlags)
+            .map_err(|e| format!("Failed to reset the keyboard protocol: {}", e))?;
+    }
+    disable_raw_mode().map_err(|e| format!("Failed to disable raw mode: {}", e))?;
+    execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen)
+        .map_err(|e| format!("Failed to leave alternate screen: {}", e))?;
+
+    // Run with inherited stdio
+    cmd.stdin(Stdio::inherit());
+    cmd.stdout(Stdio::inherit());
+    cmd.stderr(Stdio::inherit());
+
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let result = match cmd.spawn() {
+        Ok(mut child) => match child.wait() {
+            Ok(status) => Ok(status),
+            Err(e) => Err(format!("{} process error: {}", capitalize(label), e)),
+        },
+        Err(e) => Err(format!("Failed to open {} '{}': {}", label, program, e)),
+    };
+
+    // Restore TUI state
+    enable_raw_mode().map_err(|e| format!("Failed to enable raw mode: {}", e))?;
+    execute!(io::stdout(), EnterAlternateScreen)
+        .map_err(|e| format!("Failed to enter alternate screen: {}", e))?;
+    if MOUSE_CAPTURE.load(Ordering::Relaxed) {
+        execute!(io::stdout(), EnableMouseCapture)
+            .map_err(|e| format!("Failed to enable mouse capture: {}", e))?;
+    }
+    if KEYBOARD_ENHANCED.load(Ordering::Relaxed) {
+        execute!(io::stdout(), PushKeyboardEnhancementFlags(KEYBOARD_FLAGS))
+            .map_err(|e| format!("Failed to enable the keyboard protocol: {}", e))?;
+    }
+
+    // Force redraw
+    io::stdout().flush().ok();
+
+    result
+}
+
+/// Start a program in the background without suspending the TUI, for GUI
+/// programs that open their own window
+pub fn spawn_detached(cmd: &mut Command, label: &str) -> Result<(), String> {
+    // 端末には何も書かせず、Ctrl+C も届かないよう別のプロセスグループにする
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::null());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(cmd, 0);
 
-        // Force redraw
-        io::stdout().flush().ok();
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to open {} '{}': {}", label, program, e))?;
+    // 終了したプロセスがゾンビとして残らないよう回収する
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Locate a program the way the shell would: a path is checked as is,
+/// a bare name is searched for in `PATH`
+pub fn find_executable(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let dirs = std::env::var_os("PATH")?;
+    std::env::split_paths(&dirs).find_map(|dir| {
+        let candidate = dir.join(program);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        // Windows では拡張子を省略できる
+        let exe = candidate.with_extension("exe");
+        (cfg!(windows) && exe.is_file()).then_some(exe)
+    })
+}
 
-        result
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
     }
 }
 
@@ -94,6 +365,125 @@ mod tests {
     use std::fs::File;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_find_executable() {
+        let temp_dir = TempDir::new().unwrap();
+        let program = temp_dir.path().join("tool");
+        File::create(&program).unwrap();
+
+        assert_eq!(find_executable(program.to_str().unwrap()), Some(program));
+        assert!(find_executable("/nonexistent/tool").is_none());
+        assert!(find_executable("vfv-no-such-program").is_none());
+    }
+
+    #[test]
+    fn test_editor_resolution_order() {
+        let config = Config {
+            editor: "kak".to_string(),
+            editor_args: vec!["-n".to_string()],
+            editor_detach: true,
+            ..Config::default()
+        };
+        let env = |name: &str| match name {
+            "VISUAL" => Some("code --wait".to_string()),
+            "EDITOR" => Some(" ".to_string()),
+            _ => None,
+        };
+        let list = candidates(&config, env);
+        assert_eq!(list[0].command, "kak");
+        assert_eq!(list[0].args, ["-n"]);
+        // detach は設定のエディタにだけ効く
+        assert!(list[0].detach);
+        assert!(!list[1].detach);
+        assert_eq!(list[1].source, "$VISUAL");
+        assert_eq!(
+            (list[1].command.as_str(), &list[1].args[..]),
+            ("code", &["--wait".to_string()][..])
+        );
+        // 空の $EDITOR は飛ばす
+        assert_eq!(list[2].source, "default");
+
+        // 設定のエディタがなければ $VISUAL を使う
+        let find = |program: &str| (program == "code").then(|| PathBuf::from("/usr/bin/code"));
+        let (chosen, path) = resolve(&list, find).unwrap();
+        assert_eq!(chosen.source, "$VISUAL");
+        assert_eq!(path, PathBuf::from("/usr/bin/code"));
+        assert!(resolve(&list, |_: &str| None).is_none());
+
+        // 設定がなければ環境変数から
+        let list = candidates(&Config::default(), env);
+        assert_eq!(list[0].source, "$VISUAL");
+    }
+
+    #[test]
+    fn test_expand_args() {
+        let path = Path::new("src/main.rs");
+        let args = |args: &[&str], position| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            expand_args(&args, path, position)
+        };
+        assert_eq!(
+            args(
+                &["--goto", "{file}:{line}"],
+                Some(Position {
+                    line: 12,
+                    column: 3
+                })
+            )
+            .unwrap(),
+            ["--goto", "src/main.rs:12"]
+        );
+        assert_eq!(
+            args(&["-C", "{dir}", "{file}:{line}"], None).unwrap(),
+            ["-C", "src", "src/main.rs:1"]
+        );
+        assert_eq!(
+            expand_args(&["{dir}".to_string()], Path::new("main.rs"), None).unwrap(),
+            ["."]
+        );
+        // パスに含まれる {line} などはそのまま
+        assert_eq!(
+            expand_args(
+                &["{file}:{line}".to_string()],
+                Path::new("notes/{line}.md"),
+                None
+            )
+            .unwrap(),
+            ["notes/{line}.md:1"]
+        );
+        // プレースホルダがなければ従来どおりパスを末尾に足す
+        assert!(args(&["--wait"], None).is_none());
+        assert!(args(&[], None).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_expand_args_keeps_non_utf8_paths() {
+        use std::os::unix::ffi::OsStrExt;
+        let path = Path::new(OsStr::from_bytes(b"dir/caf\xe9.txt"));
+        let args = expand_args(&["--file={file}".to_string()], path, None).unwrap();
+        assert_eq!(args[0].as_bytes(), b"--file=dir/caf\xe9.txt");
+    }
+
+    #[test]
+    fn test_position_args() {
+        let path = Path::new("src/main.rs");
+        let args = |command: &str, line, column| {
+            position_args(command, path, Some(Position { line, column }))
+        };
+        assert_eq!(args("nvim", 12, 1), ["+12", "src/main.rs"]);
+        assert_eq!(
+            args("/usr/bin/vim", 12, 5),
+            ["+call cursor(12, 5)", "src/main.rs"]
+        );
+        assert_eq!(args("code", 3, 7), ["--goto", "src/main.rs:3:7"]);
+        assert_eq!(args("hx", 3, 1), ["src/main.rs:3:1"]);
+        assert_eq!(args("nano", 3, 2), ["+3,2", "src/main.rs"]);
+        // 知らないエディタや位置なしはパスだけ
+        assert_eq!(args("ed", 3, 1), ["src/main.rs"]);
+        assert_eq!(position_args("vim", path, None), ["src/main.rs"]);
+    }
+
     #[test]
     fn test_validate_path_existing_file() {
         let temp_dir = TempDir::new().unwrap();
@@ -151,13 +541,18 @@ mod tests {
 
     #[test]
     fn test_editor_new() {
+        let temp_dir = TempDir::new().unwrap();
+        let program = temp_dir.path().join("nvim");
+        File::create(&program).unwrap();
+        let program = program.to_string_lossy().to_string();
+
         let config = Config {
-            editor: "nvim".to_string(),
+            editor: program.clone(),
             editor_args: vec!["-c".to_string(), "startinsert".to_string()],
             ..Config::default()
         };
         let editor = Editor::new(&config);
-        assert_eq!(editor.command, "nvim");
+        assert_eq!(editor.command, program);
         assert_eq!(editor.args, vec!["-c", "startinsert"]);
     }
 }
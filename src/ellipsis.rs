@@ -0,0 +1,118 @@
+use std::path::MAIN_SEPARATOR;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Shorten `text` to at most `width` columns by replacing its middle with
+/// "…". Paths lose whole directories first ("/home/me/…/src/main.rs"), so
+/// the start and the file name stay readable; anything else is cut in the
+/// middle of a character run.
+pub fn middle(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let parts: Vec<&str> = text.split(MAIN_SEPARATOR).collect();
+    if parts.len() > 2
+        && let Some(shortened) = drop_directories(&parts, width)
+    {
+        return shortened;
+    }
+    cut_chars(text, width)
+}
+
+/// parts[..head] と parts[tail..] を残し、間を … にしたパス
+fn join_around(parts: &[&str], head: usize, tail: usize) -> String {
+    let separator = MAIN_SEPARATOR.to_string();
+    let prefix = if head > 0 {
+        format!("{}{}", parts[..head].join(&separator), MAIN_SEPARATOR)
+    } else {
+        String::new()
+    };
+    format!(
+        "{}…{}{}",
+        prefix,
+        MAIN_SEPARATOR,
+        parts[tail..].join(&separator)
+    )
+}
+
+/// Keep as many leading and trailing components as fit, preferring the end
+fn drop_directories(parts: &[&str], width: usize) -> Option<String> {
+    let fits = |head: usize, tail: usize| join_around(parts, head, tail).width() <= width;
+    let mut tail = parts.len() - 1;
+    let mut head = if fits(1, tail) {
+        1
+    } else if fits(0, tail) {
+        0
+    } else {
+        return None;
+    };
+    // 末尾と先頭から交互に（末尾を先に）階層を戻していく
+    loop {
+        let mut grown = false;
+        if head + 1 < tail && fits(head, tail - 1) {
+            tail -= 1;
+            grown = true;
+        }
+        if head + 1 < tail && fits(head + 1, tail) {
+            head += 1;
+            grown = true;
+        }
+        if !grown {
+            return Some(join_around(parts, head, tail));
+        }
+    }
+}
+
+/// Start and end of `text` around a "…", split by display width
+fn cut_chars(text: &str, width: usize) -> String {
+    if width == 0 {
+        return String::new();
+    }
+    let room = width - 1;
+    let mut start = String::new();
+    let mut start_width = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if start_width + w > room.div_ceil(2) {
+            break;
+        }
+        start.push(c);
+        start_width += w;
+    }
+    let mut end = Vec::new();
+    let mut end_width = 0;
+    for c in text.chars().rev() {
+        let w = c.width().unwrap_or(0);
+        if start_width + end_width + w > room {
+            break;
+        }
+        end.push(c);
+        end_width += w;
+    }
+    format!("{}…{}", start, end.into_iter().rev().collect::<String>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_paths_keep_both_ends() {
+        let path = "/home/me/projects/vive-file-viewer/src/main.rs";
+        assert_eq!(middle(path, 80), path);
+        assert_eq!(middle(path, 24), "/home/me/…/src/main.rs");
+        assert_eq!(middle(path, 13), "/…/main.rs");
+        assert_eq!(middle("docs/guide/setup.md", 12), "…/setup.md");
+        assert!(middle(path, 24).width() <= 24);
+    }
+
+    #[test]
+    fn test_cut_in_the_middle() {
+        assert_eq!(middle("abcdefghij", 5), "ab…ij");
+        assert_eq!(middle("abcdefghij", 6), "abc…ij");
+        assert_eq!(middle("日本語のファイル名", 9), "日本…ル名");
+        assert_eq!(middle("abc", 0), "");
+        assert_eq!(middle("abc", 1), "…");
+    }
+}
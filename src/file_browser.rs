@@ -1,25 +1,161 @@
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Ordering of entries within the directories-first listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    #[default]
+    Name,
+    /// Largest first
+    Size,
+    /// Newest first
+    #[serde(rename = "mtime")]
+    Modified,
+    #[serde(rename = "ext")]
+    Extension,
+}
+
+impl SortMode {
+    pub const ALL: [SortMode; 4] = [
+        SortMode::Name,
+        SortMode::Size,
+        SortMode::Modified,
+        SortMode::Extension,
+    ];
+
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Name => SortMode::Size,
+            SortMode::Size => SortMode::Modified,
+            SortMode::Modified => SortMode::Extension,
+            SortMode::Extension => SortMode::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Name => "name",
+            SortMode::Size => "size",
+            SortMode::Modified => "mtime",
+            SortMode::Extension => "ext",
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct FileEntry {
     pub name: String,
     pub path: PathBuf,
     pub is_dir: bool,
+    /// Target path when the entry is a symlink
+    pub link_target: Option<PathBuf>,
+    /// Symlink whose target does not exist
+    pub is_broken_link: bool,
+    /// Size in bytes (0 for directories)
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// Unix permission bits (None on platforms without them)
+    pub mode: Option<u32>,
+    /// Owning user and group ids (None on platforms without them)
+    pub owner: Option<(u32, u32)>,
+    /// Nesting level below `current_dir` in the tree view
+    pub depth: usize,
+    /// Directory expanded inline in the tree view
+    pub expanded: bool,
 }
 
 impl FileEntry {
     pub fn new(path: PathBuf) -> Option<Self> {
-        let metadata = fs::metadata(&path).ok()?;
+        let link_metadata = fs::symlink_metadata(&path).ok()?;
         let name = path.file_name()?.to_string_lossy().to_string();
 
+        let link_target = if link_metadata.file_type().is_symlink() {
+            fs::read_link(&path).ok()
+        } else {
+            None
+        };
+        // シンボリックリンクはリンク先のメタデータを使う（リンク切れも一覧に残す）
+        let metadata = fs::metadata(&path).ok();
+        let is_broken_link = link_target.is_some() && metadata.is_none();
+
+        let is_dir = metadata.as_ref().is_some_and(|m| m.is_dir());
+        let metadata = metadata.unwrap_or(link_metadata);
+
         Some(Self {
             name,
             path,
-            is_dir: metadata.is_dir(),
+            is_dir,
+            link_target,
+            is_broken_link,
+            size: if is_dir { 0 } else { metadata.len() },
+            modified: metadata.modified().ok(),
+            mode: file_mode(&metadata),
+            owner: file_owner(&metadata),
+            depth: 0,
+            expanded: false,
         })
     }
+
+    /// Permission string in `ls -l` form (e.g. "drwxr-xr-x")
+    pub fn permission_string(&self) -> String {
+        let kind = if self.link_target.is_some() {
+            'l'
+        } else if self.is_dir {
+            'd'
+        } else {
+            '-'
+        };
+        let Some(mode) = self.mode else {
+            return format!("{}---------", kind);
+        };
+
+        let mut s = String::with_capacity(10);
+        s.push(kind);
+        for shift in [6, 3, 0] {
+            let bits = (mode >> shift) & 0o7;
+            s.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+            s.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+            s.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+        }
+        s
+    }
+
+    /// Lowercased extension used for sorting ("" when there is none)
+    fn extension(&self) -> String {
+        Path::new(&self.name)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn file_owner(metadata: &fs::Metadata) -> Option<(u32, u32)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.uid(), metadata.gid()))
+}
+
+#[cfg(not(unix))]
+fn file_owner(_metadata: &fs::Metadata) -> Option<(u32, u32)> {
+    None
 }
 
 #[derive(Debug)]
@@ -28,6 +164,20 @@ pub struct FileBrowser {
     pub entries: Vec<FileEntry>,
     pub selected_index: usize,
     pub show_hidden: bool,
+    /// Marked entries for bulk operations (kept across directories)
+    pub marked: BTreeSet<PathBuf>,
+    pub sort: SortMode,
+    pub sort_reverse: bool,
+    /// Compare digit runs in names numerically ("file2" before "file10")
+    pub natural_sort: bool,
+    /// Show expanded directories' contents inline
+    pub tree_mode: bool,
+    /// Directories expanded in the tree view (kept while the tree is off)
+    pub expanded: BTreeSet<PathBuf>,
+    /// Names matching these globs are never listed
+    pub ignore: GlobSet,
+    /// Dotfiles left out of `entries` while hidden files are off
+    pub hidden_count: usize,
 }
 
 impl FileBrowser {
@@ -38,35 +188,129 @@ impl FileBrowser {
             entries: Vec::new(),
             selected_index: 0,
             show_hidden,
+            marked: BTreeSet::new(),
+            sort: SortMode::default(),
+            sort_reverse: false,
+            natural_sort: false,
+            tree_mode: false,
+            expanded: BTreeSet::new(),
+            ignore: GlobSet::empty(),
+            hidden_count: 0,
         };
         browser.refresh();
         browser
     }
 
     pub fn refresh(&mut self) {
-        self.entries.clear();
-
-        if let Ok(read_dir) = fs::read_dir(&self.current_dir) {
-            for entry in read_dir.flatten() {
-                if let Some(file_entry) = FileEntry::new(entry.path())
-                    && (self.show_hidden || !file_entry.name.starts_with('.'))
-                {
-                    self.entries.push(file_entry);
-                }
+        let mut entries = Vec::new();
+        self.hidden_count = self.read_level(&self.current_dir, 0, &mut entries);
+        self.entries = entries;
+
+        if self.selected_index >= self.entries.len() {
+            self.selected_index = self.entries.len().saturating_sub(1);
+        }
+
+        // 削除・移動されたエントリのマークは外す
+        self.marked
+            .retain(|path| fs::symlink_metadata(path).is_ok());
+    }
+
+    /// Hide entries whose names match any of `patterns` (e.g. `*.pyc`)
+    pub fn set_ignore(&mut self, patterns: &[String]) -> Result<(), globset::Error> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern)?);
+        }
+        self.ignore = builder.build()?;
+        self.refresh();
+        Ok(())
+    }
+
+    /// List one directory, followed in the tree view by the contents of
+    /// each expanded subdirectory right after it. Returns how many hidden
+    /// files were left out.
+    fn read_level(&self, dir: &Path, depth: usize, out: &mut Vec<FileEntry>) -> usize {
+        let Ok(read_dir) = fs::read_dir(dir) else {
+            return 0;
+        };
+        let mut hidden = 0;
+        let mut entries: Vec<FileEntry> = read_dir
+            .flatten()
+            .filter(|entry| {
+                let is_hidden =
+                    !self.show_hidden && entry.file_name().to_string_lossy().starts_with('.');
+                hidden += usize::from(is_hidden);
+                !is_hidden
+            })
+            .filter_map(|entry| FileEntry::new(entry.path()))
+            .filter(|entry| !self.ignore.is_match(&entry.name))
+            .collect();
+        sort_entries(
+            &mut entries,
+            self.sort,
+            self.sort_reverse,
+            self.natural_sort,
+        );
+
+        for mut entry in entries {
+            entry.depth = depth;
+            entry.expanded = self.tree_mode && entry.is_dir && self.expanded.contains(&entry.path);
+            let nested = entry.expanded.then(|| entry.path.clone());
+            out.push(entry);
+            if let Some(nested) = nested {
+                hidden += self.read_level(&nested, depth + 1, out);
             }
         }
+        hidden
+    }
 
-        self.entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-            (true, false) => Ordering::Less,
-            (false, true) => Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-        });
+    /// Change the sort order, keeping the selected entry selected
+    pub fn set_sort(&mut self, sort: SortMode, reverse: bool) {
+        let selected = self.selected_entry().map(|e| e.path.clone());
+        self.sort = sort;
+        self.sort_reverse = reverse;
+        self.refresh();
+        if let Some(path) = selected {
+            self.select_path(&path);
+        }
+    }
 
-        if self.selected_index >= self.entries.len() {
-            self.selected_index = self.entries.len().saturating_sub(1);
+    /// Move to another directory, selecting its first entry
+    pub fn change_dir(&mut self, path: &Path) {
+        self.current_dir = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.selected_index = 0;
+        self.refresh();
+    }
+
+    /// Toggle the mark on the selected entry and move to the next one
+    pub fn toggle_mark(&mut self) {
+        let Some(path) = self.selected_entry().map(|e| e.path.clone()) else {
+            return;
+        };
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+        if self.selected_index + 1 < self.entries.len() {
+            self.selected_index += 1;
+        }
+    }
+
+    /// Mark every entry between two indices (inclusive, in either order)
+    pub fn mark_range(&mut self, from: usize, to: usize) {
+        let (start, end) = (from.min(to), from.max(to));
+        for entry in self.entries.iter().take(end + 1).skip(start) {
+            self.marked.insert(entry.path.clone());
         }
     }
 
+    pub fn is_marked(&self, entry: &FileEntry) -> bool {
+        self.marked.contains(&entry.path)
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
     pub fn move_up(&mut self) {
         if self.entries.is_empty() {
             return;
@@ -97,6 +341,17 @@ impl FileBrowser {
         self.selected_index = self.entries.len().saturating_sub(1);
     }
 
+    /// Select the entry with the given path, if it is listed
+    pub fn select_path(&mut self, path: &Path) -> bool {
+        match self.entries.iter().position(|e| e.path == path) {
+            Some(idx) => {
+                self.selected_index = idx;
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn selected_entry(&self) -> Option<&FileEntry> {
         self.entries.get(self.selected_index)
     }
@@ -124,7 +379,10 @@ impl FileBrowser {
             self.refresh();
 
             if let Some(old_name) = old_dir_name
-                && let Some(idx) = self.entries.iter().position(|e| e.name == old_name)
+                && let Some(idx) = self
+                    .entries
+                    .iter()
+                    .position(|e| e.depth == 0 && e.name == old_name)
             {
                 self.selected_index = idx;
             }
@@ -137,6 +395,123 @@ impl FileBrowser {
         self.show_hidden = !self.show_hidden;
         self.refresh();
     }
+
+    /// Switch between the flat listing and the tree view, keeping the selection
+    pub fn toggle_tree(&mut self) {
+        let selected = self.selected_entry().map(|e| e.path.clone());
+        self.tree_mode = !self.tree_mode;
+        self.refresh();
+        // ツリーを閉じたときに入れ子のエントリを選択していたら、その親（一覧の最上位）を選ぶ
+        if let Some(path) = selected {
+            let visible = path
+                .ancestors()
+                .find(|p| p.parent() == Some(self.current_dir.as_path()))
+                .unwrap_or(&path)
+                .to_path_buf();
+            self.select_path(&visible);
+        }
+    }
+
+    /// Expand the selected directory inline
+    pub fn expand_selected(&mut self) -> bool {
+        let Some(entry) = self.selected_entry() else {
+            return false;
+        };
+        if !self.tree_mode || !entry.is_dir || entry.expanded {
+            return false;
+        }
+        self.expanded.insert(entry.path.clone());
+        self.refresh();
+        true
+    }
+
+    /// Collapse the selected directory, or move to the parent of a nested entry
+    pub fn collapse_selected(&mut self) -> bool {
+        let Some(entry) = self.selected_entry() else {
+            return false;
+        };
+        if entry.expanded {
+            let path = entry.path.clone();
+            self.expanded.remove(&path);
+            self.refresh();
+            return true;
+        }
+        if entry.depth > 0
+            && let Some(parent) = entry.path.parent().map(Path::to_path_buf)
+        {
+            return self.select_path(&parent);
+        }
+        false
+    }
+
+    /// Expand or collapse the selected directory
+    pub fn toggle_expanded(&mut self) -> bool {
+        match self.selected_entry() {
+            Some(entry) if entry.expanded => self.collapse_selected(),
+            _ => self.expand_selected(),
+        }
+    }
+}
+
+/// Sort entries by the given mode, keeping directories first
+fn sort_entries(entries: &mut [FileEntry], sort: SortMode, reverse: bool, natural: bool) {
+    entries.sort_by(|a, b| {
+        let by_kind = b.is_dir.cmp(&a.is_dir);
+        let by_name = || {
+            let (a, b) = (a.name.to_lowercase(), b.name.to_lowercase());
+            if natural {
+                natural_cmp(&a, &b)
+            } else {
+                a.cmp(&b)
+            }
+        };
+        let ordering = match sort {
+            SortMode::Name => by_name(),
+            SortMode::Size => b.size.cmp(&a.size).then_with(by_name),
+            SortMode::Modified => b.modified.cmp(&a.modified).then_with(by_name),
+            SortMode::Extension => a.extension().cmp(&b.extension()).then_with(by_name),
+        };
+        by_kind.then(if reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        })
+    });
+}
+
+/// Compare strings treating runs of ASCII digits as numbers
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(ca), Some(cb)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let (num_a, rest_a) = split_digits(a);
+            let (num_b, rest_b) = split_digits(b);
+            // 先頭の0を除いた桁数、値、0の数の順に比べる（"01" は "1" の後）
+            let (trim_a, trim_b) = (num_a.trim_start_matches('0'), num_b.trim_start_matches('0'));
+            let ordering = trim_a
+                .len()
+                .cmp(&trim_b.len())
+                .then_with(|| trim_a.cmp(trim_b))
+                .then_with(|| num_a.len().cmp(&num_b.len()));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (rest_a, rest_b);
+        } else {
+            if ca != cb {
+                return ca.cmp(&cb);
+            }
+            (a, b) = (&a[ca.len_utf8()..], &b[cb.len_utf8()..]);
+        }
+    }
+}
+
+fn split_digits(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
 }
 
 #[cfg(test)]
@@ -196,6 +571,7 @@ mod tests {
         let browser = FileBrowser::new(temp_dir.path(), false);
 
         assert!(!browser.entries.iter().any(|e| e.name.starts_with('.')));
+        assert_eq!(browser.hidden_count, 2);
     }
 
     #[test]
@@ -204,6 +580,7 @@ mod tests {
         let browser = FileBrowser::new(temp_dir.path(), true);
 
         assert!(browser.entries.iter().any(|e| e.name.starts_with('.')));
+        assert_eq!(browser.hidden_count, 0);
     }
 
     #[test]
@@ -280,6 +657,120 @@ mod tests {
         assert!(count_with_hidden > count_without_hidden);
     }
 
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec![
+            "file10.txt",
+            "file2.txt",
+            "file1.txt",
+            "file02.txt",
+            "a",
+            "file",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            [
+                "a",
+                "file",
+                "file1.txt",
+                "file2.txt",
+                "file02.txt",
+                "file10.txt"
+            ]
+        );
+        assert_eq!(natural_cmp("v1.10", "v1.9"), Ordering::Greater);
+        assert_eq!(natural_cmp("same", "same"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_natural_sort_in_listing() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["shot10.png", "shot2.png", "shot1.png"] {
+            File::create(temp_dir.path().join(name)).unwrap();
+        }
+        let mut browser = FileBrowser::new(temp_dir.path(), false);
+        let names =
+            |b: &FileBrowser| -> Vec<String> { b.entries.iter().map(|e| e.name.clone()).collect() };
+        assert_eq!(names(&browser), ["shot1.png", "shot10.png", "shot2.png"]);
+
+        browser.natural_sort = true;
+        browser.refresh();
+        assert_eq!(names(&browser), ["shot1.png", "shot2.png", "shot10.png"]);
+    }
+
+    #[test]
+    fn test_tree_expand_and_collapse() {
+        let temp_dir = setup_test_dir();
+        let mut browser = FileBrowser::new(temp_dir.path(), false);
+        let names = |b: &FileBrowser| -> Vec<(String, usize)> {
+            b.entries
+                .iter()
+                .map(|e| (e.name.clone(), e.depth))
+                .collect()
+        };
+
+        // ツリー表示でなければ展開しない
+        assert!(!browser.expand_selected());
+        browser.toggle_tree();
+
+        assert_eq!(browser.selected_entry().unwrap().name, "alpha_dir");
+        assert!(browser.expand_selected());
+        browser.move_down();
+        assert!(browser.expand_selected());
+        assert_eq!(
+            names(&browser)[..4],
+            [
+                ("alpha_dir".to_string(), 0),
+                ("nested".to_string(), 1),
+                ("deep.txt".to_string(), 2),
+                ("beta_dir".to_string(), 0),
+            ]
+        );
+
+        // 入れ子のエントリで折りたたむと親に移動する
+        browser.move_down();
+        assert!(browser.collapse_selected());
+        assert_eq!(browser.selected_entry().unwrap().name, "nested");
+        assert!(browser.collapse_selected());
+        assert_eq!(browser.entries.len(), 5);
+
+        // ツリーを閉じると入れ子の選択は最上位の親に移る
+        browser.expand_selected();
+        browser.move_down();
+        browser.toggle_tree();
+        assert_eq!(browser.selected_entry().unwrap().name, "alpha_dir");
+        assert!(browser.entries.iter().all(|e| e.depth == 0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_entries() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = setup_test_dir();
+        let base = temp_dir.path();
+        symlink(base.join("file_a.txt"), base.join("link_ok")).unwrap();
+        symlink(base.join("alpha_dir"), base.join("link_dir")).unwrap();
+        symlink(base.join("missing"), base.join("link_broken")).unwrap();
+
+        let browser = FileBrowser::new(base, false);
+        let find = |name: &str| browser.entries.iter().find(|e| e.name == name).unwrap();
+
+        let ok = find("link_ok");
+        assert_eq!(ok.link_target, Some(base.join("file_a.txt")));
+        assert!(!ok.is_broken_link);
+        assert!(!ok.is_dir);
+
+        assert!(find("link_dir").is_dir);
+
+        let broken = find("link_broken");
+        assert!(broken.is_broken_link);
+        assert!(!broken.is_dir);
+
+        assert!(find("file_a.txt").link_target.is_none());
+    }
+
     #[test]
     fn test_selected_entry() {
         let temp_dir = setup_test_dir();
@@ -288,4 +779,104 @@ mod tests {
         let entry = browser.selected_entry();
         assert!(entry.is_some());
     }
+
+    #[test]
+    fn test_ignore_patterns() {
+        let temp_dir = setup_test_dir();
+        let mut browser = FileBrowser::new(temp_dir.path(), true);
+
+        browser
+            .set_ignore(&["*.rs".to_string(), ".hidden_*".to_string()])
+            .unwrap();
+        let names: Vec<&str> = browser.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha_dir", "beta_dir", "file_a.txt"]);
+
+        assert!(browser.set_ignore(&["[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_marks() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+            fs::write(temp_dir.path().join(name), "").unwrap();
+        }
+        let mut browser = FileBrowser::new(temp_dir.path(), false);
+
+        browser.toggle_mark();
+        assert_eq!(browser.selected_index, 1);
+        assert!(browser.is_marked(&browser.entries[0]));
+
+        browser.mark_range(3, 2);
+        assert_eq!(browser.marked.len(), 3);
+        assert!(!browser.is_marked(&browser.entries[1]));
+
+        browser.selected_index = 0;
+        browser.toggle_mark();
+        assert_eq!(browser.marked.len(), 2);
+
+        // 消えたファイルのマークは再読み込みで外れる
+        fs::remove_file(temp_dir.path().join("d.txt")).unwrap();
+        browser.refresh();
+        assert_eq!(browser.marked.len(), 1);
+
+        browser.clear_marks();
+        assert!(browser.marked.is_empty());
+    }
+
+    #[test]
+    fn test_sort_modes() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        fs::create_dir(base.join("zdir")).unwrap();
+        fs::write(base.join("b.rs"), "1234567890").unwrap();
+        fs::write(base.join("a.txt"), "12345").unwrap();
+        fs::write(base.join("c.md"), "1").unwrap();
+        let mut browser = FileBrowser::new(base, false);
+
+        let names = |browser: &FileBrowser| -> Vec<String> {
+            browser.entries.iter().map(|e| e.name.clone()).collect()
+        };
+
+        assert_eq!(names(&browser), ["zdir", "a.txt", "b.rs", "c.md"]);
+
+        browser.set_sort(SortMode::Size, false);
+        assert_eq!(names(&browser), ["zdir", "b.rs", "a.txt", "c.md"]);
+
+        browser.set_sort(SortMode::Extension, false);
+        assert_eq!(names(&browser), ["zdir", "c.md", "b.rs", "a.txt"]);
+
+        // 逆順でもディレクトリは先頭のまま
+        browser.set_sort(SortMode::Name, true);
+        assert_eq!(names(&browser), ["zdir", "c.md", "b.rs", "a.txt"]);
+    }
+
+    #[test]
+    fn test_set_sort_keeps_selection() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "12345").unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "1").unwrap();
+        let mut browser = FileBrowser::new(temp_dir.path(), false);
+
+        browser.selected_index = 0;
+        browser.set_sort(SortMode::Name, true);
+        assert_eq!(browser.selected_entry().unwrap().name, "a.txt");
+        assert_eq!(browser.selected_index, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_permission_string() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("script.sh");
+        fs::write(&file, "").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o754)).unwrap();
+
+        let entry = FileEntry::new(file).unwrap();
+        assert_eq!(entry.permission_string(), "-rwxr-xr--");
+
+        let dir = FileEntry::new(temp_dir.path().to_path_buf()).unwrap();
+        assert!(dir.permission_string().starts_with('d'));
+    }
 }
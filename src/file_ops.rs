@@ -0,0 +1,1120 @@
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+
+/// Whether pasting copies or moves the registered entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferKind {
+    Copy,
+    Move,
+}
+
+/// One entry to paste
+#[derive(Debug, Clone)]
+pub struct PasteItem {
+    pub source: PathBuf,
+    pub dest: PathBuf,
+    /// Replace an existing destination once the new copy is complete
+    pub overwrite: bool,
+}
+
+/// Messages sent from a background copy, move, delete or archive
+#[derive(Debug)]
+pub enum TransferEvent {
+    Progress {
+        done: u64,
+        total: u64,
+        current: String,
+    },
+    /// Number of finished entries and a message for each failure
+    Finished { done: usize, errors: Vec<String> },
+}
+
+/// Delete a file or directory, moving it to the system trash unless
+/// `to_trash` is false. Symlinks are removed themselves, not their targets.
+pub fn delete(path: &Path, to_trash: bool) -> io::Result<()> {
+    if to_trash {
+        return trash::delete(path).map_err(io::Error::other);
+    }
+
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Rename an entry within its directory. Refuses to overwrite an existing
+/// entry or to move it elsewhere via path separators.
+pub fn rename(path: &Path, new_name: &str) -> io::Result<PathBuf> {
+    validate_name(new_name)?;
+    let parent = path.parent().unwrap_or(Path::new("."));
+    let new_path = parent.join(new_name);
+    if new_path == path {
+        return Ok(new_path);
+    }
+    if fs::symlink_metadata(&new_path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("'{}' already exists", new_name),
+        ));
+    }
+    fs::rename(path, &new_path)?;
+    Ok(new_path)
+}
+
+/// Pair each path with its edited name, keeping only the entries that change.
+/// Fails without touching anything if a name is invalid, two entries would
+/// end up with the same path, or a new name collides with an entry that is
+/// not itself being renamed.
+pub fn plan_renames(paths: &[PathBuf], names: &[String]) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    if paths.len() != names.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Expected {} names, got {}", paths.len(), names.len()),
+        ));
+    }
+
+    let mut plan = Vec::new();
+    let mut destinations = BTreeSet::new();
+    for (path, name) in paths.iter().zip(names) {
+        validate_name(name)?;
+        let parent = path.parent().unwrap_or(Path::new("."));
+        let dest = parent.join(name);
+        if !destinations.insert(dest.clone()) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' is used more than once", name),
+            ));
+        }
+        if &dest != path {
+            plan.push((path.clone(), dest));
+        }
+    }
+
+    // 名前を空ける側のエントリとの衝突は、入れ替えとして扱う
+    // （大文字小文字を区別しないファイルシステムでの大文字小文字だけの変更も）
+    for (_, dest) in &plan {
+        let vacated = paths
+            .iter()
+            .any(|path| path == dest || same_entry(path, dest));
+        if !vacated && fs::symlink_metadata(dest).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "'{}' already exists",
+                    dest.file_name().unwrap_or_default().to_string_lossy()
+                ),
+            ));
+        }
+    }
+    Ok(plan)
+}
+
+/// Whether two paths name the same existing entry, e.g. names differing only
+/// in case on a case-insensitive filesystem
+fn same_entry(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::symlink_metadata(a), fs::symlink_metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let name = |path: &Path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_lowercase())
+        };
+        a.parent() == b.parent() && name(a) == name(b) && fs::symlink_metadata(b).is_ok()
+    }
+}
+
+/// Apply a rename plan in two steps through temporary names, so entries can
+/// swap or rotate names and change only the case of a name. On failure,
+/// every entry gets its original name back where that name is still free;
+/// nothing is ever renamed over an existing entry.
+pub fn apply_renames(plan: &[(PathBuf, PathBuf)]) -> io::Result<()> {
+    let mut staged: Vec<(PathBuf, &PathBuf, &PathBuf)> = Vec::new();
+    let mut result = Ok(());
+    for (source, dest) in plan {
+        let temp = temp_sibling(source, "rename");
+        if let Err(e) = rename_into_free(source, &temp) {
+            result = Err(e);
+            break;
+        }
+        staged.push((temp, source, dest));
+    }
+
+    let mut placed = 0;
+    if result.is_ok() {
+        for (temp, _, dest) in &staged {
+            if let Err(e) = rename_into_free(temp, dest) {
+                result = Err(e);
+                break;
+            }
+            placed += 1;
+        }
+    }
+
+    if result.is_err() {
+        // 新しい名前にしたものを一時的な名前に戻してから、元の名前に戻す
+        for (temp, _, dest) in &staged[..placed] {
+            let _ = rename_into_free(dest, temp);
+        }
+        for (temp, source, _) in &staged {
+            let _ = rename_into_free(temp, source);
+        }
+    }
+    result
+}
+
+/// Rename `from` to `to`, failing instead of replacing an entry at `to`
+fn rename_into_free(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "'{}' already exists",
+                to.file_name().unwrap_or_default().to_string_lossy()
+            ),
+        ));
+    }
+    fs::rename(from, to)
+}
+
+/// Set the permission bits of an entry (follows symlinks, like chmod)
+#[cfg(unix)]
+pub fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+pub fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Permissions are only supported on Unix",
+    ))
+}
+
+/// Copy or move every item, reporting progress through `tx`.
+/// Intended to run on a background thread; stops early once `cancel` is set.
+/// Copies are made under a temporary name and only then take the place of
+/// the destination, so a cancelled or failed copy leaves it as it was.
+pub fn transfer(
+    kind: TransferKind,
+    items: Vec<PasteItem>,
+    tx: Sender<TransferEvent>,
+    cancel: &AtomicBool,
+) {
+    let sources: Vec<&Path> = items.iter().map(|item| item.source.as_path()).collect();
+    run_each(&sources, &tx, cancel, |i, progress| {
+        transfer_one(kind, &items[i], progress, cancel)
+    });
+}
+
+/// Delete every path like `delete`, reporting the freed bytes through `tx`.
+/// Intended to run on a background thread; stops early once `cancel` is set.
+pub fn delete_all(
+    paths: Vec<PathBuf>,
+    to_trash: bool,
+    tx: Sender<TransferEvent>,
+    cancel: &AtomicBool,
+) {
+    let sources: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+    run_each(&sources, &tx, cancel, |i, progress| {
+        let path = &paths[i];
+        if to_trash {
+            let size = total_size(path);
+            delete(path, true)?;
+            progress(size);
+            Ok(())
+        } else {
+            remove_tree(path, progress, cancel)
+        }
+    });
+}
+
+/// Run `job` for each source in turn, sending progress and finally the
+/// result. Failures are collected and the rest continue.
+fn run_each(
+    sources: &[&Path],
+    tx: &Sender<TransferEvent>,
+    cancel: &AtomicBool,
+    mut job: impl FnMut(usize, &mut dyn FnMut(u64)) -> io::Result<()>,
+) {
+    let total: u64 = sources.iter().map(|source| total_size(source)).sum();
+    let mut done = 0;
+
+    let mut succeeded = 0;
+    let mut errors = Vec::new();
+    for (i, source) in sources.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let current = source
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let mut report = |bytes: u64| {
+            done += bytes;
+            let _ = tx.send(TransferEvent::Progress {
+                done,
+                total,
+                current: current.clone(),
+            });
+        };
+        report(0);
+
+        match job(i, &mut report) {
+            Ok(()) => succeeded += 1,
+            Err(e) if is_cancelled(&e) => break,
+            Err(e) => errors.push(format!("{}: {}", current, e)),
+        }
+    }
+    let _ = tx.send(TransferEvent::Finished {
+        done: succeeded,
+        errors,
+    });
+}
+
+/// Error for an operation stopped by the user
+fn check_cancel(cancel: &AtomicBool) -> io::Result<()> {
+    if cancel.load(Ordering::Relaxed) {
+        return Err(io::Error::new(io::ErrorKind::Interrupted, "Cancelled"));
+    }
+    Ok(())
+}
+
+fn is_cancelled(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::Interrupted
+}
+
+fn transfer_one(
+    kind: TransferKind,
+    item: &PasteItem,
+    progress: &mut dyn FnMut(u64),
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    if !item.overwrite && fs::symlink_metadata(&item.dest).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("'{}' already exists", item.dest.display()),
+        ));
+    }
+
+    match kind {
+        TransferKind::Copy => copy_into_place(&item.source, &item.dest, progress, cancel),
+        TransferKind::Move => {
+            // 同一ファイルシステムなら rename で済む（失敗時はコピーして削除）
+            let size = total_size(&item.source);
+            let staged = temp_sibling(&item.dest, "paste");
+            if fs::rename(&item.source, &staged).is_ok() {
+                if let Err(e) = put_in_place(&staged, &item.dest) {
+                    let _ = fs::rename(&staged, &item.source);
+                    return Err(e);
+                }
+                progress(size);
+                return Ok(());
+            }
+            copy_into_place(&item.source, &item.dest, progress, cancel)?;
+            delete(&item.source, false)
+        }
+    }
+}
+
+/// Copy `source` under a temporary name next to `dest` and move it there
+/// when complete; on failure or cancel only the temporary copy is removed
+fn copy_into_place(
+    source: &Path,
+    dest: &Path,
+    progress: &mut dyn FnMut(u64),
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    let staged = temp_sibling(dest, "paste");
+    let result =
+        copy_tree(source, &staged, progress, cancel).and_then(|()| put_in_place(&staged, dest));
+    if result.is_err() && fs::symlink_metadata(&staged).is_ok() {
+        let _ = delete(&staged, false);
+    }
+    result
+}
+
+/// Move the finished `staged` entry to `dest`, replacing what is there
+fn put_in_place(staged: &Path, dest: &Path) -> io::Result<()> {
+    let Ok(old) = fs::symlink_metadata(dest) else {
+        return fs::rename(staged, dest);
+    };
+    // ファイル同士なら rename がそのまま置き換える
+    if !old.is_dir() && !fs::symlink_metadata(staged)?.is_dir() {
+        return fs::rename(staged, dest);
+    }
+    // ディレクトリは置き換えられないので、古い方を退避してから入れ替える
+    let backup = temp_sibling(dest, "old");
+    fs::rename(dest, &backup)?;
+    if let Err(e) = fs::rename(staged, dest) {
+        let _ = fs::rename(&backup, dest);
+        return Err(e);
+    }
+    delete(&backup, false)
+}
+
+/// Unused hidden name in the directory of `path`
+fn temp_sibling(path: &Path, label: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let parent = path.parent().unwrap_or(Path::new("."));
+    loop {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let temp = parent.join(format!(".vfv-{}-{}-{}", label, std::process::id(), n));
+        if fs::symlink_metadata(&temp).is_err() {
+            return temp;
+        }
+    }
+}
+
+/// Recursively copy a file, directory or symlink. `cancel` is only checked
+/// once `dest` exists, so a cancelled copy can always be removed.
+fn copy_tree(
+    source: &Path,
+    dest: &Path,
+    progress: &mut dyn FnMut(u64),
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+    let file_type = metadata.file_type();
+
+    if file_type.is_symlink() {
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(fs::read_link(source)?, dest);
+        #[cfg(not(unix))]
+        return fs::copy(source, dest).map(|_| ());
+    }
+
+    if file_type.is_dir() {
+        fs::create_dir(dest)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            check_cancel(cancel)?;
+            copy_tree(
+                &entry.path(),
+                &dest.join(entry.file_name()),
+                progress,
+                cancel,
+            )?;
+        }
+        return fs::set_permissions(dest, metadata.permissions());
+    }
+
+    let mut reader = File::open(source)?;
+    let mut writer = File::create(dest)?;
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..n])?;
+        progress(n as u64);
+        check_cancel(cancel)?;
+    }
+    fs::set_permissions(dest, metadata.permissions())
+}
+
+/// Permanently delete a tree file by file, reporting each file's size
+fn remove_tree(path: &Path, progress: &mut dyn FnMut(u64), cancel: &AtomicBool) -> io::Result<()> {
+    check_cancel(cancel)?;
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        fs::remove_file(path)?;
+        progress(metadata.len());
+        return Ok(());
+    }
+    for entry in fs::read_dir(path)? {
+        remove_tree(&entry?.path(), progress, cancel)?;
+    }
+    fs::remove_dir(path)
+}
+
+/// Total size in bytes of a file or directory tree (symlinks are not followed)
+pub fn total_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| total_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// First free path of the form "name (N).ext" in `dir`
+pub fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    // 先頭のドットは拡張子扱いしない（.bashrc など）
+    let (stem, ext) = match name.rfind('.') {
+        Some(pos) if pos > 0 => name.split_at(pos),
+        _ => (name, ""),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+        .find(|path| fs::symlink_metadata(path).is_err())
+        .expect("unbounded range always yields a free name")
+}
+
+/// Create an empty file or a directory named `name` in `dir`
+pub fn create(dir: &Path, name: &str, is_dir: bool) -> io::Result<PathBuf> {
+    validate_name(name)?;
+    let path = dir.join(name);
+    if is_dir {
+        fs::create_dir(&path)?;
+    } else {
+        File::create_new(&path)?;
+    }
+    Ok(path)
+}
+
+/// Archive format, chosen by the archive's file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Path of a new archive `name` in `dir`, checked before packing starts:
+/// the name must be valid, of a supported format and not taken
+pub fn archive_path(dir: &Path, name: &str) -> io::Result<PathBuf> {
+    validate_name(name)?;
+    if ArchiveFormat::from_name(name).is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Unsupported archive format (use .zip, .tar.gz or .tgz)",
+        ));
+    }
+    let dest = dir.join(name);
+    if fs::symlink_metadata(&dest).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("'{}' already exists", name),
+        ));
+    }
+    Ok(dest)
+}
+
+/// Pack `paths` into the archive `dest` (see `archive_path`), reporting the
+/// bytes read through `tx`. Each entry is stored under its own name;
+/// symlinks are stored as links. Intended to run on a background thread;
+/// once `cancel` is set the half-written archive is removed.
+pub fn archive(dest: PathBuf, paths: Vec<PathBuf>, tx: Sender<TransferEvent>, cancel: &AtomicBool) {
+    let total: u64 = paths.iter().map(|path| total_size(path)).sum();
+    let current = dest
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let mut done = 0;
+    let mut report = |bytes: u64| {
+        done += bytes;
+        let _ = tx.send(TransferEvent::Progress {
+            done,
+            total,
+            current: current.clone(),
+        });
+    };
+    report(0);
+
+    let result = write_archive(&dest, &paths, &mut report, cancel);
+    let errors = match &result {
+        // 中断はエラー扱いしない（読み込みの途中で止めた場合も含む）
+        Err(_) if cancel.load(Ordering::Relaxed) => Vec::new(),
+        Err(e) => vec![format!("{}: {}", current, e)],
+        Ok(()) => Vec::new(),
+    };
+    let _ = tx.send(TransferEvent::Finished {
+        done: usize::from(result.is_ok()),
+        errors,
+    });
+}
+
+fn write_archive(
+    dest: &Path,
+    paths: &[PathBuf],
+    progress: &mut dyn FnMut(u64),
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    let name = dest.file_name().unwrap_or_default().to_string_lossy();
+    let format = ArchiveFormat::from_name(&name)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Unsupported archive format"))?;
+    let file = File::create_new(dest)?;
+    let result = match format {
+        ArchiveFormat::Zip => write_zip(file, paths, progress, cancel),
+        ArchiveFormat::TarGz => write_tar_gz(file, paths, progress, cancel),
+    };
+    // 途中で失敗・中断したら作りかけのアーカイブは残さない
+    if result.is_err() {
+        let _ = fs::remove_file(dest);
+    }
+    result
+}
+
+/// Reader for a file being packed: reports the bytes read and fails once
+/// `cancel` is set. The error is not `Interrupted`, which `io::copy` would
+/// retry forever.
+struct Packing<'a, R> {
+    inner: R,
+    progress: &'a mut dyn FnMut(u64),
+    cancel: &'a AtomicBool,
+}
+
+impl<R: Read> Read for Packing<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(io::Error::other("Cancelled"));
+        }
+        let n = self.inner.read(buf)?;
+        (self.progress)(n as u64);
+        Ok(n)
+    }
+}
+
+fn write_tar_gz(
+    file: File,
+    paths: &[PathBuf],
+    progress: &mut dyn FnMut(u64),
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(false);
+    for path in paths {
+        let name = path.file_name().unwrap_or(path.as_os_str());
+        add_to_tar(&mut builder, path, Path::new(name), progress, cancel)?;
+    }
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn add_to_tar<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &Path,
+    name: &Path,
+    progress: &mut dyn FnMut(u64),
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    check_cancel(cancel)?;
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        builder.append_dir(name, path)?;
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            add_to_tar(
+                builder,
+                &entry.path(),
+                &name.join(entry.file_name()),
+                progress,
+                cancel,
+            )?;
+        }
+    } else if metadata.is_file() {
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&metadata);
+        let reader = Packing {
+            inner: File::open(path)?,
+            progress,
+            cancel,
+        };
+        builder.append_data(&mut header, name, reader)?;
+    } else {
+        // シンボリックリンクなどはリンクのまま
+        builder.append_path_with_name(path, name)?;
+    }
+    Ok(())
+}
+
+fn write_zip(
+    file: File,
+    paths: &[PathBuf],
+    progress: &mut dyn FnMut(u64),
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    let mut zip = zip::ZipWriter::new(file);
+    for path in paths {
+        let name = path.file_name().unwrap_or(path.as_os_str());
+        add_to_zip(&mut zip, path, Path::new(name), progress, cancel)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+fn add_to_zip(
+    zip: &mut zip::ZipWriter<File>,
+    path: &Path,
+    name: &Path,
+    progress: &mut dyn FnMut(u64),
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    check_cancel(cancel)?;
+    let metadata = fs::symlink_metadata(path)?;
+    let options = zip::write::SimpleFileOptions::default();
+    #[cfg(unix)]
+    let options = {
+        use std::os::unix::fs::PermissionsExt;
+        options.unix_permissions(metadata.permissions().mode())
+    };
+    // zip 内のパス区切りは常に '/'
+    let entry_name = name.to_string_lossy().replace('\\', "/");
+
+    if metadata.file_type().is_symlink() {
+        let target = fs::read_link(path)?;
+        zip.add_symlink(entry_name, target.to_string_lossy(), options)?;
+    } else if metadata.is_dir() {
+        zip.add_directory(format!("{}/", entry_name), options)?;
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            add_to_zip(
+                zip,
+                &entry.path(),
+                &name.join(entry.file_name()),
+                progress,
+                cancel,
+            )?;
+        }
+    } else {
+        zip.start_file(
+            entry_name,
+            options.large_file(metadata.len() >= u32::MAX as u64),
+        )?;
+        let mut reader = Packing {
+            inner: File::open(path)?,
+            progress,
+            cancel,
+        };
+        io::copy(&mut reader, zip)?;
+    }
+    Ok(())
+}
+
+/// Check that a name refers to a single entry in the current directory
+fn validate_name(name: &str) -> io::Result<()> {
+    let invalid = name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains('/')
+        || name.contains(std::path::MAIN_SEPARATOR);
+    if invalid {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid name: '{}'", name),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_delete_permanently() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("file.txt");
+        let dir = temp_dir.path().join("dir");
+        fs::write(&file, "x").unwrap();
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("nested.txt"), "y").unwrap();
+
+        delete(&file, false).unwrap();
+        delete(&dir, false).unwrap();
+
+        assert!(!file.exists());
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_rename() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("old.txt");
+        fs::write(&file, "x").unwrap();
+        fs::write(temp_dir.path().join("taken.txt"), "y").unwrap();
+
+        let renamed = rename(&file, "new.txt").unwrap();
+        assert_eq!(renamed, temp_dir.path().join("new.txt"));
+        assert!(renamed.exists());
+        assert!(!file.exists());
+
+        let err = rename(&renamed, "taken.txt").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let err = rename(&renamed, "../escape.txt").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(renamed.exists());
+    }
+
+    #[test]
+    fn test_plan_and_apply_renames() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        for name in ["a.txt", "b.txt", "c.txt", "other.txt"] {
+            fs::write(base.join(name), name).unwrap();
+        }
+        let paths = vec![base.join("a.txt"), base.join("b.txt"), base.join("c.txt")];
+        let names = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // 件数の不一致・重複・既存エントリとの衝突は何もせずに失敗
+        assert!(plan_renames(&paths, &names(&["a.txt"])).is_err());
+        let err = plan_renames(&paths, &names(&["x.txt", "x.txt", "c.txt"])).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let err = plan_renames(&paths, &names(&["other.txt", "b.txt", "c.txt"])).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let err = plan_renames(&paths, &names(&["a/b", "b.txt", "c.txt"])).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // a と b の入れ替え、c は変更なし
+        let plan = plan_renames(&paths, &names(&["b.txt", "a.txt", "c.txt"])).unwrap();
+        assert_eq!(plan.len(), 2);
+        apply_renames(&plan).unwrap();
+        assert_eq!(fs::read_to_string(base.join("a.txt")).unwrap(), "b.txt");
+        assert_eq!(fs::read_to_string(base.join("b.txt")).unwrap(), "a.txt");
+        assert_eq!(fs::read_dir(base).unwrap().count(), 4);
+
+        // 大文字小文字だけの変更も一時的な名前を経由する
+        let plan = plan_renames(&paths[2..], &names(&["C.txt"])).unwrap();
+        apply_renames(&plan).unwrap();
+        assert_eq!(fs::read_to_string(base.join("C.txt")).unwrap(), "c.txt");
+        assert_eq!(fs::read_dir(base).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn test_failed_renames_roll_back_without_clobbering() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        fs::write(base.join("a.txt"), "a").unwrap();
+        fs::write(base.join("b.txt"), "b").unwrap();
+
+        // 計画の後に b.txt の新しい名前が埋まった
+        let plan = vec![
+            (base.join("a.txt"), base.join("x.txt")),
+            (base.join("b.txt"), base.join("y.txt")),
+        ];
+        fs::write(base.join("y.txt"), "someone else").unwrap();
+        let err = apply_renames(&plan).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(base.join("a.txt")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(base.join("b.txt")).unwrap(), "b");
+        assert_eq!(
+            fs::read_to_string(base.join("y.txt")).unwrap(),
+            "someone else"
+        );
+        assert!(!base.join("x.txt").exists());
+        assert_eq!(fs::read_dir(base).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_transfer_copy_and_move() {
+        let temp_dir = TempDir::new().unwrap();
+        let src_dir = temp_dir.path().join("src");
+        let dest_dir = temp_dir.path().join("dest");
+        fs::create_dir_all(src_dir.join("nested")).unwrap();
+        fs::create_dir(&dest_dir).unwrap();
+        fs::write(src_dir.join("nested/a.txt"), "hello").unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "world").unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let items = vec![PasteItem {
+            source: src_dir.clone(),
+            dest: dest_dir.join("src"),
+            overwrite: false,
+        }];
+        transfer(TransferKind::Copy, items, tx, &AtomicBool::new(false));
+        let events: Vec<TransferEvent> = rx.iter().collect();
+        assert!(matches!(
+            events.last(),
+            Some(TransferEvent::Finished { done: 1, errors }) if errors.is_empty()
+        ));
+        assert!(events.iter().any(
+            |e| matches!(e, TransferEvent::Progress { done, total, .. } if done == total && *total == 5)
+        ));
+        assert_eq!(
+            fs::read_to_string(dest_dir.join("src/nested/a.txt")).unwrap(),
+            "hello"
+        );
+        assert!(src_dir.exists());
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let items = vec![PasteItem {
+            source: temp_dir.path().join("b.txt"),
+            dest: dest_dir.join("b.txt"),
+            overwrite: false,
+        }];
+        transfer(TransferKind::Move, items, tx, &AtomicBool::new(false));
+        assert!(matches!(
+            rx.iter().last(),
+            Some(TransferEvent::Finished { done: 1, .. })
+        ));
+        assert!(dest_dir.join("b.txt").exists());
+        assert!(!temp_dir.path().join("b.txt").exists());
+    }
+
+    #[test]
+    fn test_cancel_transfer() {
+        let temp_dir = TempDir::new().unwrap();
+        let src_dir = temp_dir.path().join("src");
+        fs::create_dir(&src_dir).unwrap();
+        fs::write(src_dir.join("a.txt"), "a").unwrap();
+        fs::write(src_dir.join("b.txt"), "b").unwrap();
+
+        // 最初のファイルを書いたところで中断する
+        let cancel = AtomicBool::new(false);
+        let dest = temp_dir.path().join("copy");
+        let err = copy_tree(
+            &src_dir,
+            &dest,
+            &mut |_| cancel.store(true, Ordering::Relaxed),
+            &cancel,
+        )
+        .unwrap_err();
+        assert!(is_cancelled(&err));
+        assert_eq!(fs::read_dir(&dest).unwrap().count(), 1);
+
+        // 中断済みなら何もせず終わる
+        let (tx, rx) = std::sync::mpsc::channel();
+        let items = vec![PasteItem {
+            source: src_dir.clone(),
+            dest: temp_dir.path().join("other"),
+            overwrite: false,
+        }];
+        transfer(TransferKind::Move, items, tx, &cancel);
+        assert!(matches!(
+            rx.iter().last(),
+            Some(TransferEvent::Finished { done: 0, errors }) if errors.is_empty()
+        ));
+        assert!(src_dir.join("b.txt").exists());
+        assert!(!temp_dir.path().join("other").exists());
+    }
+
+    #[test]
+    fn test_overwrite_replaces_only_when_complete() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        fs::write(base.join("new.txt"), "new").unwrap();
+        fs::create_dir(base.join("dest")).unwrap();
+        fs::write(base.join("dest/new.txt"), "old").unwrap();
+        fs::create_dir(base.join("dest/dir")).unwrap();
+        fs::write(base.join("dest/dir/keep.txt"), "old").unwrap();
+        let paste = |source: &str, dest: &str, cancel: &AtomicBool| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let items = vec![PasteItem {
+                source: base.join(source),
+                dest: base.join(dest),
+                overwrite: true,
+            }];
+            transfer(TransferKind::Copy, items, tx, cancel);
+            rx.iter().last()
+        };
+
+        // 書き込みの途中で中断しても、失敗しても既存のものは残る
+        let cancel = AtomicBool::new(false);
+        let err = copy_into_place(
+            &base.join("new.txt"),
+            &base.join("dest/new.txt"),
+            &mut |_| cancel.store(true, Ordering::Relaxed),
+            &cancel,
+        )
+        .unwrap_err();
+        assert!(is_cancelled(&err));
+        assert_eq!(
+            fs::read_to_string(base.join("dest/new.txt")).unwrap(),
+            "old"
+        );
+        let finished = paste("missing.txt", "dest/new.txt", &AtomicBool::new(false));
+        assert!(matches!(
+            finished,
+            Some(TransferEvent::Finished { done: 0, errors }) if errors.len() == 1
+        ));
+        assert_eq!(
+            fs::read_to_string(base.join("dest/new.txt")).unwrap(),
+            "old"
+        );
+
+        // 完了したら置き換わる（ディレクトリもファイルで置き換えられる）
+        paste("new.txt", "dest/new.txt", &AtomicBool::new(false));
+        assert_eq!(
+            fs::read_to_string(base.join("dest/new.txt")).unwrap(),
+            "new"
+        );
+        paste("new.txt", "dest/dir", &AtomicBool::new(false));
+        assert_eq!(fs::read_to_string(base.join("dest/dir")).unwrap(), "new");
+        // 一時ファイルは残らない
+        assert_eq!(fs::read_dir(base.join("dest")).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_delete_all_reports_bytes() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("dir");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("nested/a.txt"), "hello").unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "abc").unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let paths = vec![
+            dir.clone(),
+            temp_dir.path().join("b.txt"),
+            temp_dir.path().join("gone"),
+        ];
+        delete_all(paths, false, tx, &AtomicBool::new(false));
+        let events: Vec<TransferEvent> = rx.iter().collect();
+        assert!(events.iter().any(|e| matches!(
+            e,
+            TransferEvent::Progress {
+                done: 8,
+                total: 8,
+                ..
+            }
+        )));
+        assert!(matches!(
+            events.last(),
+            Some(TransferEvent::Finished { done: 2, errors }) if errors.len() == 1
+        ));
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_create() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let file = create(temp_dir.path(), "new.txt", false).unwrap();
+        assert!(file.is_file());
+        let dir = create(temp_dir.path(), "new_dir", true).unwrap();
+        assert!(dir.is_dir());
+
+        let err = create(temp_dir.path(), "new.txt", false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let err = create(temp_dir.path(), "", true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_create_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        fs::create_dir_all(base.join("dir/nested")).unwrap();
+        fs::write(base.join("dir/nested/a.txt"), "hello").unwrap();
+        fs::write(base.join("b.txt"), "world").unwrap();
+        let paths = vec![base.join("dir"), base.join("b.txt")];
+        let create = |name: &str, cancel: &AtomicBool| {
+            let dest = archive_path(base, name)?;
+            let (tx, rx) = std::sync::mpsc::channel();
+            archive(dest.clone(), paths.clone(), tx, cancel);
+            match rx.iter().last() {
+                Some(TransferEvent::Finished { errors, .. }) if !errors.is_empty() => {
+                    Err(io::Error::other(errors.join(", ")))
+                }
+                _ => Ok(dest),
+            }
+        };
+
+        let zip_path = create("bundle.zip", &AtomicBool::new(false)).unwrap();
+        let mut archive = zip::ZipArchive::new(File::open(&zip_path).unwrap()).unwrap();
+        let mut content = String::new();
+        archive
+            .by_name("dir/nested/a.txt")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "hello");
+        assert!(archive.by_name("b.txt").is_ok());
+
+        let tar_path = create("bundle.tar.gz", &AtomicBool::new(false)).unwrap();
+        let decoder = flate2::read::GzDecoder::new(File::open(&tar_path).unwrap());
+        let names: Vec<String> = tar::Archive::new(decoder)
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert!(names.contains(&"dir/nested/a.txt".to_string()));
+        assert!(names.contains(&"b.txt".to_string()));
+
+        // 既存のファイルは上書きせず、未対応の形式は作らない
+        let err = create("bundle.zip", &AtomicBool::new(false)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let err = create("bundle.rar", &AtomicBool::new(false)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!base.join("bundle.rar").exists());
+
+        // 中断したら作りかけのアーカイブは消える
+        create("cancelled.zip", &AtomicBool::new(true)).unwrap();
+        assert!(!base.join("cancelled.zip").exists());
+    }
+
+    #[test]
+    fn test_unique_path() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "").unwrap();
+        fs::write(temp_dir.path().join("a (1).txt"), "").unwrap();
+
+        assert_eq!(
+            unique_path(temp_dir.path(), "a.txt"),
+            temp_dir.path().join("a (2).txt")
+        );
+        assert_eq!(
+            unique_path(temp_dir.path(), ".bashrc"),
+            temp_dir.path().join(".bashrc (1)")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_set_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("script.sh");
+        fs::write(&file, "").unwrap();
+
+        set_mode(&file, 0o750).unwrap();
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o750);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_delete_symlink_keeps_target() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("target");
+        let link = temp_dir.path().join("link");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("keep.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        delete(&link, false).unwrap();
+
+        assert!(fs::symlink_metadata(&link).is_err());
+        assert!(target.join("keep.txt").exists());
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Change marker for a single line in the preview gutter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffMarker {
+    Added,
+    Modified,
+    /// Lines were removed just above this line
+    Removed,
+}
+
+/// Compute per-line diff markers for a file against HEAD.
+/// Returns an empty map when the file is not tracked by git, unchanged,
+/// or git is not available, and kills git once `cancel` is set.
+pub fn diff_markers(path: &Path, cancel: &AtomicBool) -> HashMap<usize, DiffMarker> {
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return HashMap::new();
+    };
+
+    let child = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["diff", "--no-color", "--no-ext-diff", "-U0", "HEAD", "--"])
+        .arg(file_name)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else {
+        return HashMap::new();
+    };
+
+    // パイプが詰まらないよう別スレッドで読み込む
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        buf
+    });
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if !cancel.load(Ordering::Relaxed) => {
+                thread::sleep(Duration::from_millis(10));
+            }
+            // 別のファイルに移ったら結果は要らない
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return HashMap::new();
+            }
+        }
+    };
+
+    let output = reader.join().unwrap_or_default();
+    if status.success() {
+        parse_diff(&String::from_utf8_lossy(&output))
+    } else {
+        HashMap::new()
+    }
+}
+
+/// Parse unified diff output (with -U0) into line markers keyed by new line number (1-based)
+fn parse_diff(diff: &str) -> HashMap<usize, DiffMarker> {
+    let mut markers = HashMap::new();
+
+    for line in diff.lines() {
+        let Some((old_count, new_start, new_count)) = parse_hunk_header(line) else {
+            continue;
+        };
+
+        if new_count == 0 {
+            // 削除のみ：削除位置の次の行にマーク
+            markers.insert(new_start.max(1), DiffMarker::Removed);
+            continue;
+        }
+
+        for offset in 0..new_count {
+            let marker = if offset < old_count {
+                DiffMarker::Modified
+            } else {
+                DiffMarker::Added
+            };
+            markers.insert(new_start + offset, marker);
+        }
+    }
+
+    markers
+}
+
+/// Branch checked out in the repository containing `dir` (a short commit
+/// id when detached), read from `.git/HEAD` without running git
+pub fn branch(dir: &Path) -> Option<String> {
+    for ancestor in dir.ancestors() {
+        let dot_git = ancestor.join(".git");
+        // ワークツリーやサブモジュールの .git は "gitdir: <path>" を書いたファイル
+        let git_dir = if dot_git.is_dir() {
+            dot_git
+        } else if let Ok(content) = fs::read_to_string(&dot_git) {
+            ancestor.join(content.strip_prefix("gitdir:")?.trim())
+        } else {
+            continue;
+        };
+        return parse_head(&fs::read_to_string(git_dir.join("HEAD")).ok()?);
+    }
+    None
+}
+
+/// Branch name from the contents of HEAD, or the first 7 digits of a detached commit
+fn parse_head(head: &str) -> Option<String> {
+    let head = head.trim();
+    match head.strip_prefix("ref:") {
+        Some(reference) => {
+            let reference = reference.trim();
+            Some(
+                reference
+                    .strip_prefix("refs/heads/")
+                    .unwrap_or(reference)
+                    .to_string(),
+            )
+        }
+        None if head.len() >= 7 && head.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Some(head[..7].to_string())
+        }
+        None => None,
+    }
+}
+
+/// Parse "@@ -a[,b] +c[,d] @@" into (b, c, d)
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize)> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (old, rest) = rest.split_once(" +")?;
+    let (new, _) = rest.split_once(" @@")?;
+
+    let parse_range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+
+    let (_, old_count) = parse_range(old)?;
+    let (new_start, new_count) = parse_range(new)?;
+    Some((old_count, new_start, new_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branch_from_head() {
+        assert_eq!(
+            parse_head("ref: refs/heads/main\n").as_deref(),
+            Some("main")
+        );
+        assert_eq!(
+            parse_head("ref: refs/heads/feature/x").as_deref(),
+            Some("feature/x")
+        );
+        assert_eq!(
+            parse_head("0123456789abcdef0123456789abcdef01234567\n").as_deref(),
+            Some("0123456")
+        );
+        assert_eq!(parse_head("garbage"), None);
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base = temp_dir.path();
+        fs::create_dir_all(base.join("repo/.git")).unwrap();
+        fs::create_dir_all(base.join("repo/src/deep")).unwrap();
+        fs::write(base.join("repo/.git/HEAD"), "ref: refs/heads/dev\n").unwrap();
+        assert_eq!(branch(&base.join("repo/src/deep")).as_deref(), Some("dev"));
+
+        // ワークツリーは .git ファイルの gitdir を辿る
+        fs::create_dir_all(base.join("gitdirs/wt")).unwrap();
+        fs::write(base.join("gitdirs/wt/HEAD"), "ref: refs/heads/wt\n").unwrap();
+        fs::create_dir(base.join("worktree")).unwrap();
+        fs::write(base.join("worktree/.git"), "gitdir: ../gitdirs/wt\n").unwrap();
+        assert_eq!(branch(&base.join("worktree")).as_deref(), Some("wt"));
+    }
+
+    #[test]
+    fn test_parse_hunk_header() {
+        assert_eq!(parse_hunk_header("@@ -1,2 +1,3 @@"), Some((2, 1, 3)));
+        assert_eq!(parse_hunk_header("@@ -5 +5 @@ fn main()"), Some((1, 5, 1)));
+        assert_eq!(parse_hunk_header("@@ -3,0 +4,2 @@"), Some((0, 4, 2)));
+        assert_eq!(parse_hunk_header("+++ b/file.rs"), None);
+    }
+
+    #[test]
+    fn test_parse_diff_added_lines() {
+        let markers = parse_diff("@@ -3,0 +4,2 @@\n+a\n+b\n");
+        assert_eq!(markers.get(&4), Some(&DiffMarker::Added));
+        assert_eq!(markers.get(&5), Some(&DiffMarker::Added));
+        assert_eq!(markers.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_diff_modified_and_extra_lines() {
+        let markers = parse_diff("@@ -2,1 +2,2 @@\n-old\n+new\n+more\n");
+        assert_eq!(markers.get(&2), Some(&DiffMarker::Modified));
+        assert_eq!(markers.get(&3), Some(&DiffMarker::Added));
+    }
+
+    #[test]
+    fn test_parse_diff_removed_lines() {
+        let markers = parse_diff("@@ -4,2 +3,0 @@\n-a\n-b\n@@ -1 +0,0 @@\n-x\n");
+        assert_eq!(markers.get(&3), Some(&DiffMarker::Removed));
+        // 先頭行の削除は1行目にマーク
+        assert_eq!(markers.get(&1), Some(&DiffMarker::Removed));
+    }
+
+    #[test]
+    fn test_diff_markers_outside_repo_is_empty() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("file.txt");
+        std::fs::write(&file_path, "hello\n").unwrap();
+        assert!(diff_markers(&file_path, &AtomicBool::new(false)).is_empty());
+    }
+}
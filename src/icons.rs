@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+use crate::file_browser::FileEntry;
+
+/// Icon set used for file list entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconStyle {
+    /// Plain marker for directories only (works in any terminal)
+    #[default]
+    Plain,
+    /// Per-filetype glyphs (requires a Nerd Font)
+    Nerd,
+}
+
+/// Prefix shown before an entry name (icon plus separating space)
+pub fn icon_for(entry: &FileEntry, style: IconStyle) -> &'static str {
+    match style {
+        IconStyle::Plain => {
+            if entry.expanded {
+                "▾ "
+            } else if entry.is_dir && !entry.is_broken_link {
+                "▸ "
+            } else {
+                "  "
+            }
+        }
+        IconStyle::Nerd => nerd_icon(entry),
+    }
+}
+
+fn nerd_icon(entry: &FileEntry) -> &'static str {
+    if entry.is_broken_link {
+        return "\u{f127} ";
+    }
+    if entry.is_dir {
+        return match entry.name.as_str() {
+            ".git" => "\u{e5fb} ",
+            ".github" => "\u{e5fd} ",
+            "node_modules" => "\u{e5fa} ",
+            _ if entry.expanded => "\u{f07c} ",
+            _ => "\u{f07b} ",
+        };
+    }
+
+    // ファイル名で決まるもの
+    match entry.name.as_str() {
+        "Cargo.toml" | "Cargo.lock" => return "\u{e7a8} ",
+        "Dockerfile" | "docker-compose.yml" | "docker-compose.yaml" => return "\u{f308} ",
+        "Makefile" | "justfile" => return "\u{e779} ",
+        ".gitignore" | ".gitattributes" | ".gitmodules" => return "\u{e702} ",
+        "LICENSE" | "LICENSE.md" | "LICENSE.txt" => return "\u{f0219} ",
+        _ => {}
+    }
+
+    let ext = entry
+        .name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "rs" => "\u{e7a8} ",
+        "py" | "pyi" | "ipynb" => "\u{e606} ",
+        "js" | "mjs" | "cjs" => "\u{e74e} ",
+        "ts" | "mts" | "cts" => "\u{e628} ",
+        "jsx" | "tsx" => "\u{e7ba} ",
+        "go" => "\u{e626} ",
+        "c" => "\u{e61e} ",
+        "cpp" | "cc" | "cxx" | "hpp" => "\u{e61d} ",
+        "h" => "\u{f0fd} ",
+        "java" | "jar" => "\u{e738} ",
+        "rb" => "\u{e739} ",
+        "lua" => "\u{e620} ",
+        "swift" => "\u{e755} ",
+        "html" | "htm" => "\u{e736} ",
+        "css" | "scss" | "sass" => "\u{e749} ",
+        "md" | "markdown" => "\u{e609} ",
+        "json" => "\u{e60b} ",
+        "toml" | "yaml" | "yml" | "ini" | "conf" | "cfg" => "\u{e615} ",
+        "sh" | "bash" | "zsh" | "fish" => "\u{f489} ",
+        "lock" => "\u{f023} ",
+        "txt" | "log" => "\u{f15c} ",
+        "pdf" => "\u{f1c1} ",
+        "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "svg" | "ico" => "\u{f1c5} ",
+        "mp4" | "mkv" | "mov" | "avi" | "webm" => "\u{f03d} ",
+        "mp3" | "wav" | "flac" | "ogg" | "m4a" => "\u{f001} ",
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "7z" | "rar" | "zst" => "\u{f410} ",
+        _ => "\u{f15b} ",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn entry(name: &str, is_dir: bool) -> FileEntry {
+        FileEntry {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            is_dir,
+            link_target: None,
+            is_broken_link: false,
+            size: 0,
+            modified: None,
+            mode: None,
+            owner: None,
+            depth: 0,
+            expanded: false,
+        }
+    }
+
+    #[test]
+    fn test_plain_icons() {
+        assert_eq!(icon_for(&entry("src", true), IconStyle::Plain), "▸ ");
+        assert_eq!(icon_for(&entry("main.rs", false), IconStyle::Plain), "  ");
+        let mut open = entry("src", true);
+        open.expanded = true;
+        assert_eq!(icon_for(&open, IconStyle::Plain), "▾ ");
+    }
+
+    #[test]
+    fn test_nerd_icons_by_extension_and_name() {
+        assert_eq!(
+            icon_for(&entry("main.rs", false), IconStyle::Nerd),
+            "\u{e7a8} "
+        );
+        assert_eq!(
+            icon_for(&entry("PHOTO.JPG", false), IconStyle::Nerd),
+            "\u{f1c5} "
+        );
+        assert_eq!(
+            icon_for(&entry("Cargo.toml", false), IconStyle::Nerd),
+            "\u{e7a8} "
+        );
+        assert_eq!(icon_for(&entry(".git", true), IconStyle::Nerd), "\u{e5fb} ");
+        assert_eq!(
+            icon_for(&entry("unknown.xyz", false), IconStyle::Nerd),
+            "\u{f15b} "
+        );
+    }
+}
@@ -0,0 +1,880 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+
+/// Something a key can be bound to in the file browser or the preview
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    MoveDown,
+    MoveUp,
+    Enter,
+    GoParent,
+    ExpandOrEnter,
+    CollapseOrParent,
+    ToggleTree,
+    Fold,
+    GoTop,
+    GoBottom,
+    OpenEditor,
+    OpenWith,
+    Search,
+    ToggleHidden,
+    Reload,
+    CopyPath,
+    Delete,
+    Rename,
+    BulkRename,
+    Chmod,
+    Archive,
+    Trash,
+    Zoxide,
+    Places,
+    Recent,
+    FollowLink,
+    DiskUsage,
+    NewFile,
+    NewDir,
+    ToggleMark,
+    MarkRange,
+    ClearMarks,
+    CycleSort,
+    ToggleSortReverse,
+    ToggleDetails,
+    ToggleDualPane,
+    TogglePreviewPane,
+    SplitLeft,
+    SplitRight,
+    SwitchPane,
+    CopyToOtherPane,
+    MoveToOtherPane,
+    Copy,
+    Cut,
+    Paste,
+    CancelOperation,
+    Jump,
+    JumpNext,
+    JumpPrev,
+    SetBookmark,
+    JumpBookmark,
+    Breadcrumb,
+    CommandPalette,
+    DismissNotifications,
+    Help,
+    PreviewBack,
+    ScrollDown,
+    ScrollUp,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    PreviewTop,
+    PreviewBottom,
+    GotoLine,
+    ToggleWhitespace,
+    Visual,
+    ToggleFullscreen,
+}
+
+impl Action {
+    /// One-line description for the help screen and `vfv keys`
+    pub fn description(self) -> &'static str {
+        match self {
+            Quit => "Quit",
+            MoveDown => "Move down",
+            MoveUp => "Move up",
+            Enter => "Open file / Enter directory",
+            GoParent => "Go to parent directory",
+            ExpandOrEnter => "Expand directory (tree) / Enter",
+            CollapseOrParent => "Collapse directory (tree) / Parent",
+            ToggleTree => "Toggle tree view",
+            Fold => "Fold: + a toggle, o expand, c collapse (tree)",
+            GoTop => "Go to top",
+            GoBottom => "Go to bottom",
+            OpenEditor => "Open in editor",
+            OpenWith => "Open with... (configured programs)",
+            Search => "Search all files (fuzzy)",
+            ToggleHidden => "Toggle hidden files",
+            Reload => "Reload",
+            CopyPath => "Copy path to clipboard",
+            Delete => "Delete (to trash by default)",
+            Rename => "Rename",
+            BulkRename => "Rename marked entries in editor",
+            Chmod => "Edit permissions (chmod)",
+            Archive => "Create archive (.zip/.tar.gz)",
+            Trash => "Browse trash (r: restore, d: purge)",
+            Zoxide => "cd via zoxide query or path",
+            Places => "Places (home, mounts, drives)",
+            Recent => "Recently opened files",
+            FollowLink => "Go to symlink target",
+            DiskUsage => "Disk usage (sizes of everything below)",
+            NewFile => "New file",
+            NewDir => "New directory",
+            ToggleMark => "Toggle mark",
+            MarkRange => "Mark a range",
+            ClearMarks => "Clear marks",
+            CycleSort => "Cycle sort order",
+            ToggleSortReverse => "Reverse sort order",
+            ToggleDetails => "Toggle detail columns",
+            ToggleDualPane => "Toggle dual-pane mode",
+            TogglePreviewPane => "Toggle preview pane",
+            SplitLeft => "Move the pane divider left",
+            SplitRight => "Move the pane divider right",
+            SwitchPane => "Switch pane",
+            CopyToOtherPane => "Copy to the other pane",
+            MoveToOtherPane => "Move to the other pane",
+            Copy => "Copy to register",
+            Cut => "Cut to register",
+            Paste => "Paste register here",
+            CancelOperation => "Cancel the running copy, move, delete or archive",
+            Jump => "+ char: Jump to entry starting with char",
+            JumpNext => "Jump to next match",
+            JumpPrev => "Jump to previous match",
+            SetBookmark => "+ char: Bookmark current directory",
+            JumpBookmark => "+ char: Jump to bookmark",
+            Breadcrumb => "+ digit: Go up N directories (numbered in the header)",
+            CommandPalette => "Command line (:cd, :mkdir, :sort, :theme, :set ...)",
+            DismissNotifications => "Dismiss notifications",
+            Help => "Show this help",
+            PreviewBack => "Back to browser",
+            ScrollDown => "Scroll down",
+            ScrollUp => "Scroll up",
+            HalfPageDown => "Half page down",
+            HalfPageUp => "Half page up",
+            PageDown => "Page down",
+            PageUp => "Page up",
+            PreviewTop => "Go to top",
+            PreviewBottom => "Go to bottom (NG: go to line N)",
+            GotoLine => "Go to line (:N)",
+            ToggleWhitespace => "Toggle whitespace visualization",
+            Visual => "Visual line selection (y to yank)",
+            ToggleFullscreen => "Fullscreen preview (no header, footer or borders)",
+        }
+    }
+}
+
+/// Where a binding applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyContext {
+    Browser,
+    Preview,
+}
+
+use Action::*;
+use KeyContext::*;
+
+/// Action names used in the `[keys]` config table
+const ACTION_NAMES: &[(Action, &str)] = &[
+    (Quit, "quit"),
+    (MoveDown, "move_down"),
+    (MoveUp, "move_up"),
+    (Enter, "enter"),
+    (GoParent, "go_parent"),
+    (ExpandOrEnter, "expand_or_enter"),
+    (CollapseOrParent, "collapse_or_parent"),
+    (ToggleTree, "toggle_tree"),
+    (Fold, "fold"),
+    (GoTop, "go_top"),
+    (GoBottom, "go_bottom"),
+    (OpenEditor, "open_editor"),
+    (OpenWith, "open_with"),
+    (Search, "search"),
+    (ToggleHidden, "toggle_hidden"),
+    (Reload, "reload"),
+    (CopyPath, "copy_path"),
+    (Delete, "delete"),
+    (Rename, "rename"),
+    (BulkRename, "bulk_rename"),
+    (Chmod, "chmod"),
+    (Archive, "archive"),
+    (Trash, "trash"),
+    (Zoxide, "zoxide"),
+    (Places, "places"),
+    (Recent, "recent"),
+    (FollowLink, "follow_link"),
+    (DiskUsage, "disk_usage"),
+    (NewFile, "new_file"),
+    (NewDir, "new_dir"),
+    (ToggleMark, "toggle_mark"),
+    (MarkRange, "mark_range"),
+    (ClearMarks, "clear_marks"),
+    (CycleSort, "cycle_sort"),
+    (ToggleSortReverse, "toggle_sort_reverse"),
+    (ToggleDetails, "toggle_details"),
+    (ToggleDualPane, "toggle_dual_pane"),
+    (TogglePreviewPane, "toggle_preview_pane"),
+    (SplitLeft, "split_left"),
+    (SplitRight, "split_right"),
+    (SwitchPane, "switch_pane"),
+    (CopyToOtherPane, "copy_to_other_pane"),
+    (MoveToOtherPane, "move_to_other_pane"),
+    (Copy, "copy"),
+    (Cut, "cut"),
+    (Paste, "paste"),
+    (CancelOperation, "cancel_operation"),
+    (Jump, "jump"),
+    (JumpNext, "jump_next"),
+    (JumpPrev, "jump_prev"),
+    (SetBookmark, "set_bookmark"),
+    (JumpBookmark, "jump_bookmark"),
+    (Breadcrumb, "breadcrumb"),
+    (CommandPalette, "command_palette"),
+    (DismissNotifications, "dismiss_notifications"),
+    (Help, "help"),
+    (PreviewBack, "preview_back"),
+    (ScrollDown, "scroll_down"),
+    (ScrollUp, "scroll_up"),
+    (HalfPageDown, "half_page_down"),
+    (HalfPageUp, "half_page_up"),
+    (PageDown, "page_down"),
+    (PageUp, "page_up"),
+    (PreviewTop, "preview_top"),
+    (PreviewBottom, "preview_bottom"),
+    (GotoLine, "goto_line"),
+    (ToggleWhitespace, "toggle_whitespace"),
+    (Visual, "visual"),
+    (ToggleFullscreen, "toggle_fullscreen"),
+];
+
+/// Built-in bindings
+const DEFAULT_BINDINGS: &[(KeyContext, &str, Action)] = &[
+    (Browser, "q", Quit),
+    (Browser, "j", MoveDown),
+    (Browser, "down", MoveDown),
+    (Browser, "k", MoveUp),
+    (Browser, "up", MoveUp),
+    (Browser, "l", Enter),
+    (Browser, "enter", Enter),
+    (Browser, "h", GoParent),
+    (Browser, "backspace", GoParent),
+    (Browser, "right", ExpandOrEnter),
+    (Browser, "left", CollapseOrParent),
+    (Browser, "t", ToggleTree),
+    (Browser, "z", Fold),
+    (Browser, "g", GoTop),
+    (Browser, "G", GoBottom),
+    (Browser, "e", OpenEditor),
+    (Browser, "o", OpenWith),
+    (Browser, "shift-enter", OpenWith),
+    (Browser, "/", Search),
+    (Browser, ".", ToggleHidden),
+    (Browser, "r", Reload),
+    (Browser, "y", CopyPath),
+    (Browser, "d", Delete),
+    (Browser, "R", Rename),
+    (Browser, "E", BulkRename),
+    (Browser, "P", Chmod),
+    (Browser, "Z", Archive),
+    (Browser, "T", Trash),
+    (Browser, "C", Zoxide),
+    (Browser, "M", Places),
+    (Browser, "H", Recent),
+    (Browser, "L", FollowLink),
+    (Browser, "U", DiskUsage),
+    (Browser, "a", NewFile),
+    (Browser, "A", NewDir),
+    (Browser, "space", ToggleMark),
+    (Browser, "V", MarkRange),
+    (Browser, "esc", ClearMarks),
+    (Browser, "s", CycleSort),
+    (Browser, "S", ToggleSortReverse),
+    (Browser, "i", ToggleDetails),
+    (Browser, "w", ToggleDualPane),
+    (Browser, "v", TogglePreviewPane),
+    (Browser, "<", SplitLeft),
+    (Browser, ">", SplitRight),
+    (Browser, "tab", SwitchPane),
+    (Browser, "f5", CopyToOtherPane),
+    (Browser, "f6", MoveToOtherPane),
+    (Browser, "c", Copy),
+    (Browser, "x", Cut),
+    (Browser, "p", Paste),
+    (Browser, "X", CancelOperation),
+    (Browser, "f", Jump),
+    (Browser, ";", JumpNext),
+    (Browser, ",", JumpPrev),
+    (Browser, "m", SetBookmark),
+    (Browser, "'", JumpBookmark),
+    (Browser, "b", Breadcrumb),
+    (Browser, ":", CommandPalette),
+    (Browser, "ctrl-l", DismissNotifications),
+    (Browser, "?", Help),
+    (Preview, "q", PreviewBack),
+    (Preview, "esc", PreviewBack),
+    (Preview, "h", PreviewBack),
+    (Preview, "left", PreviewBack),
+    (Preview, "ctrl-l", DismissNotifications),
+    (Preview, "j", ScrollDown),
+    (Preview, "down", ScrollDown),
+    (Preview, "k", ScrollUp),
+    (Preview, "up", ScrollUp),
+    (Preview, "ctrl-d", HalfPageDown),
+    (Preview, "ctrl-u", HalfPageUp),
+    (Preview, "ctrl-f", PageDown),
+    (Preview, "pagedown", PageDown),
+    (Preview, "ctrl-b", PageUp),
+    (Preview, "pageup", PageUp),
+    (Preview, "g", PreviewTop),
+    (Preview, "G", PreviewBottom),
+    (Preview, ":", GotoLine),
+    (Preview, "e", OpenEditor),
+    (Preview, "w", ToggleWhitespace),
+    (Preview, "z", ToggleFullscreen),
+    (Preview, "v", Visual),
+];
+
+/// One key or a list of keys for an action in the `[keys]` table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeySpec {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeySpec {
+    fn keys(&self) -> &[String] {
+        match self {
+            KeySpec::One(key) => std::slice::from_ref(key),
+            KeySpec::Many(keys) => keys,
+        }
+    }
+}
+
+/// A key press with only the modifiers that matter for bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Shift is part of the character itself ("G") and of backtab, so it is
+    /// only kept for the other named keys ("shift-enter")
+    fn from_event(event: KeyEvent) -> Self {
+        let mut kept = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        if !matches!(event.code, KeyCode::Char(_) | KeyCode::BackTab) {
+            kept |= KeyModifiers::SHIFT;
+        }
+        Self {
+            code: event.code,
+            modifiers: event.modifiers & kept,
+        }
+    }
+
+    /// The same key without Shift, for terminals that report Shift on keys
+    /// nothing is bound to with it (shift-up still moves up)
+    fn without_shift(self) -> Option<Self> {
+        self.modifiers.contains(KeyModifiers::SHIFT).then(|| Self {
+            code: self.code,
+            modifiers: self.modifiers - KeyModifiers::SHIFT,
+        })
+    }
+}
+
+/// Names of keys that are not written as their character
+const KEY_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::Enter, "enter"),
+    (KeyCode::Esc, "esc"),
+    (KeyCode::Tab, "tab"),
+    (KeyCode::BackTab, "backtab"),
+    (KeyCode::Backspace, "backspace"),
+    (KeyCode::Delete, "delete"),
+    (KeyCode::Char(' '), "space"),
+    (KeyCode::Up, "up"),
+    (KeyCode::Down, "down"),
+    (KeyCode::Left, "left"),
+    (KeyCode::Right, "right"),
+    (KeyCode::Home, "home"),
+    (KeyCode::End, "end"),
+    (KeyCode::PageUp, "pageup"),
+    (KeyCode::PageDown, "pagedown"),
+];
+
+/// Parse a key such as "j", "G", "ctrl-d", "alt-enter", "shift-enter",
+/// "space" or "f5"
+fn parse_key(spec: &str) -> Option<Key> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = spec;
+    loop {
+        let lower = rest.to_lowercase();
+        if lower.starts_with("ctrl-") && rest.len() > 5 {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = &rest[5..];
+        } else if lower.starts_with("alt-") && rest.len() > 4 {
+            modifiers |= KeyModifiers::ALT;
+            rest = &rest[4..];
+        } else if lower.starts_with("shift-") && rest.len() > 6 {
+            modifiers |= KeyModifiers::SHIFT;
+            rest = &rest[6..];
+        } else {
+            break;
+        }
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        // shift-a は A と同じ（文字の Shift は文字そのものに含まれる）
+        (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+            modifiers -= KeyModifiers::SHIFT;
+            KeyCode::Char(c.to_uppercase().next().unwrap_or(c))
+        }
+        (Some(c), None) => KeyCode::Char(c),
+        _ => {
+            let name = rest.to_lowercase();
+            match KEY_NAMES.iter().find(|(_, n)| *n == name) {
+                Some(&(code, _)) => code,
+                None => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+            }
+        }
+    };
+    Some(Key { code, modifiers })
+}
+
+/// A key written the way `[keys]` accepts it
+fn format_key(key: Key) -> String {
+    let mut text = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        text.push_str("ctrl-");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        text.push_str("alt-");
+    }
+    if key.modifiers.contains(KeyModifiers::SHIFT) {
+        text.push_str("shift-");
+    }
+    match (
+        key.code,
+        KEY_NAMES.iter().find(|(code, _)| *code == key.code),
+    ) {
+        (_, Some((_, name))) => text.push_str(name),
+        (KeyCode::Char(c), None) => text.push(c),
+        (KeyCode::F(n), None) => text.push_str(&format!("f{}", n)),
+        (code, None) => text.push_str(&format!("{:?}", code)),
+    }
+    text
+}
+
+/// A key as the footer shows it: "j", "Enter", "^D"
+fn hint_key(key: Key) -> String {
+    let name = format_key(Key {
+        code: key.code,
+        modifiers: KeyModifiers::NONE,
+    });
+    let mut text = String::new();
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        text.push_str("M-");
+    }
+    if key.modifiers.contains(KeyModifiers::SHIFT) {
+        text.push_str("S-");
+    }
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        text.push('^');
+        text.push_str(&name.to_uppercase());
+    } else if name.chars().count() > 1 && !matches!(key.code, KeyCode::F(_)) {
+        // 名前付きのキーは先頭を大文字にする（enter → Enter）
+        let mut chars = name.chars();
+        text.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        text.push_str(chars.as_str());
+    } else {
+        text.push_str(&name);
+    }
+    text
+}
+
+/// Sort key that lists plain characters before named keys and modifiers
+fn key_order(key: &Key) -> (bool, u8, String) {
+    let named = !matches!(key.code, KeyCode::Char(c) if c != ' ');
+    (named, key.modifiers.bits(), format_key(*key))
+}
+
+/// Key bindings for the browser and the preview
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<(KeyContext, Key), Action>,
+    /// Shell command templates from `[commands]`, bound in the browser
+    commands: HashMap<Key, String>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&BTreeMap::new(), &BTreeMap::new()).0
+    }
+}
+
+impl Keymap {
+    /// Defaults with the actions in `overrides` rebound. An overridden action
+    /// loses its default keys in every context it had them in. Returns the
+    /// keymap along with messages for unknown actions or keys. `commands`
+    /// binds browser keys to shell command templates.
+    pub fn new(
+        overrides: &BTreeMap<String, KeySpec>,
+        commands: &BTreeMap<String, String>,
+    ) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let mut rebound: HashMap<Action, &KeySpec> = HashMap::new();
+        for (name, spec) in overrides {
+            match action_by_name(name) {
+                Some(action) => {
+                    rebound.insert(action, spec);
+                }
+                None => errors.push(format!("Unknown action '{}'", name)),
+            }
+        }
+
+        let mut bindings = HashMap::new();
+        for &(context, key, action) in DEFAULT_BINDINGS {
+            if rebound.contains_key(&action) {
+                continue;
+            }
+            if let Some(key) = parse_key(key) {
+                bindings.insert((context, key), action);
+            }
+        }
+
+        // 設定されたキーは既定のキーより優先する
+        for (&action, spec) in &rebound {
+            let contexts: Vec<KeyContext> = DEFAULT_BINDINGS
+                .iter()
+                .filter(|(_, _, a)| *a == action)
+                .map(|(context, _, _)| *context)
+                .collect();
+            for key in spec.keys() {
+                let Some(parsed) = parse_key(key) else {
+                    errors.push(format!("Invalid key '{}'", key));
+                    continue;
+                };
+                for &context in &contexts {
+                    bindings.insert((context, parsed), action);
+                }
+            }
+        }
+        let mut command_keys = HashMap::new();
+        for (key, template) in commands {
+            match parse_key(key) {
+                Some(parsed) => {
+                    command_keys.insert(parsed, template.clone());
+                }
+                None => errors.push(format!("Invalid key '{}'", key)),
+            }
+        }
+        errors.sort();
+        errors.dedup();
+        (
+            Self {
+                bindings,
+                commands: command_keys,
+            },
+            errors,
+        )
+    }
+
+    pub fn action(&self, context: KeyContext, event: KeyEvent) -> Option<Action> {
+        let key = Key::from_event(event);
+        self.bindings
+            .get(&(context, key))
+            .or_else(|| self.bindings.get(&(context, key.without_shift()?)))
+            .copied()
+    }
+
+    /// Actions bound in `context` with their keys, in the order of the help
+    /// screen
+    pub fn bindings(&self, context: KeyContext) -> Vec<(Action, Vec<String>)> {
+        ACTION_NAMES
+            .iter()
+            .filter_map(|&(action, _)| {
+                let mut keys: Vec<Key> = self
+                    .bindings
+                    .iter()
+                    .filter(|((c, _), a)| *c == context && **a == action)
+                    .map(|((_, key), _)| *key)
+                    .collect();
+                if keys.is_empty() {
+                    return None;
+                }
+                keys.sort_by_key(key_order);
+                Some((action, keys.into_iter().map(format_key).collect()))
+            })
+            .collect()
+    }
+
+    /// Cheat sheet of the current bindings, including `[commands]`
+    pub fn help_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (title, context) in [("File Browser", Browser), ("Preview", Preview)] {
+            lines.push(format!("=== {} ===", title));
+            for (action, keys) in self.bindings(context) {
+                lines.push(format!("{:<14} {}", keys.join(", "), action.description()));
+            }
+            lines.push(String::new());
+        }
+        if !self.commands.is_empty() {
+            lines.push("=== Commands ===".to_string());
+            let mut commands: Vec<(String, &String)> = self
+                .commands
+                .iter()
+                .map(|(key, template)| (format_key(*key), template))
+                .collect();
+            commands.sort();
+            for (key, template) in commands {
+                lines.push(format!("{:<14} {}", key, template));
+            }
+            lines.push(String::new());
+        }
+        lines
+    }
+
+    /// Footer hints such as "q:quit  j/k:move" for the keys currently bound
+    /// in `context`. Each hint shows the first key of each of its actions,
+    /// joined with `/`; a hint with an unbound action is left out. Whole
+    /// hints are dropped from the end until the text fits in `width` columns.
+    pub fn hints(&self, context: KeyContext, hints: &[(&[Action], &str)], width: usize) -> String {
+        let mut text = String::new();
+        for (actions, label) in hints {
+            let keys: Option<Vec<String>> = actions
+                .iter()
+                .map(|&action| {
+                    self.bindings
+                        .iter()
+                        .filter(|((c, _), a)| *c == context && **a == action)
+                        .map(|((_, key), _)| *key)
+                        .min_by_key(key_order)
+                        .map(hint_key)
+                })
+                .collect();
+            let Some(keys) = keys else {
+                continue;
+            };
+            let hint = format!("{}:{}", keys.join("/"), label);
+            let separator = if text.is_empty() { "" } else { "  " };
+            if text.width() + separator.len() + hint.width() > width {
+                break;
+            }
+            text.push_str(separator);
+            text.push_str(&hint);
+        }
+        text
+    }
+
+    /// Custom command template bound to the key in the browser
+    pub fn command(&self, event: KeyEvent) -> Option<&str> {
+        let key = Key::from_event(event);
+        self.commands
+            .get(&key)
+            .or_else(|| self.commands.get(&key.without_shift()?))
+            .map(String::as_str)
+    }
+}
+
+/// Action names accepted in `[keys]`
+pub fn action_names() -> impl Iterator<Item = &'static str> {
+    ACTION_NAMES.iter().map(|(_, name)| *name)
+}
+
+fn action_by_name(name: &str) -> Option<Action> {
+    ACTION_NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(action, _)| *action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_default_bindings() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action(Browser, press(KeyCode::Char('j'), KeyModifiers::NONE)),
+            Some(MoveDown)
+        );
+        // Shift は文字に含まれるので無視する
+        assert_eq!(
+            keymap.action(Browser, press(KeyCode::Char('G'), KeyModifiers::SHIFT)),
+            Some(GoBottom)
+        );
+        assert_eq!(
+            keymap.action(Preview, press(KeyCode::Char('d'), KeyModifiers::CONTROL)),
+            Some(HalfPageDown)
+        );
+        assert_eq!(
+            keymap.action(Browser, press(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_shift_on_named_keys() {
+        let keymap = Keymap::default();
+        // 拡張キーボードプロトコルの端末では Shift+Enter を区別できる
+        assert_eq!(
+            keymap.action(Browser, press(KeyCode::Enter, KeyModifiers::SHIFT)),
+            Some(OpenWith)
+        );
+        assert_eq!(
+            keymap.action(Browser, press(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(Enter)
+        );
+        // Shift の付いた矢印キーは割り当てがなければ Shift なしと同じ
+        assert_eq!(
+            keymap.action(Browser, press(KeyCode::Up, KeyModifiers::SHIFT)),
+            Some(MoveUp)
+        );
+        assert_eq!(
+            keymap.action(Browser, press(KeyCode::BackTab, KeyModifiers::SHIFT)),
+            keymap.action(Browser, press(KeyCode::BackTab, KeyModifiers::NONE))
+        );
+
+        assert_eq!(parse_key("shift-a"), parse_key("A"));
+        assert_eq!(
+            format_key(parse_key("ctrl-shift-enter").unwrap()),
+            "ctrl-shift-enter"
+        );
+        // Ctrl+I は Tab とは別のキー
+        assert_ne!(parse_key("ctrl-i"), parse_key("tab"));
+    }
+
+    #[test]
+    fn test_every_default_binding_parses_and_is_named() {
+        for (_, key, action) in DEFAULT_BINDINGS {
+            assert!(parse_key(key).is_some(), "{}", key);
+            assert!(ACTION_NAMES.iter().any(|(a, _)| a == action));
+        }
+    }
+
+    #[test]
+    fn test_overrides_replace_defaults() {
+        let overrides = BTreeMap::from([
+            ("move_down".to_string(), KeySpec::One("n".to_string())),
+            (
+                "open_editor".to_string(),
+                KeySpec::Many(vec!["ctrl-e".to_string(), "F4".to_string()]),
+            ),
+            ("no_such_action".to_string(), KeySpec::One("x".to_string())),
+            ("reload".to_string(), KeySpec::One("ctrl-".to_string())),
+        ]);
+        let (keymap, errors) = Keymap::new(&overrides, &BTreeMap::new());
+
+        assert_eq!(
+            keymap.action(Browser, press(KeyCode::Char('n'), KeyModifiers::NONE)),
+            Some(MoveDown)
+        );
+        assert_eq!(
+            keymap.action(Browser, press(KeyCode::Char('j'), KeyModifiers::NONE)),
+            None
+        );
+        // 既定のキーがあったすべての画面で置き換わる
+        for context in [Browser, Preview] {
+            assert_eq!(
+                keymap.action(context, press(KeyCode::Char('e'), KeyModifiers::CONTROL)),
+                Some(OpenEditor)
+            );
+            assert_eq!(
+                keymap.action(context, press(KeyCode::F(4), KeyModifiers::NONE)),
+                Some(OpenEditor)
+            );
+        }
+        assert_eq!(
+            errors,
+            vec![
+                "Invalid key 'ctrl-'".to_string(),
+                "Unknown action 'no_such_action'".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_commands() {
+        let commands = BTreeMap::from([
+            ("ctrl-g".to_string(), "git add {path}".to_string()),
+            ("ctrl-".to_string(), "true".to_string()),
+        ]);
+        let (keymap, errors) = Keymap::new(&BTreeMap::new(), &commands);
+
+        assert_eq!(
+            keymap.command(press(KeyCode::Char('g'), KeyModifiers::CONTROL)),
+            Some("git add {path}")
+        );
+        assert_eq!(
+            keymap.command(press(KeyCode::Char('g'), KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(errors, vec!["Invalid key 'ctrl-'".to_string()]);
+    }
+
+    #[test]
+    fn test_format_key_round_trips() {
+        for key in [
+            "j",
+            "G",
+            "ctrl-d",
+            "alt-enter",
+            "space",
+            "f5",
+            "'",
+            "ctrl-alt-x",
+        ] {
+            assert_eq!(format_key(parse_key(key).unwrap()), key);
+        }
+    }
+
+    #[test]
+    fn test_help_lines_follow_overrides() {
+        let overrides = BTreeMap::from([(
+            "move_down".to_string(),
+            KeySpec::Many(vec!["down".to_string(), "n".to_string()]),
+        )]);
+        let commands = BTreeMap::from([("ctrl-g".to_string(), "git add {path}".to_string())]);
+        let (keymap, _) = Keymap::new(&overrides, &commands);
+
+        assert_eq!(
+            keymap.bindings(Browser)[1],
+            (MoveDown, vec!["n".to_string(), "down".to_string()])
+        );
+        let lines = keymap.help_lines();
+        assert_eq!(lines[0], "=== File Browser ===");
+        assert!(lines.contains(&format!("{:<14} Move down", "n, down")));
+        assert!(lines.contains(&format!("{:<14} git add {{path}}", "ctrl-g")));
+        // 既定のキーに説明のない動作はない
+        assert!(
+            ACTION_NAMES
+                .iter()
+                .all(|(a, _)| !a.description().is_empty())
+        );
+    }
+
+    #[test]
+    fn test_hints_follow_overrides_and_width() {
+        let hints: &[(&[Action], &str)] = &[
+            (&[Quit], "quit"),
+            (&[MoveDown, MoveUp], "move"),
+            (&[HalfPageDown], "half page"),
+            (&[Search], "search"),
+        ];
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.hints(Browser, hints, 80),
+            "q:quit  j/k:move  /:search"
+        );
+        assert_eq!(keymap.hints(Preview, &hints[2..3], 80), "^D:half page");
+        // 収まらないヒントは丸ごと落とす
+        assert_eq!(keymap.hints(Browser, hints, 17), "q:quit  j/k:move");
+        assert_eq!(keymap.hints(Browser, hints, 3), "");
+
+        let overrides = BTreeMap::from([
+            ("quit".to_string(), KeySpec::One("enter".to_string())),
+            ("move_up".to_string(), KeySpec::One("alt-p".to_string())),
+        ]);
+        let (keymap, _) = Keymap::new(&overrides, &BTreeMap::new());
+        assert_eq!(
+            keymap.hints(Browser, &hints[..2], 80),
+            "Enter:quit  j/M-p:move"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Split ratios last set with `<`/`>`, persisted as TOML so the next
+/// session starts with them instead of the config values. A ratio is only
+/// used while the config still has the value it was saved against, so
+/// editing the config takes effect again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedLayout {
+    /// Width of the preview pane in percent
+    pub preview_pane_ratio: Option<u16>,
+    /// Width of the left pane in dual-pane mode in percent
+    pub dual_pane_ratio: Option<u16>,
+    /// `preview_pane_ratio` of the config when the preview width was saved
+    #[serde(default)]
+    preview_pane_config: Option<u16>,
+    /// `dual_pane_ratio` of the config when the dual-pane width was saved
+    #[serde(default)]
+    dual_pane_config: Option<u16>,
+    /// Where to save; `None` keeps the layout in memory only
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl SavedLayout {
+    /// Load the layout from the data dir (missing or broken files yield none)
+    pub fn load() -> Self {
+        Self::load_from(Self::layout_path())
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let layout: Self = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            ..layout
+        }
+    }
+
+    /// Saved preview width, unless the config has changed since
+    pub fn preview_ratio(&self, configured: u16) -> Option<u16> {
+        self.preview_pane_ratio
+            .filter(|_| self.preview_pane_config == Some(configured))
+    }
+
+    /// Saved dual-pane width, unless the config has changed since
+    pub fn dual_ratio(&self, configured: u16) -> Option<u16> {
+        self.dual_pane_ratio
+            .filter(|_| self.dual_pane_config == Some(configured))
+    }
+
+    pub fn set_preview_ratio(&mut self, ratio: u16, configured: u16) {
+        self.preview_pane_ratio = Some(ratio);
+        self.preview_pane_config = Some(configured);
+    }
+
+    pub fn set_dual_ratio(&mut self, ratio: u16, configured: u16) {
+        self.dual_pane_ratio = Some(ratio);
+        self.dual_pane_config = Some(configured);
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, content)
+    }
+
+    pub fn layout_path() -> PathBuf {
+        if let Some(proj_dirs) = ProjectDirs::from("", "", "vive-file-viewer") {
+            proj_dirs.data_dir().join("layout.toml")
+        } else {
+            PathBuf::from("~/.local/share/vive-file-viewer/layout.toml")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested/layout.toml");
+
+        let mut layout = SavedLayout::load_from(path.clone());
+        assert_eq!(layout.preview_pane_ratio, None);
+        layout.set_preview_ratio(35, 50);
+        layout.save().unwrap();
+
+        let loaded = SavedLayout::load_from(path.clone());
+        assert_eq!(loaded.preview_ratio(50), Some(35));
+        assert_eq!(loaded.dual_ratio(50), None);
+        // config の値を変えたら保存した幅は使わない
+        assert_eq!(loaded.preview_ratio(40), None);
+
+        fs::write(&path, "not toml").unwrap();
+        assert_eq!(SavedLayout::load_from(path).preview_pane_ratio, None);
+    }
+}
@@ -1,39 +1,104 @@
+// 設定スキーマの json! が大きく、既定の 128 では展開しきれない
+#![recursion_limit = "256"]
+
+mod ansi;
 mod app;
+mod bookmarks;
+mod commands;
 mod config;
+mod confirm;
+mod disk_usage;
+mod doctor;
 mod editor;
+mod ellipsis;
 mod file_browser;
+mod file_ops;
+mod git;
+mod icons;
+mod keymap;
+mod layout;
+mod notebook;
+mod notify;
+mod opener;
+mod owners;
+mod palette;
+mod places;
 mod preview;
+mod prompt;
+mod recent;
+mod schema;
 mod search;
+mod shell_init;
+mod status_bar;
+mod trash_bin;
 mod ui;
+mod ui_theme;
+mod watcher;
+mod widget_style;
+mod zoxide;
 
-use std::io;
+use std::borrow::Cow;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::thread;
 use std::time::{Duration, Instant};
 
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEventKind, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    terminal::{
+        EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+        supports_keyboard_enhancement,
+    },
 };
 use indicatif::{ProgressBar, ProgressStyle};
 use ratatui::{Terminal, backend::CrosstermBackend};
 
 use app::{App, InputMode};
+use bookmarks::Bookmarks;
 use config::Config;
-use search::{FileSearcher, SearchResult};
+use file_browser::FileBrowser;
+use file_ops::TransferKind;
+use keymap::{Action, KeyContext, Keymap};
+use search::{FileSearcher, SearchOptions, SearchResult, SearchStats};
+use shell_init::InitShell;
 
 #[derive(Parser)]
 #[command(name = "vfv")]
 #[command(about = "A fast terminal file viewer with fuzzy search")]
 #[command(version)]
 struct Cli {
-    /// Directory to open (for TUI mode)
+    /// Directory to open, or a file to open in preview (for TUI mode)
     #[arg(value_name = "PATH")]
     path: Option<PathBuf>,
 
+    /// Write the last visited directory to FILE on quit (for cd-on-exit wrappers)
+    #[arg(long = "choose-dir", value_name = "FILE")]
+    choose_dir: Option<PathBuf>,
+
+    /// Pick files: Enter writes the selected (or marked) paths to FILE, one per line, and exits
+    #[arg(long = "choose-files", value_name = "FILE")]
+    choose_files: Option<PathBuf>,
+
+    /// Highlight ENTRY on start: a name in PATH, or a path (relative to PATH) to reveal
+    #[arg(long = "select", value_name = "ENTRY")]
+    select: Option<PathBuf>,
+
+    /// Read paths from standard input (one per line) and browse them as a filterable list
+    #[arg(long = "stdin")]
+    stdin: bool,
+
+    /// Use this config file instead of the default (also: VFV_CONFIG)
+    #[arg(long = "config", value_name = "FILE", global = true)]
+    config: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -50,24 +115,36 @@ enum Commands {
         path: Option<PathBuf>,
 
         /// Output as JSON
-        #[arg(short = 'j', long = "json")]
+        #[arg(short = 'j', long = "json", overrides_with = "no_json")]
         json: bool,
 
+        /// Plain output even when [find] json is set
+        #[arg(long = "no-json")]
+        no_json: bool,
+
+        /// Stream one JSON object per line as matches are found (unranked)
+        #[arg(long = "ndjson")]
+        ndjson: bool,
+
+        /// Keys in JSON output [default: path,name,is_dir,score]
+        #[arg(long = "fields", value_name = "FIELDS", value_delimiter = ',')]
+        fields: Vec<FindField>,
+
         /// Search directories only
         #[arg(short = 'd', long = "dir")]
         dir_only: bool,
 
-        /// Maximum number of results
-        #[arg(short = 'n', long = "limit", default_value = "20")]
-        limit: usize,
+        /// Maximum number of results [default: 20, or [find] limit]
+        #[arg(short = 'n', long = "limit")]
+        limit: Option<usize>,
 
         /// Output only the top result (shortcut for -n 1)
         #[arg(short = '1', long = "first")]
         first: bool,
 
-        /// Timeout in seconds (0 = no timeout)
-        #[arg(short = 't', long = "timeout", default_value = "0")]
-        timeout: u64,
+        /// Timeout in seconds (0 = no timeout) [default: 0, or [find] timeout]
+        #[arg(short = 't', long = "timeout")]
+        timeout: Option<u64>,
 
         /// Quiet mode (no spinner)
         #[arg(short = 'q', long = "quiet")]
@@ -77,9 +154,66 @@ enum Commands {
         #[arg(short = 'c', long = "compact")]
         compact: bool,
 
+        /// Print entries scanned, matches, skipped directories and time to stderr
+        #[arg(long = "stats")]
+        stats: bool,
+
         /// Exact match (no fuzzy matching)
-        #[arg(short = 'e', long = "exact")]
+        #[arg(short = 'e', long = "exact", overrides_with = "fuzzy")]
         exact: bool,
+
+        /// Fuzzy match even when [find] exact is set
+        #[arg(long = "fuzzy")]
+        fuzzy: bool,
+
+        /// Include hidden files (the default unless [find] hidden = false)
+        #[arg(long = "hidden", overrides_with = "no_hidden")]
+        hidden: bool,
+
+        /// Skip hidden files
+        #[arg(long = "no-hidden")]
+        no_hidden: bool,
+
+        /// Do not respect .gitignore and other ignore files
+        #[arg(long = "no-ignore", overrides_with = "ignore")]
+        no_ignore: bool,
+
+        /// Respect ignore files even when [find] no_ignore is set
+        #[arg(long = "ignore")]
+        ignore: bool,
+    },
+
+    /// Open a file with its configured extension opener (or the system default)
+    Open {
+        /// File to open
+        file: PathBuf,
+
+        /// Use this entry of [openers] instead
+        #[arg(short = 'w', long = "with", value_name = "NAME")]
+        with: Option<String>,
+
+        /// Print the command instead of running it
+        #[arg(short = 'p', long = "print")]
+        print: bool,
+    },
+
+    /// List a directory (as a table or JSON)
+    Ls {
+        /// Directory to list (default: current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// Include hidden files
+        #[arg(short = 'a', long = "all")]
+        all: bool,
+
+        /// Output as JSON
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+
+        /// Compact JSON output (single line)
+        #[arg(short = 'c', long = "compact")]
+        compact: bool,
     },
 
     /// Initialize config, shell completions, and man page
@@ -87,41 +221,301 @@ enum Commands {
         /// Overwrite existing files
         #[arg(short, long)]
         force: bool,
+
+        /// Print the files that would be created and the rc-file lines that would be added,
+        /// without changing anything
+        #[arg(short = 'n', long = "dry-run")]
+        dry_run: bool,
     },
 
-    /// Generate man page
+    /// Print the man page of vfv or one of its subcommands
     #[command(name = "man")]
-    ManPage,
+    ManPage {
+        /// Subcommand, e.g. "find" or "config-get" (default: vfv itself)
+        command: Option<String>,
+    },
+
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate completions for
+        shell: CompletionShell,
+    },
+
+    /// Print shell code defining `vcd` (cd to where vfv quits), for eval in your rc file
+    ShellInit {
+        /// Shell to generate code for
+        shell: InitShell,
+
+        /// Also bind Ctrl+F to vcd
+        #[arg(long = "bind")]
+        bind: bool,
+    },
+
+    /// List the key bindings in effect, including [keys] and [commands]
+    Keys,
+
+    /// Time repeated searches to report or compare performance
+    #[command(hide = true)]
+    Bench {
+        /// Search query
+        query: String,
+
+        /// Directory to search in (default: current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// Number of searches to run
+        #[arg(short = 'r', long = "runs", default_value_t = 5)]
+        runs: usize,
+
+        /// Exact match (no fuzzy matching)
+        #[arg(short = 'e', long = "exact")]
+        exact: bool,
+
+        /// Output as JSON
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+
+    /// Add, list, remove, or print bookmarks shared with the TUI
+    Bookmarks {
+        #[command(subcommand)]
+        command: BookmarksCommand,
+    },
+
+    /// Check the terminal, config, editor, clipboard and locale, and suggest fixes
+    Doctor,
+
+    /// Show or change settings in the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+/// Shells `vfv completions` and `vfv init` can generate completions for
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    #[value(name = "powershell")]
+    PowerShell,
+    Elvish,
+    Nushell,
+}
+
+/// A key of `find` JSON output
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FindField {
+    Path,
+    Name,
+    #[value(name = "is_dir")]
+    IsDir,
+    Score,
+    /// Size in bytes (0 for directories)
+    Size,
+    /// Modification time (RFC 3339)
+    Mtime,
+}
+
+/// Keys printed when `--fields` is not given
+const DEFAULT_FIND_FIELDS: &[FindField] = &[
+    FindField::Path,
+    FindField::Name,
+    FindField::IsDir,
+    FindField::Score,
+];
+
+#[derive(Subcommand)]
+enum BookmarksCommand {
+    /// Bookmark a directory (single-character names can be jumped to in the TUI)
+    Add {
+        /// Bookmark name
+        name: String,
+
+        /// Directory to bookmark (default: current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+    },
+
+    /// List bookmarks as "NAME<TAB>PATH"
+    List {
+        /// Output as JSON
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+
+    /// Delete a bookmark
+    Remove {
+        /// Bookmark name
+        name: String,
+    },
+
+    /// Print a bookmarked directory, e.g. cd "$(vfv bookmarks jump work)"
+    Jump {
+        /// Bookmark name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the effective value of a setting (all settings without KEY)
+    Get {
+        /// Setting name, e.g. "editor" or "previewers.md"
+        key: Option<String>,
+    },
+
+    /// Write a setting to the config file (comments are kept)
+    Set {
+        /// Setting name, e.g. "theme" or "openers.less"
+        key: String,
+
+        /// New value; TOML values such as true, 4 or ["--wait"] keep their type
+        value: String,
+    },
+
+    /// Validate the config file (exits with 1 when there are problems)
+    Check,
+
+    /// Print a JSON Schema of the config file for editor validation
+    Schema,
 }
 
 fn main() -> io::Result<()> {
     let cli = Cli::parse();
+    if let Some(path) = cli.config {
+        Config::set_config_path(path);
+    }
 
     match cli.command {
         Some(Commands::Find {
             query,
             path,
             json,
+            no_json,
+            ndjson,
+            fields,
             dir_only,
             limit,
             first,
             timeout,
             quiet,
             compact,
+            stats,
             exact,
-        }) => run_find(
-            query, path, json, dir_only, limit, first, timeout, quiet, compact, exact,
-        ),
-        Some(Commands::Init { force }) => run_init(force),
-        Some(Commands::ManPage) => {
-            run_man_page();
+            fuzzy,
+            hidden,
+            no_hidden,
+            no_ignore,
+            ignore,
+        }) => {
+            // 指定のないフラグは [find] の設定に従う
+            let defaults = Config::load().find;
+            let options = SearchOptions {
+                dirs_only: dir_only,
+                exact: flag(exact, fuzzy, defaults.exact),
+                hidden: flag(hidden, no_hidden, defaults.hidden),
+                no_ignore: flag(no_ignore, ignore, defaults.no_ignore),
+            };
+            run_find(
+                query,
+                path,
+                flag(json, no_json, defaults.json),
+                ndjson,
+                &fields,
+                options,
+                limit.unwrap_or(defaults.limit),
+                first,
+                timeout.unwrap_or(defaults.timeout),
+                quiet,
+                compact,
+                stats,
+            )
+        }
+        Some(Commands::Open { file, with, print }) => run_open(&file, with.as_deref(), print),
+        Some(Commands::Ls {
+            path,
+            all,
+            json,
+            compact,
+        }) => run_ls(path, all, json, compact),
+        Some(Commands::Init { force, dry_run }) => run_init(force, dry_run),
+        Some(Commands::ManPage { command }) => run_man_page(command.as_deref()),
+        Some(Commands::Completions { shell }) => io::stdout().write_all(&completion_script(shell)),
+        Some(Commands::ShellInit { shell, bind }) => {
+            print!("{}", shell_init::script(shell, bind));
+            Ok(())
+        }
+        Some(Commands::Bench {
+            query,
+            path,
+            runs,
+            exact,
+            json,
+        }) => run_bench(&query, path, runs, exact, json),
+        Some(Commands::Keys) => {
+            run_keys();
+            Ok(())
+        }
+        Some(Commands::Bookmarks { command }) => run_bookmarks(command),
+        Some(Commands::Doctor) => {
+            run_doctor();
             Ok(())
         }
+        Some(Commands::Config { command }) => run_config(command),
         None => {
             let start_path = cli.path.unwrap_or(std::env::current_dir()?);
-            run_tui(&start_path)
+            let paths = if cli.stdin {
+                Some(read_stdin_paths()?)
+            } else {
+                None
+            };
+            run_tui(
+                &start_path,
+                cli.choose_dir.as_deref(),
+                cli.choose_files.as_deref(),
+                cli.select.as_deref(),
+                paths,
+            )
+        }
+    }
+}
+
+/// Paths piped to `vfv --stdin`, one per line (blank lines skipped)
+fn read_stdin_paths() -> io::Result<Vec<String>> {
+    use std::io::{BufRead, IsTerminal};
+
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        eprintln!("--stdin expects paths on standard input, e.g. git ls-files | vfv --stdin");
+        std::process::exit(2);
+    }
+    let mut paths = Vec::new();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if !line.is_empty() {
+            paths.push(line.to_string());
         }
     }
+    if paths.is_empty() {
+        eprintln!("No paths on standard input");
+        std::process::exit(1);
+    }
+    Ok(paths)
+}
+
+/// Value of an on/off flag pair, falling back to the configured default
+fn flag(on: bool, off: bool, default: bool) -> bool {
+    if on {
+        true
+    } else if off {
+        false
+    } else {
+        default
+    }
 }
 
 /// Maximum allowed query length to prevent memory exhaustion
@@ -132,13 +526,15 @@ fn run_find(
     query: String,
     path: Option<PathBuf>,
     json: bool,
-    dir_only: bool,
+    ndjson: bool,
+    fields: &[FindField],
+    options: SearchOptions,
     limit: usize,
     first: bool,
     timeout: u64,
     quiet: bool,
     compact: bool,
-    exact: bool,
+    stats: bool,
 ) -> io::Result<()> {
     // Validate query length
     if query.len() > MAX_QUERY_LENGTH {
@@ -157,6 +553,22 @@ fn run_find(
     } else {
         None
     };
+    let fields = if fields.is_empty() {
+        DEFAULT_FIND_FIELDS
+    } else {
+        fields
+    };
+    if ndjson {
+        return run_find_ndjson(
+            query,
+            base_dir,
+            fields,
+            options,
+            actual_limit,
+            timeout,
+            stats,
+        );
+    }
 
     // スピナー表示（quiet/jsonモードでは非表示）
     let show_spinner = !quiet && !json;
@@ -173,21 +585,22 @@ fn run_find(
     };
 
     // 検索をバックグラウンドスレッドで実行
-    let (tx, rx) = mpsc::channel::<Vec<SearchResult>>();
+    let (tx, rx) = mpsc::channel::<(Vec<SearchResult>, SearchStats)>();
     let search_query = query.clone();
     let search_dir = base_dir.clone();
 
     thread::spawn(move || {
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(&search_dir, &search_query, actual_limit, dir_only, exact);
-        let _ = tx.send(results);
+        searcher.count_skipped = stats;
+        let results = searcher.search(&search_dir, &search_query, actual_limit, options);
+        let _ = tx.send((results, searcher.stats));
     });
 
     // タイムアウト付きで結果を待つ
     let start = Instant::now();
     let results = loop {
         match rx.try_recv() {
-            Ok(results) => break Some(results),
+            Ok(found) => break Some(found),
             Err(mpsc::TryRecvError::Empty) => {
                 if let Some(timeout_dur) = timeout_duration
                     && start.elapsed() >= timeout_dur
@@ -196,7 +609,7 @@ fn run_find(
                 }
                 thread::sleep(Duration::from_millis(50));
             }
-            Err(mpsc::TryRecvError::Disconnected) => break Some(Vec::new()),
+            Err(mpsc::TryRecvError::Disconnected) => break Some(Default::default()),
         }
     };
 
@@ -207,21 +620,12 @@ fn run_find(
 
     // 結果出力
     match results {
-        Some(results) => {
+        Some((results, search_stats)) => {
             let is_empty = results.is_empty();
 
             if json {
-                let json_results: Vec<serde_json::Value> = results
-                    .iter()
-                    .map(|r| {
-                        serde_json::json!({
-                            "path": r.path.to_string_lossy(),
-                            "name": r.path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default(),
-                            "is_dir": r.is_dir,
-                            "score": r.score
-                        })
-                    })
-                    .collect();
+                let json_results: Vec<serde_json::Value> =
+                    results.iter().map(|r| result_json(r, fields)).collect();
 
                 let output = if compact {
                     serde_json::to_string(&json_results)
@@ -240,6 +644,9 @@ fn run_find(
                     println!("{}", result.path.display());
                 }
             }
+            if stats {
+                print_find_stats(search_stats, start.elapsed(), json);
+            }
 
             // 結果が0件の場合は終了コード1
             if is_empty {
@@ -271,177 +678,807 @@ fn run_find(
     Ok(())
 }
 
-fn run_tui(start_path: &Path) -> io::Result<()> {
-    let config = Config::load();
+/// JSON object with the requested keys of a search result
+fn result_json(result: &SearchResult, fields: &[FindField]) -> serde_json::Value {
+    // size と mtime を求めたときだけファイル情報を読む
+    let metadata = fields
+        .iter()
+        .any(|f| matches!(f, FindField::Size | FindField::Mtime))
+        .then(|| std::fs::metadata(&result.path).ok())
+        .flatten();
+    let mut object = serde_json::Map::new();
+    for field in fields {
+        let (key, value) = match field {
+            FindField::Path => ("path", serde_json::json!(result.path.to_string_lossy())),
+            FindField::Name => (
+                "name",
+                serde_json::json!(
+                    result
+                        .path
+                        .file_name()
+                        .map(|n| n.to_string_lossy())
+                        .unwrap_or_default()
+                ),
+            ),
+            FindField::IsDir => ("is_dir", serde_json::json!(result.is_dir)),
+            FindField::Score => ("score", serde_json::json!(result.score)),
+            FindField::Size => (
+                "size",
+                serde_json::json!(
+                    metadata
+                        .as_ref()
+                        .map(|m| if m.is_dir() { 0 } else { m.len() })
+                ),
+            ),
+            FindField::Mtime => (
+                "mtime",
+                serde_json::json!(
+                    metadata
+                        .as_ref()
+                        .and_then(|m| m.modified().ok())
+                        .map(|time| chrono::DateTime::<chrono::Local>::from(time).to_rfc3339())
+                ),
+            ),
+        };
+        object.insert(key.to_string(), value);
+    }
+    serde_json::Value::Object(object)
+}
+
+/// `find --stats`: counts from the search on stderr, so stdout stays the
+/// same for scripts (a JSON object when the results are JSON)
+fn print_find_stats(stats: SearchStats, elapsed: Duration, json: bool) {
+    if json {
+        let report = serde_json::json!({
+            "stats": {
+                "scanned": stats.scanned,
+                "matches": stats.matched,
+                "skipped_dirs": stats.skipped_dirs,
+                "elapsed_ms": elapsed.as_secs_f64() * 1000.0,
+            }
+        });
+        eprintln!("{}", report);
+    } else {
+        eprintln!(
+            "{} scanned, {} matches, {} directories skipped, {:.1} ms",
+            stats.scanned,
+            stats.matched,
+            stats.skipped_dirs,
+            elapsed.as_secs_f64() * 1000.0
+        );
+    }
+}
+
+/// `find --ndjson`: print each match as a line of JSON as soon as the walk
+/// finds it, stopping after `limit` matches
+fn run_find_ndjson(
+    query: String,
+    base_dir: PathBuf,
+    fields: &[FindField],
+    options: SearchOptions,
+    limit: usize,
+    timeout: u64,
+    stats: bool,
+) -> io::Result<()> {
+    // 検索が終わると最後に統計が届く
+    let (tx, rx) = mpsc::channel::<Result<SearchResult, SearchStats>>();
+    let start = Instant::now();
+    thread::spawn(move || {
+        let mut searcher = FileSearcher::new();
+        searcher.count_skipped = stats;
+        let mut sent = 0;
+        searcher.search_each(&base_dir, &query, options, |result| {
+            if sent >= limit {
+                return false;
+            }
+            sent += 1;
+            tx.send(Ok(result)).is_ok()
+        });
+        let _ = tx.send(Err(searcher.stats));
+    });
+
+    let deadline = (timeout > 0).then(|| Instant::now() + Duration::from_secs(timeout));
+    let mut stdout = io::stdout().lock();
+    let mut found = 0;
+    loop {
+        let next = match deadline {
+            Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        let line = match next {
+            Ok(Ok(result)) => result_json(&result, fields).to_string(),
+            Ok(Err(search_stats)) => {
+                if stats {
+                    print_find_stats(search_stats, start.elapsed(), true);
+                }
+                break;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let error = serde_json::json!({ "error": "timeout", "timeout_seconds": timeout });
+                let _ = writeln!(stdout, "{}", error);
+                let _ = stdout.flush();
+                std::process::exit(124); // タイムアウトの終了コード
+            }
+        };
+        // 読み手が先に終了した場合（head など）は正常終了とする
+        match writeln!(stdout, "{}", line).and_then(|()| stdout.flush()) {
+            Ok(()) => found += 1,
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+
+    // 結果が0件の場合は終了コード1
+    if found == 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_tui(
+    start_path: &Path,
+    choose_dir: Option<&Path>,
+    choose_files: Option<&Path>,
+    select: Option<&Path>,
+    paths: Option<Vec<String>>,
+) -> io::Result<()> {
+    // .vfv.toml は開始ディレクトリから上に向かって探す
+    let project_dir = if start_path.is_dir() {
+        start_path
+    } else {
+        start_path.parent().unwrap_or(start_path)
+    };
+    Config::set_project_dir(
+        std::fs::canonicalize(project_dir).unwrap_or_else(|_| project_dir.to_path_buf()),
+    );
+    let config = match Config::load_with_result() {
+        Ok(config) => config,
+        // 指定した設定が読めないまま既定値で画面に入ると、警告が見えず気づけない
+        Err(e) if Config::has_explicit_path() => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Config warning: {}", e);
+            Config::default()
+        }
+    };
     let mut app = App::new(start_path, config);
+    app.picker = choose_files.is_some();
+    if let Some(entry) = select {
+        // 相対パスは開いたディレクトリを基準にする（絶対パスはそのまま）
+        let target = app.browser.current_dir.join(entry);
+        if app.reveal(&target).is_none() {
+            app.toasts.warn(format!("Not found: {}", entry.display()));
+        }
+    }
+    if let Some(paths) = paths {
+        app.open_path_list(paths);
+    }
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
+    if app.config.mouse {
+        execute!(stdout, EnableMouseCapture)?;
+        editor::MOUSE_CAPTURE.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    // 対応端末では Shift+Enter や Ctrl+I を区別できるようにする
+    let enhanced = app.config.keyboard_protocol && supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced {
+        execute!(stdout, PushKeyboardEnhancementFlags(editor::KEYBOARD_FLAGS))?;
+        editor::KEYBOARD_ENHANCED.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     let result = run_app(&mut terminal, &mut app);
 
+    if enhanced {
+        execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags)?;
+    }
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        DisableMouseCapture,
+        LeaveAlternateScreen
+    )?;
     terminal.show_cursor()?;
+    result?;
 
-    result
+    // シェル側で cd できるよう最後のディレクトリを書き出す
+    if let Some(file) = choose_dir {
+        std::fs::write(file, path_bytes(&app.browser.current_dir))?;
+    }
+    // 選ばずに終了した場合は空にする（前回の結果を残さない）
+    if let Some(file) = choose_files {
+        let mut content = Vec::new();
+        for path in &app.chosen {
+            content.extend_from_slice(&path_bytes(path));
+            content.push(b'\n');
+        }
+        std::fs::write(file, content)?;
+    }
+    Ok(())
+}
+
+/// A path as written for shell wrappers and scripts: the raw bytes on unix,
+/// so names that are not UTF-8 survive
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    Cow::Owned(path.to_string_lossy().into_owned().into_bytes())
+}
+
+fn is_ctrl_c(key: KeyEvent) -> bool {
+    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// ファイルブラウザでのキー操作
+fn run_browser_action(app: &mut App, action: Action) {
+    match action {
+        Action::Quit => app.quit(),
+        Action::MoveDown => app.move_down(),
+        Action::MoveUp => app.move_up(),
+        Action::Enter if app.picker => app.pick(),
+        Action::Enter => app.enter(),
+        Action::GoParent => app.go_parent(),
+        Action::ExpandOrEnter => app.expand_or_enter(),
+        Action::CollapseOrParent => app.collapse_or_parent(),
+        Action::ToggleTree => app.toggle_tree(),
+        Action::Fold => app.start_fold(),
+        Action::GoTop => app.go_to_top(),
+        Action::GoBottom => app.go_to_bottom(),
+        Action::OpenEditor => app.open_in_editor(),
+        Action::OpenWith => app.start_open_with(),
+        Action::Search => app.start_search(),
+        Action::ToggleHidden => app.toggle_hidden(),
+        Action::Reload => app.reload(),
+        Action::CopyPath => app.copy_path(),
+        Action::Delete => app.start_delete(),
+        Action::Rename => app.start_rename(),
+        Action::BulkRename => app.bulk_rename(),
+        Action::Chmod => app.start_chmod(),
+        Action::Archive => app.start_archive(),
+        Action::Trash => app.open_trash(),
+        Action::Zoxide => app.start_zoxide(),
+        Action::Places => app.open_places(),
+        Action::Recent => app.open_recent(),
+        Action::FollowLink => app.follow_link(),
+        Action::DiskUsage => app.open_disk_usage(),
+        Action::NewFile => app.start_create(false),
+        Action::NewDir => app.start_create(true),
+        Action::ToggleMark => app.toggle_mark(),
+        Action::MarkRange => app.start_mark_range(),
+        Action::ClearMarks => app.clear_marks(),
+        Action::CycleSort => app.cycle_sort(),
+        Action::ToggleSortReverse => app.toggle_sort_reverse(),
+        Action::ToggleDetails => app.toggle_details(),
+        Action::ToggleDualPane => app.toggle_dual_pane(),
+        Action::TogglePreviewPane => app.toggle_preview_pane(),
+        Action::SplitLeft => app.move_split(-1),
+        Action::SplitRight => app.move_split(1),
+        Action::SwitchPane => app.switch_pane(),
+        Action::CopyToOtherPane => app.transfer_to_other_pane(TransferKind::Copy),
+        Action::MoveToOtherPane => app.transfer_to_other_pane(TransferKind::Move),
+        Action::Copy => app.yank_to_register(TransferKind::Copy),
+        Action::Cut => app.yank_to_register(TransferKind::Move),
+        Action::Paste => app.paste(),
+        Action::CancelOperation => app.cancel_operation(),
+        Action::Jump => app.start_jump(),
+        Action::JumpNext => app.jump_next(),
+        Action::JumpPrev => app.jump_prev(),
+        Action::SetBookmark => app.start_set_bookmark(),
+        Action::JumpBookmark => app.start_jump_bookmark(),
+        Action::Breadcrumb => app.start_breadcrumb(),
+        Action::CommandPalette => app.start_command(),
+        Action::DismissNotifications => app.toasts.dismiss_all(),
+        Action::Help => app.show_help(),
+        _ => {}
+    }
+}
+
+/// プレビューでのキー操作
+fn run_preview_action(app: &mut App, action: Action) {
+    let half = (app.preview_height / 2).max(1);
+    let page = app.preview_height.saturating_sub(2);
+    match action {
+        Action::PreviewBack => app.exit_preview(),
+        Action::ScrollDown => app.scroll_preview_down(1),
+        Action::ScrollUp => app.scroll_preview_up(1),
+        Action::HalfPageDown => app.scroll_preview_down(half),
+        Action::HalfPageUp => app.scroll_preview_up(half),
+        Action::PageDown => app.scroll_preview_down(page),
+        Action::PageUp => app.scroll_preview_up(page),
+        Action::PreviewTop => app.preview_scroll = 0,
+        Action::PreviewBottom => app.preview_go_to_bottom_or_count(),
+        Action::GotoLine => app.start_goto_line(),
+        Action::OpenEditor => app.open_in_editor(),
+        Action::ToggleWhitespace => app.toggle_whitespace(),
+        Action::Visual => app.start_visual(),
+        Action::ToggleFullscreen => app.fullscreen_preview = !app.fullscreen_preview,
+        Action::DismissNotifications => app.toasts.dismiss_all(),
+        _ => {}
+    }
 }
 
+/// How often the screen is redrawn while a spinner or progress bar moves
+const BUSY_TICK: Duration = Duration::from_millis(200);
+
+/// How often background results (watcher, config, sizes) are checked while
+/// idle; nothing is drawn unless one of them changed something
+const IDLE_POLL: Duration = Duration::from_millis(250);
+
 fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> io::Result<()> {
+    let mut dirty = true;
+    let mut last_tick = Instant::now();
     loop {
         // vim から戻ってきた場合は画面をクリアして再描画
         if app.needs_redraw {
             terminal.clear()?;
             app.needs_redraw = false;
+            dirty = true;
         }
 
-        terminal.draw(|f| ui::draw(f, app))?;
+        // 何も変わっていなければ描かない
+        if dirty {
+            terminal.draw(|f| ui::draw(f, app))?;
+            dirty = false;
+        }
 
-        if event::poll(Duration::from_millis(100))?
-            && let Event::Key(key) = event::read()?
+        let busy = app.is_busy();
+        let timeout = if busy {
+            BUSY_TICK.saturating_sub(last_tick.elapsed())
+        } else {
+            IDLE_POLL
+        };
+        let event = if event::poll(timeout)? {
+            Some(event::read()?)
+        } else {
+            None
+        };
+        // マウスの移動だけでは描き直さない
+        dirty |= match &event {
+            Some(Event::Mouse(mouse)) => mouse.kind != MouseEventKind::Moved,
+            Some(_) => true,
+            None => false,
+        };
+        // マウスは通知とヘッダーのパスのクリックだけ扱う
+        if let Some(Event::Mouse(mouse)) = &event
+            && mouse.kind == MouseEventKind::Down(MouseButton::Left)
+            && !app.click_toast(mouse.column, mouse.row)
+            && mouse.row == 0
+        {
+            app.click_header(mouse.column);
+        }
+        // キーを離したイベント（拡張キーボードプロトコル）は使わない
+        if let Some(Event::Key(key)) = event
+            && key.kind != KeyEventKind::Release
         {
-            app.status_message = None;
-
             match app.input_mode {
-                InputMode::Normal => match key.code {
-                    KeyCode::Char('q') => {
+                InputMode::Normal => {
+                    if is_ctrl_c(key) {
                         app.quit();
+                    } else if let Some(template) = app.keymap.command(key) {
+                        // [commands] のキーは既定の操作より優先する
+                        let template = template.to_string();
+                        app.run_custom_command(&template);
+                    } else if let Some(action) = app.keymap.action(KeyContext::Browser, key) {
+                        run_browser_action(app, action);
                     }
-                    KeyCode::Char('j') | KeyCode::Down => {
-                        app.move_down();
-                    }
-                    KeyCode::Char('k') | KeyCode::Up => {
-                        app.move_up();
+                }
+                InputMode::Help => {
+                    let half_page = (app.help_height / 2).max(1);
+                    match key.code {
+                        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
+                            app.close_help();
+                        }
+                        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.scroll_help_down(half_page);
+                        }
+                        KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.scroll_help_up(half_page);
+                        }
+                        KeyCode::Char('j') | KeyCode::Down => app.scroll_help_down(1),
+                        KeyCode::Char('k') | KeyCode::Up => app.scroll_help_up(1),
+                        KeyCode::PageDown => app.scroll_help_down(app.help_height),
+                        KeyCode::PageUp => app.scroll_help_up(app.help_height),
+                        KeyCode::Char('g') | KeyCode::Home => app.scroll_help_up(usize::MAX),
+                        KeyCode::Char('G') | KeyCode::End => app.scroll_help_down(usize::MAX),
+                        _ => {}
                     }
-                    KeyCode::Char('l') | KeyCode::Enter | KeyCode::Right => {
-                        app.enter();
+                }
+                InputMode::Confirm => app.confirm_key(key),
+                InputMode::Rename
+                | InputMode::NewFile
+                | InputMode::NewDir
+                | InputMode::Archive
+                | InputMode::ZoxideInput => match key.code {
+                    KeyCode::Enter => {
+                        app.confirm_name_input();
                     }
-                    KeyCode::Char('h') | KeyCode::Backspace | KeyCode::Left => {
-                        app.go_parent();
+                    KeyCode::Esc => {
+                        app.cancel_name_input();
                     }
-                    KeyCode::Char('g') => {
-                        app.go_to_top();
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.cancel_name_input();
                     }
-                    KeyCode::Char('G') => {
-                        app.go_to_bottom();
+                    _ => {
+                        if let Some(input) = app.active_input() {
+                            input.handle_key(key);
+                        }
                     }
-                    KeyCode::Char('e') => {
-                        app.open_in_editor();
+                },
+                InputMode::Command => match key.code {
+                    KeyCode::Enter => app.confirm_command(),
+                    KeyCode::Esc => app.cancel_command(),
+                    KeyCode::Tab => app.complete_command(true),
+                    KeyCode::BackTab => app.complete_command(false),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.cancel_command();
                     }
-                    KeyCode::Char('/') => {
-                        app.start_search();
+                    _ => app.command_key(key),
+                },
+                InputMode::DiskUsage => match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app.usage_move(1);
                     }
-                    KeyCode::Char('.') => {
-                        app.toggle_hidden();
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.usage_move(-1);
                     }
-                    KeyCode::Char('r') => {
-                        app.reload();
+                    KeyCode::Char('g') => {
+                        app.usage_move(isize::MIN / 2);
                     }
-                    KeyCode::Char('y') => {
-                        app.copy_path();
+                    KeyCode::Char('G') => {
+                        app.usage_move(isize::MAX / 2);
                     }
-                    KeyCode::Char('f') => {
-                        app.start_jump();
+                    KeyCode::Char('l') | KeyCode::Enter | KeyCode::Right => {
+                        app.usage_enter();
                     }
-                    KeyCode::Char(';') => {
-                        app.jump_next();
+                    KeyCode::Char('h') | KeyCode::Backspace | KeyCode::Left => {
+                        app.usage_parent();
                     }
-                    KeyCode::Char(',') => {
-                        app.jump_prev();
+                    KeyCode::Char('d') => {
+                        app.start_usage_delete();
                     }
-                    KeyCode::Char('?') => {
-                        app.show_help();
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('U') => {
+                        app.close_disk_usage();
                     }
                     KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                         app.quit();
                     }
                     _ => {}
                 },
-                InputMode::Help => match key.code {
-                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
-                        app.close_help();
-                    }
-                    _ => {}
-                },
-                InputMode::JumpInput => match key.code {
-                    KeyCode::Char(c) => {
-                        app.execute_jump(c);
+                InputMode::PathList => match key.code {
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.quit();
                     }
-                    KeyCode::Esc => {
-                        app.cancel_jump();
+                    KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.path_list_move(1);
                     }
-                    _ => {
-                        app.cancel_jump();
+                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.path_list_move(-1);
                     }
-                },
-                InputMode::Preview => match key.code {
-                    KeyCode::Char('q') | KeyCode::Esc | KeyCode::Char('h') | KeyCode::Left => {
-                        app.exit_preview();
+                    KeyCode::Down => {
+                        app.path_list_move(1);
                     }
-                    KeyCode::Char('j') | KeyCode::Down => {
-                        app.scroll_preview_down(1);
+                    KeyCode::Up => {
+                        app.path_list_move(-1);
                     }
-                    KeyCode::Char('k') | KeyCode::Up => {
-                        app.scroll_preview_up(1);
+                    KeyCode::PageDown => {
+                        app.path_list_move(10);
                     }
-                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        let half = app.preview_height / 2;
-                        app.scroll_preview_down(half.max(1));
+                    KeyCode::PageUp => {
+                        app.path_list_move(-10);
                     }
-                    KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        let half = app.preview_height / 2;
-                        app.scroll_preview_up(half.max(1));
+                    KeyCode::Enter => {
+                        app.open_path_list_entry();
                     }
-                    KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.scroll_preview_down(app.preview_height.saturating_sub(2));
+                    KeyCode::Esc => {
+                        app.close_path_list();
                     }
-                    KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.scroll_preview_up(app.preview_height.saturating_sub(2));
+                    KeyCode::Backspace => {
+                        app.path_filter_backspace();
                     }
-                    KeyCode::PageUp => {
-                        app.scroll_preview_up(app.preview_height.saturating_sub(2));
+                    KeyCode::Char(c) => {
+                        app.path_filter_char(c);
                     }
-                    KeyCode::PageDown => {
-                        app.scroll_preview_down(app.preview_height.saturating_sub(2));
+                    _ => {}
+                },
+                InputMode::Recent => match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app.recent_move(1);
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.recent_move(-1);
                     }
                     KeyCode::Char('g') => {
-                        app.preview_scroll = 0;
+                        app.recent_move(isize::MIN / 2);
                     }
                     KeyCode::Char('G') => {
-                        if let Some(ref content) = app.preview_content {
-                            app.preview_scroll =
-                                content.lines.len().saturating_sub(app.preview_height);
-                        }
+                        app.recent_move(isize::MAX / 2);
                     }
-                    KeyCode::Char('e') => {
-                        app.open_in_editor();
+                    KeyCode::Enter | KeyCode::Char('l') => {
+                        app.open_recent_file();
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('H') => {
+                        app.close_recent();
                     }
                     KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                         app.quit();
                     }
                     _ => {}
                 },
-                InputMode::SearchInput => match key.code {
+                InputMode::Trash => match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app.trash_move(1);
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.trash_move(-1);
+                    }
+                    KeyCode::Char('g') => {
+                        app.trash_move(isize::MIN / 2);
+                    }
+                    KeyCode::Char('G') => {
+                        app.trash_move(isize::MAX / 2);
+                    }
+                    KeyCode::Char('r') | KeyCode::Enter => {
+                        app.restore_trash_entry();
+                    }
+                    KeyCode::Char('d') => {
+                        app.start_purge();
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('T') => {
+                        app.close_trash();
+                    }
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.quit();
+                    }
+                    _ => {}
+                },
+                InputMode::Places => match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app.places_move(1);
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.places_move(-1);
+                    }
+                    KeyCode::Enter | KeyCode::Char('l') => {
+                        app.confirm_places();
+                    }
+                    KeyCode::Char(c @ '1'..='9') => {
+                        app.places_index(c as usize - '1' as usize);
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        app.cancel_places();
+                    }
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.cancel_places();
+                    }
+                    _ => {}
+                },
+                InputMode::OpenWith => match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app.open_with_move(1);
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.open_with_move(-1);
+                    }
                     KeyCode::Enter => {
-                        app.execute_search();
+                        app.confirm_open_with();
+                    }
+                    KeyCode::Char(c @ '1'..='9') => {
+                        app.open_with_index(c as usize - '1' as usize);
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        app.cancel_open_with();
+                    }
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.cancel_open_with();
+                    }
+                    _ => {}
+                },
+                InputMode::Chmod => match key.code {
+                    KeyCode::Enter => {
+                        app.confirm_chmod();
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        app.cancel_chmod();
+                    }
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.cancel_chmod();
+                    }
+                    KeyCode::Char(' ') => {
+                        app.chmod_toggle();
+                    }
+                    KeyCode::Char('h') | KeyCode::Left => {
+                        app.chmod_move(0, -1);
+                    }
+                    KeyCode::Char('l') | KeyCode::Right => {
+                        app.chmod_move(0, 1);
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.chmod_move(-1, 0);
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app.chmod_move(1, 0);
+                    }
+                    KeyCode::Backspace => {
+                        app.chmod_backspace();
+                    }
+                    KeyCode::Char(c) => {
+                        app.chmod_digit(c);
+                    }
+                    _ => {}
+                },
+                InputMode::MarkRange => match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app.move_down();
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.move_up();
+                    }
+                    KeyCode::Char('g') => {
+                        app.go_to_top();
+                    }
+                    KeyCode::Char('G') => {
+                        app.go_to_bottom();
+                    }
+                    KeyCode::Char('V') | KeyCode::Char(' ') | KeyCode::Enter => {
+                        app.confirm_mark_range();
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        app.cancel_mark_range();
+                    }
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.cancel_mark_range();
+                    }
+                    _ => {}
+                },
+                InputMode::FoldInput => match key.code {
+                    KeyCode::Char(c) => {
+                        app.execute_fold(c);
+                    }
+                    _ => {
+                        app.cancel_jump();
+                    }
+                },
+                InputMode::SetBookmark => match key.code {
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.set_bookmark(c);
+                    }
+                    _ => {
+                        app.cancel_jump();
+                    }
+                },
+                InputMode::JumpBookmark => match key.code {
+                    KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.jump_to_bookmark(c);
+                    }
+                    _ => {
+                        app.cancel_jump();
+                    }
+                },
+                InputMode::Breadcrumb => match key.code {
+                    KeyCode::Char(c @ '1'..='9') => {
+                        app.jump_up(c as usize - '0' as usize);
+                    }
+                    _ => {
+                        app.cancel_jump();
+                    }
+                },
+                InputMode::JumpInput => match key.code {
+                    KeyCode::Char(c) => {
+                        app.execute_jump(c);
                     }
                     KeyCode::Esc => {
-                        app.cancel_search();
+                        app.cancel_jump();
+                    }
+                    _ => {
+                        app.cancel_jump();
+                    }
+                },
+                InputMode::Preview => {
+                    let action = app.keymap.action(KeyContext::Preview, key);
+                    // 数字と G 以外のキーで行番号プレフィックスをリセット
+                    let digit = match key.code {
+                        KeyCode::Char(c) if key.modifiers.is_empty() => c.to_digit(10),
+                        _ => None,
+                    };
+                    if digit.is_none() && action != Some(Action::PreviewBottom) {
+                        app.preview_count = None;
+                    }
+
+                    if is_ctrl_c(key) {
+                        app.quit();
+                    } else if let Some(action) = action {
+                        run_preview_action(app, action);
+                    } else if let Some(digit) = digit {
+                        app.push_preview_count(digit);
+                    }
+                }
+                InputMode::GotoLine => match key.code {
+                    KeyCode::Enter => {
+                        app.confirm_goto_line();
+                    }
+                    KeyCode::Esc => {
+                        app.cancel_goto_line();
                     }
                     KeyCode::Backspace => {
-                        app.search_input_backspace();
+                        app.goto_input_backspace();
                     }
                     KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.cancel_search();
+                        app.cancel_goto_line();
                     }
                     KeyCode::Char(c) => {
-                        app.search_input_char(c);
+                        app.goto_input_char(c);
                     }
                     _ => {}
                 },
+                InputMode::Visual => match key.code {
+                    KeyCode::Esc | KeyCode::Char('v') | KeyCode::Char('q') => {
+                        app.cancel_visual();
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app.visual_move(1);
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.visual_move(-1);
+                    }
+                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        let half = (app.preview_height / 2).max(1);
+                        app.visual_move(half as isize);
+                    }
+                    KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        let half = (app.preview_height / 2).max(1);
+                        app.visual_move(-(half as isize));
+                    }
+                    KeyCode::Char('g') => {
+                        app.visual_move(isize::MIN);
+                    }
+                    KeyCode::Char('G') => {
+                        app.visual_move(isize::MAX);
+                    }
+                    KeyCode::Char('y') => {
+                        app.yank_visual();
+                    }
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.quit();
+                    }
+                    _ => {}
+                },
+                InputMode::SearchInput => match key.code {
+                    KeyCode::Enter => {
+                        app.execute_search();
+                    }
+                    KeyCode::Esc => {
+                        app.cancel_search();
+                    }
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.cancel_search();
+                    }
+                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.toggle_search_dirs_only();
+                    }
+                    KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.toggle_search_exact();
+                    }
+                    KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.toggle_search_hidden();
+                    }
+                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.toggle_search_no_ignore();
+                    }
+                    _ => {
+                        app.search_input.handle_key(key);
+                    }
+                },
                 InputMode::Searching => match key.code {
                     KeyCode::Esc | KeyCode::Char('q') => {
                         app.cancel_search();
@@ -479,7 +1516,44 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App)
 
         // 検索中の場合、結果をポーリング
         if app.input_mode == InputMode::Searching {
-            app.poll_search();
+            dirty |= app.poll_search();
+        }
+
+        // コピー・移動の進捗をポーリング
+        dirty |= app.poll_transfer();
+
+        // ディスク使用量の計測結果をポーリング
+        dirty |= app.poll_disk_usage();
+
+        // 移動したディレクトリを zoxide に記録
+        app.record_visit();
+
+        // 詳細表示のディレクトリサイズを反映
+        dirty |= app.poll_dir_sizes();
+
+        // ステータスバーのブランチ名・空き容量を反映
+        dirty |= app.poll_dir_info();
+
+        // 外部プレビューコマンドの出力を反映
+        dirty |= app.poll_external_preview();
+
+        // プレビュー中のファイルの git 差分を反映
+        dirty |= app.poll_diff_markers();
+
+        // config.toml の変更を反映
+        dirty |= app.poll_config();
+
+        // 外部での作成・削除・名前変更を反映
+        dirty |= app.poll_watcher();
+
+        // 時間切れの通知を消す
+        dirty |= app.toasts.expire(Instant::now());
+
+        // スピナー・進捗バーは一定間隔で進める（途中の進捗や結果もここで描く）
+        if busy && last_tick.elapsed() >= BUSY_TICK {
+            app.tick_spinner();
+            last_tick = Instant::now();
+            dirty = true;
         }
 
         if app.should_quit {
@@ -492,16 +1566,450 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App)
 
 /// Detect current shell from $SHELL environment variable
 fn detect_shell() -> String {
-    std::env::var("SHELL")
-        .unwrap_or_default()
-        .rsplit('/')
-        .next()
-        .unwrap_or("unknown")
-        .to_string()
+    let shell = std::env::var("SHELL").unwrap_or_default();
+    // Windows の PowerShell は SHELL を設定しない
+    if shell.is_empty() && std::env::var_os("PSModulePath").is_some() {
+        return "pwsh".to_string();
+    }
+    shell.rsplit('/').next().unwrap_or("unknown").to_string()
+}
+
+/// Run the same search `runs` times and report entries walked, time and throughput
+fn run_bench(
+    query: &str,
+    path: Option<PathBuf>,
+    runs: usize,
+    exact: bool,
+    json: bool,
+) -> io::Result<()> {
+    let base_dir = path.unwrap_or(std::env::current_dir()?);
+    if !base_dir.is_dir() {
+        eprintln!("Not a directory: {}", base_dir.display());
+        std::process::exit(1);
+    }
+    // find と同じ条件で測る（[find] の設定に従う）
+    let defaults = Config::load().find;
+    let options = SearchOptions {
+        dirs_only: false,
+        exact: exact || defaults.exact,
+        hidden: defaults.hidden,
+        no_ignore: defaults.no_ignore,
+    };
+
+    let mut searcher = FileSearcher::new();
+    let mut samples = Vec::new();
+    for run in 1..=runs.max(1) {
+        let start = Instant::now();
+        let matches = searcher.search(&base_dir, query, usize::MAX, options).len();
+        let elapsed = start.elapsed();
+        if !json {
+            println!(
+                "run {}: {} entries, {} matches, {:.1} ms",
+                run,
+                searcher.stats.scanned,
+                matches,
+                elapsed.as_secs_f64() * 1000.0
+            );
+        }
+        samples.push((searcher.stats.scanned, matches, elapsed));
+    }
+
+    let mut times: Vec<f64> = samples
+        .iter()
+        .map(|(_, _, elapsed)| elapsed.as_secs_f64())
+        .collect();
+    times.sort_by(f64::total_cmp);
+    let median = times[times.len() / 2];
+    let scanned = samples[0].0;
+    let throughput = if median > 0.0 {
+        scanned as f64 / median
+    } else {
+        0.0
+    };
+
+    if json {
+        let report = serde_json::json!({
+            "query": query,
+            "path": base_dir.to_string_lossy(),
+            "options": {
+                "exact": options.exact,
+                "hidden": options.hidden,
+                "no_ignore": options.no_ignore,
+            },
+            "runs": samples.iter().map(|(scanned, matches, elapsed)| serde_json::json!({
+                "scanned": scanned,
+                "matches": matches,
+                "elapsed_ms": elapsed.as_secs_f64() * 1000.0,
+            })).collect::<Vec<_>>(),
+            "min_ms": times[0] * 1000.0,
+            "median_ms": median * 1000.0,
+            "max_ms": times[times.len() - 1] * 1000.0,
+            "entries_per_sec": throughput,
+            "version": env!("CARGO_PKG_VERSION"),
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    println!(
+        "min {:.1} ms, median {:.1} ms, max {:.1} ms",
+        times[0] * 1000.0,
+        median * 1000.0,
+        times[times.len() - 1] * 1000.0
+    );
+    println!(
+        "{:.0} entries/s (median; exact={}, hidden={}, no_ignore={}, vfv {})",
+        throughput,
+        options.exact,
+        options.hidden,
+        options.no_ignore,
+        env!("CARGO_PKG_VERSION")
+    );
+    Ok(())
+}
+
+fn run_keys() {
+    let config = Config::load();
+    let (keymap, errors) = Keymap::new(&config.keys, &config.commands);
+    for error in errors {
+        eprintln!("Config [keys]/[commands]: {}", error);
+    }
+    for line in keymap.help_lines() {
+        println!("{}", line.trim_end());
+    }
+}
+
+/// Print each check; exits with 1 when any of them is an error
+fn run_doctor() {
+    let findings = doctor::run();
+    for finding in &findings {
+        let mut lines = finding.detail.lines();
+        let label = format!("[{}]", finding.level.label());
+        println!(
+            "{:<8}{}: {}",
+            label,
+            finding.name,
+            lines.next().unwrap_or_default()
+        );
+        for line in lines {
+            println!("{:<8}  {}", "", line);
+        }
+        if let Some(fix) = &finding.fix {
+            println!("{:<8}fix: {}", "", fix);
+        }
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|f| f.level == doctor::Level::Error)
+        .count();
+    let warnings = findings
+        .iter()
+        .filter(|f| f.level == doctor::Level::Warn)
+        .count();
+    println!();
+    println!("{} error(s), {} warning(s)", errors, warnings);
+    if errors > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn run_bookmarks(command: BookmarksCommand) -> io::Result<()> {
+    let mut bookmarks = Bookmarks::load();
+    match command {
+        BookmarksCommand::Add { name, path } => {
+            if name.is_empty() {
+                eprintln!("Bookmark name must not be empty");
+                std::process::exit(1);
+            }
+            let dir = path.unwrap_or(std::env::current_dir()?);
+            let dir = match dir.canonicalize() {
+                Ok(dir) if dir.is_dir() => dir,
+                _ => {
+                    eprintln!("Not a directory: {}", dir.display());
+                    std::process::exit(1);
+                }
+            };
+            bookmarks.set(&name, &dir)?;
+            println!("Bookmarked '{}' -> {}", name, dir.display());
+        }
+        BookmarksCommand::List { json } => {
+            if json {
+                let marks: serde_json::Map<String, serde_json::Value> = bookmarks
+                    .marks
+                    .iter()
+                    .map(|(name, dir)| (name.clone(), dir.to_string_lossy().into()))
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&marks)?);
+            } else {
+                for (name, dir) in &bookmarks.marks {
+                    println!("{}\t{}", name, dir.display());
+                }
+            }
+        }
+        BookmarksCommand::Remove { name } => {
+            if !bookmarks.remove(&name)? {
+                eprintln!("No bookmark '{}'", name);
+                std::process::exit(1);
+            }
+        }
+        BookmarksCommand::Jump { name } => match bookmarks.get(&name) {
+            Some(dir) if dir.is_dir() => println!("{}", dir.display()),
+            Some(dir) => {
+                eprintln!("Bookmark '{}' not found: {}", name, dir.display());
+                std::process::exit(1);
+            }
+            None => {
+                eprintln!("No bookmark '{}'", name);
+                std::process::exit(1);
+            }
+        },
+    }
+    Ok(())
+}
+
+fn run_config(command: ConfigCommand) -> io::Result<()> {
+    match command {
+        ConfigCommand::Get { key } => {
+            let config = Config::load_with_result().unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+            match config::lookup(&config, key.as_deref()) {
+                // 文字列は引用符なしで出力（スクリプトで使いやすくする）
+                Ok(toml::Value::String(s)) => println!("{}", s),
+                Ok(toml::Value::Table(table)) => match toml::to_string(&table) {
+                    Ok(s) => print!("{}", s),
+                    Err(e) => {
+                        eprintln!("Failed to serialize config: {}", e);
+                        std::process::exit(1);
+                    }
+                },
+                Ok(value) => println!("{}", value),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        ConfigCommand::Schema => match serde_json::to_string_pretty(&schema::config_schema()) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize schema: {}", e);
+                std::process::exit(1);
+            }
+        },
+        ConfigCommand::Check => {
+            let config_path = Config::config_path();
+            if !config_path.exists() {
+                // 明示的に指定したファイルがない場合はエラー
+                if let Err(e) = Config::load_with_result() {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+                println!(
+                    "No config file at {} (defaults in use)",
+                    config_path.display()
+                );
+                return Ok(());
+            }
+            let content = std::fs::read_to_string(&config_path)?;
+            let problems = config::check(&content);
+            if problems.is_empty() {
+                println!("OK: {}", config_path.display());
+                return Ok(());
+            }
+            eprintln!("{}:", config_path.display());
+            for problem in &problems {
+                eprintln!("  - {}", problem);
+            }
+            std::process::exit(1);
+        }
+        ConfigCommand::Set { key, value } => {
+            let config_path = Config::config_path();
+            let content = match std::fs::read_to_string(&config_path) {
+                Ok(content) => content,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e),
+            };
+            match config::set_value(&content, &key, &value) {
+                Ok(updated) => {
+                    if let Some(parent) = config_path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&config_path, updated)?;
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// List a directory the way the file browser shows it (config sort order
+/// and `browser_ignore` apply)
+fn run_open(file: &Path, with: Option<&str>, print: bool) -> io::Result<()> {
+    if !file.exists() {
+        eprintln!("No such file: {}", file.display());
+        std::process::exit(1);
+    }
+    let config = Config::load();
+    let opener = match with {
+        Some(name) => opener::openers(&config.openers)
+            .into_iter()
+            .find(|opener| opener.name == name),
+        None => opener::resolve(&config.extension_openers, file),
+    };
+    let Some(opener) = opener else {
+        match with {
+            Some(name) => eprintln!("No opener named '{}' in [openers]", name),
+            None => eprintln!("No opener for {} on this platform", file.display()),
+        }
+        std::process::exit(1);
+    };
+    let Some(mut cmd) = opener::build_command(&opener.command, file) else {
+        eprintln!("Empty command for '{}'", opener.name);
+        std::process::exit(1);
+    };
+
+    if print {
+        let line: Vec<String> = std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|part| commands::shell_quote(&part.to_string_lossy()))
+            .collect();
+        println!("{}", line.join(" "));
+        return Ok(());
+    }
+    if opener.detach {
+        if let Err(e) = editor::spawn_detached(&mut cmd, &opener.name) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let status = cmd.status().unwrap_or_else(|e| {
+        eprintln!("Failed to run '{}': {}", opener.name, e);
+        std::process::exit(1);
+    });
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+fn run_ls(path: Option<PathBuf>, all: bool, json: bool, compact: bool) -> io::Result<()> {
+    let dir = path.unwrap_or(std::env::current_dir()?);
+    if !dir.is_dir() {
+        eprintln!("Not a directory: {}", dir.display());
+        std::process::exit(1);
+    }
+
+    let config = Config::load();
+    let mut browser = FileBrowser::new(&dir, config.show_hidden || all);
+    browser.natural_sort = config.natural_sort;
+    if let Err(e) = browser.set_ignore(&config.browser_ignore) {
+        eprintln!("Invalid browser_ignore pattern: {}", e);
+    }
+    browser.set_sort(config.sort, config.sort_reverse);
+
+    if !json {
+        for entry in &browser.entries {
+            let modified = entry
+                .modified
+                .map(|time| {
+                    chrono::DateTime::<chrono::Local>::from(time)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_else(|| "-".repeat(16));
+            let size = if entry.is_dir {
+                "-".to_string()
+            } else {
+                ui::format_size(entry.size)
+            };
+            let mut name = entry.name.clone();
+            if entry.is_dir {
+                name.push('/');
+            }
+            if let Some(ref target) = entry.link_target {
+                name = format!("{} -> {}", name, target.display());
+            }
+            println!(
+                "{} {:>9} {} {}",
+                entry.permission_string(),
+                size,
+                modified,
+                name
+            );
+        }
+        return Ok(());
+    }
+
+    let entries: Vec<serde_json::Value> = browser
+        .entries
+        .iter()
+        .map(|entry| {
+            let kind = if entry.link_target.is_some() {
+                "symlink"
+            } else if entry.is_dir {
+                "dir"
+            } else {
+                "file"
+            };
+            serde_json::json!({
+                "name": entry.name,
+                "path": entry.path.to_string_lossy(),
+                "type": kind,
+                "size": entry.size,
+                "mtime": entry
+                    .modified
+                    .map(|time| chrono::DateTime::<chrono::Local>::from(time).to_rfc3339()),
+                "permissions": entry.permission_string(),
+                "mode": entry.mode.map(|mode| format!("{:04o}", mode & 0o7777)),
+                "target": entry.link_target.as_ref().map(|t| t.to_string_lossy()),
+            })
+        })
+        .collect();
+    let output = if compact {
+        serde_json::to_string(&entries)
+    } else {
+        serde_json::to_string_pretty(&entries)
+    };
+    match output {
+        Ok(s) => println!("{}", s),
+        Err(e) => {
+            eprintln!("Failed to serialize JSON: {}", e);
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// Completion script for `shell`, generated from the CLI definition
+fn completion_script(shell: CompletionShell) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    let mut cmd = Cli::command();
+    let shell = match shell {
+        CompletionShell::Bash => Shell::Bash,
+        CompletionShell::Zsh => Shell::Zsh,
+        CompletionShell::Fish => Shell::Fish,
+        CompletionShell::PowerShell => Shell::PowerShell,
+        CompletionShell::Elvish => Shell::Elvish,
+        // nushell は clap_complete 本体ではなく別クレートで生成する
+        CompletionShell::Nushell => {
+            let generator = clap_complete_nushell::Nushell;
+            clap_complete::generate(generator, &mut cmd, "vfv", &mut buffer);
+            return buffer;
+        }
+    };
+    clap_complete::generate(shell, &mut cmd, "vfv", &mut buffer);
+    buffer
 }
 
 /// Initialize configuration, shell completions, and man page
-fn run_init(force: bool) -> io::Result<()> {
+fn run_init(force: bool, dry_run: bool) -> io::Result<()> {
     let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
     let shell = detect_shell();
 
@@ -511,29 +2019,90 @@ fn run_init(force: bool) -> io::Result<()> {
     // 1. Config file (all shells)
     let config_path = Config::config_path();
     if !config_path.exists() || force {
-        if let Some(parent) = config_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
         let default_config = r#"# vfv configuration file
 # See https://github.com/noumi0k/vive-file-viewer for more information
 
-# Editor command to use when pressing 'e'
-editor = "vim"
+# Config format version
+version = 1
+
+# Editor command to use when pressing 'e' (empty: $VISUAL, then $EDITOR,
+# then vim/nvim/nano/vi, or notepad on Windows)
+editor = ""
+# Arguments before the file path; {file}, {line} and {dir} are filled in
+# and then the path is not added at the end, e.g. ["--goto", "{file}:{line}"]
 editor_args = []
+# Start the editor in the background without suspending vfv (GUI editors)
+editor_detach = false
 
 # Show hidden files by default
 show_hidden = false
 
+# Hide names matching these globs from the file list
+browser_ignore = []
+
 # Maximum lines to preview (for performance)
 preview_max_lines = 1000
 
-# Syntax highlighting theme
-# Options: "base16-ocean.dark", "base16-eighties.dark",
-#          "base16-mocha.dark", "Solarized (dark)", "Solarized (light)"
-theme = "base16-ocean.dark"
+# Maximum bytes to read for preview (larger files are truncated)
+preview_max_bytes = 10485760
+
+# Show the preview pane beside the file list at startup (toggle with v)
+preview_pane = false
+# Width of the preview pane in percent (10-90)
+preview_pane_ratio = 50
+# Width of the left pane in dual-pane mode in percent (10-90)
+dual_pane_ratio = 50
+
+# Below this terminal width, show one pane, a shortened path and short hints
+narrow_width = 60
+
+# Number of columns a tab expands to in preview
+tab_width = 4
+
+# File list icons: "plain" or "nerd" (requires a Nerd Font)
+icons = "plain"
+
+# Initial sort order: "name", "size", "mtime" or "ext"
+sort = "name"
+sort_reverse = false
+
+# Sort numbered names numerically ("file2" before "file10")
+natural_sort = false
+
+# Record directories visited in vfv in zoxide
+zoxide = false
+
+# Capture the mouse (click a directory in the header path to go there);
+# off by default so the terminal's text selection keeps working
+mouse = false
+
+# Use the kitty keyboard protocol where supported (shift-enter, ctrl-i apart
+# from tab)
+keyboard_protocol = true
+
+# Move deleted files to the trash (false = delete permanently)
+delete_to_trash = true
+
+# UI color preset: "dark", "light" (for light terminal backgrounds),
+# "high-contrast" or "monochrome"; it also picks a matching syntax theme
+ui_theme = "dark"
+
+# Syntax highlighting theme (overrides the one ui_theme picks)
+# Options: "base16-ocean.dark", "base16-eighties.dark", "base16-mocha.dark",
+#          "base16-ocean.light", "InspiredGitHub", "Solarized (dark)", "Solarized (light)"
+# theme = "base16-ocean.dark"
+
+# Borders ("plain", "rounded", "thick" or "none"), title alignment and the
+# selected row's background (the ui_theme preset's unless set), boldness
+# and prefix
+[style]
+border = "plain"
+title_alignment = "left"
+# highlight_bg = "blue"
+highlight_bold = true
+highlight_symbol = ""
 "#;
-        std::fs::write(&config_path, default_config)?;
-        println!("Created: {}", config_path.display());
+        init_create(&config_path, default_config.as_bytes(), dry_run)?;
     } else {
         println!(
             "Exists:  {} (use --force to overwrite)",
@@ -541,51 +2110,84 @@ theme = "base16-ocean.dark"
         );
     }
 
-    // 2. Man page (all shells)
+    // 2. Man pages for vfv and each subcommand (all shells)
     let man_dir = PathBuf::from(&home).join(".local/share/man/man1");
-    let man_path = man_dir.join("vfv.1");
-    if !man_path.exists() || force {
-        std::fs::create_dir_all(&man_dir)?;
-        let cmd = Cli::command();
-        let man = clap_mangen::Man::new(cmd);
-        let mut buffer = Vec::new();
-        man.render(&mut buffer)
-            .expect("Failed to generate man page");
-        std::fs::write(&man_path, buffer)?;
-        println!("Created: {}", man_path.display());
-    } else {
-        println!("Exists:  {} (use --force to overwrite)", man_path.display());
+    for (file, page) in man_pages() {
+        let man_path = man_dir.join(file);
+        if !man_path.exists() || force {
+            init_create(&man_path, &page, dry_run)?;
+        } else {
+            println!("Exists:  {} (use --force to overwrite)", man_path.display());
+        }
     }
 
     // 3. Shell-specific setup
     match shell.as_str() {
-        "zsh" => setup_zsh(&home, force)?,
-        "bash" => setup_bash(&home, force)?,
-        "fish" => setup_fish(&home, force)?,
+        "zsh" => setup_zsh(&home, force, dry_run)?,
+        "bash" => setup_bash(&home, force, dry_run)?,
+        "fish" => setup_fish(&home, force, dry_run)?,
+        "nu" => setup_nushell(&home, force, dry_run)?,
+        "pwsh" | "powershell" => setup_powershell(&home, force, dry_run)?,
+        "elvish" => setup_elvish(&home, force, dry_run)?,
         _ => {
             println!();
             println!("Shell '{}' is not supported for auto-setup.", shell);
             println!("Manual setup:");
-            println!(
-                "  - Completions: Copy from https://github.com/noumi0k/vive-file-viewer/tree/main/completions"
-            );
+            println!("  - Completions: vfv completions <bash|zsh|fish|powershell|elvish|nushell>");
             println!("  - Man page: Add to MANPATH: $HOME/.local/share/man");
         }
     }
 
+    if dry_run {
+        println!();
+        println!("Dry run: nothing was changed.");
+    }
+
+    Ok(())
+}
+
+/// Create (or overwrite) a file for init; a dry run only reports it
+fn init_create(path: &Path, content: &[u8], dry_run: bool) -> io::Result<()> {
+    if dry_run {
+        println!("Would create: {}", path.display());
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    println!("Created: {}", path.display());
+    Ok(())
+}
+
+/// Write an rc file with `added` lines injected; a dry run only prints those lines
+fn init_update(path: &Path, content: &str, added: &[&str], dry_run: bool) -> io::Result<()> {
+    if dry_run {
+        println!("Would update: {}", path.display());
+        for line in added {
+            println!("  + {}", line);
+        }
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    println!("Updated: {}", path.display());
     Ok(())
 }
 
 /// Setup for zsh
-fn setup_zsh(home: &str, force: bool) -> io::Result<()> {
+fn setup_zsh(home: &str, force: bool, dry_run: bool) -> io::Result<()> {
     // Install completion script
     let zfunc_dir = PathBuf::from(home).join(".zfunc");
     let completion_path = zfunc_dir.join("_vfv");
     if !completion_path.exists() || force {
-        std::fs::create_dir_all(&zfunc_dir)?;
-        let completion_script = include_str!("../completions/_vfv");
-        std::fs::write(&completion_path, completion_script)?;
-        println!("Created: {}", completion_path.display());
+        init_create(
+            &completion_path,
+            &completion_script(CompletionShell::Zsh),
+            dry_run,
+        )?;
     } else {
         println!(
             "Exists:  {} (use --force to overwrite)",
@@ -631,29 +2233,32 @@ fn setup_zsh(home: &str, force: bool) -> io::Result<()> {
                 }
             }
 
-            std::fs::write(&zshrc_path, new_lines.join("\n") + "\n")?;
-            println!("Updated: {}", zshrc_path.display());
+            let added: Vec<&str> = std::iter::once("# vfv setup").chain(updates).collect();
+            init_update(&zshrc_path, &(new_lines.join("\n") + "\n"), &added, dry_run)?;
         } else {
             println!("OK:      {} (already configured)", zshrc_path.display());
         }
     }
 
-    println!();
-    println!("Done! Restart your shell or run: source ~/.zshrc");
+    if !dry_run {
+        println!();
+        println!("Done! Restart your shell or run: source ~/.zshrc");
+    }
 
     Ok(())
 }
 
 /// Setup for bash
-fn setup_bash(home: &str, force: bool) -> io::Result<()> {
+fn setup_bash(home: &str, force: bool, dry_run: bool) -> io::Result<()> {
     // Install completion script
     let bash_completion_dir = PathBuf::from(home).join(".local/share/bash-completion/completions");
     let completion_path = bash_completion_dir.join("vfv");
     if !completion_path.exists() || force {
-        std::fs::create_dir_all(&bash_completion_dir)?;
-        let completion_script = include_str!("../completions/vfv.bash");
-        std::fs::write(&completion_path, completion_script)?;
-        println!("Created: {}", completion_path.display());
+        init_create(
+            &completion_path,
+            &completion_script(CompletionShell::Bash),
+            dry_run,
+        )?;
     } else {
         println!(
             "Exists:  {} (use --force to overwrite)",
@@ -684,29 +2289,32 @@ fn setup_bash(home: &str, force: bool) -> io::Result<()> {
                 new_content.push_str(update);
                 new_content.push('\n');
             }
-            std::fs::write(&bashrc_path, new_content)?;
-            println!("Updated: {}", bashrc_path.display());
+            let added: Vec<&str> = std::iter::once("# vfv setup").chain(updates).collect();
+            init_update(&bashrc_path, &new_content, &added, dry_run)?;
         } else {
             println!("OK:      {} (already configured)", bashrc_path.display());
         }
     }
 
-    println!();
-    println!("Done! Restart your shell or run: source ~/.bashrc");
+    if !dry_run {
+        println!();
+        println!("Done! Restart your shell or run: source ~/.bashrc");
+    }
 
     Ok(())
 }
 
 /// Setup for fish
-fn setup_fish(home: &str, force: bool) -> io::Result<()> {
+fn setup_fish(home: &str, force: bool, dry_run: bool) -> io::Result<()> {
     // Install completion script
     let fish_completion_dir = PathBuf::from(home).join(".config/fish/completions");
     let completion_path = fish_completion_dir.join("vfv.fish");
     if !completion_path.exists() || force {
-        std::fs::create_dir_all(&fish_completion_dir)?;
-        let completion_script = include_str!("../completions/vfv.fish");
-        std::fs::write(&completion_path, completion_script)?;
-        println!("Created: {}", completion_path.display());
+        init_create(
+            &completion_path,
+            &completion_script(CompletionShell::Fish),
+            dry_run,
+        )?;
     } else {
         println!(
             "Exists:  {} (use --force to overwrite)",
@@ -716,8 +2324,6 @@ fn setup_fish(home: &str, force: bool) -> io::Result<()> {
 
     // Update config.fish for MANPATH
     let config_fish_path = PathBuf::from(home).join(".config/fish/config.fish");
-    let config_fish_dir = PathBuf::from(home).join(".config/fish");
-    std::fs::create_dir_all(&config_fish_dir)?;
 
     let config_content = if config_fish_path.exists() {
         std::fs::read_to_string(&config_fish_path)?
@@ -730,10 +2336,16 @@ fn setup_fish(home: &str, force: bool) -> io::Result<()> {
         if !new_content.is_empty() && !new_content.ends_with('\n') {
             new_content.push('\n');
         }
-        new_content.push_str("\n# vfv setup\n");
-        new_content.push_str("set -gx MANPATH $HOME/.local/share/man $MANPATH\n");
-        std::fs::write(&config_fish_path, new_content)?;
-        println!("Updated: {}", config_fish_path.display());
+        let added = [
+            "# vfv setup",
+            "set -gx MANPATH $HOME/.local/share/man $MANPATH",
+        ];
+        new_content.push('\n');
+        for line in added {
+            new_content.push_str(line);
+            new_content.push('\n');
+        }
+        init_update(&config_fish_path, &new_content, &added, dry_run)?;
     } else {
         println!(
             "OK:      {} (already configured)",
@@ -741,18 +2353,191 @@ fn setup_fish(home: &str, force: bool) -> io::Result<()> {
         );
     }
 
-    println!();
-    println!("Done! Restart your shell.");
+    if !dry_run {
+        println!();
+        println!("Done! Restart your shell.");
+    }
+
+    Ok(())
+}
+
+/// Setup for nushell
+fn setup_nushell(home: &str, force: bool, dry_run: bool) -> io::Result<()> {
+    let nu_dir = directories::BaseDirs::new()
+        .map(|dirs| dirs.config_dir().join("nushell"))
+        .unwrap_or_else(|| PathBuf::from(home).join(".config/nushell"));
+    let completion_path = nu_dir.join("completions/vfv.nu");
+    install_completion(&completion_path, CompletionShell::Nushell, force, dry_run)?;
+
+    let source = format!("source '{}'", completion_path.display());
+    let manpath =
+        "$env.MANPATH = $\"($env.HOME)/.local/share/man:($env.MANPATH? | default '')\"".to_string();
+    append_to_rc(
+        &nu_dir.join("config.nu"),
+        &[
+            ("completions/vfv.nu", source),
+            (".local/share/man", manpath),
+        ],
+        dry_run,
+    )?;
+
+    if !dry_run {
+        println!();
+        println!("Done! Restart nushell.");
+    }
 
     Ok(())
 }
 
+/// Setup for PowerShell
+fn setup_powershell(home: &str, force: bool, dry_run: bool) -> io::Result<()> {
+    // Windows はドキュメントフォルダ、それ以外は ~/.config/powershell にプロファイルがある
+    let profile_dir = if cfg!(windows) {
+        directories::UserDirs::new()
+            .and_then(|dirs| dirs.document_dir().map(|dir| dir.join("PowerShell")))
+            .unwrap_or_else(|| PathBuf::from(home).join("Documents/PowerShell"))
+    } else {
+        PathBuf::from(home).join(".config/powershell")
+    };
+    let completion_path = profile_dir.join("vfv-completions.ps1");
+    install_completion(
+        &completion_path,
+        CompletionShell::PowerShell,
+        force,
+        dry_run,
+    )?;
+
+    let mut updates = vec![(
+        "vfv-completions.ps1",
+        format!(". '{}'", completion_path.display()),
+    )];
+    if !cfg!(windows) {
+        updates.push((
+            ".local/share/man",
+            "$env:MANPATH = \"$HOME/.local/share/man:$env:MANPATH\"".to_string(),
+        ));
+    }
+    append_to_rc(
+        &profile_dir.join("Microsoft.PowerShell_profile.ps1"),
+        &updates,
+        dry_run,
+    )?;
+
+    if !dry_run {
+        println!();
+        println!("Done! Restart PowerShell or run: . $PROFILE");
+    }
+
+    Ok(())
+}
+
+/// Setup for elvish
+fn setup_elvish(home: &str, force: bool, dry_run: bool) -> io::Result<()> {
+    let elvish_dir = PathBuf::from(home).join(".config/elvish");
+    let completion_path = elvish_dir.join("lib/vfv-completions.elv");
+    install_completion(&completion_path, CompletionShell::Elvish, force, dry_run)?;
+
+    let source = format!("eval (slurp < '{}')", completion_path.display());
+    let manpath = "set-env MANPATH $E:HOME'/.local/share/man:'$E:MANPATH".to_string();
+    append_to_rc(
+        &elvish_dir.join("rc.elv"),
+        &[
+            ("vfv-completions.elv", source),
+            (".local/share/man", manpath),
+        ],
+        dry_run,
+    )?;
+
+    if !dry_run {
+        println!();
+        println!("Done! Restart elvish.");
+    }
+
+    Ok(())
+}
+
+/// Write a completion script unless it exists (or `force` is set)
+fn install_completion(
+    path: &Path,
+    shell: CompletionShell,
+    force: bool,
+    dry_run: bool,
+) -> io::Result<()> {
+    if !path.exists() || force {
+        init_create(path, &completion_script(shell), dry_run)
+    } else {
+        println!("Exists:  {} (use --force to overwrite)", path.display());
+        Ok(())
+    }
+}
+
+/// Append each `(marker, line)` whose marker is not in the rc file yet,
+/// creating the file when missing
+fn append_to_rc(path: &Path, updates: &[(&str, String)], dry_run: bool) -> io::Result<()> {
+    let content = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    let missing: Vec<&str> = updates
+        .iter()
+        .filter(|(marker, _)| !content.contains(marker))
+        .map(|(_, line)| line.as_str())
+        .collect();
+    if missing.is_empty() {
+        println!("OK:      {} (already configured)", path.display());
+        return Ok(());
+    }
+
+    let added: Vec<&str> = std::iter::once("# vfv setup").chain(missing).collect();
+    let mut new_content = content;
+    if !new_content.is_empty() {
+        if !new_content.ends_with('\n') {
+            new_content.push('\n');
+        }
+        new_content.push('\n');
+    }
+    for line in &added {
+        new_content.push_str(line);
+        new_content.push('\n');
+    }
+    init_update(path, &new_content, &added, dry_run)
+}
+
 /// Generate man page to stdout
-fn run_man_page() {
-    let cmd = Cli::command();
-    let man = clap_mangen::Man::new(cmd);
-    let mut buffer = Vec::new();
-    man.render(&mut buffer)
-        .expect("Failed to generate man page");
-    io::Write::write_all(&mut io::stdout(), &buffer).expect("Failed to write man page");
+fn run_man_page(command: Option<&str>) -> io::Result<()> {
+    let name = match command {
+        Some(command) => format!("vfv-{}.1", command.replace(' ', "-")),
+        None => "vfv.1".to_string(),
+    };
+    match man_pages().into_iter().find(|(file, _)| *file == name) {
+        Some((_, page)) => io::stdout().write_all(&page),
+        None => {
+            eprintln!("No man page for '{}'", command.unwrap_or_default());
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Man pages of vfv and every subcommand as `(file name, roff)`, e.g. `vfv-find.1`
+fn man_pages() -> Vec<(String, Vec<u8>)> {
+    fn collect(cmd: &clap::Command, pages: &mut Vec<(String, Vec<u8>)>) {
+        // サブコマンドのページにも "vfv 0.2.0" を表示する
+        let source = format!("vfv {}", env!("CARGO_PKG_VERSION"));
+        let man = clap_mangen::Man::new(cmd.clone()).source(source);
+        let mut buffer = Vec::new();
+        man.render(&mut buffer)
+            .expect("Failed to generate man page");
+        pages.push((man.get_filename(), buffer));
+        for sub in cmd.get_subcommands().filter(|s| !s.is_hide_set()) {
+            collect(sub, pages);
+        }
+    }
+
+    // build() でサブコマンドに "vfv-find" のような表示名が付く
+    let mut cmd = Cli::command().disable_help_subcommand(true);
+    cmd.build();
+    let mut pages = Vec::new();
+    collect(&cmd, &mut pages);
+    pages
 }
@@ -0,0 +1,102 @@
+use serde_json::Value;
+
+/// Kind of a Jupyter notebook cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    Markdown,
+    Code,
+    Raw,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cell {
+    pub kind: CellKind,
+    pub source: String,
+    pub execution_count: Option<u64>,
+}
+
+/// Parsed notebook with outputs stripped
+#[derive(Debug, Clone)]
+pub struct Notebook {
+    /// Kernel language used for code cells (e.g. "python")
+    pub language: String,
+    pub cells: Vec<Cell>,
+}
+
+impl Notebook {
+    /// Parse .ipynb JSON. Returns None when the content is not a notebook.
+    pub fn parse(text: &str) -> Option<Self> {
+        let json: Value = serde_json::from_str(text).ok()?;
+        let cells = json.get("cells")?.as_array()?;
+
+        let metadata = json.get("metadata");
+        let language = metadata
+            .and_then(|m| m.pointer("/kernelspec/language"))
+            .or_else(|| metadata.and_then(|m| m.pointer("/language_info/name")))
+            .and_then(Value::as_str)
+            .unwrap_or("python")
+            .to_string();
+
+        let cells = cells
+            .iter()
+            .map(|cell| {
+                let kind = match cell.get("cell_type").and_then(Value::as_str) {
+                    Some("markdown") => CellKind::Markdown,
+                    Some("code") => CellKind::Code,
+                    _ => CellKind::Raw,
+                };
+                Cell {
+                    kind,
+                    source: join_source(cell.get("source")),
+                    execution_count: cell.get("execution_count").and_then(Value::as_u64),
+                }
+            })
+            .collect();
+
+        Some(Self { language, cells })
+    }
+}
+
+/// Cell source may be a single string or a list of lines
+fn join_source(source: Option<&Value>) -> String {
+    match source {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_notebook_cells() {
+        let text = r##"{
+            "cells": [
+                {"cell_type": "markdown", "metadata": {}, "source": ["# Title\n", "text"]},
+                {"cell_type": "code", "execution_count": 3, "metadata": {},
+                 "outputs": [{"output_type": "stream", "text": ["out"]}],
+                 "source": "print(1)"}
+            ],
+            "metadata": {"kernelspec": {"language": "python", "name": "python3"}},
+            "nbformat": 4,
+            "nbformat_minor": 5
+        }"##;
+
+        let notebook = Notebook::parse(text).unwrap();
+        assert_eq!(notebook.language, "python");
+        assert_eq!(notebook.cells.len(), 2);
+        assert_eq!(notebook.cells[0].kind, CellKind::Markdown);
+        assert_eq!(notebook.cells[0].source, "# Title\ntext");
+        assert_eq!(notebook.cells[1].kind, CellKind::Code);
+        assert_eq!(notebook.cells[1].source, "print(1)");
+        assert_eq!(notebook.cells[1].execution_count, Some(3));
+    }
+
+    #[test]
+    fn test_parse_non_notebook_returns_none() {
+        assert!(Notebook::parse("{\"foo\": 1}").is_none());
+        assert!(Notebook::parse("not json").is_none());
+    }
+}
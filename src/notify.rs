@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How serious a notification is; decides its color and how long it stays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    fn duration(self) -> Duration {
+        match self {
+            Level::Info => Duration::from_secs(3),
+            Level::Warn => Duration::from_secs(5),
+            Level::Error => Duration::from_secs(10),
+        }
+    }
+}
+
+/// A message shown in the corner until it expires or is dismissed
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub level: Level,
+    pub text: String,
+    expires: Instant,
+}
+
+/// Toasts shown at most at once; older ones give way, errors last
+const MAX_TOASTS: usize = 4;
+
+/// Queue of timed notifications, oldest first
+#[derive(Debug, Default)]
+pub struct Toasts {
+    queue: VecDeque<Toast>,
+}
+
+impl Toasts {
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(Level::Info, text);
+    }
+
+    pub fn warn(&mut self, text: impl Into<String>) {
+        self.push(Level::Warn, text);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Level::Error, text);
+    }
+
+    pub fn push(&mut self, level: Level, text: impl Into<String>) {
+        let text = text.into();
+        let expires = Instant::now() + level.duration();
+        // 同じ通知が続いたら出し直さず表示時間だけ延ばす
+        if let Some(last) = self.queue.back_mut()
+            && last.level == level
+            && last.text == text
+        {
+            last.expires = expires;
+            return;
+        }
+        self.queue.push_back(Toast {
+            level,
+            text,
+            expires,
+        });
+        // 溢れたら古いものから消す（エラーはなるべく残す）
+        while self.queue.len() > MAX_TOASTS {
+            let index = self
+                .queue
+                .iter()
+                .position(|toast| toast.level != Level::Error)
+                .unwrap_or(0);
+            self.queue.remove(index);
+        }
+    }
+
+    /// Drop the toasts whose time is up; true when any was dropped
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let before = self.queue.len();
+        self.queue.retain(|toast| toast.expires > now);
+        self.queue.len() != before
+    }
+
+    pub fn dismiss(&mut self, index: usize) {
+        self.queue.remove(index);
+    }
+
+    pub fn dismiss_all(&mut self) {
+        self.queue.clear();
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Toast> {
+        self.queue.iter()
+    }
+
+    /// Text of the newest toast
+    #[cfg(test)]
+    pub fn latest(&self) -> Option<&str> {
+        self.queue.back().map(|toast| toast.text.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expire_by_level() {
+        let mut toasts = Toasts::default();
+        toasts.info("Copied");
+        toasts.error("Failed to delete a");
+        assert!(!toasts.expire(Instant::now()));
+
+        assert!(toasts.expire(Instant::now() + Duration::from_secs(4)));
+        assert_eq!(toasts.latest(), Some("Failed to delete a"));
+        toasts.expire(Instant::now() + Duration::from_secs(11));
+        assert!(toasts.is_empty());
+    }
+
+    #[test]
+    fn test_overflow_keeps_errors() {
+        let mut toasts = Toasts::default();
+        toasts.error("first error");
+        for i in 0..MAX_TOASTS {
+            toasts.info(format!("info {}", i));
+        }
+        let texts: Vec<&str> = toasts.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts.len(), MAX_TOASTS);
+        assert_eq!(texts[0], "first error");
+        assert_eq!(texts[MAX_TOASTS - 1], format!("info {}", MAX_TOASTS - 1));
+
+        // 同じ通知は重ねない
+        toasts.info(format!("info {}", MAX_TOASTS - 1));
+        assert_eq!(toasts.iter().count(), MAX_TOASTS);
+        toasts.dismiss(0);
+        assert_eq!(
+            toasts.latest(),
+            Some(format!("info {}", MAX_TOASTS - 1).as_str())
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+/// An `[openers]` / `[extension_openers]` entry: a command line, or a table
+/// with the command and `detach = true` for GUI programs that should not
+/// suspend vfv
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OpenerSpec {
+    Command(String),
+    Table {
+        command: String,
+        #[serde(default)]
+        detach: bool,
+    },
+}
+
+impl OpenerSpec {
+    pub fn command(&self) -> &str {
+        match self {
+            OpenerSpec::Command(command) | OpenerSpec::Table { command, .. } => command,
+        }
+    }
+
+    pub fn detach(&self) -> bool {
+        matches!(self, OpenerSpec::Table { detach: true, .. })
+    }
+}
+
+/// A program the selected file can be opened with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opener {
+    pub name: String,
+    /// Command line; `{}` is replaced by the file path, otherwise it is appended
+    pub command: String,
+    /// Start it in the background instead of suspending the TUI until it exits
+    pub detach: bool,
+}
+
+/// Configured openers (in name order) followed by the system default opener
+pub fn openers(configured: &BTreeMap<String, OpenerSpec>) -> Vec<Opener> {
+    let mut openers: Vec<Opener> = configured
+        .iter()
+        .map(|(name, spec)| Opener {
+            name: name.clone(),
+            command: spec.command().to_string(),
+            detach: spec.detach(),
+        })
+        .collect();
+    openers.extend(system_default());
+    openers
+}
+
+/// Program for `path`: its `extension_openers` entry if one is configured,
+/// else the system default
+pub fn resolve(by_extension: &BTreeMap<String, OpenerSpec>, path: &Path) -> Option<Opener> {
+    for_extension(by_extension, path).or_else(system_default)
+}
+
+fn system_default() -> Option<Opener> {
+    system_opener().map(|command| Opener {
+        name: "System default".to_string(),
+        command: command.to_string(),
+        detach: false,
+    })
+}
+
+/// Opener configured for the file's extension (keys may start with a dot,
+/// and match case-insensitively like previewers)
+pub fn for_extension(by_extension: &BTreeMap<String, OpenerSpec>, path: &Path) -> Option<Opener> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    let spec = by_extension
+        .iter()
+        .find(|(key, _)| key.trim_start_matches('.').to_lowercase() == ext)
+        .map(|(_, spec)| spec)?;
+    Some(Opener {
+        name: spec.command().split_whitespace().next()?.to_string(),
+        command: spec.command().to_string(),
+        detach: spec.detach(),
+    })
+}
+
+/// Platform command that opens a file with its associated application
+pub fn system_opener() -> Option<&'static str> {
+    if cfg!(target_os = "macos") {
+        Some("open")
+    } else if cfg!(target_os = "windows") {
+        Some("explorer")
+    } else if cfg!(unix) {
+        Some("xdg-open")
+    } else {
+        None
+    }
+}
+
+/// Build the process for opening `path` with a command template
+pub fn build_command(template: &str, path: &Path) -> Option<Command> {
+    let path_str = path.to_string_lossy();
+    let mut parts = template.split_whitespace();
+    let mut cmd = Command::new(parts.next()?);
+    cmd.args(parts.map(|arg| arg.replace("{}", &path_str)));
+    if !template.contains("{}") {
+        cmd.arg(path);
+    }
+    Some(cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openers_lists_configured_then_system() {
+        let mut configured = BTreeMap::new();
+        configured.insert(
+            "less".to_string(),
+            OpenerSpec::Command("less -R".to_string()),
+        );
+        configured.insert(
+            "code".to_string(),
+            OpenerSpec::Table {
+                command: "code".to_string(),
+                detach: true,
+            },
+        );
+
+        let openers = openers(&configured);
+        let names: Vec<&str> = openers.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names[..2], ["code", "less"]);
+        assert!(openers[0].detach);
+        assert!(!openers[1].detach);
+        assert_eq!(openers.len(), 2 + system_opener().iter().count());
+    }
+
+    #[test]
+    fn test_for_extension() {
+        let mut by_extension = BTreeMap::new();
+        by_extension.insert(
+            "png".to_string(),
+            OpenerSpec::Command("feh --scale-down".to_string()),
+        );
+        by_extension.insert(
+            ".PDF".to_string(),
+            OpenerSpec::Table {
+                command: "zathura".to_string(),
+                detach: true,
+            },
+        );
+        by_extension.insert("txt".to_string(), OpenerSpec::Command(" ".to_string()));
+
+        let opener = for_extension(&by_extension, Path::new("photo.PNG")).unwrap();
+        assert_eq!(opener.name, "feh");
+        assert_eq!(opener.command, "feh --scale-down");
+        assert!(!opener.detach);
+        let opener = for_extension(&by_extension, Path::new("doc.pdf")).unwrap();
+        assert_eq!(opener.name, "zathura");
+        assert!(opener.detach);
+        assert!(for_extension(&by_extension, Path::new("notes.txt")).is_none());
+        assert!(for_extension(&by_extension, Path::new("Makefile")).is_none());
+    }
+
+    #[test]
+    fn test_resolve_falls_back_to_system() {
+        let mut by_extension = BTreeMap::new();
+        by_extension.insert("png".to_string(), OpenerSpec::Command("feh".to_string()));
+
+        let opener = resolve(&by_extension, Path::new("a.png")).unwrap();
+        assert_eq!(opener.command, "feh");
+        assert_eq!(
+            resolve(&by_extension, Path::new("a.txt")).map(|o| o.command),
+            system_opener().map(str::to_string)
+        );
+    }
+
+    #[test]
+    fn test_build_command() {
+        let cmd = build_command("hexyl --border none {}", Path::new("a b.bin")).unwrap();
+        assert_eq!(cmd.get_program(), "hexyl");
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(args, ["--border", "none", "a b.bin"]);
+
+        let cmd = build_command("less -R", Path::new("file.txt")).unwrap();
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(args, ["-R", "file.txt"]);
+
+        assert!(build_command("  ", Path::new("file.txt")).is_none());
+    }
+}
@@ -0,0 +1,79 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// User and group names by id, looked up through the system's account
+/// databases (`getpwuid_r`/`getgrgid_r`, so NSS sources such as LDAP count
+/// too) and remembered for the session
+#[derive(Debug, Clone, Default)]
+pub struct Owners {
+    users: RefCell<HashMap<u32, String>>,
+    groups: RefCell<HashMap<u32, String>>,
+}
+
+impl Owners {
+    /// User name for `uid`, or the number itself when unknown (like `ls -l`)
+    pub fn user(&self, uid: u32) -> String {
+        self.users
+            .borrow_mut()
+            .entry(uid)
+            .or_insert_with(|| user_name(uid).unwrap_or_else(|| uid.to_string()))
+            .clone()
+    }
+
+    pub fn group(&self, gid: u32) -> String {
+        self.groups
+            .borrow_mut()
+            .entry(gid)
+            .or_insert_with(|| group_name(gid).unwrap_or_else(|| gid.to_string()))
+            .clone()
+    }
+}
+
+#[cfg(unix)]
+fn user_name(uid: u32) -> Option<String> {
+    use nix::unistd::{Uid, User};
+    User::from_uid(Uid::from_raw(uid))
+        .ok()
+        .flatten()
+        .map(|user| user.name)
+}
+
+#[cfg(unix)]
+fn group_name(gid: u32) -> Option<String> {
+    use nix::unistd::{Gid, Group};
+    Group::from_gid(Gid::from_raw(gid))
+        .ok()
+        .flatten()
+        .map(|group| group.name)
+}
+
+#[cfg(not(unix))]
+fn user_name(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+fn group_name(_gid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_known_ids_have_names() {
+        let owners = Owners::default();
+        assert_eq!(owners.user(0), "root");
+        // 一度引いた名前は覚えておく
+        assert!(owners.users.borrow().contains_key(&0));
+    }
+
+    #[test]
+    fn test_unknown_ids_fall_back_to_numbers() {
+        let owners = Owners::default();
+        assert_eq!(owners.user(4_000_000_000), "4000000000");
+        assert_eq!(owners.group(4_000_000_001), "4000000001");
+    }
+}
@@ -0,0 +1,319 @@
+use std::fs;
+use std::path::Path;
+
+use crate::file_browser::SortMode;
+use crate::preview::Previewer;
+use crate::ui_theme::UiTheme;
+
+/// Commands of the `:` prompt, with the usage shown for completion
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("cd", "cd <path>"),
+    ("mkdir", "mkdir <name>"),
+    ("touch", "touch <name>"),
+    ("sort", "sort name|size|mtime|ext"),
+    ("theme", "theme <preset or syntax theme>"),
+    ("set", "set <option>, no<option> or <option>!"),
+    ("reload", "reload"),
+    ("help", "help"),
+    ("quit", "quit"),
+];
+
+/// A parsed `:` command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Cd(String),
+    Mkdir(String),
+    Touch(String),
+    Sort(SortMode),
+    Theme(String),
+    /// `value` is `None` for `option!` (toggle)
+    Set {
+        option: SetOption,
+        value: Option<bool>,
+    },
+    Reload,
+    Help,
+    Quit,
+}
+
+/// On/off switches `:set` can change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOption {
+    ShowHidden,
+    Details,
+    PreviewPane,
+    DualPane,
+    Tree,
+    SortReverse,
+}
+
+impl SetOption {
+    pub const ALL: [SetOption; 6] = [
+        SetOption::ShowHidden,
+        SetOption::Details,
+        SetOption::PreviewPane,
+        SetOption::DualPane,
+        SetOption::Tree,
+        SetOption::SortReverse,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SetOption::ShowHidden => "show_hidden",
+            SetOption::Details => "details",
+            SetOption::PreviewPane => "preview_pane",
+            SetOption::DualPane => "dual_pane",
+            SetOption::Tree => "tree",
+            SetOption::SortReverse => "sort_reverse",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|option| option.name() == name)
+    }
+}
+
+/// Parse a command line (without the leading `:`)
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, arg) = match line.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (line, ""),
+    };
+    let Some(name) = resolve(name) else {
+        return Err(format!("Unknown command: {}", name));
+    };
+    let required = |what: &str| {
+        if arg.is_empty() {
+            Err(format!(":{} needs {}", name, what))
+        } else {
+            Ok(arg.to_string())
+        }
+    };
+
+    match name {
+        "cd" => Ok(Command::Cd(
+            if arg.is_empty() { "~" } else { arg }.to_string(),
+        )),
+        "mkdir" => required("a name").map(Command::Mkdir),
+        "touch" => required("a name").map(Command::Touch),
+        "sort" => SortMode::ALL
+            .into_iter()
+            .find(|sort| sort.label() == arg)
+            .map(Command::Sort)
+            .ok_or_else(|| format!("Unknown sort '{}' (name, size, mtime or ext)", arg)),
+        "theme" => required("a theme name").map(Command::Theme),
+        "set" => parse_set(arg),
+        "reload" => Ok(Command::Reload),
+        "help" => Ok(Command::Help),
+        "quit" | "q" => Ok(Command::Quit),
+        // COMMANDS に足したのにここを忘れたとき
+        _ => Err(format!("Unknown command: {}", name)),
+    }
+}
+
+/// Usage of the command being typed, as a hint for its arguments
+pub fn usage(line: &str) -> Option<&'static str> {
+    let (name, _) = line.trim_start().split_once(' ')?;
+    let name = resolve(name)?;
+    COMMANDS
+        .iter()
+        .find(|(command, _)| *command == name)
+        .map(|(_, usage)| *usage)
+}
+
+/// Full command name for `name` or an unambiguous prefix of it (`:q`, `:so`)
+fn resolve(name: &str) -> Option<&'static str> {
+    if name.is_empty() {
+        return None;
+    }
+    let mut matches = COMMANDS
+        .iter()
+        .map(|(command, _)| *command)
+        .filter(|command| command.starts_with(name));
+    let first = matches.next()?;
+    // 完全一致は他の候補があっても優先する
+    if first == name || matches.next().is_none() {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+fn parse_set(arg: &str) -> Result<Command, String> {
+    let (name, value) = if let Some(name) = arg.strip_suffix('!') {
+        (name, None)
+    } else if let Some(option) = SetOption::from_name(arg) {
+        return Ok(Command::Set {
+            option,
+            value: Some(true),
+        });
+    } else if let Some(name) = arg.strip_prefix("no") {
+        (name, Some(false))
+    } else {
+        (arg, Some(true))
+    };
+    SetOption::from_name(name)
+        .map(|option| Command::Set { option, value })
+        .ok_or_else(|| format!("Unknown option: {}", arg))
+}
+
+/// Candidates for the word under the cursor (the end of `line`): where that
+/// word starts, and the words that can replace it
+pub fn complete(line: &str, cwd: &Path) -> (usize, Vec<String>) {
+    let Some((name, arg)) = line.split_once(' ') else {
+        let names = COMMANDS
+            .iter()
+            .map(|(command, _)| command.to_string())
+            .filter(|command| command.starts_with(line))
+            .collect();
+        return (0, names);
+    };
+    let start = name.len() + 1 + (arg.len() - arg.trim_start().len());
+    let arg = arg.trim_start();
+
+    let words: Vec<String> = match resolve(name) {
+        Some("cd") => return (start, complete_path(arg, cwd, true)),
+        Some("mkdir") | Some("touch") => return (start, complete_path(arg, cwd, false)),
+        Some("sort") => SortMode::ALL
+            .iter()
+            .map(|sort| sort.label().to_string())
+            .collect(),
+        Some("theme") => {
+            let mut names: Vec<String> = UiTheme::ALL
+                .iter()
+                .map(|theme| theme.name().to_string())
+                .collect();
+            let mut syntax = Previewer::theme_names();
+            syntax.sort();
+            names.extend(syntax);
+            names
+        }
+        Some("set") => {
+            let prefix = if arg.starts_with("no") { "no" } else { "" };
+            SetOption::ALL
+                .iter()
+                .map(|option| format!("{}{}", prefix, option.name()))
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    let words = words.into_iter().filter(|w| w.starts_with(arg)).collect();
+    (start, words)
+}
+
+/// Paths below `cwd` (or `~`) starting with `arg`; directories end in `/`
+fn complete_path(arg: &str, cwd: &Path, dirs_only: bool) -> Vec<String> {
+    let (parent, prefix) = match arg.rfind('/') {
+        Some(slash) => (&arg[..=slash], &arg[slash + 1..]),
+        None => ("", arg),
+    };
+    let base = if parent == "~/" || parent.starts_with("~/") {
+        match directories::BaseDirs::new() {
+            Some(dirs) => dirs.home_dir().join(&parent[2..]),
+            None => return Vec::new(),
+        }
+    } else {
+        cwd.join(parent)
+    };
+    let Ok(entries) = fs::read_dir(&base) else {
+        return Vec::new();
+    };
+
+    let mut words: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // 隠しファイルは . から入力したときだけ候補にする
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let is_dir = entry.path().is_dir();
+            if dirs_only && !is_dir {
+                return None;
+            }
+            let slash = if is_dir { "/" } else { "" };
+            Some(format!("{}{}{}", parent, name, slash))
+        })
+        .collect();
+    words.sort();
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse("cd src"), Ok(Command::Cd("src".to_string())));
+        assert_eq!(parse("cd"), Ok(Command::Cd("~".to_string())));
+        assert_eq!(
+            parse("  mkdir  new dir "),
+            Ok(Command::Mkdir("new dir".to_string()))
+        );
+        assert_eq!(parse("sort mtime"), Ok(Command::Sort(SortMode::Modified)));
+        assert_eq!(parse("q"), Ok(Command::Quit));
+        assert!(parse("mkdir").unwrap_err().contains("needs a name"));
+        assert!(parse("sort age").is_err());
+        // 曖昧な省略形は受け付けない
+        assert!(parse("s").is_err());
+        assert!(parse("bogus").unwrap_err().contains("Unknown command"));
+        // 一覧にあるコマンドはどれも解釈できる
+        for (name, _) in COMMANDS {
+            let result = parse(name);
+            assert!(
+                !matches!(result, Err(ref e) if e.starts_with("Unknown command")),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_set() {
+        let set = |option, value| Ok(Command::Set { option, value });
+        assert_eq!(
+            parse("set show_hidden"),
+            set(SetOption::ShowHidden, Some(true))
+        );
+        assert_eq!(parse("set nodetails"), set(SetOption::Details, Some(false)));
+        assert_eq!(parse("set tree!"), set(SetOption::Tree, None));
+        assert!(parse("set nothing").is_err());
+    }
+
+    #[test]
+    fn test_usage_of_typed_command() {
+        assert_eq!(usage("so "), Some("sort name|size|mtime|ext"));
+        assert_eq!(usage("sort"), None);
+        assert_eq!(usage("nope x"), None);
+    }
+
+    #[test]
+    fn test_complete_commands_and_args() {
+        let cwd = Path::new(".");
+        assert_eq!(complete("s", cwd), (0, vec!["sort".into(), "set".into()]));
+        assert_eq!(complete("sort m", cwd), (5, vec!["mtime".into()]));
+        assert_eq!(complete("set nop", cwd), (4, vec!["nopreview_pane".into()]));
+        let (start, themes) = complete("theme high", cwd);
+        assert_eq!((start, themes), (6, vec!["high-contrast".into()]));
+    }
+
+    #[test]
+    fn test_complete_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("src/bin")).unwrap();
+        fs::create_dir(temp_dir.path().join(".secret")).unwrap();
+        fs::write(temp_dir.path().join("src/main.rs"), "").unwrap();
+        fs::write(temp_dir.path().join("setup.sh"), "").unwrap();
+
+        assert_eq!(complete("cd s", temp_dir.path()), (3, vec!["src/".into()]));
+        assert_eq!(
+            complete("touch src/", temp_dir.path()),
+            (6, vec!["src/bin/".into(), "src/main.rs".into()])
+        );
+        assert_eq!(complete("cd ", temp_dir.path()).1, vec!["src/"]);
+        assert_eq!(complete("cd .s", temp_dir.path()).1, vec![".secret/"]);
+    }
+}
@@ -0,0 +1,171 @@
+use std::path::{Path, PathBuf};
+
+/// A location offered in the places menu (home, mounted filesystems, drives)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Place {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Place {
+    fn new(path: PathBuf) -> Self {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string());
+        Self { name, path }
+    }
+}
+
+/// Home directory followed by the mounted filesystems of this platform
+pub fn list() -> Vec<Place> {
+    let mut places = Vec::new();
+    if let Some(dirs) = directories::BaseDirs::new() {
+        places.push(Place {
+            name: "Home".to_string(),
+            path: dirs.home_dir().to_path_buf(),
+        });
+    }
+    for place in mounts() {
+        if !places.iter().any(|p| p.path == place.path) {
+            places.push(place);
+        }
+    }
+    places
+}
+
+#[cfg(target_os = "linux")]
+fn mounts() -> Vec<Place> {
+    std::fs::read_to_string("/proc/self/mounts")
+        .map(|content| parse_mounts(&content))
+        .unwrap_or_else(|_| vec![Place::new(PathBuf::from("/"))])
+}
+
+#[cfg(target_os = "macos")]
+fn mounts() -> Vec<Place> {
+    let mut places = vec![Place::new(PathBuf::from("/"))];
+    if let Ok(entries) = std::fs::read_dir("/Volumes") {
+        let mut volumes: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            // 起動ディスクは / へのシンボリックリンクなので除く
+            .filter(|p| !p.is_symlink() && p.is_dir())
+            .collect();
+        volumes.sort();
+        places.extend(volumes.into_iter().map(Place::new));
+    }
+    places
+}
+
+#[cfg(windows)]
+fn mounts() -> Vec<Place> {
+    ('A'..='Z')
+        .map(|letter| PathBuf::from(format!("{}:\\", letter)))
+        .filter(|path| path.is_dir())
+        .map(|path| Place {
+            name: path.to_string_lossy().trim_end_matches('\\').to_string(),
+            path,
+        })
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn mounts() -> Vec<Place> {
+    vec![Place::new(PathBuf::from("/"))]
+}
+
+/// Network filesystems worth listing even though they have no block device
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const NETWORK_FS: &[&str] = &["nfs", "nfs4", "cifs", "smb3", "smbfs", "fuse.sshfs"];
+
+/// Pick user-facing mounts out of `/proc/self/mounts`: filesystems backed by
+/// a device or a network share, skipping snap images and system partitions
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_mounts(content: &str) -> Vec<Place> {
+    let mut places: Vec<Place> = Vec::new();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(device), Some(mount_point), Some(fs_type)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if !device.starts_with('/') && !NETWORK_FS.contains(&fs_type) {
+            continue;
+        }
+        let path = PathBuf::from(unescape(mount_point));
+        if is_system_mount(&path) || places.iter().any(|p| p.path == path) {
+            continue;
+        }
+        places.push(Place::new(path));
+    }
+    places
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn is_system_mount(path: &Path) -> bool {
+    ["/boot", "/snap", "/var/snap", "/proc", "/sys", "/dev"]
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+}
+
+/// Decode the octal escapes (`\040` for a space) used in the mounts table
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn unescape(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(pos) = rest.find('\\') {
+        out.push_str(&rest[..pos]);
+        let code = rest.get(pos + 1..pos + 4);
+        match code.and_then(|c| u8::from_str_radix(c, 8).ok()) {
+            Some(byte) => {
+                out.push(byte as char);
+                rest = &rest[pos + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[pos + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mounts() {
+        let content = "\
+proc /proc proc rw,nosuid 0 0
+/dev/nvme0n1p2 / ext4 rw,relatime 0 0
+/dev/nvme0n1p1 /boot/efi vfat rw 0 0
+tmpfs /run tmpfs rw 0 0
+/dev/loop3 /snap/core/123 squashfs ro 0 0
+/dev/sdb1 /media/me/USB\\040Stick vfat rw 0 0
+server:/export /mnt/nfs nfs4 rw 0 0
+/dev/nvme0n1p2 / ext4 rw,relatime 0 0
+";
+        let places = parse_mounts(content);
+        let paths: Vec<&Path> = places.iter().map(|p| p.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("/"),
+                Path::new("/media/me/USB Stick"),
+                Path::new("/mnt/nfs"),
+            ]
+        );
+        assert_eq!(places[0].name, "/");
+        assert_eq!(places[1].name, "USB Stick");
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("/a\\040b"), "/a b");
+        assert_eq!(unescape("/tab\\011x"), "/tab\tx");
+        assert_eq!(unescape("/odd\\x"), "/odd\\x");
+    }
+}
@@ -1,97 +1,208 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
 use syntect::easy::HighlightLines;
-use syntect::highlighting::{Style, ThemeSet};
-use syntect::parsing::SyntaxSet;
+use syntect::highlighting::{Color, Style, Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
 use syntect::util::LinesWithEndings;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::ansi;
+use crate::commands;
+use crate::git::DiffMarker;
+use crate::notebook::{CellKind, Notebook};
+
+/// Default byte limit for reading a file (10MB)
+const DEFAULT_MAX_BYTES: usize = 10 * 1024 * 1024;
 
 pub struct PreviewContent {
     pub lines: Vec<PreviewLine>,
+    /// Detected text encoding (None for non-text content)
+    pub encoding: Option<&'static str>,
+    /// The file was cut off at the byte limit
+    pub truncated: bool,
+    /// Line numbers are the file's own lines (not a notebook or the output
+    /// of an external previewer), so an editor can be opened at them
+    pub source_lines: bool,
+}
+
+impl PreviewContent {
+    /// Single-line placeholder content (directory, binary, errors)
+    fn message(text: &str) -> Self {
+        Self {
+            lines: vec![PreviewLine {
+                line_number: 0,
+                segments: vec![(Style::default(), text.to_string())],
+                text: text.to_string(),
+                diff: None,
+            }],
+            encoding: None,
+            truncated: false,
+            source_lines: false,
+        }
+    }
 }
 
 pub struct PreviewLine {
     pub line_number: usize,
     pub segments: Vec<(Style, String)>,
+    /// Original line text without the line ending (used for yanking)
+    pub text: String,
+    pub diff: Option<DiffMarker>,
+}
+
+impl PreviewLine {
+    /// Screen rows the line takes when wrapped at `width` columns. A wide
+    /// character (CJK, emoji) that would straddle the edge moves to the next
+    /// row, the same way the preview draws it.
+    pub fn rows(&self, width: usize) -> usize {
+        let mut rows = 1;
+        let mut column = 0;
+        for c in self
+            .segments
+            .iter()
+            .flat_map(|(_, text)| text.chars())
+            .filter(|c| !matches!(c, '\n' | '\r'))
+        {
+            let w = c.width().unwrap_or(0);
+            if column + w > width && column > 0 {
+                rows += 1;
+                column = 0;
+            }
+            column += w;
+        }
+        rows
+    }
 }
 
 pub struct Previewer {
     syntax_set: SyntaxSet,
     theme_set: ThemeSet,
     theme_name: String,
-    max_lines: usize,
+    /// Maximum number of lines loaded for a preview
+    pub max_lines: usize,
+    pub tab_width: usize,
+    /// Maximum number of bytes read from a file
+    pub max_bytes: usize,
+    pub show_whitespace: bool,
+    /// External preview commands keyed by file extension
+    pub external_previewers: BTreeMap<String, String>,
 }
 
 impl Previewer {
-    pub fn new(theme_name: &str, max_lines: usize) -> Self {
+    pub fn new(theme_name: &str, max_lines: usize, tab_width: usize) -> Self {
         Self {
             syntax_set: SyntaxSet::load_defaults_newlines(),
             theme_set: ThemeSet::load_defaults(),
             theme_name: theme_name.to_string(),
             max_lines,
+            tab_width,
+            max_bytes: DEFAULT_MAX_BYTES,
+            show_whitespace: false,
+            external_previewers: BTreeMap::new(),
         }
     }
 
     pub fn preview(&self, path: &Path) -> PreviewContent {
+        if path.is_symlink() && !path.exists() {
+            let target = std::fs::read_link(path)
+                .map(|t| t.display().to_string())
+                .unwrap_or_default();
+            return PreviewContent::message(&format!("[Broken symlink → {}]", target));
+        }
+
         if !path.is_file() {
-            return PreviewContent {
-                lines: vec![PreviewLine {
-                    line_number: 0,
-                    segments: vec![(Style::default(), "[Directory]".to_string())],
-                }],
-            };
+            return PreviewContent::message("[Directory]");
         }
 
         let file = match File::open(path) {
             Ok(f) => f,
-            Err(e) => {
-                return PreviewContent {
-                    lines: vec![PreviewLine {
-                        line_number: 0,
-                        segments: vec![(Style::default(), format!("Error reading file: {}", e))],
-                    }],
-                };
-            }
+            Err(e) => return PreviewContent::message(&format!("Error reading file: {}", e)),
         };
 
         let mut reader = BufReader::new(file);
 
         // Read first 8KB for binary detection
-        let mut header = vec![0u8; 8000];
-        let header_len = reader.read(&mut header).unwrap_or(0);
-        header.truncate(header_len);
+        let mut bytes = vec![0u8; 8000];
+        let header_len = reader.read(&mut bytes).unwrap_or(0);
+        bytes.truncate(header_len);
 
-        if is_binary(&header) {
-            return PreviewContent {
-                lines: vec![PreviewLine {
-                    line_number: 0,
-                    segments: vec![(Style::default(), "[Binary file]".to_string())],
-                }],
-            };
+        // UTF-16 files contain many null bytes, so check the BOM before binary detection
+        if Encoding::for_bom(&bytes).is_none() && is_binary(&bytes) {
+            return PreviewContent::message("[Binary file]");
         }
 
-        // Convert header to string and read remaining lines up to max_lines
-        // Use byte limit (10MB) to prevent memory issues with long lines
-        const MAX_BYTES: usize = 10 * 1024 * 1024;
-        let mut total_bytes = header_len;
-        let mut text = String::from_utf8_lossy(&header).into_owned();
+        // Notebooks are JSON, so they must be read whole to be parsed
+        let is_notebook = path.extension().is_some_and(|ext| ext == "ipynb");
+        let line_limit = if is_notebook {
+            usize::MAX
+        } else {
+            self.max_lines
+        };
 
-        // Read remaining content up to limits
-        for line in reader.lines() {
-            if text.lines().count() >= self.max_lines || total_bytes >= MAX_BYTES {
-                break;
-            }
-            match line {
-                Ok(l) => {
-                    total_bytes += l.len() + 1;
-                    text.push_str(&l);
-                    text.push('\n');
-                }
-                Err(_) => break,
+        // Read remaining lines up to max_lines
+        // Use byte limit to prevent memory issues with long lines
+        let mut line_count = bytes.iter().filter(|&&b| b == b'\n').count();
+        while line_count < line_limit && bytes.len() < self.max_bytes {
+            let remaining = (self.max_bytes - bytes.len()) as u64;
+            match reader
+                .by_ref()
+                .take(remaining)
+                .read_until(b'\n', &mut bytes)
+            {
+                Ok(0) | Err(_) => break,
+                Ok(_) => line_count += 1,
             }
         }
+        let overflow = if bytes.len() > self.max_bytes {
+            bytes.split_off(self.max_bytes)
+        } else {
+            Vec::new()
+        };
+        let has_more = !overflow.is_empty()
+            || line_end(&bytes, line_limit) < bytes.len()
+            || reader.fill_buf().is_ok_and(|rest| !rest.is_empty());
+        // 上限に達した時点でまだ続きがあれば切り詰め
+        let truncated = bytes.len() >= self.max_bytes && has_more;
 
-        let text = text;
+        // 上限を超えるファイルは先頭と末尾を表示し、間を省略する
+        // （UTF-16 などは改行位置をバイト単位で探せないので対象外）
+        let tail_lines = self.max_lines.saturating_sub(1) / 2;
+        let ascii_compatible =
+            Encoding::for_bom(&bytes).is_none_or(|(encoding, _)| encoding.is_ascii_compatible());
+        let tail = if has_more && !is_notebook && ascii_compatible && tail_lines > 0 {
+            let head_lines = self.max_lines - 1 - tail_lines;
+            // 先頭の行より後ろは捨て、末尾はシークして読み直す
+            bytes.truncate(line_end(&bytes, head_lines));
+            Some(read_tail(
+                reader.get_mut(),
+                bytes.len() as u64,
+                tail_lines,
+                self.max_bytes / 2,
+            ))
+        } else {
+            None
+        };
+
+        let (text, encoding) = decode_text(&bytes);
+
+        if is_notebook && let Some(notebook) = Notebook::parse(&text) {
+            return PreviewContent {
+                lines: self.render_notebook(&notebook),
+                encoding: Some(encoding.name()),
+                truncated,
+                source_lines: false,
+            };
+        }
 
         let syntax = self
             .syntax_set
@@ -100,8 +211,101 @@ impl Previewer {
             .flatten()
             .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
 
-        let theme = self
-            .theme_set
+        let mut lines = Vec::new();
+        self.render_into(&text, syntax, &mut lines);
+
+        if let Some((omitted, tail_bytes)) = tail {
+            let (tail_text, _) = encoding.decode_without_bom_handling(&tail_bytes);
+            // 数えていなければ末尾の行番号もわからない
+            let first_line = omitted.map_or(0, |omitted| lines.len() + omitted + 1);
+
+            let marker = match omitted {
+                Some(omitted) => format!("··· {} lines omitted ···", omitted),
+                None => "··· lines omitted ···".to_string(),
+            };
+            lines.push(PreviewLine {
+                line_number: 0,
+                segments: vec![(
+                    Style {
+                        foreground: MUTED_COLOR,
+                        ..Style::default()
+                    },
+                    marker.clone(),
+                )],
+                text: marker,
+                diff: None,
+            });
+
+            let mut tail_lines = Vec::new();
+            self.render_into(&tail_text, syntax, &mut tail_lines);
+            for (offset, mut line) in tail_lines.into_iter().enumerate() {
+                line.line_number = if first_line > 0 {
+                    first_line + offset
+                } else {
+                    0
+                };
+                lines.push(line);
+            }
+        }
+
+        PreviewContent {
+            lines,
+            encoding: Some(encoding.name()),
+            truncated,
+            source_lines: true,
+        }
+    }
+
+    /// External preview command for the file: the entry for its extension,
+    /// else one for its mime type ("image/png", or "image/*" for the kind)
+    pub fn external_command(&self, path: &Path) -> Option<String> {
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        let by_extension = self
+            .external_previewers
+            .iter()
+            .filter(|(key, _)| !key.contains('/'))
+            .find(|(key, _)| Some(key.trim_start_matches('.').to_lowercase()) == ext);
+        if let Some((_, command)) = by_extension {
+            return Some(command.clone());
+        }
+
+        // mime type のキーがあるときだけ中身を調べる
+        if !self.external_previewers.keys().any(|key| key.contains('/')) {
+            return None;
+        }
+        let mime = mime_type(path)?;
+        let kind = mime.split('/').next().unwrap_or_default();
+        self.external_previewers
+            .iter()
+            .find(|(key, _)| {
+                let key = key.to_lowercase();
+                key == mime || key.strip_suffix("/*") == Some(kind)
+            })
+            .map(|(_, command)| command.clone())
+    }
+
+    /// Preview of an external previewer's output (ANSI colors kept)
+    pub fn external_content(&self, output: &[u8]) -> PreviewContent {
+        let (text, encoding) = decode_text(output);
+        let mut lines = Vec::new();
+        self.ansi_into(&text, &mut lines);
+        PreviewContent {
+            lines,
+            encoding: Some(encoding.name()),
+            truncated: false,
+            source_lines: false,
+        }
+    }
+
+    /// Names accepted by the `theme` setting
+    pub fn theme_names() -> Vec<String> {
+        ThemeSet::load_defaults().themes.into_keys().collect()
+    }
+
+    fn theme(&self) -> &Theme {
+        self.theme_set
             .themes
             .get(&self.theme_name)
             .unwrap_or_else(|| {
@@ -110,32 +314,401 @@ impl Previewer {
                     .values()
                     .next()
                     .expect("No themes available")
-            });
+            })
+    }
 
-        let mut highlighter = HighlightLines::new(syntax, theme);
-        let mut lines = Vec::new();
+    /// Append text as preview lines, interpreting ANSI colors when present
+    fn render_into(&self, text: &str, syntax: &SyntaxReference, lines: &mut Vec<PreviewLine>) {
+        if ansi::contains_ansi(text) {
+            self.ansi_into(text, lines);
+        } else {
+            self.highlight_into(text, syntax, lines);
+        }
+    }
+
+    /// Highlight text and append it to lines, numbering continuously up to max_lines
+    fn highlight_into(&self, text: &str, syntax: &SyntaxReference, lines: &mut Vec<PreviewLine>) {
+        let mut highlighter = HighlightLines::new(syntax, self.theme());
 
-        for (line_num, line) in LinesWithEndings::from(&text).enumerate() {
-            if line_num >= self.max_lines {
+        for line in LinesWithEndings::from(text) {
+            if lines.len() >= self.max_lines {
                 break;
             }
 
             let ranges = highlighter
                 .highlight_line(line, &self.syntax_set)
                 .unwrap_or_default();
+            self.push_line(ranges, line, lines);
+        }
+    }
+
+    /// Convert ANSI color codes into styles instead of syntax highlighting
+    fn ansi_into(&self, text: &str, lines: &mut Vec<PreviewLine>) {
+        let default = Style {
+            foreground: self.theme().settings.foreground.unwrap_or(Color::WHITE),
+            ..Style::default()
+        };
+        let mut style = default;
+
+        for line in LinesWithEndings::from(text) {
+            if lines.len() >= self.max_lines {
+                break;
+            }
+
+            let parsed = ansi::parse_line(line, &mut style, default);
+            let plain: String = parsed.iter().map(|(_, t)| t.as_str()).collect();
+            let ranges = parsed.iter().map(|(s, t)| (*s, t.as_str())).collect();
+            self.push_line(ranges, &plain, lines);
+        }
+    }
 
-            let segments: Vec<(Style, String)> = ranges
+    /// Build the display segments for one line and append it
+    fn push_line(&self, ranges: Vec<(Style, &str)>, line: &str, lines: &mut Vec<PreviewLine>) {
+        let segments: Vec<(Style, String)> = if self.show_whitespace {
+            visualize_whitespace(ranges, line, self.tab_width)
+        } else {
+            let mut column = 0;
+            ranges
                 .into_iter()
-                .map(|(style, text)| (style, text.to_string()))
-                .collect();
+                .map(|(style, text)| (style, expand_tabs(text, &mut column, self.tab_width)))
+                .collect()
+        };
+
+        lines.push(PreviewLine {
+            line_number: lines.len() + 1,
+            segments,
+            text: line.trim_end_matches(['\n', '\r']).to_string(),
+            diff: None,
+        });
+    }
+
+    /// Render notebook cells: markdown and highlighted code, with outputs stripped
+    fn render_notebook(&self, notebook: &Notebook) -> Vec<PreviewLine> {
+        let markdown = self
+            .syntax_set
+            .find_syntax_by_extension("md")
+            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+        let code = self
+            .syntax_set
+            .find_syntax_by_token(&notebook.language)
+            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+        let plain = self.syntax_set.find_syntax_plain_text();
+        let header_style = Style {
+            foreground: MUTED_COLOR,
+            ..Style::default()
+        };
 
+        let mut lines = Vec::new();
+        for cell in &notebook.cells {
+            if lines.len() >= self.max_lines {
+                break;
+            }
+
+            let (header, syntax) = match cell.kind {
+                CellKind::Markdown => ("── [markdown]".to_string(), markdown),
+                CellKind::Code => {
+                    let count = cell
+                        .execution_count
+                        .map(|n| n.to_string())
+                        .unwrap_or_else(|| " ".to_string());
+                    (format!("── In [{}]:", count), code)
+                }
+                CellKind::Raw => ("── [raw]".to_string(), plain),
+            };
             lines.push(PreviewLine {
-                line_number: line_num + 1,
-                segments,
+                line_number: lines.len() + 1,
+                segments: vec![(header_style, header.clone())],
+                text: header,
+                diff: None,
             });
+
+            let mut source = cell.source.clone();
+            if !source.ends_with('\n') {
+                source.push('\n');
+            }
+            source.push('\n');
+            self.highlight_into(&source, syntax, &mut lines);
+        }
+        lines
+    }
+}
+
+/// Time limit for external preview commands
+const EXTERNAL_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Mime type of a file from its first bytes: a known format, else
+/// "text/plain" for text and "application/octet-stream" for other data
+pub fn mime_type(path: &Path) -> Option<String> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(8192)
+        .read_to_end(&mut bytes)
+        .ok()?;
+    let mime = match infer::get(&bytes) {
+        Some(kind) => kind.mime_type(),
+        None if is_binary(&bytes) => "application/octet-stream",
+        None => "text/plain",
+    };
+    Some(mime.to_string())
+}
+
+/// Run an external preview command through the shell and capture its
+/// stdout. `{}` in the command is replaced by the quoted file path; without
+/// it the path is appended. Gives up after `EXTERNAL_TIMEOUT` or once
+/// `cancel` is set (the preview moved on to another file).
+pub fn run_external(command: &str, path: &Path, cancel: &AtomicBool) -> io::Result<Vec<u8>> {
+    if command.trim().is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty command"));
+    }
+    let quoted = commands::shell_quote(&path.to_string_lossy());
+    let line = if command.contains("{}") {
+        commands::fill(command, &[("{}", OsStr::new(&quoted))])
+            .to_string_lossy()
+            .into_owned()
+    } else {
+        format!("{} {}", command, quoted)
+    };
+
+    let mut child = commands::shell_command(&line)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // パイプが詰まらないよう別スレッドで読み込む
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        buf
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "previewer cancelled",
+            ));
+        }
+        if start.elapsed() >= EXTERNAL_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "previewer timed out",
+            ));
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let output = reader.join().unwrap_or_default();
+    if !status.success() && output.is_empty() {
+        return Err(io::Error::other(format!(
+            "previewer exited with {}",
+            status
+        )));
+    }
+    Ok(output)
+}
+
+/// Byte offset just past the `count`-th line ending (or the end of `bytes`)
+fn line_end(bytes: &[u8], count: usize) -> usize {
+    if count == 0 {
+        return 0;
+    }
+    bytes
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| **b == b'\n')
+        .nth(count - 1)
+        .map_or(bytes.len(), |(i, _)| i + 1)
+}
+
+/// Largest gap between the head and the tail whose lines are counted for
+/// the "lines omitted" marker; longer gaps are skipped without reading
+const COUNT_OMITTED_LIMIT: u64 = 8 * 1024 * 1024;
+
+/// Last `max_lines` lines (at most `max_bytes`) of a file whose head ends at
+/// byte `head_end`. Only the final `max_bytes` are read, after a seek to the
+/// end, so huge logs stay quick. Returns the number of omitted lines (None
+/// when the gap is too large to count) and the tail bytes.
+fn read_tail(
+    file: &mut (impl Read + Seek),
+    head_end: u64,
+    max_lines: usize,
+    max_bytes: usize,
+) -> (Option<usize>, Vec<u8>) {
+    let count_lines = |bytes: &[u8]| bytes.iter().filter(|&&b| b == b'\n').count();
+    let max_bytes = max_bytes.max(1) as u64;
+    let Ok(len) = file.seek(SeekFrom::End(0)) else {
+        return (Some(0), Vec::new());
+    };
+    let start = len.saturating_sub(max_bytes).max(head_end);
+
+    // 読まない部分の改行を数える（大きすぎるときは数えない）
+    let mut omitted = None;
+    // 読まない部分が行末で終わっているか
+    let mut at_line_start = true;
+    let gap = start - head_end;
+    if gap > 0 && file.seek(SeekFrom::Start(head_end)).is_ok() {
+        if gap <= COUNT_OMITTED_LIMIT {
+            let mut skipped = Vec::new();
+            let _ = file.by_ref().take(gap).read_to_end(&mut skipped);
+            omitted = Some(count_lines(&skipped));
+            at_line_start = skipped.last() == Some(&b'\n');
+        } else {
+            let mut last = [0u8];
+            let _ = file.seek(SeekFrom::Start(start - 1));
+            let _ = file.read_exact(&mut last);
+            at_line_start = last[0] == b'\n';
+        }
+    } else {
+        omitted = Some(0);
+    }
+
+    let mut tail = Vec::new();
+    if file.seek(SeekFrom::Start(start)).is_ok() {
+        let _ = file.by_ref().take(max_bytes).read_to_end(&mut tail);
+    }
+
+    // 途中から始まる行は省略側に含める
+    if !at_line_start
+        && let Some(pos) = tail.iter().position(|&b| b == b'\n')
+        && pos + 1 < tail.len()
+    {
+        tail.drain(..=pos);
+        omitted = omitted.map(|omitted| omitted + 1);
+    }
+
+    // 行数の上限を超える分も省略
+    let total = count_lines(&tail) + usize::from(!tail.ends_with(b"\n"));
+    if total > max_lines {
+        let cut = line_end(&tail, total - max_lines);
+        omitted = omitted.map(|omitted| omitted + total - max_lines);
+        tail.drain(..cut);
+    }
+    (omitted, tail)
+}
+
+/// Decode raw bytes into text, detecting the encoding when it is not UTF-8
+fn decode_text(bytes: &[u8]) -> (String, &'static Encoding) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return (text.into_owned(), encoding);
+    }
+
+    // 読み込みが文字の途中で切れた場合（末尾が不完全なだけ）も UTF-8 とみなす
+    match std::str::from_utf8(bytes) {
+        Ok(text) => return (text.to_string(), UTF_8),
+        Err(e) if e.error_len().is_none() => {
+            return (String::from_utf8_lossy(bytes).into_owned(), UTF_8);
+        }
+        Err(_) => {}
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), encoding)
+}
+
+/// Expand tabs to spaces, tracking the display column across segments
+fn expand_tabs(text: &str, column: &mut usize, tab_width: usize) -> String {
+    if !text.contains('\t') {
+        *column += text.width();
+        return text.to_string();
+    }
+
+    let tab_width = tab_width.max(1);
+    let mut expanded = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\t' {
+            let spaces = tab_width - (*column % tab_width);
+            expanded.extend(std::iter::repeat_n(' ', spaces));
+            *column += spaces;
+        } else {
+            expanded.push(c);
+            *column += c.width().unwrap_or(0);
+        }
+    }
+    expanded
+}
+
+/// Foreground color for annotations (whitespace symbols, notebook cell headers)
+const MUTED_COLOR: Color = Color {
+    r: 0x80,
+    g: 0x80,
+    b: 0x80,
+    a: 0xff,
+};
+
+/// Render tabs, trailing whitespace and control characters with visible symbols
+fn visualize_whitespace(
+    ranges: Vec<(Style, &str)>,
+    line: &str,
+    tab_width: usize,
+) -> Vec<(Style, String)> {
+    let content = line.trim_end_matches(['\n', '\r']);
+    let trailing_start = content.trim_end_matches([' ', '\t']).len();
+    let tab_width = tab_width.max(1);
+
+    let mut segments: Vec<(Style, String)> = Vec::new();
+    let mut push = |style: Style, text: &str| match segments.last_mut() {
+        Some((last_style, last_text)) if *last_style == style => last_text.push_str(text),
+        _ => segments.push((style, text.to_string())),
+    };
+
+    let mut offset = 0;
+    let mut column = 0;
+    for (style, text) in ranges {
+        let symbol_style = Style {
+            foreground: MUTED_COLOR,
+            ..style
+        };
+        for c in text.chars() {
+            let trailing = offset >= trailing_start && offset < content.len();
+            offset += c.len_utf8();
+
+            match c {
+                '\n' => push(style, "\n"),
+                '\t' => {
+                    let spaces = tab_width - (column % tab_width);
+                    push(symbol_style, "→");
+                    push(symbol_style, &" ".repeat(spaces - 1));
+                    column += spaces;
+                }
+                ' ' if trailing => {
+                    push(symbol_style, "·");
+                    column += 1;
+                }
+                c if c.is_control() => {
+                    push(symbol_style, &control_symbol(c).to_string());
+                    column += 1;
+                }
+                c => {
+                    push(style, c.encode_utf8(&mut [0; 4]));
+                    column += c.width().unwrap_or(0);
+                }
+            }
         }
+    }
+    segments
+}
 
-        PreviewContent { lines }
+/// Map a control character to its Unicode "control picture" symbol
+fn control_symbol(c: char) -> char {
+    match c as u32 {
+        code @ 0x00..=0x1f => char::from_u32(0x2400 + code).unwrap_or('?'),
+        0x7f => '␡',
+        _ => '�',
     }
 }
 
@@ -148,14 +721,14 @@ fn is_binary(content: &[u8]) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs::File;
+    use std::fs::{self, File};
     use std::io::Write;
     use tempfile::TempDir;
 
     #[test]
     fn test_preview_directory_returns_directory_message() {
         let temp_dir = TempDir::new().unwrap();
-        let previewer = Previewer::new("base16-ocean.dark", 100);
+        let previewer = Previewer::new("base16-ocean.dark", 100, 4);
 
         let content = previewer.preview(temp_dir.path());
 
@@ -170,7 +743,7 @@ mod tests {
 
     #[test]
     fn test_preview_nonexistent_file_returns_error() {
-        let previewer = Previewer::new("base16-ocean.dark", 100);
+        let previewer = Previewer::new("base16-ocean.dark", 100, 4);
         let nonexistent = Path::new("/nonexistent/file.txt");
 
         let content = previewer.preview(nonexistent);
@@ -185,6 +758,46 @@ mod tests {
         );
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_preview_symlink_shows_target_content() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("target.txt");
+        fs::write(&target, "linked content\n").unwrap();
+        let link = temp_dir.path().join("link.txt");
+        symlink(&target, &link).unwrap();
+
+        let previewer = Previewer::new("base16-ocean.dark", 100, 4);
+        let content = previewer.preview(&link);
+
+        assert!(
+            content.lines[0]
+                .segments
+                .iter()
+                .any(|(_, text)| text.contains("linked content"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_preview_broken_symlink_is_flagged() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = TempDir::new().unwrap();
+        let link = temp_dir.path().join("broken");
+        symlink("/nonexistent/target", &link).unwrap();
+
+        let previewer = Previewer::new("base16-ocean.dark", 100, 4);
+        let content = previewer.preview(&link);
+
+        assert_eq!(content.lines.len(), 1);
+        assert!(content.lines[0].segments.iter().any(
+            |(_, text)| text.contains("Broken symlink") && text.contains("/nonexistent/target")
+        ));
+    }
+
     #[test]
     fn test_preview_text_file() {
         let temp_dir = TempDir::new().unwrap();
@@ -194,7 +807,7 @@ mod tests {
         writeln!(file, "Line 2").unwrap();
         writeln!(file, "Line 3").unwrap();
 
-        let previewer = Previewer::new("base16-ocean.dark", 100);
+        let previewer = Previewer::new("base16-ocean.dark", 100, 4);
         let content = previewer.preview(&file_path);
 
         assert!(content.lines.len() >= 3);
@@ -210,12 +823,74 @@ mod tests {
             writeln!(file, "Line {}", i).unwrap();
         }
 
-        let previewer = Previewer::new("base16-ocean.dark", 10);
+        let previewer = Previewer::new("base16-ocean.dark", 10, 4);
         let content = previewer.preview(&file_path);
 
         assert!(content.lines.len() <= 10);
     }
 
+    #[test]
+    fn test_preview_truncated_at_byte_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("big.log");
+        let mut file = File::create(&file_path).unwrap();
+        for i in 1..=100 {
+            writeln!(file, "Line {}", i).unwrap();
+        }
+
+        let mut previewer = Previewer::new("base16-ocean.dark", 1000, 4);
+        previewer.max_bytes = 64;
+        let content = previewer.preview(&file_path);
+        assert!(content.truncated);
+        assert!(content.lines.len() < 100);
+
+        previewer.max_bytes = 10 * 1024;
+        let content = previewer.preview(&file_path);
+        assert!(!content.truncated);
+        assert_eq!(content.lines.len(), 100);
+    }
+
+    #[test]
+    fn test_preview_head_and_tail() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("app.log");
+        let mut file = File::create(&file_path).unwrap();
+        for i in 1..=100 {
+            writeln!(file, "Line {}", i).unwrap();
+        }
+
+        let previewer = Previewer::new("base16-ocean.dark", 11, 4);
+        let content = previewer.preview(&file_path);
+
+        let texts: Vec<&str> = content.lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts.len(), 11);
+        assert_eq!(texts[0], "Line 1");
+        assert_eq!(texts[4], "Line 5");
+        assert_eq!(texts[5], "··· 90 lines omitted ···");
+        assert_eq!(texts[6], "Line 96");
+        assert_eq!(texts[10], "Line 100");
+        assert_eq!(content.lines[6].line_number, 96);
+        assert_eq!(content.lines[10].line_number, 100);
+    }
+
+    #[test]
+    fn test_read_tail_counts_omitted_lines() {
+        let text: String = (1..=50).map(|i| format!("{}\n", i)).collect();
+        let text = format!("head\nx\n{}", text);
+        let (omitted, tail) = read_tail(&mut io::Cursor::new(text), 5, 3, 10);
+        assert_eq!(String::from_utf8(tail).unwrap(), "48\n49\n50\n");
+        assert_eq!(omitted, Some(48));
+    }
+
+    #[test]
+    fn test_read_tail_skips_large_gaps() {
+        let mut text = b"a\n".repeat(COUNT_OMITTED_LIMIT as usize);
+        text.extend_from_slice(b"partial line\nlast\n");
+        let (omitted, tail) = read_tail(&mut io::Cursor::new(text), 0, 3, 10);
+        assert_eq!(String::from_utf8(tail).unwrap(), "last\n");
+        assert_eq!(omitted, None);
+    }
+
     #[test]
     fn test_preview_binary_file() {
         let temp_dir = TempDir::new().unwrap();
@@ -227,7 +902,7 @@ mod tests {
             .collect();
         file.write_all(&binary_content).unwrap();
 
-        let previewer = Previewer::new("base16-ocean.dark", 100);
+        let previewer = Previewer::new("base16-ocean.dark", 100, 4);
         let content = previewer.preview(&file_path);
 
         assert_eq!(content.lines.len(), 1);
@@ -247,13 +922,280 @@ mod tests {
         writeln!(file, "fn main() {{}}").unwrap();
 
         // Use an invalid theme name
-        let previewer = Previewer::new("nonexistent-theme", 100);
+        let previewer = Previewer::new("nonexistent-theme", 100, 4);
         let content = previewer.preview(&file_path);
 
         // Should not panic and should return content
         assert!(!content.lines.is_empty());
     }
 
+    #[test]
+    fn test_preview_utf8_file_reports_encoding() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("utf8.txt");
+        fs::write(&file_path, "こんにちは\n").unwrap();
+
+        let previewer = Previewer::new("base16-ocean.dark", 100, 4);
+        let content = previewer.preview(&file_path);
+
+        assert_eq!(content.encoding, Some("UTF-8"));
+    }
+
+    #[test]
+    fn test_preview_decodes_shift_jis() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("sjis.txt");
+        let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode("日本語のテキストファイルです。\n");
+        fs::write(&file_path, &bytes).unwrap();
+
+        let previewer = Previewer::new("base16-ocean.dark", 100, 4);
+        let content = previewer.preview(&file_path);
+
+        assert_eq!(content.encoding, Some("Shift_JIS"));
+        let text: String = content.lines[0]
+            .segments
+            .iter()
+            .map(|(_, t)| t.as_str())
+            .collect();
+        assert!(text.contains("日本語"));
+    }
+
+    #[test]
+    fn test_decode_text_utf16_bom() {
+        let bytes = [0xFF, 0xFE, b'h', 0, b'i', 0];
+        let (text, encoding) = decode_text(&bytes);
+        assert_eq!(text, "hi");
+        assert_eq!(encoding.name(), "UTF-16LE");
+    }
+
+    #[test]
+    fn test_expand_tabs_aligns_to_tab_stops() {
+        let mut column = 0;
+        assert_eq!(expand_tabs("a\tb", &mut column, 4), "a   b");
+        assert_eq!(column, 5);
+
+        let mut column = 0;
+        assert_eq!(expand_tabs("\t", &mut column, 8), "        ");
+    }
+
+    #[test]
+    fn test_expand_tabs_continues_column_across_segments() {
+        let mut column = 0;
+        expand_tabs("ab", &mut column, 4);
+        assert_eq!(expand_tabs("\tc", &mut column, 4), "  c");
+
+        // 全角文字は 2 桁として数える
+        let mut column = 0;
+        assert_eq!(expand_tabs("日\tx", &mut column, 4), "日  x");
+        assert_eq!(column, 5);
+    }
+
+    #[test]
+    fn test_rows_count_wide_characters() {
+        let line = |text: &str| PreviewLine {
+            line_number: 1,
+            segments: vec![(Style::default(), format!("{}\n", text))],
+            text: text.to_string(),
+            diff: None,
+        };
+        assert_eq!(line("").rows(4), 1);
+        assert_eq!(line("abcd").rows(4), 1);
+        assert_eq!(line("abcde").rows(4), 2);
+        // 端をまたぐ全角文字は次の行に送る
+        assert_eq!(line("a日本").rows(4), 2);
+        assert_eq!(line("日本語🦀").rows(4), 2);
+        assert_eq!(line("a日本語🦀").rows(4), 3);
+    }
+
+    #[test]
+    fn test_preview_expands_tabs() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("Makefile");
+        fs::write(&file_path, "all:\n\techo hi\n").unwrap();
+
+        let previewer = Previewer::new("base16-ocean.dark", 100, 2);
+        let content = previewer.preview(&file_path);
+
+        let text: String = content.lines[1]
+            .segments
+            .iter()
+            .map(|(_, t)| t.as_str())
+            .collect();
+        assert!(text.starts_with("  echo"));
+        assert!(!text.contains('\t'));
+    }
+
+    #[test]
+    fn test_visualize_whitespace_marks_tabs_and_trailing_spaces() {
+        let style = Style::default();
+        let line = "a\tb  \n";
+        let segments = visualize_whitespace(vec![(style, line)], line, 4);
+
+        let text: String = segments.iter().map(|(_, t)| t.as_str()).collect();
+        assert_eq!(text, "a→  b··\n");
+        assert!(
+            segments
+                .iter()
+                .any(|(s, t)| t.contains('→') && s.foreground == MUTED_COLOR)
+        );
+    }
+
+    #[test]
+    fn test_visualize_whitespace_tab_stops_after_wide_chars() {
+        // 全角文字は2桁として次のタブ位置を決める（expand_tabs と同じ）
+        let line = "漢\tb\n";
+        let segments = visualize_whitespace(vec![(Style::default(), line)], line, 4);
+        let text: String = segments.iter().map(|(_, t)| t.as_str()).collect();
+        assert_eq!(text, "漢→ b\n");
+        assert_eq!(expand_tabs("漢\tb", &mut 0, 4), "漢  b");
+    }
+
+    #[test]
+    fn test_visualize_whitespace_keeps_inner_spaces() {
+        let line = "a b\r\n";
+        let segments = visualize_whitespace(vec![(Style::default(), line)], line, 4);
+
+        let text: String = segments.iter().map(|(_, t)| t.as_str()).collect();
+        assert_eq!(text, "a b␍\n");
+    }
+
+    #[test]
+    fn test_control_symbol() {
+        assert_eq!(control_symbol('\0'), '␀');
+        assert_eq!(control_symbol('\x1b'), '␛');
+        assert_eq!(control_symbol('\x7f'), '␡');
+    }
+
+    #[test]
+    fn test_preview_renders_notebook_cells() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("analysis.ipynb");
+        fs::write(
+            &file_path,
+            r##"{"cells": [
+                {"cell_type": "markdown", "source": ["# Heading"]},
+                {"cell_type": "code", "execution_count": 1, "source": ["x = 1\n", "x"],
+                 "outputs": [{"output_type": "execute_result", "data": {"text/plain": ["SECRET_OUTPUT"]}}]}
+            ], "metadata": {}, "nbformat": 4, "nbformat_minor": 5}"##,
+        )
+        .unwrap();
+
+        let previewer = Previewer::new("base16-ocean.dark", 100, 4);
+        let content = previewer.preview(&file_path);
+
+        let text: Vec<String> = content
+            .lines
+            .iter()
+            .map(|l| l.segments.iter().map(|(_, t)| t.as_str()).collect())
+            .collect();
+        assert!(text[0].contains("[markdown]"));
+        assert!(text[1].starts_with("# Heading"));
+        assert!(text.iter().any(|l| l.contains("In [1]:")));
+        assert!(text.iter().any(|l| l.starts_with("x = 1")));
+        assert!(!text.iter().any(|l| l.contains("SECRET_OUTPUT")));
+        assert!(!text.iter().any(|l| l.contains("cell_type")));
+    }
+
+    #[test]
+    fn test_preview_line_keeps_original_text() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("tabs.txt");
+        fs::write(&file_path, "a\tb\r\nc\n").unwrap();
+
+        let mut previewer = Previewer::new("base16-ocean.dark", 100, 4);
+        previewer.show_whitespace = true;
+        let content = previewer.preview(&file_path);
+
+        assert_eq!(content.lines[0].text, "a\tb");
+        assert_eq!(content.lines[1].text, "c");
+    }
+
+    #[test]
+    fn test_preview_converts_ansi_colors() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("ci.log");
+        fs::write(&file_path, "\x1b[32mPASS\x1b[0m test_a\n").unwrap();
+
+        let previewer = Previewer::new("base16-ocean.dark", 100, 4);
+        let content = previewer.preview(&file_path);
+
+        let segments = &content.lines[0].segments;
+        let text: String = segments.iter().map(|(_, t)| t.as_str()).collect();
+        assert_eq!(text, "PASS test_a\n");
+        assert!(!text.contains('\x1b'));
+        assert_ne!(segments[0].0.foreground, segments[1].0.foreground);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_preview_uses_external_previewer() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("doc.custom");
+        fs::write(&file_path, "raw content\n").unwrap();
+
+        let mut previewer = Previewer::new("base16-ocean.dark", 100, 4);
+        previewer.external_previewers.insert(
+            "custom".to_string(),
+            "printf '%s|' 'converted text' {}".to_string(),
+        );
+        let command = previewer.external_command(&file_path).unwrap();
+        let output = run_external(&command, &file_path, &AtomicBool::new(false)).unwrap();
+        let content = previewer.external_content(&output);
+
+        let text: String = content.lines[0]
+            .segments
+            .iter()
+            .map(|(_, t)| t.as_str())
+            .collect();
+        assert_eq!(text, format!("converted text|{}|", file_path.display()));
+        assert!(!content.source_lines);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_external_previewer_failures() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("doc.custom");
+        fs::write(&file_path, "raw content\n").unwrap();
+
+        let cancel = AtomicBool::new(false);
+        assert!(run_external("vfv-nonexistent-previewer-command", &file_path, &cancel).is_err());
+        assert!(run_external("  ", &file_path, &cancel).is_err());
+        // 別のファイルに移ったら打ち切る
+        cancel.store(true, Ordering::Relaxed);
+        let err = run_external("sleep 5", &file_path, &cancel).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+    }
+
+    #[test]
+    fn test_external_command_by_mime_type() {
+        let temp_dir = TempDir::new().unwrap();
+        let png = temp_dir.path().join("picture.dat");
+        fs::write(&png, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        let text = temp_dir.path().join("notes");
+        fs::write(&text, "plain words\n").unwrap();
+
+        let mut previewer = Previewer::new("base16-ocean.dark", 100, 4);
+        assert_eq!(mime_type(&png).as_deref(), Some("image/png"));
+        assert_eq!(mime_type(&text).as_deref(), Some("text/plain"));
+        assert!(previewer.external_command(&png).is_none());
+
+        previewer
+            .external_previewers
+            .insert("image/*".to_string(), "chafa".to_string());
+        previewer
+            .external_previewers
+            .insert("text/plain".to_string(), "cat".to_string());
+        previewer
+            .external_previewers
+            .insert("dat".to_string(), "hexyl".to_string());
+        // 拡張子のエントリが優先
+        assert_eq!(previewer.external_command(&png).as_deref(), Some("hexyl"));
+        previewer.external_previewers.remove("dat");
+        assert_eq!(previewer.external_command(&png).as_deref(), Some("chafa"));
+        assert_eq!(previewer.external_command(&text).as_deref(), Some("cat"));
+    }
+
     #[test]
     fn test_is_binary_detects_binary() {
         // Content with >10% null bytes is binary
@@ -283,7 +1225,7 @@ mod tests {
         writeln!(file, "    println!(\"Hello\");").unwrap();
         writeln!(file, "}}").unwrap();
 
-        let previewer = Previewer::new("base16-ocean.dark", 100);
+        let previewer = Previewer::new("base16-ocean.dark", 100, 4);
         let content = previewer.preview(&file_path);
 
         assert!(content.lines.len() >= 3);
@@ -0,0 +1,303 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::UnicodeWidthStr;
+
+/// Single-line text input shared by the prompts (search, rename, new file,
+/// go to path, `:`), with a movable cursor and a history of submitted lines
+///
+/// Ctrl+A and Ctrl+E are left to the prompts, since the search prompt uses
+/// them as flag toggles; Home and End move to the ends instead.
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    text: String,
+    /// Byte offset into `text`, always on a char boundary
+    cursor: usize,
+    /// Maximum length in chars (`None` for no limit)
+    limit: Option<usize>,
+    history: Vec<String>,
+    /// History entry shown by Up/Down, and the line that was being typed
+    browsing: Option<(usize, String)>,
+}
+
+/// Lines kept in each prompt's history
+const HISTORY_SIZE: usize = 100;
+
+impl TextInput {
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..Self::default()
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Display width of the text before the cursor, for placing it on screen
+    pub fn cursor_width(&self) -> u16 {
+        self.text[..self.cursor].width() as u16
+    }
+
+    /// Replace the text and put the cursor at its end
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+        self.browsing = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    /// Add the current line to the history (skipping blanks and repeats)
+    pub fn remember(&mut self) {
+        self.browsing = None;
+        if self.text.trim().is_empty() || self.history.last() == Some(&self.text) {
+            return;
+        }
+        self.history.push(self.text.clone());
+        if self.history.len() > HISTORY_SIZE {
+            self.history.remove(0);
+        }
+    }
+
+    /// Take the line for running it, remembering it in the history
+    pub fn submit(&mut self) -> String {
+        self.remember();
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    pub fn insert(&mut self, c: char) {
+        if self
+            .limit
+            .is_some_and(|limit| self.text.chars().count() >= limit)
+        {
+            return;
+        }
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+            self.text.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if let Some(c) = self.text[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    /// Start of the word before the cursor (words end at spaces and `/`)
+    fn word_start(&self) -> usize {
+        let before = &self.text[..self.cursor];
+        let trimmed = before.trim_end_matches(is_separator);
+        // 区切りは ASCII なので次の文字は i + 1 から
+        trimmed.rfind(is_separator).map_or(0, |i| i + 1)
+    }
+
+    /// End of the word after the cursor
+    fn word_end(&self) -> usize {
+        let after = &self.text[self.cursor..];
+        let skipped = after.len() - after.trim_start_matches(is_separator).len();
+        let rest = &after[skipped..];
+        self.cursor + skipped + rest.find(is_separator).unwrap_or(rest.len())
+    }
+
+    pub fn word_left(&mut self) {
+        self.cursor = self.word_start();
+    }
+
+    pub fn word_right(&mut self) {
+        self.cursor = self.word_end();
+    }
+
+    /// Ctrl+W: delete the word before the cursor
+    pub fn delete_word(&mut self) {
+        let start = self.word_start();
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    pub fn delete_to_start(&mut self) {
+        self.text.replace_range(..self.cursor, "");
+        self.cursor = 0;
+    }
+
+    pub fn delete_to_end(&mut self) {
+        self.text.truncate(self.cursor);
+    }
+
+    /// Up: the previous history entry (the typed line is kept for Down)
+    pub fn history_prev(&mut self) {
+        let index = match self.browsing {
+            Some((0, _)) => return,
+            Some((index, _)) => index - 1,
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        let draft = match self.browsing.take() {
+            Some((_, draft)) => draft,
+            None => self.text.clone(),
+        };
+        self.set(self.history[index].clone());
+        self.browsing = Some((index, draft));
+    }
+
+    /// Down: the next history entry, then back to the typed line
+    pub fn history_next(&mut self) {
+        let Some((index, draft)) = self.browsing.take() else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.set(self.history[index + 1].clone());
+            self.browsing = Some((index + 1, draft));
+        } else {
+            self.set(draft);
+        }
+    }
+
+    /// Apply an editing key; false when the key is not an editing key, so
+    /// the prompt can use it (Enter, Esc, Tab, its own Ctrl keys)
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('w') if ctrl => self.delete_word(),
+            KeyCode::Char('u') if ctrl => self.delete_to_start(),
+            KeyCode::Char('k') if ctrl => self.delete_to_end(),
+            KeyCode::Char('b') if alt => self.word_left(),
+            KeyCode::Char('f') if alt => self.word_right(),
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Backspace if alt || ctrl => self.delete_word(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left if ctrl || alt => self.word_left(),
+            KeyCode::Right if ctrl || alt => self.word_right(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            KeyCode::Up => self.history_prev(),
+            KeyCode::Down => self.history_next(),
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn is_separator(c: char) -> bool {
+    c == ' ' || c == '/'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(text: &str) -> TextInput {
+        let mut input = TextInput::default();
+        for c in text.chars() {
+            input.insert(c);
+        }
+        input
+    }
+
+    #[test]
+    fn test_edit_in_the_middle() {
+        let mut input = typed("helo wrld");
+        input.word_left();
+        input.move_right();
+        input.insert('o');
+        assert_eq!(input.text(), "helo world");
+        input.word_left();
+        input.move_left();
+        input.backspace();
+        input.insert('l');
+        input.insert('o');
+        assert_eq!(input.text(), "hello world");
+        input.cursor = 0;
+        input.delete();
+        input.word_right();
+        assert_eq!(input.text(), "ello world");
+        assert_eq!(input.cursor_width(), 4);
+    }
+
+    #[test]
+    fn test_word_deletion() {
+        let mut input = typed("cd src/bin/ ");
+        input.delete_word();
+        assert_eq!(input.text(), "cd src/");
+        input.delete_word();
+        assert_eq!(input.text(), "cd ");
+        input.set("a b c");
+        input.word_left();
+        input.delete_to_start();
+        assert_eq!(input.text(), "c");
+        input.delete_to_end();
+        assert_eq!(input.text(), "");
+    }
+
+    #[test]
+    fn test_multibyte_cursor() {
+        let mut input = typed("日本語");
+        input.move_left();
+        input.backspace();
+        assert_eq!(input.text(), "日語");
+        assert_eq!(input.cursor_width(), 2);
+        input.delete_word();
+        assert_eq!(input.text(), "語");
+    }
+
+    #[test]
+    fn test_history_keeps_the_typed_line() {
+        let mut input = TextInput::default();
+        for line in ["first", "second", "second", " "] {
+            input.set(line);
+            input.submit();
+        }
+        input.set("draft");
+        input.history_prev();
+        assert_eq!(input.text(), "second");
+        input.history_prev();
+        input.history_prev();
+        assert_eq!(input.text(), "first");
+        input.history_next();
+        input.history_next();
+        assert_eq!(input.text(), "draft");
+    }
+
+    #[test]
+    fn test_limit_and_keys() {
+        let mut input = TextInput::with_limit(3);
+        for c in "abcd".chars() {
+            input.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        assert_eq!(input.text(), "abc");
+        // プロンプト側の Ctrl キーは扱わない
+        assert!(!input.handle_key(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL)));
+        assert!(!input.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)));
+        assert!(input.handle_key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL)));
+        assert!(input.is_empty());
+    }
+}
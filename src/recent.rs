@@ -0,0 +1,108 @@
+use directories::ProjectDirs;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// How many files the history keeps
+const MAX_RECENT: usize = 100;
+
+/// Files opened in the preview or the editor, most recent first, persisted
+/// one path per line
+#[derive(Debug, Clone, Default)]
+pub struct RecentFiles {
+    pub files: Vec<PathBuf>,
+    /// Where to save; `None` keeps the history in memory only
+    path: Option<PathBuf>,
+}
+
+impl RecentFiles {
+    /// Load the history from the data dir (a missing file yields none)
+    pub fn load() -> Self {
+        Self::load_from(Self::recent_path())
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let files = fs::read_to_string(&path)
+            .map(|content| {
+                content
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(PathBuf::from)
+                    .take(MAX_RECENT)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            files,
+            path: Some(path),
+        }
+    }
+
+    /// Move `file` to the top of the history and save immediately
+    pub fn record(&mut self, file: &Path) -> io::Result<()> {
+        self.files.retain(|f| f != file);
+        self.files.insert(0, file.to_path_buf());
+        self.files.truncate(MAX_RECENT);
+        self.save()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = String::new();
+        for file in &self.files {
+            content.push_str(&file.to_string_lossy());
+            content.push('\n');
+        }
+        fs::write(path, content)
+    }
+
+    pub fn recent_path() -> PathBuf {
+        if let Some(proj_dirs) = ProjectDirs::from("", "", "vive-file-viewer") {
+            proj_dirs.data_dir().join("recent_files")
+        } else {
+            PathBuf::from("~/.local/share/vive-file-viewer/recent_files")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_moves_to_top_and_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested/recent_files");
+
+        let mut recent = RecentFiles::load_from(path.clone());
+        assert!(recent.files.is_empty());
+        recent.record(Path::new("/a.txt")).unwrap();
+        recent.record(Path::new("/b.txt")).unwrap();
+        recent.record(Path::new("/a.txt")).unwrap();
+
+        let loaded = RecentFiles::load_from(path);
+        assert_eq!(
+            loaded.files,
+            vec![PathBuf::from("/a.txt"), PathBuf::from("/b.txt")]
+        );
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut recent = RecentFiles::default();
+        for i in 0..MAX_RECENT + 5 {
+            recent.record(&PathBuf::from(format!("/{}", i))).unwrap();
+        }
+        assert_eq!(recent.files.len(), MAX_RECENT);
+        assert_eq!(
+            recent.files[0],
+            PathBuf::from(format!("/{}", MAX_RECENT + 4))
+        );
+    }
+}
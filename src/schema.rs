@@ -0,0 +1,311 @@
+use serde_json::{Value, json};
+
+use crate::config::{Config, SPLIT_RATIOS};
+use crate::file_browser::SortMode;
+use crate::icons::IconStyle;
+use crate::keymap;
+use crate::preview::Previewer;
+use crate::ui_theme::UiTheme;
+use crate::widget_style::{Border, TitleAlign};
+
+/// JSON Schema (draft-07) of `config.toml`, with the defaults filled in from
+/// `Config::default()`, for editors such as VS Code or taplo
+pub fn config_schema() -> Value {
+    let string_map = |description: &str| {
+        json!({
+            "type": "object",
+            "description": description,
+            "additionalProperties": { "type": "string" },
+        })
+    };
+    let opener_map = |description: &str| {
+        json!({
+            "type": "object",
+            "description": description,
+            "additionalProperties": {
+                "oneOf": [
+                    { "type": "string" },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "command": { "type": "string" },
+                            "detach": {
+                                "type": "boolean",
+                                "description": "Start in the background without suspending vfv",
+                            },
+                        },
+                        "required": ["command"],
+                        "additionalProperties": false,
+                    },
+                ],
+            },
+        })
+    };
+    let key_spec = json!({
+        "oneOf": [
+            { "type": "string" },
+            { "type": "array", "items": { "type": "string" } },
+        ],
+    });
+    let key_properties: serde_json::Map<String, Value> = keymap::action_names()
+        .map(|name| (name.to_string(), key_spec.clone()))
+        .collect();
+
+    let mut sort_modes = vec![SortMode::default()];
+    while let Some(next) = sort_modes.last().map(|mode| mode.next())
+        && next != sort_modes[0]
+    {
+        sort_modes.push(next);
+    }
+
+    let mut properties = json!({
+        "version": {
+            "type": "integer",
+            "minimum": 1,
+            "description": "Config format version",
+        },
+        "editor": {
+            "type": "string",
+            "description": "Editor command used by e (empty: $VISUAL, $EDITOR, then vim/nano/vi or notepad)",
+        },
+        "editor_args": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Arguments passed to the editor before the file paths; with {file}, {line} or {dir} in them the path is not appended",
+        },
+        "editor_detach": {
+            "type": "boolean",
+            "description": "Start the editor in the background without suspending vfv (GUI editors)",
+        },
+        "show_hidden": {
+            "type": "boolean",
+            "description": "Show hidden files on startup",
+        },
+        "browser_ignore": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Glob patterns for names hidden from the file list",
+        },
+        "preview_max_lines": {
+            "type": "integer",
+            "minimum": 0,
+            "description": "Maximum lines to preview",
+        },
+        "preview_max_bytes": {
+            "type": "integer",
+            "minimum": 0,
+            "description": "Maximum bytes read for a preview",
+        },
+        "preview_pane": {
+            "type": "boolean",
+            "description": "Show the preview pane beside the file list at startup",
+        },
+        "preview_pane_ratio": {
+            "type": "integer",
+            "minimum": SPLIT_RATIOS.start(),
+            "maximum": SPLIT_RATIOS.end(),
+            "description": "Width of the preview pane in percent of the screen",
+        },
+        "dual_pane_ratio": {
+            "type": "integer",
+            "minimum": SPLIT_RATIOS.start(),
+            "maximum": SPLIT_RATIOS.end(),
+            "description": "Width of the left pane in dual-pane mode in percent of the screen",
+        },
+        "narrow_width": {
+            "type": "integer",
+            "minimum": 0,
+            "description": "Below this terminal width, show one pane, a shortened path and short key hints",
+        },
+        "theme": {
+            "type": "string",
+            "enum": Previewer::theme_names(),
+            "description": "Syntax highlighting theme (unset: the one ui_theme picks)",
+        },
+        "tab_width": {
+            "type": "integer",
+            "minimum": 0,
+            "description": "Number of columns a tab expands to in the preview",
+        },
+        "ui_theme": {
+            "enum": UiTheme::ALL,
+            "description": "Color preset for the whole UI (also picks the syntax theme unless theme is set)",
+        },
+        "icons": {
+            "enum": [IconStyle::Plain, IconStyle::Nerd],
+            "description": "File list icons (nerd requires a Nerd Font)",
+        },
+        "sort": {
+            "enum": sort_modes,
+            "description": "Initial sort order of the file list",
+        },
+        "sort_reverse": {
+            "type": "boolean",
+            "description": "Start with the sort order reversed",
+        },
+        "natural_sort": {
+            "type": "boolean",
+            "description": "Order numbered names numerically (\"file2\" before \"file10\")",
+        },
+        "zoxide": {
+            "type": "boolean",
+            "description": "Record visited directories in zoxide's database",
+        },
+        "status_bar": {
+            "type": "string",
+            "description": "Footer template of {segment}s (mode, selection, position, marks, branch, free, hints)",
+        },
+        "mouse": {
+            "type": "boolean",
+            "description": "Capture the mouse (click a directory in the header path to go there); off by default",
+        },
+        "keyboard_protocol": {
+            "type": "boolean",
+            "description": "Use the kitty keyboard protocol where supported (shift-enter, ctrl-i apart from tab)",
+        },
+        "delete_to_trash": {
+            "type": "boolean",
+            "description": "Move deleted entries to the trash instead of removing them",
+        },
+        "previewers": string_map(
+            "External preview commands keyed by file extension or mime type (image/*), run through the shell ({} is the quoted path)"
+        ),
+        "openers": opener_map(
+            "Programs offered by the \"open with\" menu, keyed by display name"
+        ),
+        "extension_openers": opener_map("Programs that open these extensions on Enter/e"),
+        "keys": {
+            "type": "object",
+            "description": "Key bindings replacing the defaults, keyed by action name",
+            "properties": key_properties,
+            "additionalProperties": false,
+        },
+        "commands": string_map(
+            "Shell commands run from the browser, keyed by key ({path}, {dir}, {marked})"
+        ),
+        "style": {
+            "type": "object",
+            "description": "Borders, title alignment and the selection highlight",
+            "properties": {
+                "border": {
+                    "enum": [Border::Plain, Border::Rounded, Border::Thick, Border::None],
+                    "description": "Frame around the panes (popups keep a plain one with none)",
+                },
+                "title_alignment": {
+                    "enum": [TitleAlign::Left, TitleAlign::Center, TitleAlign::Right],
+                    "description": "Where titles sit on the top border",
+                },
+                "highlight_bg": {
+                    "type": "string",
+                    "description": "Background of the selected row: a color name, #rrggbb or reset (unset: the ui_theme preset's)",
+                },
+                "highlight_bold": { "type": "boolean", "description": "Bold selected row" },
+                "highlight_symbol": {
+                    "type": "string",
+                    "description": "Drawn before the selected row, e.g. \"> \"",
+                },
+            },
+            "additionalProperties": false,
+        },
+        "find": {
+            "type": "object",
+            "description": "Defaults for vfv find flags",
+            "properties": {
+                "limit": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Maximum number of results",
+                },
+                "timeout": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Timeout in seconds (0 = no timeout)",
+                },
+                "exact": { "type": "boolean", "description": "Exact match instead of fuzzy" },
+                "hidden": { "type": "boolean", "description": "Include hidden files" },
+                "no_ignore": {
+                    "type": "boolean",
+                    "description": "Do not respect .gitignore and other ignore files",
+                },
+                "json": { "type": "boolean", "description": "Output as JSON" },
+            },
+            "additionalProperties": false,
+        },
+    });
+    fill_defaults(&mut properties, &json!(Config::default()));
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "vfv config.toml",
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    })
+}
+
+/// Add `default` to each property from the matching default value
+fn fill_defaults(properties: &mut Value, defaults: &Value) {
+    let Some(properties) = properties.as_object_mut() else {
+        return;
+    };
+    for (key, property) in properties {
+        // 未設定が既定のもの（theme など）には既定値を付けない
+        let Some(default) = defaults.get(key).filter(|default| !default.is_null()) else {
+            continue;
+        };
+        // テーブルの中の項目にも既定値を付ける
+        if let Some(nested) = property.get_mut("properties")
+            && default.is_object()
+        {
+            fill_defaults(nested, default);
+        }
+        property["default"] = default.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_covers_every_setting() {
+        let schema = config_schema();
+        let properties = schema["properties"].as_object().unwrap();
+        let defaults = json!(Config::default());
+        let defaults = defaults.as_object().unwrap();
+
+        let mut schema_keys: Vec<&String> = properties.keys().collect();
+        let mut config_keys: Vec<&String> = defaults.keys().collect();
+        schema_keys.sort();
+        config_keys.sort();
+        assert_eq!(schema_keys, config_keys);
+        // theme の既定は ui_theme 次第
+        assert!(
+            properties
+                .iter()
+                .all(|(key, p)| p.get("default").is_some() == (key != "theme"))
+        );
+
+        let find = properties["find"]["properties"].as_object().unwrap();
+        assert_eq!(find.len(), defaults["find"].as_object().unwrap().len());
+        assert_eq!(find["limit"]["default"], 20);
+    }
+
+    #[test]
+    fn test_schema_enums() {
+        let schema = config_schema();
+        let properties = &schema["properties"];
+        assert_eq!(
+            properties["sort"]["enum"],
+            json!(["name", "size", "mtime", "ext"])
+        );
+        assert_eq!(properties["icons"]["enum"], json!(["plain", "nerd"]));
+        assert!(
+            properties["theme"]["enum"]
+                .as_array()
+                .unwrap()
+                .contains(&json!(Config::default().syntax_theme()))
+        );
+        assert!(properties["keys"]["properties"].get("move_down").is_some());
+    }
+}
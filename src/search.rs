@@ -9,6 +9,33 @@ const MAX_SEARCH_DEPTH: usize = 10;
 /// Score assigned to exact matches
 const EXACT_MATCH_SCORE: u32 = 1000;
 
+/// Options that control which entries a search visits and how they match
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Match directories only
+    pub dirs_only: bool,
+    /// Exact file name match instead of fuzzy
+    pub exact: bool,
+    /// Include hidden files and directories
+    pub hidden: bool,
+    /// Do not respect .gitignore and other ignore files
+    pub no_ignore: bool,
+}
+
+/// Counts from the last search
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Entries the walk visited (matching or not)
+    pub scanned: usize,
+    /// Matches found, before any limit is applied
+    pub matched: usize,
+    /// Directories left out by ignore or hidden rules (only counted when
+    /// `count_skipped` is set, since it reads every directory twice). Only
+    /// directories the walk finished are counted, so a search that stops
+    /// early does not report the ones it never reached.
+    pub skipped_dirs: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct SearchResult {
     pub path: PathBuf,
@@ -19,12 +46,18 @@ pub struct SearchResult {
 
 pub struct FileSearcher {
     matcher: Matcher,
+    /// Counts from the last search
+    pub stats: SearchStats,
+    /// Count directories skipped by ignore rules into `stats`
+    pub count_skipped: bool,
 }
 
 impl FileSearcher {
     pub fn new() -> Self {
         Self {
             matcher: Matcher::new(Config::DEFAULT),
+            stats: SearchStats::default(),
+            count_skipped: false,
         }
     }
 
@@ -33,11 +66,39 @@ impl FileSearcher {
         base_dir: &Path,
         query: &str,
         max_results: usize,
-        dir_only: bool,
-        exact: bool,
+        options: SearchOptions,
     ) -> Vec<SearchResult> {
+        let mut results: Vec<SearchResult> = Vec::new();
+        self.search_each(base_dir, query, options, |result| {
+            results.push(result);
+            true
+        });
+
+        // スコアで降順ソート
+        results.sort_by_key(|r| std::cmp::Reverse(r.score));
+        results.truncate(max_results);
+        results
+    }
+
+    /// Call `on_match` for each match in the order the walk finds them
+    /// (unranked); the walk stops when it returns false
+    pub fn search_each(
+        &mut self,
+        base_dir: &Path,
+        query: &str,
+        options: SearchOptions,
+        mut on_match: impl FnMut(SearchResult) -> bool,
+    ) {
+        let SearchOptions {
+            dirs_only,
+            exact,
+            hidden,
+            no_ignore,
+        } = options;
+
+        self.stats = SearchStats::default();
         if query.is_empty() {
-            return Vec::new();
+            return;
         }
 
         let is_path_query = query.contains('/');
@@ -63,22 +124,43 @@ impl FileSearcher {
             None
         };
 
-        let mut results: Vec<SearchResult> = Vec::new();
-
         let walker = WalkBuilder::new(base_dir)
-            .hidden(false)
-            .git_ignore(true)
-            .git_global(true)
-            .git_exclude(true)
+            .hidden(!hidden)
+            .ignore(!no_ignore)
+            .git_ignore(!no_ignore)
+            .git_global(!no_ignore)
+            .git_exclude(!no_ignore)
             .max_depth(Some(MAX_SEARCH_DEPTH))
             .build();
 
+        // 開いているディレクトリごとに (深さ, まだ歩いていないサブディレクトリ数)
+        let mut open_dirs: Vec<(usize, usize)> = Vec::new();
         for entry in walker.flatten() {
+            self.stats.scanned += 1;
             let path = entry.path();
             let is_dir = path.is_dir();
 
+            if self.count_skipped {
+                // 深さが戻ったら抜けたディレクトリの残りを除外された数として数える
+                let depth = entry.depth();
+                while let Some(&(open_depth, left)) = open_dirs.last()
+                    && open_depth >= depth
+                {
+                    self.stats.skipped_dirs += left;
+                    open_dirs.pop();
+                }
+                if entry.file_type().is_some_and(|t| t.is_dir()) {
+                    if let Some((_, left)) = open_dirs.last_mut() {
+                        *left = left.saturating_sub(1);
+                    }
+                    if depth < MAX_SEARCH_DEPTH {
+                        open_dirs.push((depth, count_subdirs(path)));
+                    }
+                }
+            }
+
             // ディレクトリのみモードの場合、ファイルをスキップ
-            if dir_only && !is_dir {
+            if dirs_only && !is_dir {
                 continue;
             }
 
@@ -113,13 +195,17 @@ impl FileSearcher {
                     file_name_lower == query_lower
                 };
 
-                if matches {
-                    results.push(SearchResult {
-                        path: path.to_path_buf(),
-                        display_path,
-                        score: EXACT_MATCH_SCORE,
-                        is_dir,
-                    });
+                if !matches {
+                    continue;
+                }
+                self.stats.matched += 1;
+                if !on_match(SearchResult {
+                    path: path.to_path_buf(),
+                    display_path,
+                    score: EXACT_MATCH_SCORE,
+                    is_dir,
+                }) {
+                    return;
                 }
             } else {
                 // ファジーマッチモード
@@ -139,29 +225,84 @@ impl FileSearcher {
                         continue;
                     }
 
-                    results.push(SearchResult {
+                    self.stats.matched += 1;
+                    if !on_match(SearchResult {
                         path: path.to_path_buf(),
                         display_path,
                         score,
                         is_dir,
-                    });
+                    }) {
+                        return;
+                    }
                 }
             }
         }
+        // 最後まで歩いたので、開いたままのディレクトリに残った分も除外されたもの
+        // （途中で止めたときは上で return し、まだ見ていない分は数えない）
+        self.stats.skipped_dirs += open_dirs.iter().map(|&(_, left)| left).sum::<usize>();
+    }
 
-        // スコアで降順ソート
-        results.sort_by(|a, b| b.score.cmp(&a.score));
-        results.truncate(max_results);
-        results
+    /// Indices of `candidates` that fuzzy-match `query`, best first
+    /// (all of them, in order, for an empty query)
+    pub fn rank(&mut self, candidates: &[String], query: &str) -> Vec<usize> {
+        if query.is_empty() {
+            return (0..candidates.len()).collect();
+        }
+        let pattern = Pattern::new(
+            query,
+            CaseMatching::Smart,
+            Normalization::Smart,
+            AtomKind::Fuzzy,
+        );
+        let mut buf = Vec::new();
+        let mut scored: Vec<(usize, u32)> = candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, candidate)| {
+                let haystack = Utf32Str::new(candidate, &mut buf);
+                pattern
+                    .score(haystack, &mut self.matcher)
+                    .map(|score| (i, score))
+            })
+            .collect();
+        // 同点のものは入力順のまま
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        scored.into_iter().map(|(i, _)| i).collect()
     }
 }
 
+/// Subdirectories directly under `dir` (symlinks are not followed, as in the walk)
+fn count_subdirs(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs::{self, File};
     use tempfile::TempDir;
 
+    fn dirs_only() -> SearchOptions {
+        SearchOptions {
+            dirs_only: true,
+            ..SearchOptions::default()
+        }
+    }
+
+    fn exact() -> SearchOptions {
+        SearchOptions {
+            exact: true,
+            ..SearchOptions::default()
+        }
+    }
+
     fn setup_test_dir() -> TempDir {
         let temp_dir = TempDir::new().unwrap();
         let base = temp_dir.path();
@@ -181,11 +322,83 @@ mod tests {
         temp_dir
     }
 
+    #[test]
+    fn test_rank_candidates() {
+        let candidates: Vec<String> = ["src/main.rs", "README.md", "src/lib.rs", "docs/main.md"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut searcher = FileSearcher::new();
+        assert_eq!(searcher.rank(&candidates, ""), vec![0, 1, 2, 3]);
+
+        let ranked = searcher.rank(&candidates, "main");
+        assert_eq!(ranked.len(), 2);
+        assert!(ranked.contains(&0) && ranked.contains(&3));
+        assert_eq!(searcher.rank(&candidates, "lib"), vec![2]);
+        assert!(searcher.rank(&candidates, "zzz").is_empty());
+    }
+
+    #[test]
+    fn test_scanned_counts_walked_entries() {
+        let temp_dir = setup_test_dir();
+        let mut searcher = FileSearcher::new();
+        let results = searcher.search(temp_dir.path(), "main", 10, SearchOptions::default());
+        // ベースディレクトリ + 4 ディレクトリ + 6 ファイル
+        assert_eq!(searcher.stats.scanned, 11);
+        assert_eq!(searcher.stats.matched, results.len());
+        assert!(results.len() < searcher.stats.scanned);
+        assert_eq!(searcher.stats.skipped_dirs, 0);
+
+        searcher.search(temp_dir.path(), "", 10, SearchOptions::default());
+        assert_eq!(searcher.stats, SearchStats::default());
+    }
+
+    #[test]
+    fn test_stats_count_ignored_dirs() {
+        let temp_dir = setup_test_dir();
+        fs::write(temp_dir.path().join(".ignore"), "src/\n").unwrap();
+        fs::create_dir(temp_dir.path().join(".cache")).unwrap();
+        let mut searcher = FileSearcher::new();
+        let options = SearchOptions {
+            hidden: false,
+            ..SearchOptions::default()
+        };
+        searcher.search(temp_dir.path(), "main", 10, options);
+        // 数えるよう指定しない限り 0 のまま
+        assert_eq!(searcher.stats.skipped_dirs, 0);
+
+        searcher.count_skipped = true;
+        searcher.search(temp_dir.path(), "main", 10, options);
+        // src/ は .ignore で、.cache は隠しディレクトリとして除外される
+        assert_eq!(searcher.stats.skipped_dirs, 2);
+        // src/main.rs は除外され tests/test_main.rs だけが残る
+        assert_eq!(searcher.stats.matched, 1);
+    }
+
+    #[test]
+    fn test_stats_skip_nothing_when_stopped_early() {
+        let temp_dir = TempDir::new().unwrap();
+        for dir in ["a1", "a2", "zz1", "zz2"] {
+            fs::create_dir(temp_dir.path().join(dir)).unwrap();
+        }
+        fs::write(temp_dir.path().join(".ignore"), "zz1/\nzz2/\n").unwrap();
+        let mut searcher = FileSearcher::new();
+        searcher.count_skipped = true;
+
+        searcher.search_each(temp_dir.path(), "a", SearchOptions::default(), |_| true);
+        assert_eq!(searcher.stats.skipped_dirs, 2);
+
+        // 最初の一致で止めると、ベースディレクトリは歩き終わっていない
+        searcher.search_each(temp_dir.path(), "a", SearchOptions::default(), |_| false);
+        assert_eq!(searcher.stats.matched, 1);
+        assert_eq!(searcher.stats.skipped_dirs, 0);
+    }
+
     #[test]
     fn test_empty_query_returns_empty() {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(temp_dir.path(), "", 10, false, false);
+        let results = searcher.search(temp_dir.path(), "", 10, SearchOptions::default());
         assert!(results.is_empty());
     }
 
@@ -193,16 +406,31 @@ mod tests {
     fn test_fuzzy_search_finds_files() {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(temp_dir.path(), "main", 10, false, false);
+        let results = searcher.search(temp_dir.path(), "main", 10, SearchOptions::default());
         assert!(!results.is_empty());
         assert!(results.iter().any(|r| r.display_path.contains("main")));
     }
 
+    #[test]
+    fn test_search_each_stops_when_asked() {
+        let temp_dir = setup_test_dir();
+        let mut searcher = FileSearcher::new();
+        let all = searcher.search(temp_dir.path(), "rs", 100, SearchOptions::default());
+        assert!(all.len() > 1);
+
+        let mut seen = Vec::new();
+        searcher.search_each(temp_dir.path(), "rs", SearchOptions::default(), |result| {
+            seen.push(result);
+            false
+        });
+        assert_eq!(seen.len(), 1);
+    }
+
     #[test]
     fn test_exact_match() {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(temp_dir.path(), "main.rs", 10, false, true);
+        let results = searcher.search(temp_dir.path(), "main.rs", 10, exact());
         assert!(!results.is_empty());
         assert!(
             results
@@ -215,7 +443,7 @@ mod tests {
     fn test_dir_only_mode() {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(temp_dir.path(), "src", 10, true, false);
+        let results = searcher.search(temp_dir.path(), "src", 10, dirs_only());
         assert!(results.iter().all(|r| r.is_dir));
     }
 
@@ -223,7 +451,7 @@ mod tests {
     fn test_path_query() {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(temp_dir.path(), "src/main", 10, false, false);
+        let results = searcher.search(temp_dir.path(), "src/main", 10, SearchOptions::default());
         assert!(!results.is_empty());
         assert!(
             results
@@ -236,7 +464,7 @@ mod tests {
     fn test_max_results_limit() {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(temp_dir.path(), "r", 2, false, false);
+        let results = searcher.search(temp_dir.path(), "r", 2, SearchOptions::default());
         assert!(results.len() <= 2);
     }
 
@@ -244,7 +472,7 @@ mod tests {
     fn test_results_sorted_by_score() {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(temp_dir.path(), "main", 10, false, false);
+        let results = searcher.search(temp_dir.path(), "main", 10, SearchOptions::default());
         for i in 1..results.len() {
             assert!(results[i - 1].score >= results[i].score);
         }
@@ -254,7 +482,7 @@ mod tests {
     fn test_exact_match_uses_constant_score() {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(temp_dir.path(), "main.rs", 10, false, true);
+        let results = searcher.search(temp_dir.path(), "main.rs", 10, exact());
         assert!(!results.is_empty());
         // All exact matches should have EXACT_MATCH_SCORE
         for result in &results {
@@ -272,14 +500,19 @@ mod tests {
     fn test_max_results_zero_returns_empty() {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(temp_dir.path(), "main", 0, false, false);
+        let results = searcher.search(temp_dir.path(), "main", 0, SearchOptions::default());
         assert!(results.is_empty());
     }
 
     #[test]
     fn test_search_nonexistent_directory() {
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(Path::new("/nonexistent/path"), "test", 10, false, false);
+        let results = searcher.search(
+            Path::new("/nonexistent/path"),
+            "test",
+            10,
+            SearchOptions::default(),
+        );
         assert!(results.is_empty());
     }
 
@@ -288,7 +521,7 @@ mod tests {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
         // Search for nested path
-        let results = searcher.search(temp_dir.path(), "docs/api", 10, true, false);
+        let results = searcher.search(temp_dir.path(), "docs/api", 10, dirs_only());
         assert!(results.iter().any(|r| r.display_path.contains("api")));
     }
 
@@ -296,16 +529,51 @@ mod tests {
     fn test_exact_match_no_match() {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
-        let results = searcher.search(temp_dir.path(), "nonexistent.xyz", 10, false, true);
+        let results = searcher.search(temp_dir.path(), "nonexistent.xyz", 10, exact());
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn test_hidden_option_includes_hidden_entries() {
+        let temp_dir = setup_test_dir();
+        File::create(temp_dir.path().join(".main_hidden")).unwrap();
+        let mut searcher = FileSearcher::new();
+
+        // 既定では隠しファイルは探さない（-H で含める）
+        let results = searcher.search(temp_dir.path(), "main", 10, SearchOptions::default());
+        assert!(!results.iter().any(|r| r.display_path == ".main_hidden"));
+
+        let options = SearchOptions {
+            hidden: true,
+            ..SearchOptions::default()
+        };
+        let results = searcher.search(temp_dir.path(), "main", 10, options);
+        assert!(results.iter().any(|r| r.display_path == ".main_hidden"));
+    }
+
+    #[test]
+    fn test_no_ignore_option_includes_ignored_entries() {
+        let temp_dir = setup_test_dir();
+        fs::write(temp_dir.path().join(".ignore"), "src/\n").unwrap();
+        let mut searcher = FileSearcher::new();
+
+        let results = searcher.search(temp_dir.path(), "main.rs", 10, exact());
+        assert!(!results.iter().any(|r| r.display_path.starts_with("src")));
+
+        let options = SearchOptions {
+            no_ignore: true,
+            ..exact()
+        };
+        let results = searcher.search(temp_dir.path(), "main.rs", 10, options);
+        assert!(results.iter().any(|r| r.display_path.starts_with("src")));
+    }
+
     #[test]
     fn test_fuzzy_search_partial_match() {
         let temp_dir = setup_test_dir();
         let mut searcher = FileSearcher::new();
         // Search with partial name
-        let results = searcher.search(temp_dir.path(), "mai", 10, false, false);
+        let results = searcher.search(temp_dir.path(), "mai", 10, SearchOptions::default());
         assert!(results.iter().any(|r| r.display_path.contains("main")));
     }
 }
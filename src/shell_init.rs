@@ -0,0 +1,75 @@
+use clap::ValueEnum;
+
+/// Shells `vfv shell-init` can emit integration code for
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InitShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+const BASH_FUNCTION: &str = r#"vcd() {
+  local tmp dir
+  tmp="$(mktemp)" || return
+  command vfv --choose-dir "$tmp" "$@"
+  dir="$(cat "$tmp")"
+  rm -f "$tmp"
+  [ -n "$dir" ] && [ "$dir" != "$PWD" ] && cd -- "$dir"
+}
+"#;
+
+const BASH_BINDING: &str = r#"bind -x '"\C-f": vcd'
+"#;
+
+const ZSH_BINDING: &str = r#"vfv-cd-widget() {
+  vcd < /dev/tty
+  zle reset-prompt
+}
+zle -N vfv-cd-widget
+bindkey '^F' vfv-cd-widget
+"#;
+
+const FISH_FUNCTION: &str = r#"function vcd
+    set -l tmp (mktemp); or return
+    command vfv --choose-dir $tmp $argv
+    set -l dir (cat $tmp)
+    rm -f $tmp
+    test -n "$dir"; and test "$dir" != "$PWD"; and cd $dir
+end
+"#;
+
+const FISH_BINDING: &str = r#"bind \cf 'vcd; commandline -f repaint'
+"#;
+
+/// Shell code defining `vcd` (browse, then cd to the directory vfv quit in),
+/// plus a Ctrl+F binding for it when `bind` is set
+pub fn script(shell: InitShell, bind: bool) -> String {
+    let (function, binding) = match shell {
+        InitShell::Bash => (BASH_FUNCTION, BASH_BINDING),
+        // zsh は bash と同じ関数定義をそのまま使える
+        InitShell::Zsh => (BASH_FUNCTION, ZSH_BINDING),
+        InitShell::Fish => (FISH_FUNCTION, FISH_BINDING),
+    };
+    let mut script = function.to_string();
+    if bind {
+        script.push_str(binding);
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_binds_only_when_asked() {
+        for shell in [InitShell::Bash, InitShell::Zsh, InitShell::Fish] {
+            let plain = script(shell, false);
+            assert!(plain.contains("vcd"));
+            assert!(plain.contains("--choose-dir"));
+            assert!(!plain.contains("bind"));
+            assert!(script(shell, true).contains("bind"));
+        }
+        assert!(script(InitShell::Zsh, true).contains("bindkey '^F' vfv-cd-widget"));
+    }
+}
@@ -0,0 +1,80 @@
+/// Segments a `status_bar` template can use
+pub const SEGMENTS: &[&str] = &[
+    "mode",
+    "selection",
+    "position",
+    "marks",
+    "branch",
+    "free",
+    "hints",
+];
+
+/// Replace each `{segment}` in `template` with `value(segment)`; unknown
+/// names and unmatched braces are kept as written
+pub fn expand(template: &str, value: impl Fn(&str) -> String) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        match after.find('}') {
+            Some(close) if is_segment(&after[..close]) => {
+                out.push_str(&value(&after[..close]));
+                rest = &after[close + 1..];
+            }
+            _ => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Names in braces that are not segments, for `vfv config check`
+pub fn unknown_segments(template: &str) -> Vec<String> {
+    let mut unknown = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let after = &rest[open + 1..];
+        let Some(close) = after.find('}') else {
+            break;
+        };
+        let name = &after[..close];
+        if !is_segment(name) {
+            unknown.push(name.to_string());
+        }
+        rest = &after[close + 1..];
+    }
+    unknown
+}
+
+fn is_segment(name: &str) -> bool {
+    SEGMENTS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_segments() {
+        let value = |name: &str| match name {
+            "mode" => "NORMAL".to_string(),
+            "branch" => "main".to_string(),
+            _ => String::new(),
+        };
+        assert_eq!(
+            expand("{mode} | {branch}{marks} {nope} {", value),
+            "NORMAL | main {nope} {"
+        );
+        assert_eq!(expand("plain", value), "plain");
+    }
+
+    #[test]
+    fn test_unknown_segments() {
+        assert!(unknown_segments("{mode} {free} {hints}").is_empty());
+        assert_eq!(unknown_segments("{mode} {brnch} {"), vec!["brnch"]);
+    }
+}
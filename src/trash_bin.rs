@@ -0,0 +1,150 @@
+use std::io;
+use std::path::PathBuf;
+
+/// An item in the system trash
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    pub name: String,
+    /// Where the item was deleted from (unknown on macOS)
+    pub original_path: Option<PathBuf>,
+    /// Deletion time as seconds since the Unix epoch
+    pub deleted: Option<i64>,
+    handle: platform::Handle,
+}
+
+/// List trashed items, most recently deleted first
+pub fn list() -> io::Result<Vec<TrashEntry>> {
+    let mut entries = platform::list()?;
+    entries.sort_by(|a, b| b.deleted.cmp(&a.deleted).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+/// Move an item back to where it was deleted from
+pub fn restore(entry: TrashEntry) -> io::Result<()> {
+    platform::restore(entry)
+}
+
+/// Permanently delete an item from the trash
+pub fn purge(entry: TrashEntry) -> io::Result<()> {
+    platform::purge(entry)
+}
+
+/// Whether restoring is possible on this platform
+pub fn can_restore() -> bool {
+    platform::CAN_RESTORE
+}
+
+// Linux などの Freedesktop 準拠環境と Windows は trash クレートで一覧・復元できる
+#[cfg(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+mod platform {
+    use super::TrashEntry;
+    use std::io;
+    use trash::os_limited;
+
+    pub type Handle = trash::TrashItem;
+
+    pub const CAN_RESTORE: bool = true;
+
+    pub fn list() -> io::Result<Vec<TrashEntry>> {
+        let items = os_limited::list().map_err(to_io_error)?;
+        Ok(items
+            .into_iter()
+            .map(|item| TrashEntry {
+                name: item.name.to_string_lossy().to_string(),
+                original_path: Some(item.original_path()),
+                deleted: Some(item.time_deleted),
+                handle: item,
+            })
+            .collect())
+    }
+
+    pub fn restore(entry: TrashEntry) -> io::Result<()> {
+        os_limited::restore_all([entry.handle]).map_err(to_io_error)
+    }
+
+    pub fn purge(entry: TrashEntry) -> io::Result<()> {
+        os_limited::purge_all([entry.handle]).map_err(to_io_error)
+    }
+
+    fn to_io_error(e: trash::Error) -> io::Error {
+        match e {
+            trash::Error::RestoreCollision { path, .. } => io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' already exists", path.display()),
+            ),
+            e => io::Error::other(e),
+        }
+    }
+}
+
+// macOS は元の場所を取得できないため ~/.Trash の一覧と削除のみ
+#[cfg(not(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+mod platform {
+    use super::TrashEntry;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+    use std::time::UNIX_EPOCH;
+
+    pub type Handle = PathBuf;
+
+    pub const CAN_RESTORE: bool = false;
+
+    fn trash_dir() -> Option<PathBuf> {
+        directories::BaseDirs::new().map(|dirs| dirs.home_dir().join(".Trash"))
+    }
+
+    pub fn list() -> io::Result<Vec<TrashEntry>> {
+        let Some(dir) = trash_dir() else {
+            return Ok(Vec::new());
+        };
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(dir)?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == ".DS_Store" {
+                continue;
+            }
+            // 削除日時は記録されないので更新日時で代用
+            let deleted = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64);
+            entries.push(TrashEntry {
+                name,
+                original_path: None,
+                deleted,
+                handle: entry.path(),
+            });
+        }
+        Ok(entries)
+    }
+
+    pub fn restore(_entry: TrashEntry) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Restoring is not supported on this platform",
+        ))
+    }
+
+    pub fn purge(entry: TrashEntry) -> io::Result<()> {
+        crate::file_ops::delete(&entry.handle, false)
+    }
+}
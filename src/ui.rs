@@ -1,14 +1,43 @@
 use ratatui::{
     Frame,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, HorizontalAlignment, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        Clear, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState,
+    },
 };
 
-use crate::app::{App, InputMode};
+use crate::app::{App, InputMode, PermissionEdit, TransferProgress};
+use crate::confirm::{Confirm, Subject};
+use crate::ellipsis;
+use crate::file_browser::{FileBrowser, FileEntry};
+use crate::git::DiffMarker;
+use crate::icons;
+use crate::keymap::{Action, KeyContext};
+use crate::notify::Level;
+use crate::owners::Owners;
+use crate::palette;
+use crate::preview::PreviewContent;
+use crate::prompt::TextInput;
+use crate::search::SearchOptions;
+use crate::status_bar;
+use crate::ui_theme::UiTheme;
+use crate::widget_style::WidgetStyle;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use syntect::highlighting::Style as SyntectStyle;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 pub fn draw(frame: &mut Frame, app: &mut App) {
+    app.set_screen_width(frame.area().width);
+    if fullscreen_preview(app) {
+        draw_fullscreen_preview(frame, app);
+        return;
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -23,57 +52,470 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
     draw_footer(frame, app, chunks[2]);
 }
 
+/// プレビューを全画面で表示中か（ファイル一覧の横のプレビューは対象外）
+fn fullscreen_preview(app: &App) -> bool {
+    app.fullscreen_preview
+        && matches!(
+            app.input_mode,
+            InputMode::Preview | InputMode::Visual | InputMode::GotoLine
+        )
+}
+
+/// 全画面プレビュー: 範囲選択と行番号の入力中だけフッターを出す
+fn draw_fullscreen_preview(frame: &mut Frame, app: &mut App) {
+    let area = frame.area();
+    if app.input_mode == InputMode::Preview {
+        draw_preview(frame, app, area);
+    } else {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(area);
+        draw_preview(frame, app, chunks[0]);
+        draw_footer(frame, app, chunks[1]);
+    }
+    draw_toasts(frame, app, area);
+}
+
 fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = app.config.ui_theme;
     let (content, style) = match app.input_mode {
-        InputMode::SearchInput | InputMode::SearchResult => {
-            let text = format!("/{}", app.search_input);
-            (
-                text,
+        InputMode::SearchInput => {
+            // 検索プロンプトの横に記憶中のフラグを表示
+            let style = theme.style(
                 Style::default()
                     .fg(Color::Yellow)
                     .add_modifier(Modifier::BOLD),
+            );
+            let mut spans = vec![Span::styled(format!("/{}", app.search_input.text()), style)];
+            spans.extend(search_flag_spans(&app.search_options, theme));
+            frame.render_widget(Paragraph::new(Line::from(spans)), area);
+            frame.set_cursor_position((area.x + 1 + app.search_input.cursor_width(), area.y));
+            return;
+        }
+        InputMode::SearchResult => {
+            let text = format!("/{}", app.search_input.text());
+            (
+                text,
+                theme.style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            )
+        }
+        InputMode::PathList => {
+            let text = format!("> {}", app.path_filter);
+            (
+                text,
+                theme.style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
             )
         }
         InputMode::Searching => {
             let spinner = app.spinner_char();
-            let text = format!("{} /{}", spinner, app.search_input);
+            let text = format!("{} /{}", spinner, app.search_input.text());
             (
                 text,
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
+                theme.style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
             )
         }
         _ => {
-            let path_str = app.browser.current_dir.to_string_lossy().to_string();
-            (
-                path_str,
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
-            )
+            frame.render_widget(Paragraph::new(breadcrumb_line(app)), area);
+            return;
         }
     };
-    let header = Paragraph::new(content).style(style);
+    let header = Paragraph::new(ellipsis::middle(&content, area.width as usize)).style(style);
     frame.render_widget(header, area);
 }
 
+/// 現在のパス（上位のディレクトリはクリックや b + 数字で移動できる）
+fn breadcrumb_line(app: &App) -> Line<'static> {
+    let theme = app.config.ui_theme;
+    let crumbs = app.breadcrumbs();
+    let ancestor = theme.style(Style::default().fg(Color::Cyan));
+    let separator = Span::styled(std::path::MAIN_SEPARATOR_STR, ancestor);
+    let mut spans = Vec::new();
+    let mut column = 0;
+    for (index, crumb) in crumbs.iter().enumerate() {
+        if crumb.columns.start > column {
+            spans.push(separator.clone());
+        }
+        column = crumb.columns.end;
+        if let Some(number) = crumb.number {
+            spans.push(Span::styled(
+                format!("{}:", number),
+                theme.style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            ));
+        }
+        let style = if index + 1 == crumbs.len() {
+            ancestor.add_modifier(Modifier::BOLD)
+        } else {
+            ancestor
+        };
+        spans.push(Span::styled(crumb.name.clone(), style));
+    }
+    Line::from(spans)
+}
+
 fn draw_main(frame: &mut Frame, app: &mut App, area: Rect) {
-    match app.input_mode {
-        InputMode::Preview => draw_preview(frame, app, area),
+    // 確認ダイアログは問い合わせ元の画面の上に重ねる
+    let mode = match app.confirm {
+        Some(ref confirm) => match confirm.subject {
+            Subject::UsageDelete => InputMode::DiskUsage,
+            Subject::Purge => InputMode::Trash,
+            Subject::Delete | Subject::PasteConflict => InputMode::Normal,
+        },
+        None => app.input_mode,
+    };
+    match mode {
+        InputMode::Preview | InputMode::Visual | InputMode::GotoLine => {
+            draw_preview(frame, app, area)
+        }
         InputMode::SearchInput => draw_search_input(frame, app, area),
         InputMode::Searching => draw_searching(frame, app, area),
         InputMode::SearchResult => draw_search_results(frame, app, area),
-        InputMode::Help => draw_help(frame, area),
-        InputMode::Normal | InputMode::JumpInput => draw_file_list(frame, app, area),
+        InputMode::Trash => draw_trash(frame, app, area),
+        InputMode::Recent => draw_recent(frame, app, area),
+        InputMode::PathList => draw_path_list(frame, app, area),
+        InputMode::DiskUsage => draw_disk_usage(frame, app, area),
+        InputMode::Normal
+        | InputMode::JumpInput
+        | InputMode::Confirm
+        | InputMode::Rename
+        | InputMode::NewFile
+        | InputMode::NewDir
+        | InputMode::Archive
+        | InputMode::ZoxideInput
+        | InputMode::MarkRange
+        | InputMode::Chmod
+        | InputMode::OpenWith
+        | InputMode::Places
+        | InputMode::FoldInput
+        | InputMode::SetBookmark
+        | InputMode::JumpBookmark
+        | InputMode::Breadcrumb
+        | InputMode::Command
+        | InputMode::Help => draw_file_list(frame, app, area),
+    }
+
+    if let Some(ref edit) = app.permission_edit {
+        draw_chmod_dialog(frame, edit, &app.config.style, app.config.ui_theme, area);
+    }
+    if app.input_mode == InputMode::OpenWith {
+        draw_open_with_menu(frame, app, area);
+    }
+    if app.input_mode == InputMode::Places {
+        draw_places_menu(frame, app, area);
+    }
+    if app.input_mode == InputMode::Help {
+        draw_help(frame, app, area);
+    }
+    if let Some(ref confirm) = app.confirm {
+        draw_confirm_dialog(frame, confirm, &app.config.style, app.config.ui_theme, area);
+    }
+    draw_toasts(frame, app, area);
+}
+
+/// 通知を右上に古い順に積み、クリック判定用に位置を記録
+fn draw_toasts(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.ui_theme;
+    if app.toasts.is_empty() {
+        app.toast_areas.clear();
+        return;
+    }
+    let max_width = (area.width * 2 / 3).max(20).min(area.width);
+    let mut areas = Vec::new();
+    let mut y = area.y;
+    for toast in app.toasts.iter() {
+        let color = match toast.level {
+            Level::Info => Color::Cyan,
+            Level::Warn => Color::Yellow,
+            Level::Error => Color::Red,
+        };
+        let width = (toast.text.width() as u16 + 4).clamp(12, max_width);
+        let rows = wrap_text(&toast.text, width.saturating_sub(4) as usize);
+        let height = rows.len() as u16 + 2;
+        if y + height > area.bottom() {
+            break;
+        }
+        let toast_area = Rect::new(area.right() - width, y, width, height);
+        let block = app
+            .config
+            .style
+            .popup_block()
+            .border_style(theme.style(Style::default().fg(color)));
+        let lines: Vec<Line> = rows
+            .into_iter()
+            .map(|row| Line::from(format!(" {}", row)))
+            .collect();
+        frame.render_widget(Clear, toast_area);
+        frame.render_widget(Paragraph::new(lines).block(block), toast_area);
+        areas.push(toast_area);
+        y += height;
+    }
+    app.toast_areas = areas;
+}
+
+/// 表示幅 width ごとに文字単位で折り返す
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut rows = vec![String::new()];
+    let mut column = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if column + w > width.max(1) && column > 0 {
+            rows.push(String::new());
+            column = 0;
+        }
+        rows.last_mut().unwrap().push(c);
+        column += w;
+    }
+    rows
+}
+
+/// 領域の中央に置くダイアログの位置（領域より大きければ縮める）
+fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
     }
 }
 
-fn draw_search_input(frame: &mut Frame, _app: &App, area: Rect) {
-    let block = Block::default()
-        .borders(Borders::ALL)
+fn draw_open_with_menu(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = app.config.ui_theme;
+    let items: Vec<ListItem> = app
+        .open_with
+        .iter()
+        .enumerate()
+        .map(|(i, opener)| {
+            let key = if i < 9 {
+                format!("{} ", i + 1)
+            } else {
+                "  ".to_string()
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(key, theme.style(Style::default().fg(Color::DarkGray))),
+                Span::raw(opener.name.clone()),
+                Span::styled(
+                    format!("  {}", opener.command),
+                    theme.style(Style::default().fg(Color::DarkGray)),
+                ),
+            ]))
+        })
+        .collect();
+
+    let width = app
+        .open_with
+        .iter()
+        .map(|o| o.name.width() + o.command.width() + 6)
+        .max()
+        .unwrap_or(0)
+        .max(24) as u16;
+    let dialog = centered_rect(area, width + 2, app.open_with.len() as u16 + 2);
+    let block = app
+        .config
+        .style
+        .popup_block()
+        .title(" Open with ")
+        .border_style(theme.style(Style::default().fg(Color::Yellow)));
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(app.config.style.highlight(theme))
+        .highlight_symbol(app.config.style.highlight_symbol.as_str());
+    let mut state = ListState::default();
+    state.select(Some(app.open_with_selected));
+    frame.render_widget(Clear, dialog);
+    frame.render_stateful_widget(list, dialog, &mut state);
+}
+
+fn draw_places_menu(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = app.config.ui_theme;
+    let items: Vec<ListItem> = app
+        .places
+        .iter()
+        .enumerate()
+        .map(|(i, place)| {
+            let key = if i < 9 {
+                format!("{} ", i + 1)
+            } else {
+                "  ".to_string()
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(key, theme.style(Style::default().fg(Color::DarkGray))),
+                Span::raw(place.name.clone()),
+                Span::styled(
+                    format!("  {}", place.path.display()),
+                    theme.style(Style::default().fg(Color::DarkGray)),
+                ),
+            ]))
+        })
+        .collect();
+
+    let width = app
+        .places
+        .iter()
+        .map(|p| p.name.width() + p.path.to_string_lossy().width() + 6)
+        .max()
+        .unwrap_or(0)
+        .max(24) as u16;
+    let dialog = centered_rect(area, width + 2, app.places.len() as u16 + 2);
+    let block = app
+        .config
+        .style
+        .popup_block()
+        .title(" Places ")
+        .border_style(theme.style(Style::default().fg(Color::Yellow)));
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(app.config.style.highlight(theme))
+        .highlight_symbol(app.config.style.highlight_symbol.as_str());
+    let mut state = ListState::default();
+    state.select(Some(app.places_selected));
+    frame.render_widget(Clear, dialog);
+    frame.render_stateful_widget(list, dialog, &mut state);
+}
+
+/// パーミッション編集ダイアログ（一覧の上に重ねて表示）
+/// 確認ダイアログ（選択中の答えを反転表示し、各答えのキーに下線を引く）
+fn draw_confirm_dialog(
+    frame: &mut Frame,
+    confirm: &Confirm,
+    style: &WidgetStyle,
+    theme: UiTheme,
+    area: Rect,
+) {
+    let mut buttons = Vec::new();
+    for (i, choice) in confirm.choices.iter().enumerate() {
+        if i > 0 {
+            buttons.push(Span::raw("  "));
+        }
+        let style = if i == confirm.selected {
+            theme.style(Style::default().fg(Color::Black).bg(Color::Red))
+        } else {
+            theme.style(Style::default().fg(Color::White))
+        };
+        // 押すキーの文字に下線を引く
+        let (before, key, after) = choice.label_parts();
+        buttons.push(Span::styled(format!("[ {}", before), style));
+        buttons.push(Span::styled(key, style.add_modifier(Modifier::UNDERLINED)));
+        buttons.push(Span::styled(format!("{} ]", after), style));
+    }
+    let buttons = Line::from(buttons).centered();
+    let message = Line::from(format!(" {} ", confirm.message)).centered();
+
+    let width = message.width().max(buttons.width()) as u16 + 4;
+    let dialog = centered_rect(area, width.max(30), 6);
+    let block = style
+        .popup_block()
+        .title(format!(" {} ", confirm.title))
+        .border_style(theme.style(Style::default().fg(Color::Red)));
+    let lines = vec![Line::default(), message, Line::default(), buttons];
+    frame.render_widget(Clear, dialog);
+    frame.render_widget(Paragraph::new(lines).block(block), dialog);
+}
+
+fn draw_chmod_dialog(
+    frame: &mut Frame,
+    edit: &PermissionEdit,
+    style: &WidgetStyle,
+    theme: UiTheme,
+    area: Rect,
+) {
+    let dialog = centered_rect(area, 32, 7);
+
+    let mut mode_line = vec![Span::raw(format!(" mode: {:04o}", edit.mode))];
+    if !edit.input.is_empty() {
+        mode_line.push(Span::styled(
+            format!("  typed: {}", edit.input),
+            theme.style(Style::default().fg(Color::Yellow)),
+        ));
+    }
+    let mut lines = vec![
+        Line::from(mode_line),
+        Line::from(Span::styled(
+            "          r  w  x",
+            theme.style(Style::default().fg(Color::DarkGray)),
+        )),
+    ];
+    for (row, label) in ["owner", "group", "other"].iter().enumerate() {
+        let mut spans = vec![Span::raw(format!(" {:<7}", label))];
+        for (col, flag) in ['r', 'w', 'x'].iter().enumerate() {
+            let cursor = row * 3 + col;
+            let set = edit.mode & PermissionEdit::bit(cursor) != 0;
+            let mut style = if set {
+                theme.style(Style::default().fg(Color::Green))
+            } else {
+                theme.style(Style::default().fg(Color::DarkGray))
+            };
+            if cursor == edit.cursor {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            spans.push(Span::styled(
+                format!(" {} ", if set { *flag } else { '-' }),
+                style,
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let name = edit
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let block = style
+        .popup_block()
+        .title(format!(" chmod {} ", name))
+        .border_style(theme.style(Style::default().fg(Color::Yellow)));
+    frame.render_widget(Clear, dialog);
+    frame.render_widget(Paragraph::new(lines).block(block), dialog);
+}
+
+/// 検索フラグのインジケーター（有効なものを強調表示）
+fn search_flag_spans(options: &SearchOptions, theme: UiTheme) -> Vec<Span<'static>> {
+    let flags = [
+        ("dir", options.dirs_only),
+        ("exact", options.exact),
+        ("hidden", options.hidden),
+        ("no-ignore", options.no_ignore),
+    ];
+
+    let mut spans = vec![Span::raw("  ")];
+    for (label, enabled) in flags {
+        let style = if enabled {
+            theme.style(Style::default().fg(Color::Black).bg(Color::Yellow))
+        } else {
+            theme.style(Style::default().fg(Color::DarkGray))
+        };
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(format!("[{}]", label), style));
+    }
+    spans
+}
+
+fn draw_search_input(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = app.config.ui_theme;
+    let block = app
+        .config
+        .style
+        .block()
         .title("Search (Enter to search)")
-        .border_style(Style::default().fg(Color::Yellow));
+        .border_style(theme.style(Style::default().fg(Color::Yellow)));
 
     let inner_area = block.inner(area);
     frame.render_widget(block, area);
@@ -81,63 +523,135 @@ fn draw_search_input(frame: &mut Frame, _app: &App, area: Rect) {
     let help_lines = vec![
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Usage: ", Style::default().fg(Color::White)),
-            Span::styled("<query> [options]", Style::default().fg(Color::Cyan)),
+            Span::styled("  Usage: ", theme.style(Style::default().fg(Color::White))),
+            Span::styled(
+                "<query> [options]",
+                theme.style(Style::default().fg(Color::Cyan)),
+            ),
         ]),
         Line::from(""),
         Line::from(vec![Span::styled(
             "  Options:",
-            Style::default().fg(Color::White),
+            theme.style(Style::default().fg(Color::White)),
         )]),
         Line::from(vec![
-            Span::styled("    -d, --dir    ", Style::default().fg(Color::Yellow)),
-            Span::styled("Directories only", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                "    -d, --dir    ",
+                theme.style(Style::default().fg(Color::Yellow)),
+            ),
+            Span::styled(
+                "Directories only",
+                theme.style(Style::default().fg(Color::DarkGray)),
+            ),
         ]),
         Line::from(vec![
-            Span::styled("    -e, --exact  ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "    -e, --exact  ",
+                theme.style(Style::default().fg(Color::Yellow)),
+            ),
             Span::styled(
                 "Exact match (no fuzzy)",
-                Style::default().fg(Color::DarkGray),
+                theme.style(Style::default().fg(Color::DarkGray)),
             ),
         ]),
         Line::from(vec![
-            Span::styled("    -b, --base   ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "    -H, --hidden ",
+                theme.style(Style::default().fg(Color::Yellow)),
+            ),
+            Span::styled(
+                "Include hidden files",
+                theme.style(Style::default().fg(Color::DarkGray)),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "    -I, --no-ignore ",
+                theme.style(Style::default().fg(Color::Yellow)),
+            ),
+            Span::styled(
+                "Do not respect .gitignore",
+                theme.style(Style::default().fg(Color::DarkGray)),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "    -b, --base   ",
+                theme.style(Style::default().fg(Color::Yellow)),
+            ),
             Span::styled(
                 "Search base directory",
-                Style::default().fg(Color::DarkGray),
+                theme.style(Style::default().fg(Color::DarkGray)),
+            ),
+        ]),
+        Line::from(""),
+        Line::from(vec![Span::styled(
+            "  Toggle flags (remembered for this session):",
+            theme.style(Style::default().fg(Color::White)),
+        )]),
+        Line::from(vec![
+            Span::styled(
+                "    Ctrl+d/e     ",
+                theme.style(Style::default().fg(Color::Yellow)),
+            ),
+            Span::styled(
+                "Directories only / Exact",
+                theme.style(Style::default().fg(Color::DarkGray)),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "    Ctrl+a/g     ",
+                theme.style(Style::default().fg(Color::Yellow)),
+            ),
+            Span::styled(
+                "Hidden files / No ignore",
+                theme.style(Style::default().fg(Color::DarkGray)),
             ),
         ]),
         Line::from(""),
         Line::from(vec![Span::styled(
             "  Examples:",
-            Style::default().fg(Color::White),
+            theme.style(Style::default().fg(Color::White)),
         )]),
         Line::from(vec![
-            Span::styled("    main.rs      ", Style::default().fg(Color::Cyan)),
+            Span::styled(
+                "    main.rs      ",
+                theme.style(Style::default().fg(Color::Cyan)),
+            ),
             Span::styled(
                 "Fuzzy search for main.rs",
-                Style::default().fg(Color::DarkGray),
+                theme.style(Style::default().fg(Color::DarkGray)),
             ),
         ]),
         Line::from(vec![
-            Span::styled("    src/main -d  ", Style::default().fg(Color::Cyan)),
+            Span::styled(
+                "    src/main -d  ",
+                theme.style(Style::default().fg(Color::Cyan)),
+            ),
             Span::styled(
                 "Directories containing 'main' under 'src'",
-                Style::default().fg(Color::DarkGray),
+                theme.style(Style::default().fg(Color::DarkGray)),
             ),
         ]),
         Line::from(vec![
-            Span::styled("    config -e    ", Style::default().fg(Color::Cyan)),
+            Span::styled(
+                "    config -e    ",
+                theme.style(Style::default().fg(Color::Cyan)),
+            ),
             Span::styled(
                 "Exact match for 'config'",
-                Style::default().fg(Color::DarkGray),
+                theme.style(Style::default().fg(Color::DarkGray)),
             ),
         ]),
         Line::from(vec![
-            Span::styled("    main -b ~/dev", Style::default().fg(Color::Cyan)),
+            Span::styled(
+                "    main -b ~/dev",
+                theme.style(Style::default().fg(Color::Cyan)),
+            ),
             Span::styled(
                 "Search 'main' under ~/dev",
-                Style::default().fg(Color::DarkGray),
+                theme.style(Style::default().fg(Color::DarkGray)),
             ),
         ]),
     ];
@@ -147,18 +661,21 @@ fn draw_search_input(frame: &mut Frame, _app: &App, area: Rect) {
 }
 
 fn draw_searching(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = app.config.ui_theme;
     let spinner = app.spinner_char();
-    let title = format!("{} Searching: {}", spinner, app.search_input);
+    let title = format!("{} Searching: {}", spinner, app.search_input.text());
 
-    let block = Block::default()
-        .borders(Borders::ALL)
+    let block = app
+        .config
+        .style
+        .block()
         .title(title)
-        .border_style(Style::default().fg(Color::Yellow));
+        .border_style(theme.style(Style::default().fg(Color::Yellow)));
 
     let inner_area = block.inner(area);
     frame.render_widget(block, area);
 
-    let mode = if app.search_dirs_only {
+    let mode = if app.search_options.dirs_only {
         "folders"
     } else {
         "files"
@@ -168,27 +685,30 @@ fn draw_searching(frame: &mut Frame, app: &App, area: Rect) {
         mode,
         app.base_dir.display()
     ))
-    .style(Style::default().fg(Color::DarkGray));
+    .style(theme.style(Style::default().fg(Color::DarkGray)));
     frame.render_widget(text, inner_area);
 }
 
 fn draw_search_results(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.ui_theme;
+    let width = list_text_width(app, area);
     let items: Vec<ListItem> = app
         .search_results
         .iter()
         .map(|result| {
             let (icon, style) = if result.is_dir {
-                ("▸ ", Style::default().fg(Color::Yellow))
+                ("▸ ", theme.style(Style::default().fg(Color::Yellow)))
             } else {
-                ("  ", Style::default().fg(Color::White))
+                ("  ", theme.style(Style::default().fg(Color::White)))
             };
-            let name = format!("{}{}", icon, result.display_path);
+            let path = ellipsis::middle(&result.display_path, width.saturating_sub(2));
+            let name = format!("{}{}", icon, path);
 
             ListItem::new(name).style(style)
         })
         .collect();
 
-    let mode = if app.search_dirs_only {
+    let mode = if app.search_options.dirs_only {
         "Folders"
     } else {
         "All"
@@ -196,222 +716,1169 @@ fn draw_search_results(frame: &mut Frame, app: &mut App, area: Rect) {
     let title = format!(
         "{}: {} ({} results)",
         mode,
-        app.search_input,
+        app.search_input.text(),
         app.search_results.len()
     );
+    let title = ellipsis::middle(&title, width);
 
     let list = List::new(items)
         .block(
-            Block::default()
-                .borders(Borders::ALL)
+            app.config
+                .style
+                .block()
                 .title(title)
-                .border_style(Style::default().fg(Color::Yellow)),
+                .border_style(theme.style(Style::default().fg(Color::Yellow))),
         )
-        .highlight_style(
-            Style::default()
-                .bg(Color::Blue)
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD),
-        );
+        .highlight_style(app.config.style.highlight(theme))
+        .highlight_symbol(app.config.style.highlight_symbol.as_str());
 
     frame.render_stateful_widget(list, area, &mut app.search_list_state);
 }
 
 fn draw_file_list(frame: &mut Frame, app: &mut App, area: Rect) {
-    let items: Vec<ListItem> = app
-        .browser
+    let range = (app.input_mode == InputMode::MarkRange).then(|| app.mark_range());
+    let options = ListOptions {
+        icons: app.config.icons,
+        show_details: app.show_details,
+        dir_sizes: &app.dir_sizes,
+        owners: &app.owners,
+        style: &app.config.style,
+        theme: app.config.ui_theme,
+        narrow: app.narrow,
+    };
+
+    // 狭い端末ではもう一方のペインもプレビューペインも出さない
+    let other = app.other_browser.as_ref().filter(|_| !app.narrow);
+    let Some(other) = other else {
+        // プレビューペインは 2ペイン表示中は出さない
+        let (list_area, preview_area) = if app.show_preview_pane && !app.narrow {
+            let ratio = app.preview_ratio;
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Percentage(100 - ratio),
+                    Constraint::Percentage(ratio),
+                ])
+                .split(area);
+            (chunks[0], Some(chunks[1]))
+        } else {
+            (area, None)
+        };
+        draw_browser(
+            frame,
+            list_area,
+            &app.browser,
+            &mut app.list_state,
+            options,
+            range,
+            true,
+        );
+        if let Some(preview_area) = preview_area {
+            draw_preview(frame, app, preview_area);
+        }
+        return;
+    };
+
+    // 2ペイン表示（フォーカス中のペインが active_pane 側）
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(app.dual_ratio),
+            Constraint::Percentage(100 - app.dual_ratio),
+        ])
+        .split(area);
+    let (active_area, other_area) = if app.active_pane == 0 {
+        (chunks[0], chunks[1])
+    } else {
+        (chunks[1], chunks[0])
+    };
+    draw_browser(
+        frame,
+        active_area,
+        &app.browser,
+        &mut app.list_state,
+        options,
+        range,
+        true,
+    );
+    draw_browser(
+        frame,
+        other_area,
+        other,
+        &mut app.other_list_state,
+        options,
+        None,
+        false,
+    );
+}
+
+/// Display settings shared by both panes
+#[derive(Clone, Copy)]
+struct ListOptions<'a> {
+    icons: icons::IconStyle,
+    show_details: bool,
+    /// Directory sizes measured so far (missing ones show as "-")
+    dir_sizes: &'a HashMap<PathBuf, u64>,
+    owners: &'a Owners,
+    style: &'a WidgetStyle,
+    theme: UiTheme,
+    /// Compact layout: no counts in the title, only the size in details
+    narrow: bool,
+}
+
+fn draw_browser(
+    frame: &mut Frame,
+    area: Rect,
+    browser: &FileBrowser,
+    list_state: &mut ListState,
+    options: ListOptions,
+    range: Option<(usize, usize)>,
+    focused: bool,
+) {
+    let theme = options.theme;
+    // マークがあるときだけマーク列を表示
+    let show_marks = range.is_some() || !browser.marked.is_empty();
+    let width = (options.style.block().inner(area).width as usize)
+        .saturating_sub(options.style.highlight_symbol.width());
+
+    let items: Vec<ListItem> = browser
         .entries
         .iter()
-        .map(|entry| {
-            let (icon, style) = if entry.is_dir {
-                ("▸ ", Style::default().fg(Color::Yellow))
+        .enumerate()
+        .map(|(index, entry)| {
+            let mut style = if entry.is_broken_link {
+                Style::default().fg(Color::Red)
+            } else if entry.is_dir {
+                Style::default().fg(Color::Yellow)
             } else {
-                ("  ", Style::default().fg(Color::White))
+                Style::default().fg(Color::White)
+            };
+            let icon = icons::icon_for(entry, options.icons);
+
+            let in_range = range.is_some_and(|(start, end)| (start..=end).contains(&index));
+            let marked = in_range || browser.is_marked(entry);
+            if marked {
+                style = style.fg(Color::Magenta).add_modifier(Modifier::BOLD);
+            }
+            let style = theme.style(style);
+            let mark = match (show_marks, marked) {
+                (false, _) => "",
+                (true, true) => "*",
+                (true, false) => " ",
             };
-            let name = format!("{}{}", icon, entry.name);
+            let indent = "  ".repeat(entry.depth);
+            let details = options
+                .show_details
+                .then(|| format!("{}  ", entry_details(entry, options)));
+            // 長い名前は拡張子が見えるよう中を省く
+            let prefix = format!("{}{}{}", mark, indent, icon);
+            let room = width.saturating_sub(
+                prefix.width() + details.as_ref().map_or(0, |details| details.width()),
+            );
+            let name = format!(
+                "{}{}",
+                prefix,
+                ellipsis::middle(&entry_display_name(entry), room)
+            );
 
+            if let Some(details) = details {
+                let details =
+                    Span::styled(details, theme.style(Style::default().fg(Color::DarkGray)));
+                return ListItem::new(Line::from(vec![details, Span::styled(name, style)]));
+            }
             ListItem::new(name).style(style)
         })
         .collect();
 
-    let total = app.browser.entries.len();
+    let total = browser.entries.len();
+    let sort = format!(
+        " sort:{}{}{}",
+        browser.sort.label(),
+        if browser.sort_reverse { " rev" } else { "" },
+        if browser.tree_mode { " tree" } else { "" }
+    );
+    let summary = if options.narrow {
+        String::new()
+    } else {
+        listing_summary(browser, options.dir_sizes)
+    };
     let title = if total > 0 {
-        format!("Files [{}/{}]", app.browser.selected_index + 1, total)
+        let marked = browser.marked.len();
+        let marked = if marked > 0 {
+            format!(" ({} marked)", marked)
+        } else {
+            String::new()
+        };
+        format!(
+            "Files [{}/{}]{}{}{}",
+            browser.selected_index + 1,
+            total,
+            summary,
+            sort,
+            marked
+        )
     } else {
-        "Files [empty]".to_string()
+        format!("Files [empty]{}", summary)
     };
 
+    // フォーカスのないペインは控えめに表示
+    let (border_color, highlight) = if focused {
+        (Color::Cyan, options.style.highlight(theme))
+    } else {
+        (
+            Color::DarkGray,
+            theme.style(Style::default().bg(Color::DarkGray)),
+        )
+    };
+    let border_style = theme.style(Style::default().fg(border_color));
+
     let list = List::new(items)
         .block(
-            Block::default()
-                .borders(Borders::ALL)
+            options
+                .style
+                .block()
                 .title(title)
-                .border_style(Style::default().fg(Color::Cyan)),
+                .border_style(border_style),
         )
-        .highlight_style(
-            Style::default()
-                .bg(Color::Blue)
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD),
-        );
+        .highlight_style(highlight)
+        .highlight_symbol(options.style.highlight_symbol.as_str());
+
+    frame.render_stateful_widget(list, area, list_state);
+
+    // 全体が収まらない場合はスクロールバーを右枠上に表示
+    let visible_height = area.height.saturating_sub(2) as usize;
+    if total > visible_height {
+        let state = ScrollbarState::new(total)
+            .position(browser.selected_index)
+            .viewport_content_length(visible_height);
+        draw_scrollbar(frame, area, state, border_style);
+    }
+}
+
+/// 枠の右辺にスクロールバーを描く
+fn draw_scrollbar(frame: &mut Frame, area: Rect, mut state: ScrollbarState, style: Style) {
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .style(style);
+    frame.render_stateful_widget(
+        scrollbar,
+        area.inner(Margin {
+            vertical: 1,
+            horizontal: 0,
+        }),
+        &mut state,
+    );
+}
 
-    frame.render_stateful_widget(list, area, &mut app.list_state);
+/// エントリ名（シンボリックリンクはリンク先も表示）
+/// タイトル用の件数と合計サイズ（例: " 2 dirs, 5 files, 1.2 MB, 3 hidden"）
+///
+/// 合計サイズはディレクトリのサイズがすべて測り終わっているときだけ表示する
+fn listing_summary(browser: &FileBrowser, dir_sizes: &HashMap<PathBuf, u64>) -> String {
+    let dirs = browser.entries.iter().filter(|e| e.is_dir).count();
+    let files = browser.entries.len() - dirs;
+    let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+
+    let mut parts = Vec::new();
+    if !browser.entries.is_empty() {
+        parts.push(plural(dirs, "dir"));
+        parts.push(plural(files, "file"));
+        // ツリー表示で展開した中身は親ディレクトリのサイズに含まれる
+        let size: Option<u64> = browser
+            .entries
+            .iter()
+            .filter(|e| e.depth == 0)
+            .map(|e| {
+                if e.is_dir {
+                    dir_sizes.get(&e.path).copied()
+                } else {
+                    Some(e.size)
+                }
+            })
+            .sum();
+        if let Some(size) = size {
+            parts.push(format_size(size));
+        }
+    }
+    if browser.hidden_count > 0 {
+        parts.push(format!("{} hidden", browser.hidden_count));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", parts.join(", "))
+    }
+}
+
+fn entry_display_name(entry: &FileEntry) -> String {
+    match entry.link_target {
+        Some(ref target) => format!("{} → {}", entry.name, target.display()),
+        None => entry.name.clone(),
+    }
+}
+
+/// Width of the percentage bar in the disk usage list
+const USAGE_BAR_WIDTH: usize = 20;
+
+fn draw_disk_usage(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.ui_theme;
+    let total: u64 = app.usage_entries.iter().map(|e| e.size).sum();
+    let items: Vec<ListItem> = app
+        .usage_entries
+        .iter()
+        .map(|entry| {
+            let ratio = if total > 0 {
+                entry.size as f64 / total as f64
+            } else {
+                0.0
+            };
+            let filled = (ratio * USAGE_BAR_WIDTH as f64).round() as usize;
+            let bar = format!(
+                "{}{}",
+                "█".repeat(filled),
+                " ".repeat(USAGE_BAR_WIDTH - filled)
+            );
+            let name = if entry.is_dir {
+                format!("{}/", entry.name)
+            } else {
+                entry.name.clone()
+            };
+            let name_style = if entry.is_dir {
+                theme.style(Style::default().fg(Color::Blue))
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{:>9} ", format_size(entry.size))),
+                Span::styled(
+                    format!("{:>5.1}% ", ratio * 100.0),
+                    theme.style(Style::default().fg(Color::DarkGray)),
+                ),
+                Span::styled(
+                    format!("[{}] ", bar),
+                    theme.style(Style::default().fg(Color::Green)),
+                ),
+                Span::styled(name, name_style),
+            ]))
+        })
+        .collect();
+
+    let scanning = if app.usage_receiver.is_some() {
+        format!(" {} scanning...", app.spinner_char())
+    } else {
+        String::new()
+    };
+    let title = format!(
+        "Disk usage: {}  total {}{}",
+        app.usage_dir.display(),
+        format_size(total),
+        scanning
+    );
+    let list = List::new(items)
+        .block(
+            app.config
+                .style
+                .block()
+                .title(title)
+                .border_style(theme.style(Style::default().fg(Color::Green))),
+        )
+        .highlight_style(app.config.style.highlight(theme))
+        .highlight_symbol(app.config.style.highlight_symbol.as_str());
+    frame.render_stateful_widget(list, area, &mut app.usage_list_state);
+}
+
+fn draw_recent(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.ui_theme;
+    let width = list_text_width(app, area);
+    let items: Vec<ListItem> = app
+        .recent
+        .files
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            // 消えたファイルは赤で表示
+            let style = if path.is_file() {
+                theme.style(Style::default().fg(Color::White))
+            } else {
+                theme.style(Style::default().fg(Color::Red))
+            };
+            let parent = path
+                .parent()
+                .map(|p| {
+                    let room = width.saturating_sub(name.width() + 2);
+                    ellipsis::middle(&p.display().to_string(), room)
+                })
+                .unwrap_or_default();
+            ListItem::new(Line::from(vec![
+                Span::styled(name, style),
+                Span::styled(
+                    format!("  {}", parent),
+                    theme.style(Style::default().fg(Color::DarkGray)),
+                ),
+            ]))
+        })
+        .collect();
+
+    let title = format!(
+        "Recent files [{}/{}]",
+        app.recent_list_state.selected().unwrap_or(0) + 1,
+        app.recent.files.len()
+    );
+    let list = List::new(items)
+        .block(
+            app.config
+                .style
+                .block()
+                .title(title)
+                .border_style(theme.style(Style::default().fg(Color::Cyan))),
+        )
+        .highlight_style(app.config.style.highlight(theme))
+        .highlight_symbol(app.config.style.highlight_symbol.as_str());
+    frame.render_stateful_widget(list, area, &mut app.recent_list_state);
+}
+
+fn draw_path_list(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.ui_theme;
+    let width = list_text_width(app, area);
+    let items: Vec<ListItem> = app
+        .path_matches
+        .iter()
+        .filter_map(|&i| app.path_list.get(i))
+        // 大量のパスでも描画のたびに stat しないよう、渡されたまま表示する
+        .map(|path| {
+            ListItem::new(ellipsis::middle(path, width))
+                .style(theme.style(Style::default().fg(Color::White)))
+        })
+        .collect();
+
+    let title = format!("Paths ({}/{})", app.path_matches.len(), app.path_list.len());
+    let list = List::new(items)
+        .block(
+            app.config
+                .style
+                .block()
+                .title(title)
+                .border_style(theme.style(Style::default().fg(Color::Yellow))),
+        )
+        .highlight_style(app.config.style.highlight(theme))
+        .highlight_symbol(app.config.style.highlight_symbol.as_str());
+    frame.render_stateful_widget(list, area, &mut app.path_list_state);
+}
+
+fn draw_trash(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.ui_theme;
+    let items: Vec<ListItem> = app
+        .trash_entries
+        .iter()
+        .map(|entry| {
+            let deleted = entry
+                .deleted
+                .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                .map(|time| {
+                    time.with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_else(|| "-".repeat(16));
+            let mut spans = vec![
+                Span::styled(
+                    format!("{} ", deleted),
+                    theme.style(Style::default().fg(Color::DarkGray)),
+                ),
+                Span::raw(entry.name.clone()),
+            ];
+            if let Some(ref original) = entry.original_path {
+                spans.push(Span::styled(
+                    format!("  ← {}", original.display()),
+                    theme.style(Style::default().fg(Color::DarkGray)),
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let title = if app.trash_entries.is_empty() {
+        "Trash [empty]".to_string()
+    } else {
+        format!(
+            "Trash [{}/{}]",
+            app.trash_list_state.selected().unwrap_or(0) + 1,
+            app.trash_entries.len()
+        )
+    };
+    let list = List::new(items)
+        .block(
+            app.config
+                .style
+                .block()
+                .title(title)
+                .border_style(theme.style(Style::default().fg(Color::Red))),
+        )
+        .highlight_style(app.config.style.highlight(theme))
+        .highlight_symbol(app.config.style.highlight_symbol.as_str());
+    frame.render_stateful_widget(list, area, &mut app.trash_list_state);
+}
+
+/// `ls -l` style columns: permissions, owner and group (Unix), size and
+/// modification time
+fn entry_details(entry: &FileEntry, options: ListOptions) -> String {
+    let size = if entry.is_dir {
+        options
+            .dir_sizes
+            .get(&entry.path)
+            .map_or_else(|| "-".to_string(), |&size| format_size(size))
+    } else {
+        format_size(entry.size)
+    };
+    if options.narrow {
+        return format!("{:>9}", size);
+    }
+    // 長い名前は列の幅に収める
+    let column = |name: String| pad_to_width(&ellipsis::middle(&name, 8), 8);
+    let owner = entry
+        .owner
+        .map(|(uid, gid)| {
+            format!(
+                "{} {} ",
+                column(options.owners.user(uid)),
+                column(options.owners.group(gid))
+            )
+        })
+        .unwrap_or_default();
+    let modified = format_modified(entry).unwrap_or_else(|| "-".repeat(16));
+    format!(
+        "{} {}{:>9} {}",
+        entry.permission_string(),
+        owner,
+        size,
+        modified
+    )
+}
+
+/// Modification time as "2024-01-31 12:34" in local time
+fn format_modified(entry: &FileEntry) -> Option<String> {
+    entry.modified.map(|time| {
+        chrono::DateTime::<chrono::Local>::from(time)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    })
+}
+
+/// Size, modification time and permissions of the selected entry for the
+/// footer; a directory's size only once it has been measured
+fn entry_summary(entry: &FileEntry, dir_sizes: &HashMap<PathBuf, u64>) -> String {
+    let size = if entry.is_dir {
+        dir_sizes.get(&entry.path).map(|&size| format_size(size))
+    } else {
+        Some(format_size(entry.size))
+    };
+    size.into_iter()
+        .chain(format_modified(entry))
+        .chain(entry.mode.map(|_| entry.permission_string()))
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// `text` followed by spaces up to `width` columns (`{:<8}` counts chars,
+/// which misaligns names with wide characters)
+fn pad_to_width(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(text.width())))
+}
+
+/// Columns left for an item's text in a list drawn in `area`
+fn list_text_width(app: &App, area: Rect) -> usize {
+    let style = &app.config.style;
+    (style.block().inner(area).width as usize).saturating_sub(style.highlight_symbol.width())
+}
+
+/// Width of the preview gutter: a diff marker, the line number (at least
+/// four digits, more when the file's largest line number needs them) and a space
+fn gutter_width(content: Option<&PreviewContent>) -> usize {
+    let largest = content
+        .and_then(|content| content.lines.iter().map(|line| line.line_number).max())
+        .unwrap_or(0);
+    let digits = largest.checked_ilog10().map_or(1, |log| log as usize + 1);
+    digits.max(4) + 2
+}
+
+/// Split styled segments into rows of at most `width` columns (a wide
+/// character that does not fit starts the next row; see `PreviewLine::rows`)
+fn wrap_segments(segments: &[(SyntectStyle, String)], width: usize) -> Vec<Vec<Span<'static>>> {
+    let mut rows: Vec<Vec<Span<'static>>> = vec![Vec::new()];
+    let mut column = 0;
+
+    for (style, text) in segments {
+        let fg = Color::Rgb(style.foreground.r, style.foreground.g, style.foreground.b);
+        let mut current = String::new();
+        for c in text.chars().filter(|c| !matches!(c, '\n' | '\r')) {
+            let w = c.width().unwrap_or(0);
+            if column + w > width && column > 0 {
+                let row = rows.last_mut().unwrap();
+                row.push(Span::styled(
+                    std::mem::take(&mut current),
+                    Style::default().fg(fg),
+                ));
+                rows.push(Vec::new());
+                column = 0;
+            }
+            current.push(c);
+            column += w;
+        }
+        if !current.is_empty() {
+            rows.last_mut()
+                .unwrap()
+                .push(Span::styled(current, Style::default().fg(fg)));
+        }
+    }
+    rows
 }
 
 fn draw_preview(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.ui_theme;
     let file_name = app
         .browser
         .selected_entry()
-        .map(|e| e.name.clone())
+        .map(entry_display_name)
         .unwrap_or_else(|| "Preview".to_string());
 
+    // 全画面では枠も行番号も出さず、本文に端末の幅をすべて使う
+    let fullscreen = fullscreen_preview(app);
+    let gutter_width = if fullscreen {
+        0
+    } else {
+        gutter_width(app.preview_content.as_ref())
+    };
     // 一時的にinner_areaを計算するためのブロック
-    let temp_block = Block::default().borders(Borders::ALL);
-    let inner_area = temp_block.inner(area);
+    let temp_block = app.config.style.block();
+    let inner_area = if fullscreen {
+        area
+    } else {
+        temp_block.inner(area)
+    };
     let visible_height = inner_area.height as usize;
-    app.set_preview_height(visible_height);
+    // 行番号ガター（差分マーカー + 行番号）を除いた折り返し幅
+    let wrap_width = (inner_area.width as usize)
+        .saturating_sub(gutter_width)
+        .max(1);
+    app.set_preview_size(visible_height, wrap_width);
 
-    // タイトルに位置情報を追加
-    let title = if let Some(ref content) = app.preview_content {
+    // タイトルに位置情報を追加（長いファイル名は中を省く）
+    let position = if let Some(ref content) = app.preview_content {
         let total = content.lines.len();
         let current_line = app.preview_scroll + 1;
-        let end_line = (app.preview_scroll + visible_height).min(total);
-        format!("{} [{}-{}/{}]", file_name, current_line, end_line, total)
+        let end_line = app.preview_visible_end().min(total);
+        let encoding = content
+            .encoding
+            .map(|e| format!(" {}", e))
+            .unwrap_or_default();
+        let truncated = if content.truncated {
+            " [truncated]"
+        } else {
+            ""
+        };
+        format!(
+            " [{}-{}/{}]{}{}",
+            current_line, end_line, total, encoding, truncated
+        )
     } else {
-        file_name
+        String::new()
     };
+    let room = (inner_area.width as usize).saturating_sub(position.width());
+    let title = format!("{}{}", ellipsis::middle(&file_name, room), position);
 
-    let block = Block::default()
-        .borders(Borders::ALL)
+    // ファイル一覧の横に出しているときはフォーカスのないペインとして表示
+    let focused = matches!(
+        app.input_mode,
+        InputMode::Preview | InputMode::Visual | InputMode::GotoLine
+    );
+    let border_style = theme.style(Style::default().fg(if focused {
+        Color::Cyan
+    } else {
+        Color::DarkGray
+    }));
+    let block = app
+        .config
+        .style
+        .block()
         .title(title)
-        .border_style(Style::default().fg(Color::Cyan));
+        .border_style(border_style);
 
-    frame.render_widget(block, area);
+    if !fullscreen {
+        frame.render_widget(block, area);
+    }
 
     if let Some(ref content) = app.preview_content {
         let start = app.preview_scroll;
-        let end = (start + visible_height).min(content.lines.len());
+        let end = app.preview_visible_end();
 
-        let lines: Vec<Line> = content.lines[start..end]
-            .iter()
-            .map(|preview_line| {
-                let mut spans = vec![Span::styled(
-                    format!("{:4} ", preview_line.line_number),
-                    Style::default().fg(Color::DarkGray),
-                )];
-
-                for (style, text) in &preview_line.segments {
-                    let fg = Color::Rgb(style.foreground.r, style.foreground.g, style.foreground.b);
-                    spans.push(Span::styled(text.clone(), Style::default().fg(fg)));
-                }
+        let selection = (app.input_mode == InputMode::Visual).then(|| app.visual_range());
 
-                Line::from(spans)
-            })
-            .collect();
+        // スクロール位置と折り返し幅が一致するよう、折り返しは自前で行う
+        let mut lines: Vec<Line> = Vec::with_capacity(visible_height);
+        for (index, preview_line) in content.lines.iter().enumerate().take(end).skip(start) {
+            let selected = matches!(
+                selection,
+                Some((sel_start, sel_end)) if (sel_start..=sel_end).contains(&index)
+            );
+            for (row, body) in wrap_segments(&preview_line.segments, wrap_width)
+                .into_iter()
+                .enumerate()
+            {
+                let mut spans = if fullscreen {
+                    Vec::new()
+                } else if row == 0 {
+                    vec![
+                        diff_marker_span(preview_line.diff, theme),
+                        Span::styled(
+                            match preview_line.line_number {
+                                // 省略マーカーなど行番号を持たない行
+                                0 => " ".repeat(gutter_width - 1),
+                                n => format!("{:>1$} ", n, gutter_width - 2),
+                            },
+                            theme.style(Style::default().fg(Color::DarkGray)),
+                        ),
+                    ]
+                } else {
+                    vec![Span::raw(" ".repeat(gutter_width))]
+                };
+                spans.extend(body);
 
-        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
-        frame.render_widget(paragraph, inner_area);
+                let line = Line::from(spans);
+                lines.push(if selected {
+                    line.style(theme.style(Style::default().bg(Color::DarkGray)))
+                } else {
+                    line
+                });
+            }
+        }
+        lines.truncate(visible_height);
+
+        frame.render_widget(Paragraph::new(lines), inner_area);
+
+        // 全体が収まらない場合はスクロールバーを右枠上に表示
+        let max_scroll = app.preview_max_scroll();
+        if max_scroll > 0 && !fullscreen {
+            let state = ScrollbarState::new(max_scroll + 1)
+                .position(app.preview_scroll)
+                .viewport_content_length(visible_height);
+            draw_scrollbar(frame, area, state, border_style);
+        }
     } else if let Some(entry) = app.browser.selected_entry()
         && entry.is_dir
     {
-        let text = Paragraph::new("[Directory]").style(Style::default().fg(Color::DarkGray));
+        let text =
+            Paragraph::new("[Directory]").style(theme.style(Style::default().fg(Color::DarkGray)));
         frame.render_widget(text, inner_area);
     }
 }
 
-fn draw_help(frame: &mut Frame, area: Rect) {
-    let help_text = vec![
-        "",
-        "  vfv - Vive File Viewer",
-        "",
-        "  === File Browser ===",
-        "  j/k, ↑/↓     Move up/down",
-        "  Enter, l     Open file / Enter directory",
-        "  h, Backspace Go to parent directory",
-        "  g/G          Go to top/bottom",
-        "  e            Open in editor",
-        "  y            Copy path to clipboard",
-        "  f + char     Jump to entry starting with char",
-        "  ;            Jump to next match",
-        "  ,            Jump to previous match",
-        "  /            Search all files (fuzzy)",
-        "  D            Search folders only",
-        "  .            Toggle hidden files",
-        "  r            Reload",
-        "  ?            Show this help",
-        "  q            Quit",
-        "",
-        "  === Preview ===",
-        "  j/k          Scroll up/down",
-        "  Ctrl+d/u     Half page down/up",
-        "  Ctrl+f/b     Page down/up",
-        "  g/G          Go to top/bottom",
-        "  e            Open in editor",
-        "  h/q          Back to browser",
-        "",
-        "  Press q or ? to close",
-    ];
+/// gitの差分マーカーをガター用のSpanに変換
+fn diff_marker_span(marker: Option<DiffMarker>, theme: UiTheme) -> Span<'static> {
+    match marker {
+        Some(DiffMarker::Added) => {
+            Span::styled("+", theme.style(Style::default().fg(Color::Green)))
+        }
+        Some(DiffMarker::Modified) => {
+            Span::styled("~", theme.style(Style::default().fg(Color::Yellow)))
+        }
+        Some(DiffMarker::Removed) => {
+            Span::styled("_", theme.style(Style::default().fg(Color::Red)))
+        }
+        None => Span::raw(" "),
+    }
+}
 
-    let lines: Vec<Line> = help_text.iter().map(|&s| Line::from(s)).collect();
+/// ファイル一覧の上に重ねるヘルプ（短い端末でもスクロールで全部見られる）
+fn draw_help(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.config.ui_theme;
+    // 実際のキー割り当てから生成する（[keys] や [commands] の変更も反映される）
+    let help_lines = app.keymap.help_lines();
+    let lines: Vec<Line> = help_lines
+        .iter()
+        .map(|line| match line.strip_prefix("=== ") {
+            Some(title) => Line::from(Span::styled(
+                format!(" {}", title.trim_end_matches(" ===")),
+                theme.style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                ),
+            )),
+            None => Line::from(format!("  {}", line)),
+        })
+        .collect();
 
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .title("Help")
-        .border_style(Style::default().fg(Color::Green));
+    let content_width = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+    let total = lines.len();
+    let popup = centered_rect(
+        area,
+        content_width.saturating_add(4).max(40),
+        (total as u16).saturating_add(2),
+    );
+    let visible_height = popup.height.saturating_sub(2) as usize;
+    app.set_help_size(total, visible_height);
 
+    let block = app
+        .config
+        .style
+        .popup_block()
+        .title(" vfv - Vive File Viewer ")
+        .title_bottom(" j/k: Scroll  q: Close ")
+        .border_style(theme.style(Style::default().fg(Color::Green)));
     let paragraph = Paragraph::new(lines)
         .block(block)
-        .style(Style::default().fg(Color::White));
+        .style(theme.style(Style::default().fg(Color::White)))
+        .scroll((app.help_scroll as u16, 0));
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(paragraph, popup);
+
+    let max_scroll = app.help_max_scroll();
+    if max_scroll > 0 {
+        let state = ScrollbarState::new(max_scroll + 1)
+            .position(app.help_scroll)
+            .viewport_content_length(visible_height);
+        draw_scrollbar(
+            frame,
+            popup,
+            state,
+            theme.style(Style::default().fg(Color::Green)),
+        );
+    }
+}
+
+/// Human-readable byte size (e.g. "1.5 MB")
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
 
-    frame.render_widget(paragraph, area);
+/// 実行中のコピー・移動・削除・圧縮の進捗バー
+fn progress_line(progress: &TransferProgress, now: Instant, narrow: bool) -> String {
+    const BAR_WIDTH: u64 = 20;
+    let percent = (progress.done * 100)
+        .checked_div(progress.total)
+        .unwrap_or(100)
+        .min(100);
+    let filled = (percent * BAR_WIDTH / 100) as usize;
+    let eta = match progress.eta(now) {
+        Some(eta) => format!("  ETA {}", format_duration(eta)),
+        None => String::new(),
+    };
+    if narrow {
+        return format!("{} {}%{}  X:cancel", progress.label, percent, eta);
+    }
+    format!(
+        "{} {}  [{}{}] {}%  {} / {}{}  X:cancel",
+        progress.label,
+        progress.current,
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH as usize - filled),
+        percent,
+        format_size(progress.done),
+        format_size(progress.total),
+        eta
+    )
+}
+
+/// "1:05" / "1:02:03" 形式の残り時間
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// フッターに出すヒント（動作とその説明）。幅が足りなければ後ろから落とす
+const BROWSER_HINTS: &[(&[Action], &str)] = &[
+    (&[Action::Quit], "quit"),
+    (&[Action::MoveDown, Action::MoveUp], "move"),
+    (&[Action::Jump], "jump"),
+    (&[Action::Enter], "open"),
+    (&[Action::OpenEditor], "editor"),
+    (&[Action::Search], "search"),
+    (&[Action::Help], "help"),
+];
+
+const PICKER_HINTS: &[(&[Action], &str)] = &[
+    (&[Action::Enter], "choose"),
+    (&[Action::ToggleMark], "mark"),
+    (&[Action::MoveDown, Action::MoveUp], "move"),
+    (&[Action::Jump], "jump"),
+    (&[Action::Search], "search"),
+    (&[Action::Quit], "cancel"),
+];
+
+const PREVIEW_HINTS: &[(&[Action], &str)] = &[
+    (&[Action::ScrollDown, Action::ScrollUp], "scroll"),
+    (&[Action::PreviewTop, Action::PreviewBottom], "top/bottom"),
+    (&[Action::OpenEditor], "editor"),
+    (&[Action::ToggleWhitespace], "whitespace"),
+    (&[Action::PreviewBack], "back"),
+    (&[Action::ToggleFullscreen], "fullscreen"),
+];
+
+/// ファイル一覧でのキーのヒント（キーは今のキーマップから引く）
+fn normal_hints(app: &App, width: usize) -> String {
+    let is_file = app
+        .browser
+        .selected_entry()
+        .map(|e| !e.is_dir)
+        .unwrap_or(false);
+    let mut hints: Vec<(&[Action], &str)> = if app.narrow {
+        // 狭い端末では最低限のキーだけ
+        if app.picker {
+            vec![(&[Action::Enter], "choose"), (&[Action::Quit], "cancel")]
+        } else {
+            vec![(&[Action::Quit], "quit"), (&[Action::Help], "help")]
+        }
+    } else if app.picker {
+        PICKER_HINTS.to_vec()
+    } else {
+        BROWSER_HINTS
+            .iter()
+            .filter(|(actions, _)| is_file || actions[0] != Action::OpenEditor)
+            .copied()
+            .collect()
+    };
+    let jump_label = app.last_jump_char.map(|c| format!("next/prev '{}'", c));
+    if let Some(ref label) = jump_label {
+        let at = hints
+            .iter()
+            .position(|(actions, _)| actions[0] == Action::Jump)
+            .map_or(hints.len(), |i| i + 1);
+        hints.insert(at, (&[Action::JumpNext, Action::JumpPrev], label));
+    }
+
+    if app.picker {
+        let prefix = "PICK  ";
+        let keys = app.keymap.hints(
+            KeyContext::Browser,
+            &hints,
+            width.saturating_sub(prefix.len()),
+        );
+        format!("{}{}", prefix, keys)
+    } else {
+        app.keymap.hints(KeyContext::Browser, &hints, width)
+    }
+}
+
+/// status_bar のテンプレートの {segment} の中身（分からないものは空）
+fn status_segment(app: &App, segment: &str) -> String {
+    let browser = &app.browser;
+    match segment {
+        "mode" => if app.picker { "PICK" } else { "NORMAL" }.to_string(),
+        "selection" => match browser.selected_entry() {
+            Some(entry) if entry.is_dir => format!("{}/", entry.name),
+            Some(entry) => format!("{} {}", entry.name, format_size(entry.size)),
+            None => String::new(),
+        },
+        "position" if browser.entries.is_empty() => String::new(),
+        "position" => format!("{}/{}", browser.selected_index + 1, browser.entries.len()),
+        "marks" if browser.marked.is_empty() => String::new(),
+        "marks" => format!("{} marked", browser.marked.len()),
+        "branch" => app.dir_info.branch.clone().unwrap_or_default(),
+        "free" => app
+            .dir_info
+            .free_space
+            .map(|bytes| format!("{} free", format_size(bytes)))
+            .unwrap_or_default(),
+        "hints" => normal_hints(app, usize::MAX),
+        _ => String::new(),
+    }
 }
 
 fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = app.config.ui_theme;
+    // 何もしていないときは選択中のエントリの情報を右端に出す（詳細表示中は一覧にある）
+    let entry_info = if app.input_mode == InputMode::Normal
+        && app.transfer_progress.is_none()
+        && app.config.status_bar.is_empty()
+        && !app.narrow
+        && !app.show_details
+    {
+        app.browser
+            .selected_entry()
+            .map(|entry| entry_summary(entry, &app.dir_sizes))
+    } else {
+        None
+    };
+    let hints_width = (area.width as usize)
+        .saturating_sub(entry_info.as_ref().map_or(0, |info| info.width() + 2));
+
     let content = match app.input_mode {
-        InputMode::SearchInput => "Enter:search  Esc:cancel".to_string(),
+        InputMode::SearchInput => "Enter:search  ^D/^E/^A/^G:toggle flags  Esc:cancel".to_string(),
         InputMode::Searching => "Searching...  Esc:cancel".to_string(),
         InputMode::SearchResult => "j/k:select  Enter:open  /:re-search  Esc:cancel".to_string(),
         InputMode::JumpInput => "Type a character to jump...".to_string(),
+        InputMode::OpenWith => "j/k:select  Enter/1-9:open  Esc:cancel".to_string(),
+        InputMode::Places => "j/k:select  Enter/1-9:go  Esc:cancel".to_string(),
+        InputMode::DiskUsage => "j/k:select  l:open dir  h:parent  d:delete  q:back".to_string(),
+        InputMode::Trash => "j/k:select  r:restore  d:delete permanently  q:back".to_string(),
+        InputMode::Recent => "j/k:select  Enter:open  q:back".to_string(),
+        InputMode::PathList => {
+            "Type to filter  ↑/↓:select  Enter:open  Esc:clear/browse  ^C:quit".to_string()
+        }
+        InputMode::Confirm => app.confirm.as_ref().map(Confirm::hints).unwrap_or_default(),
+        InputMode::Chmod => {
+            "Space:toggle  hjkl:move  0-7:octal  Enter:apply  Esc:cancel".to_string()
+        }
+        InputMode::FoldInput => "za:toggle  zo:expand  zc:collapse  Esc:cancel".to_string(),
+        InputMode::SetBookmark => "Type a character to bookmark this directory...".to_string(),
+        InputMode::JumpBookmark => {
+            format!("Jump to bookmark: [{}]  Esc:cancel", app.bookmarks.keys())
+        }
+        InputMode::Breadcrumb => "Type a number to go up that many directories...".to_string(),
+        InputMode::Command => match app.command_completion {
+            // 巡回中の候補を並べ、選択中のものを [] で囲む
+            Some(ref completion) => {
+                let words: Vec<String> = completion
+                    .words
+                    .iter()
+                    .enumerate()
+                    .map(|(i, word)| {
+                        if i == completion.index {
+                            format!("[{}]", word)
+                        } else {
+                            word.clone()
+                        }
+                    })
+                    .collect();
+                format!(":{}  {}", app.command_input.text(), words.join(" "))
+            }
+            None => format!(
+                ":{}  ({})",
+                app.command_input.text(),
+                palette::usage(app.command_input.text())
+                    .unwrap_or("Tab:complete  Enter:run  Esc:cancel")
+            ),
+        },
+        InputMode::MarkRange => {
+            let (start, end) = app.mark_range();
+            format!(
+                "-- MARK -- {} entries  j/k:extend  V/Enter:mark  Esc:cancel",
+                end - start + 1
+            )
+        }
+        InputMode::Rename
+        | InputMode::NewFile
+        | InputMode::NewDir
+        | InputMode::Archive
+        | InputMode::ZoxideInput => match name_prompt(app) {
+            Some((label, input)) => {
+                format!("{}: {}  (Enter:confirm  Esc:cancel)", label, input.text())
+            }
+            None => String::new(),
+        },
         InputMode::Normal => {
-            if let Some(ref msg) = app.status_message {
-                msg.clone()
+            if let Some(ref progress) = app.transfer_progress {
+                progress_line(progress, Instant::now(), app.narrow)
+            } else if app.config.status_bar.is_empty() {
+                normal_hints(app, hints_width)
             } else {
-                let is_file = app
-                    .browser
-                    .selected_entry()
-                    .map(|e| !e.is_dir)
-                    .unwrap_or(false);
-                let jump_hint = if let Some(c) = app.last_jump_char {
-                    format!("  ;/,:next/prev '{}'", c)
-                } else {
-                    String::new()
-                };
-                if is_file {
-                    format!(
-                        "q:quit  j/k:move  f:jump{}  Enter:open  e:editor  /:search",
-                        jump_hint
-                    )
-                } else {
-                    format!(
-                        "q:quit  j/k:move  f:jump{}  Enter:open  /:search",
-                        jump_hint
-                    )
-                }
+                status_bar::expand(&app.config.status_bar, |segment| {
+                    status_segment(app, segment)
+                })
             }
         }
-        InputMode::Preview => "j/k:scroll  g/G:top/bottom  e:editor  h/q:back".to_string(),
-        InputMode::Help => "Press q or ? to close".to_string(),
+        InputMode::Preview => {
+            let hints = if app.narrow {
+                &[PREVIEW_HINTS[0], PREVIEW_HINTS[4]][..]
+            } else {
+                PREVIEW_HINTS
+            };
+            app.keymap
+                .hints(KeyContext::Preview, hints, area.width as usize)
+        }
+        InputMode::Visual => {
+            let (start, end) = app.visual_range();
+            format!(
+                "-- VISUAL -- {} line(s)  j/k:extend  y:yank  Esc:cancel",
+                end - start + 1
+            )
+        }
+        InputMode::GotoLine => format!(":{}", app.goto_input),
+        InputMode::Help => {
+            "j/k: Scroll  Ctrl+d/u: Half page  g/G: Top/Bottom  q/?: Close".to_string()
+        }
     };
 
-    let style = match app.input_mode {
-        InputMode::SearchInput | InputMode::SearchResult | InputMode::Searching => {
+    let style = theme.style(match app.input_mode {
+        InputMode::SearchInput
+        | InputMode::SearchResult
+        | InputMode::Searching
+        | InputMode::PathList => Style::default().fg(Color::Yellow),
+        InputMode::JumpInput
+        | InputMode::FoldInput
+        | InputMode::SetBookmark
+        | InputMode::JumpBookmark
+        | InputMode::Breadcrumb
+        | InputMode::Help => Style::default().fg(Color::Green),
+        InputMode::Preview => Style::default().fg(Color::Cyan),
+        InputMode::Visual => Style::default().fg(Color::Magenta),
+        InputMode::Confirm => Style::default().fg(Color::Red),
+        InputMode::Trash | InputMode::DiskUsage | InputMode::Recent => {
+            Style::default().fg(Color::DarkGray)
+        }
+        InputMode::Rename
+        | InputMode::NewFile
+        | InputMode::NewDir
+        | InputMode::Archive
+        | InputMode::ZoxideInput
+        | InputMode::Command => Style::default().fg(Color::Yellow),
+        InputMode::Chmod | InputMode::OpenWith | InputMode::Places => {
             Style::default().fg(Color::Yellow)
         }
-        InputMode::JumpInput | InputMode::Help => Style::default().fg(Color::Green),
-        InputMode::Preview => Style::default().fg(Color::Cyan),
+        InputMode::MarkRange => Style::default().fg(Color::Magenta),
+        InputMode::GotoLine => Style::default().fg(Color::Yellow),
         InputMode::Normal => Style::default().fg(Color::DarkGray),
-    };
+    });
 
     let footer = Paragraph::new(content).style(style);
     frame.render_widget(footer, area);
+    if let Some(info) = entry_info {
+        let info = Paragraph::new(info)
+            .style(style)
+            .alignment(HorizontalAlignment::Right);
+        frame.render_widget(info, area);
+    }
+
+    // 入力中のプロンプトでは端末のカーソルを入力位置に置く
+    let column = if app.input_mode == InputMode::Command {
+        Some(1 + app.command_input.cursor_width())
+    } else {
+        name_prompt(app).map(|(label, input)| label.width() as u16 + 2 + input.cursor_width())
+    };
+    if let Some(column) = column {
+        frame.set_cursor_position((area.x + column, area.y));
+    }
+}
+
+/// 名前・パスを入力するプロンプトのラベルと入力欄
+fn name_prompt(app: &App) -> Option<(&'static str, &TextInput)> {
+    let label = match app.input_mode {
+        InputMode::Rename => "Rename",
+        InputMode::NewFile => "New file",
+        InputMode::NewDir => "New directory",
+        InputMode::Archive => "Archive (.zip/.tar.gz)",
+        InputMode::ZoxideInput => return Some(("cd (zoxide query or path)", &app.path_input)),
+        _ => return None,
+    };
+    Some((label, &app.name_input))
 }
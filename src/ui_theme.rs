@@ -0,0 +1,161 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+/// Named look for the whole UI: the colors of the lists, borders and
+/// prompts, and the syntax theme of the preview
+///
+/// Widgets pick their colors from the dark preset and pass the style through
+/// `UiTheme::style`; colors from elsewhere (`[style]`, syntax highlighting)
+/// are left as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UiTheme {
+    /// For dark terminal backgrounds
+    #[default]
+    Dark,
+    /// For light terminal backgrounds
+    Light,
+    /// Bright text and an inverted selection
+    HighContrast,
+    /// Terminal default colors only, with reverse video for selections
+    Monochrome,
+}
+
+impl UiTheme {
+    pub const ALL: [UiTheme; 4] = [
+        UiTheme::Dark,
+        UiTheme::Light,
+        UiTheme::HighContrast,
+        UiTheme::Monochrome,
+    ];
+
+    /// Name as written in `ui_theme`
+    pub fn name(self) -> &'static str {
+        match self {
+            UiTheme::Dark => "dark",
+            UiTheme::Light => "light",
+            UiTheme::HighContrast => "high-contrast",
+            UiTheme::Monochrome => "monochrome",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.name() == name)
+    }
+
+    /// Syntax theme used for the preview unless `theme` is set
+    pub fn syntax_theme(self) -> &'static str {
+        match self {
+            UiTheme::Dark | UiTheme::Monochrome => "base16-ocean.dark",
+            UiTheme::Light => "InspiredGitHub",
+            UiTheme::HighContrast => "base16-eighties.dark",
+        }
+    }
+
+    /// The preset's version of a style picked in the dark preset's colors
+    /// (colors the style leaves unset stay unset where the preset allows)
+    pub fn style(self, style: Style) -> Style {
+        if self == UiTheme::Dark {
+            return style;
+        }
+        let (fg, bg, reverse) = self.recolor(
+            style.fg.unwrap_or(Color::Reset),
+            style.bg.unwrap_or(Color::Reset),
+        );
+        let keep = |before: Option<Color>, after: Color| {
+            (before.is_some() || after != Color::Reset).then_some(after)
+        };
+        let themed = Style {
+            fg: keep(style.fg, fg),
+            bg: keep(style.bg, bg),
+            ..style
+        };
+        if reverse {
+            themed.add_modifier(Modifier::REVERSED)
+        } else {
+            themed
+        }
+    }
+
+    /// Colors for a cell (and whether to reverse it), given its dark colors
+    fn recolor(self, fg: Color, bg: Color) -> (Color, Color, bool) {
+        match self {
+            UiTheme::Dark => (fg, bg, false),
+            UiTheme::Light => {
+                let bg = match bg {
+                    Color::Blue => Color::Rgb(190, 210, 250),
+                    Color::DarkGray => Color::Rgb(220, 220, 220),
+                    other => other,
+                };
+                // 明るい背景で読めない色だけ濃くする（構文ハイライトの RGB はそのまま）
+                let fg = match fg {
+                    Color::White => Color::Black,
+                    Color::Yellow => Color::Rgb(135, 95, 0),
+                    Color::Cyan => Color::Rgb(0, 105, 135),
+                    Color::Green => Color::Rgb(0, 125, 0),
+                    Color::Magenta => Color::Rgb(145, 0, 145),
+                    other => other,
+                };
+                (fg, bg, false)
+            }
+            UiTheme::HighContrast => match bg {
+                // 選択行は白地に黒
+                Color::Blue | Color::DarkGray => (Color::Black, Color::White, false),
+                _ => {
+                    let fg = match fg {
+                        Color::DarkGray => Color::Gray,
+                        Color::Cyan => Color::LightCyan,
+                        Color::Yellow => Color::LightYellow,
+                        Color::Green => Color::LightGreen,
+                        Color::Magenta => Color::LightMagenta,
+                        Color::Red => Color::LightRed,
+                        Color::Blue => Color::LightBlue,
+                        other => other,
+                    };
+                    (fg, bg, false)
+                }
+            },
+            UiTheme::Monochrome => (Color::Reset, Color::Reset, bg != Color::Reset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recolor_keeps_selection_readable() {
+        // 選択行（青地に白）は文字と背景が同じ色にならない
+        for theme in UiTheme::ALL {
+            let (fg, bg, reverse) = theme.recolor(Color::White, Color::Blue);
+            assert!(fg != bg || reverse, "{:?}", theme);
+        }
+        assert_eq!(
+            UiTheme::Light.recolor(Color::White, Color::Reset),
+            (Color::Black, Color::Reset, false)
+        );
+        // 構文ハイライトの色は light では変えない
+        let rgb = Color::Rgb(10, 20, 30);
+        assert_eq!(UiTheme::Light.recolor(rgb, Color::Reset).0, rgb);
+    }
+
+    #[test]
+    fn test_monochrome_strips_colors() {
+        let text = UiTheme::Monochrome.style(Style::default().fg(Color::Cyan));
+        assert_eq!(text, Style::default().fg(Color::Reset));
+        let selected = UiTheme::Monochrome.style(Style::default().fg(Color::White).bg(Color::Blue));
+        assert_eq!(selected.bg, Some(Color::Reset));
+        assert!(selected.add_modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn test_style_leaves_unset_colors() {
+        let style = Style::default().fg(Color::Yellow);
+        assert_eq!(UiTheme::Dark.style(style), style);
+        assert_eq!(UiTheme::Light.style(style).bg, None);
+        // 背景だけの選択行は high-contrast では白地に黒
+        let line = UiTheme::HighContrast.style(Style::default().bg(Color::DarkGray));
+        assert_eq!((line.fg, line.bg), (Some(Color::Black), Some(Color::White)));
+    }
+}
@@ -0,0 +1,171 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher, event::ModifyKind};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+/// Watches a single directory for entries being created, removed or renamed
+pub struct DirWatcher {
+    watcher: RecommendedWatcher,
+    receiver: Receiver<notify::Result<Event>>,
+    /// Directory last passed to `watch`, whether or not watching it worked
+    target: Option<PathBuf>,
+    watched: Option<PathBuf>,
+}
+
+impl DirWatcher {
+    pub fn new() -> notify::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let watcher = notify::recommended_watcher(tx)?;
+        Ok(Self {
+            watcher,
+            receiver: rx,
+            target: None,
+            watched: None,
+        })
+    }
+
+    /// Switch the watch to `dir` (no-op if it is already the target, so a
+    /// directory that cannot be watched is only retried after moving away)
+    pub fn watch(&mut self, dir: &Path) {
+        if self.target.as_deref() == Some(dir) {
+            return;
+        }
+        self.target = Some(dir.to_path_buf());
+        if let Some(old) = self.watched.take() {
+            let _ = self.watcher.unwatch(&old);
+        }
+        // 監視できないディレクトリでも閲覧は続けられるようにする
+        if self.watcher.watch(dir, RecursiveMode::NonRecursive).is_ok() {
+            self.watched = Some(dir.to_path_buf());
+        }
+        // 以前のディレクトリのイベントは捨てる
+        while self.receiver.try_recv().is_ok() {}
+    }
+
+    /// Drain pending events, returning whether the entry list changed
+    pub fn has_changes(&self) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.receiver.try_recv() {
+            if let Ok(event) = event {
+                changed |= matches!(
+                    event.kind,
+                    EventKind::Create(_)
+                        | EventKind::Remove(_)
+                        | EventKind::Modify(ModifyKind::Name(_))
+                );
+            }
+        }
+        changed
+    }
+}
+
+/// Watches a single file, including being replaced by an editor's
+/// write-and-rename save
+pub struct FileWatcher {
+    // drop すると監視が止まるので保持しておく
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<notify::Result<Event>>,
+    path: PathBuf,
+}
+
+impl FileWatcher {
+    /// Watch `path` through its parent directory, which must exist
+    pub fn new(path: &Path) -> notify::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            receiver: rx,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Drain pending events, returning whether the file was written,
+    /// created or replaced
+    pub fn has_changed(&self) -> bool {
+        let file_name = self.path.file_name();
+        let mut changed = false;
+        while let Ok(event) = self.receiver.try_recv() {
+            if let Ok(event) = event {
+                changed |= !event.kind.is_access()
+                    && event.paths.iter().any(|p| p.file_name() == file_name);
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
+
+    fn wait_for_changes(watcher: &DirWatcher) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if watcher.has_changes() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn test_file_watcher_ignores_other_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().canonicalize().unwrap();
+        let watcher = FileWatcher::new(&dir.join("config.toml")).unwrap();
+
+        fs::write(dir.join("other.toml"), "x").unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!watcher.has_changed());
+
+        fs::write(dir.join("config.toml"), "x").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !watcher.has_changed() {
+            assert!(Instant::now() < deadline);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_detects_created_and_removed_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().canonicalize().unwrap();
+        let mut watcher = DirWatcher::new().unwrap();
+        watcher.watch(&dir);
+        assert!(!watcher.has_changes());
+
+        fs::write(dir.join("new.txt"), "x").unwrap();
+        assert!(wait_for_changes(&watcher));
+
+        fs::remove_file(dir.join("new.txt")).unwrap();
+        assert!(wait_for_changes(&watcher));
+    }
+
+    #[test]
+    fn test_unwatchable_dir_is_retried_only_after_moving() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().canonicalize().unwrap();
+        let missing = dir.join("later");
+        let mut watcher = DirWatcher::new().unwrap();
+        watcher.watch(&missing);
+        assert_eq!(watcher.watched, None);
+
+        // 毎回のポーリングでは再試行しない
+        fs::create_dir(&missing).unwrap();
+        watcher.watch(&missing);
+        assert_eq!(watcher.watched, None);
+
+        watcher.watch(&dir);
+        watcher.watch(&missing);
+        assert_eq!(watcher.watched, Some(missing));
+    }
+}
@@ -0,0 +1,172 @@
+use std::str::FromStr;
+
+use ratatui::layout::HorizontalAlignment;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, BorderType, Borders};
+use serde::{Deserialize, Serialize};
+
+use crate::ui_theme::UiTheme;
+
+/// Line drawn around the panes and popups
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Border {
+    #[default]
+    Plain,
+    Rounded,
+    Thick,
+    /// No frame around the panes (popups keep a plain one)
+    None,
+}
+
+/// Where pane and popup titles sit on the top border
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TitleAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// The `[style]` table: borders, titles and the selection highlight
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WidgetStyle {
+    pub border: Border,
+    pub title_alignment: TitleAlign,
+    /// Background of the selected row: a color name ("blue", "dark-gray"),
+    /// "#rrggbb" or "reset" for none; unset uses the `ui_theme` preset's
+    pub highlight_bg: Option<String>,
+    pub highlight_bold: bool,
+    /// Drawn before the selected row, e.g. "> "
+    pub highlight_symbol: String,
+}
+
+impl Default for WidgetStyle {
+    fn default() -> Self {
+        Self {
+            border: Border::default(),
+            title_alignment: TitleAlign::default(),
+            highlight_bg: None,
+            highlight_bold: true,
+            highlight_symbol: String::new(),
+        }
+    }
+}
+
+impl WidgetStyle {
+    /// Block for a pane (the file list, the preview, result lists)
+    pub fn block(&self) -> Block<'static> {
+        let block = Block::default().title_alignment(match self.title_alignment {
+            TitleAlign::Left => HorizontalAlignment::Left,
+            TitleAlign::Center => HorizontalAlignment::Center,
+            TitleAlign::Right => HorizontalAlignment::Right,
+        });
+        let border_type = match self.border {
+            Border::None => return block.borders(Borders::NONE),
+            Border::Plain => BorderType::Plain,
+            Border::Rounded => BorderType::Rounded,
+            Border::Thick => BorderType::Thick,
+        };
+        block.borders(Borders::ALL).border_type(border_type)
+    }
+
+    /// Block for a popup drawn over the panes, which always needs an edge
+    pub fn popup_block(&self) -> Block<'static> {
+        let block = self.block();
+        match self.border {
+            Border::None => block.borders(Borders::ALL),
+            _ => block,
+        }
+    }
+
+    /// Style of the selected row in a focused list. A `highlight_bg` of
+    /// one's own is used as written; only the preset's colors follow `theme`.
+    pub fn highlight(&self, theme: UiTheme) -> Style {
+        let preset = theme.style(Style::default().bg(Color::Blue).fg(Color::White));
+        let style = match self.highlight_bg.as_deref().and_then(parse_color) {
+            Some(bg) => theme.style(Style::default().fg(Color::White)).bg(bg),
+            None => preset,
+        };
+        if self.highlight_bold {
+            style.add_modifier(Modifier::BOLD)
+        } else {
+            style
+        }
+    }
+
+    /// Problems to report for `vfv config check`
+    pub fn problems(&self) -> Vec<String> {
+        match self.highlight_bg {
+            Some(ref bg) if parse_color(bg).is_none() => {
+                vec![format!("style.highlight_bg: unknown color '{}'", bg)]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Color from a name or "#rrggbb" (`-` and `_` are ignored in names)
+fn parse_color(text: &str) -> Option<Color> {
+    let text = text.trim();
+    if text.starts_with('#') {
+        return Color::from_str(text).ok();
+    }
+    Color::from_str(&text.replace(['-', '_'], "")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_table() {
+        let style: WidgetStyle = toml::from_str(
+            "border = \"rounded\"\ntitle_alignment = \"center\"\nhighlight_bg = \"dark-gray\"\nhighlight_bold = false",
+        )
+        .unwrap();
+        assert_eq!(style.border, Border::Rounded);
+        assert_eq!(style.title_alignment, TitleAlign::Center);
+        assert_eq!(
+            style.highlight(UiTheme::Dark),
+            Style::default().bg(Color::DarkGray).fg(Color::White)
+        );
+        // 自分で決めた背景色はプリセットに変えられない
+        assert_eq!(
+            style.highlight(UiTheme::HighContrast).bg,
+            Some(Color::DarkGray)
+        );
+        assert!(style.problems().is_empty());
+        assert!(toml::from_str::<WidgetStyle>("borders = \"none\"").is_err());
+    }
+
+    #[test]
+    fn test_colors() {
+        assert_eq!(parse_color("#ff8000"), Some(Color::Rgb(255, 128, 0)));
+        assert_eq!(parse_color("light_blue"), Some(Color::LightBlue));
+        assert_eq!(parse_color("reset"), Some(Color::Reset));
+        let style = WidgetStyle {
+            highlight_bg: Some("bluish".to_string()),
+            ..WidgetStyle::default()
+        };
+        assert_eq!(style.highlight(UiTheme::Dark).bg, Some(Color::Blue));
+        assert_eq!(style.problems().len(), 1);
+        // 未設定ならプリセットの色
+        assert_eq!(
+            WidgetStyle::default().highlight(UiTheme::Light).bg,
+            Some(Color::Rgb(190, 210, 250))
+        );
+    }
+
+    #[test]
+    fn test_popups_keep_a_border() {
+        let style = WidgetStyle {
+            border: Border::None,
+            ..WidgetStyle::default()
+        };
+        let area = ratatui::layout::Rect::new(0, 0, 10, 5);
+        assert_eq!(style.block().inner(area), area);
+        assert_eq!(style.popup_block().inner(area).width, 8);
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Ask zoxide for the best matching directory for `query`
+pub fn query(query: &str, exclude: &Path) -> Result<PathBuf, String> {
+    let output = Command::new("zoxide")
+        .arg("query")
+        .arg("--exclude")
+        .arg(exclude)
+        .arg("--")
+        .args(query.split_whitespace())
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run zoxide: {}", e))?;
+
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || path.is_empty() {
+        return Err(format!("No zoxide match for '{}'", query));
+    }
+    Ok(PathBuf::from(path))
+}
+
+/// Record a visit in zoxide's database without blocking the UI
+pub fn add(dir: &Path) {
+    let dir = dir.to_path_buf();
+    thread::spawn(move || {
+        let _ = Command::new("zoxide")
+            .arg("add")
+            .arg("--")
+            .arg(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    });
+}
+
+/// Directory typed directly as a path (absolute, `~/...` or relative to `cwd`)
+pub fn as_path(input: &str, cwd: &Path) -> Option<PathBuf> {
+    let path = if input == "~" || input.starts_with("~/") {
+        let home = directories::BaseDirs::new()?.home_dir().to_path_buf();
+        home.join(input.trim_start_matches('~').trim_start_matches('/'))
+    } else {
+        cwd.join(input)
+    };
+    path.is_dir().then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_as_path() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+
+        assert_eq!(
+            as_path("sub", temp_dir.path()),
+            Some(temp_dir.path().join("sub"))
+        );
+        let absolute = temp_dir.path().join("sub");
+        assert_eq!(
+            as_path(&absolute.to_string_lossy(), Path::new("/")),
+            Some(absolute)
+        );
+        assert_eq!(as_path("missing", temp_dir.path()), None);
+    }
+}
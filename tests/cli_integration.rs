@@ -51,6 +51,187 @@ fn test_find_json_output() {
     assert!(parsed.is_ok(), "Output should be valid JSON: {}", stdout);
 }
 
+#[test]
+fn test_find_uses_config_defaults() {
+    let temp_dir = setup_test_dir();
+    let config = temp_dir.path().join("config.toml");
+    fs::write(&config, "[find]\njson = true\nlimit = 1\n").unwrap();
+    let base = temp_dir.path().to_str().unwrap();
+
+    let output = vfv_binary()
+        .args(["--config", config.to_str().unwrap(), "find", "rs", base])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let parsed: Vec<serde_json::Value> =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    assert_eq!(parsed.len(), 1);
+
+    // フラグは設定より優先する
+    let output = vfv_binary()
+        .args(["--config", config.to_str().unwrap(), "find", "rs", base])
+        .args(["--no-json", "-n", "5"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.starts_with('['));
+    assert!(stdout.lines().count() > 1);
+}
+
+#[test]
+fn test_bookmarks_cli() {
+    let temp_dir = setup_test_dir();
+    let config_home = TempDir::new().unwrap();
+    let bookmarks = |args: &[&str]| {
+        vfv_binary()
+            .arg("bookmarks")
+            .args(args)
+            .env("HOME", config_home.path())
+            .env("XDG_CONFIG_HOME", config_home.path())
+            .output()
+            .expect("Failed to execute command")
+    };
+    let src = temp_dir.path().join("src").canonicalize().unwrap();
+
+    let output = bookmarks(&["add", "work", src.to_str().unwrap()]);
+    assert!(output.status.success());
+
+    let output = bookmarks(&["jump", "work"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        src.to_str().unwrap()
+    );
+
+    let output = bookmarks(&["list", "--json"]);
+    let marks: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(marks["work"], src.to_str().unwrap());
+
+    assert!(bookmarks(&["remove", "work"]).status.success());
+    assert!(!bookmarks(&["remove", "work"]).status.success());
+    assert!(!bookmarks(&["jump", "work"]).status.success());
+    assert!(
+        !bookmarks(&["add", "x", "/nonexistent/dir"])
+            .status
+            .success()
+    );
+}
+
+#[test]
+fn test_find_json_fields() {
+    let temp_dir = setup_test_dir();
+    fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    let base = temp_dir.path().to_str().unwrap();
+
+    let output = vfv_binary()
+        .args([
+            "find",
+            "main.rs",
+            base,
+            "-j",
+            "-e",
+            "--fields",
+            "path,size,mtime",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let results: Vec<serde_json::Value> =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    let result = results[0].as_object().unwrap();
+    let mut keys: Vec<&str> = result.keys().map(String::as_str).collect();
+    keys.sort();
+    assert_eq!(keys, ["mtime", "path", "size"]);
+    assert_eq!(result["size"], 13);
+
+    let output = vfv_binary()
+        .args(["find", "main", base, "--ndjson", "--fields", "is_dir"])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).lines().next(),
+        Some("{\"is_dir\":false}")
+    );
+
+    let output = vfv_binary()
+        .args(["find", "main", base, "-j", "--fields", "owner"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_find_ndjson() {
+    let temp_dir = setup_test_dir();
+    let base = temp_dir.path().to_str().unwrap();
+
+    let output = vfv_binary()
+        .args(["find", "rs", base, "--ndjson"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be JSON"))
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert!(
+        lines
+            .iter()
+            .all(|l| l["path"].as_str().unwrap().ends_with(".rs"))
+    );
+
+    let output = vfv_binary()
+        .args(["find", "rs", base, "--ndjson", "-n", "1"])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 1);
+
+    let output = vfv_binary()
+        .args(["find", "nothing-here", base, "--ndjson"])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_ls_json() {
+    let temp_dir = setup_test_dir();
+    fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    File::create(temp_dir.path().join("src/.hidden")).unwrap();
+    let src = temp_dir.path().join("src");
+
+    let output = vfv_binary()
+        .args(["ls", src.to_str().unwrap(), "--json"])
+        .env("VFV_SHOW_HIDDEN", "false")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let entries: Vec<serde_json::Value> =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    let names: Vec<&str> = entries
+        .iter()
+        .map(|e| e["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["lib.rs", "main.rs"]);
+    assert_eq!(entries[1]["type"], "file");
+    assert_eq!(entries[1]["size"], 13);
+    assert!(entries[1]["mtime"].is_string());
+
+    let output = vfv_binary()
+        .args(["ls", src.to_str().unwrap(), "--all"])
+        .env("VFV_SHOW_HIDDEN", "false")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 3);
+    assert!(stdout.lines().any(|l| l.ends_with(" .hidden")));
+}
+
 #[test]
 fn test_find_compact_json() {
     let temp_dir = setup_test_dir();
@@ -249,6 +430,9 @@ fn test_help_flag() {
     assert!(output.status.success());
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("fuzzy search"));
+    assert!(stdout.contains("--choose-dir <FILE>"));
+    assert!(stdout.contains("--choose-files <FILE>"));
+    assert!(stdout.contains("--config <FILE>"));
 }
 
 #[test]
@@ -279,6 +463,383 @@ fn test_init_creates_config() {
     assert!(stdout.contains("--force"));
 }
 
+#[test]
+fn test_init_dry_run_changes_nothing() {
+    let home = TempDir::new().unwrap();
+    let bashrc = home.path().join(".bashrc");
+    fs::write(&bashrc, "# mine\n").unwrap();
+    let config = home.path().join("vfv/config.toml");
+
+    let output = vfv_binary()
+        .args(["--config", config.to_str().unwrap(), "init", "--dry-run"])
+        .env("HOME", home.path())
+        .env("SHELL", "/bin/bash")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("Would create: {}", config.display())));
+    assert!(stdout.contains(&format!("Would update: {}", bashrc.display())));
+    assert!(stdout.contains("  + export MANPATH="));
+
+    assert!(!config.exists());
+    assert!(!home.path().join(".local").exists());
+    assert_eq!(fs::read_to_string(&bashrc).unwrap(), "# mine\n");
+}
+
+// 設定ディレクトリの場所が XDG に従うのは Linux のみ
+#[cfg(target_os = "linux")]
+#[test]
+fn test_init_nushell_powershell_elvish() {
+    let home = TempDir::new().unwrap();
+    let config_dir = home.path().join(".config");
+    let init = |shell: &str| {
+        vfv_binary()
+            .args([
+                "--config",
+                home.path().join("vfv.toml").to_str().unwrap(),
+                "init",
+            ])
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", &config_dir)
+            .env("SHELL", format!("/usr/bin/{}", shell))
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    for (shell, completion, rc, source) in [
+        (
+            "nu",
+            "nushell/completions/vfv.nu",
+            "nushell/config.nu",
+            "source '",
+        ),
+        (
+            "pwsh",
+            "powershell/vfv-completions.ps1",
+            "powershell/Microsoft.PowerShell_profile.ps1",
+            ". '",
+        ),
+        (
+            "elvish",
+            "elvish/lib/vfv-completions.elv",
+            "elvish/rc.elv",
+            "eval (slurp < '",
+        ),
+    ] {
+        assert!(init(shell).status.success(), "{}", shell);
+        let script = fs::read_to_string(config_dir.join(completion)).unwrap();
+        assert!(script.contains("no-json"), "{}", shell);
+        let rc_content = fs::read_to_string(config_dir.join(rc)).unwrap();
+        assert!(rc_content.starts_with("# vfv setup\n"), "{}", shell);
+        assert!(rc_content.contains(source), "{}", shell);
+        assert!(rc_content.contains(".local/share/man"), "{}", shell);
+
+        // 2回目は追記しない
+        assert!(init(shell).status.success(), "{}", shell);
+        assert_eq!(fs::read_to_string(config_dir.join(rc)).unwrap(), rc_content);
+    }
+}
+
+#[test]
+fn test_man_pages_for_subcommands() {
+    let output = vfv_binary()
+        .args(["man", "find"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let page = String::from_utf8_lossy(&output.stdout);
+    assert!(page.contains(".TH vfv-find 1"));
+    assert!(page.contains("no\\-json"));
+
+    let home = TempDir::new().unwrap();
+    let output = vfv_binary()
+        .args([
+            "--config",
+            home.path().join("vfv.toml").to_str().unwrap(),
+            "init",
+        ])
+        .env("HOME", home.path())
+        .env("SHELL", "/bin/unknown")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let man_dir = home.path().join(".local/share/man/man1");
+    for page in ["vfv.1", "vfv-find.1", "vfv-init.1", "vfv-config-get.1"] {
+        assert!(man_dir.join(page).exists(), "{}", page);
+    }
+
+    let output = vfv_binary()
+        .args(["man", "nope"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_doctor_reports_config_problems() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = temp_dir.path().join("config.toml");
+    fs::write(&config, "editor = \"sh\"\nbogus = 1\n").unwrap();
+
+    let output = vfv_binary()
+        .args(["--config", config.to_str().unwrap(), "doctor"])
+        .env("TERM", "xterm-256color")
+        .env("COLORTERM", "truecolor")
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[ok]    Truecolor: COLORTERM=truecolor"));
+    assert!(stdout.contains("[error] Config: "));
+    assert!(stdout.contains("Unknown key 'bogus'"));
+    assert!(stdout.contains("fix: Correct the listed settings"));
+
+    fs::write(&config, "editor = \"sh\"\n").unwrap();
+    let output = vfv_binary()
+        .args(["--config", config.to_str().unwrap(), "doctor"])
+        .env("TERM", "xterm-256color")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("0 error(s)"));
+}
+
+#[test]
+fn test_stdin_requires_paths() {
+    let output = vfv_binary()
+        .arg("--stdin")
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No paths on standard input"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_open_uses_extension_opener() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = temp_dir.path().join("config.toml");
+    fs::write(
+        &config,
+        "[extension_openers]\ntxt = \"cat\"\n[openers]\nhead = \"head -c 2 {}\"\n",
+    )
+    .unwrap();
+    let file = temp_dir.path().join("notes.txt");
+    fs::write(&file, "hello").unwrap();
+    let open = |args: &[&str]| {
+        vfv_binary()
+            .args(["--config", config.to_str().unwrap(), "open"])
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = open(&[file.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hello");
+
+    let output = open(&[file.to_str().unwrap(), "--with", "head"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "he");
+
+    let output = open(&[file.to_str().unwrap(), "--print"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        format!("'cat' '{}'", file.display())
+    );
+
+    assert!(
+        !open(&[file.to_str().unwrap(), "--with", "nope"])
+            .status
+            .success()
+    );
+    assert!(!open(&["missing.txt"]).status.success());
+}
+
+#[test]
+fn test_bench_reports_runs() {
+    let temp_dir = setup_test_dir();
+    let output = vfv_binary()
+        .args([
+            "bench",
+            "main",
+            temp_dir.path().to_str().unwrap(),
+            "-r",
+            "2",
+            "-j",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let runs = report["runs"].as_array().unwrap();
+    assert_eq!(runs.len(), 2);
+    assert!(runs[0]["scanned"].as_u64().unwrap() > 0);
+    assert!(runs[0]["matches"].as_u64().unwrap() >= 1);
+
+    // 配管用のコマンドなのでヘルプには出さない
+    let output = vfv_binary().arg("--help").output().unwrap();
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("bench"));
+}
+
+#[test]
+fn test_find_stats_on_stderr() {
+    let temp_dir = setup_test_dir();
+    fs::write(temp_dir.path().join(".ignore"), "tests/\n").unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+
+    let output = vfv_binary()
+        .args(["find", "main", path, "-q", "--stats"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    // 標準出力は結果だけのまま
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 1);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("1 matches, 1 directories skipped"),
+        "{}",
+        stderr
+    );
+
+    let output = vfv_binary()
+        .args(["find", "main", path, "-j", "--stats"])
+        .output()
+        .expect("Failed to execute command");
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(results.as_array().unwrap().len(), 1);
+    let report: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(report["stats"]["matches"], 1);
+    assert_eq!(report["stats"]["skipped_dirs"], 1);
+    assert!(report["stats"]["scanned"].as_u64().unwrap() >= 4);
+
+    let output = vfv_binary()
+        .args(["find", "main", path, "--ndjson", "--stats"])
+        .output()
+        .expect("Failed to execute command");
+    let report: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(report["stats"]["matches"], 1);
+}
+
+#[test]
+fn test_config_set_and_get() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = temp_dir.path().join("config.toml");
+    fs::write(&config, "# keep me\neditor = \"vim\"\n").unwrap();
+    let config_arg = config.to_str().unwrap();
+
+    let output = vfv_binary()
+        .args([
+            "config",
+            "set",
+            "theme",
+            "Solarized (dark)",
+            "--config",
+            config_arg,
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let content = fs::read_to_string(&config).unwrap();
+    assert!(content.starts_with("# keep me\n"));
+
+    let output = vfv_binary()
+        .args(["--config", config_arg, "config", "get", "theme"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Solarized (dark)\n"
+    );
+
+    let output = vfv_binary()
+        .args(["--config", config_arg, "config", "set", "tab_width", "wide"])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("tab_width"));
+}
+
+#[test]
+fn test_env_overrides_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = temp_dir.path().join("config.toml");
+    fs::write(&config, "editor = \"vim\"\n").unwrap();
+
+    let output = vfv_binary()
+        .args([
+            "--config",
+            config.to_str().unwrap(),
+            "config",
+            "get",
+            "editor",
+        ])
+        .env("VFV_EDITOR", "nano")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "nano\n");
+
+    let output = vfv_binary()
+        .args(["--config", config.to_str().unwrap(), "config", "get"])
+        .env("VFV_TAB_WIDTH", "wide")
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("VFV_TAB_WIDTH"));
+}
+
+#[test]
+fn test_config_check() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = temp_dir.path().join("config.toml");
+    fs::write(&config, "editor = \"sh\"\n").unwrap();
+    let config_arg = config.to_str().unwrap();
+
+    let output = vfv_binary()
+        .args(["--config", config_arg, "config", "check"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    fs::write(&config, "editor = \"sh\"\ntheme = \"Nope\"\n").unwrap();
+    let output = vfv_binary()
+        .args(["--config", config_arg, "config", "check"])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown theme 'Nope'"));
+}
+
+#[test]
+fn test_tui_refuses_unloadable_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let missing = temp_dir.path().join("missing.toml");
+    let output = vfv_binary()
+        .args(["--config", missing.to_str().unwrap()])
+        .arg(temp_dir.path())
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Config file not found"));
+    // 画面を切り替える前に止まる
+    assert!(output.stdout.is_empty());
+
+    let broken = temp_dir.path().join("broken.toml");
+    fs::write(&broken, "editor = \n").unwrap();
+    let output = vfv_binary()
+        .env("VFV_CONFIG", &broken)
+        .arg(temp_dir.path())
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("Config warning"), "{}", stderr);
+    assert!(output.stdout.is_empty());
+}
+
 #[test]
 fn test_subcommands_in_help() {
     let output = vfv_binary()
@@ -293,4 +854,68 @@ fn test_subcommands_in_help() {
     assert!(stdout.contains("find"));
     assert!(stdout.contains("init"));
     assert!(stdout.contains("man"));
+    assert!(stdout.contains("config"));
+    assert!(stdout.contains("keys"));
+}
+
+#[test]
+fn test_completions_cover_current_flags() {
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish", "nushell"] {
+        let output = vfv_binary()
+            .args(["completions", shell])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success(), "{}", shell);
+        let script = String::from_utf8_lossy(&output.stdout);
+        assert!(script.contains("no-json"), "{}", shell);
+    }
+
+    let output = vfv_binary()
+        .args(["completions", "tcsh"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_shell_init_bash_defines_vcd() {
+    let output = vfv_binary()
+        .args(["shell-init", "bash", "--bind"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let script = String::from_utf8_lossy(&output.stdout);
+    assert!(script.contains("vcd()"));
+    assert!(script.contains("\\C-f"));
+
+    // bash があれば構文チェックも行う
+    if let Ok(status) = Command::new("bash").args(["-n", "-c", &script]).status() {
+        assert!(status.success());
+    }
+}
+
+#[test]
+fn test_keys_lists_overrides() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = temp_dir.path().join("config.toml");
+    fs::write(
+        &config,
+        "[keys]\nquit = \"ctrl-q\"\n[commands]\nctrl-g = \"git add {path}\"\n",
+    )
+    .unwrap();
+
+    let output = vfv_binary()
+        .args(["--config", config.to_str().unwrap(), "keys"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout
+            .lines()
+            .any(|l| l.starts_with("ctrl-q") && l.ends_with("Quit"))
+    );
+    assert!(!stdout.lines().any(|l| l.starts_with("q ")));
+    assert!(stdout.contains("git add {path}"));
 }